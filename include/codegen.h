@@ -106,6 +106,14 @@ private:
     std::string capitalize(const std::string& name);
 };
 
+/**
+ * `///` lines carrying a C++ doc comment into Rust. Code examples
+ * (`@code` ... `@endcode` or Markdown fences) become ```text blocks marked
+ * as the original C++, so rustdoc keeps them without running them as
+ * doctests. Empty for an empty comment.
+ */
+std::vector<std::string> rustDocLines(const std::string& doc_comment);
+
 } // namespace hybrid
 
 #endif // HYBRID_CODEGEN_H
@@ -9,9 +9,13 @@
 #ifndef HYBRID_TRANSPILER_FFI_H
 #define HYBRID_TRANSPILER_FFI_H
 
+#include "ir.h"
 #include <string>
 #include <vector>
+#include <map>
 #include <memory>
+#include <set>
+#include <utility>
 #include <unordered_map>
 
 namespace hybrid_transpiler {
@@ -29,6 +33,35 @@ struct FFIParameter {
     bool is_pointer;
     bool is_const;
     bool is_reference;
+    bool is_volatile;          // volatile-qualified (e.g. memory-mapped register)
+    std::string default_value; // C++ default argument as written, empty if none
+    std::string span_element;  // Element type of a std::span parameter, empty if not a span
+    bool span_const;           // std::span<const T>
+    size_t span_extent;        // Fixed std::span extent, 0 for std::dynamic_extent
+    std::string generic_type;  // Type before template substitution (`T`), empty outside instantiations
+};
+
+/**
+ * @brief Ownership of a pointer returned across the FFI boundary
+ */
+enum class PointerOwnership {
+    Inferred,   // Decided by the generator from the function shape
+    Owned,      // Caller owns the pointee and must free it
+    Borrowed    // Pointee is owned elsewhere (e.g. a parent object)
+};
+
+/**
+ * @brief Kind of limitation a declaration is skipped for, set where the
+ *        skip is decided and grouping the support report
+ */
+enum class SkipCategory {
+    Other,
+    Templates,
+    StandardLibrary,      // std:: types other than strings, spans and durations
+    Exceptions,
+    SelfEmbedding,        // Structs containing themselves by value
+    ByValueClass,         // Opaque classes passed or returned by value
+    MultipleInheritance
 };
 
 /**
@@ -48,6 +81,19 @@ struct FFIFunction {
     bool is_virtual;            // true if virtual function
     bool can_use_ffi;           // true if FFI-compatible
     std::string reason;         // Reason if not FFI-compatible
+    SkipCategory skip_category = SkipCategory::Other;  // Kind of reason, if not FFI-compatible
+    PointerOwnership return_ownership = PointerOwnership::Inferred;  // From `// @owns_return`, the name or the config
+    std::string link_name;      // Library the symbol is linked from, empty for the module's library
+    std::string static_field;   // Static data member this accessor reads or writes, empty otherwise
+    std::string namespace_path; // Enclosing C++ namespaces, inline ones included, empty if global
+    std::string source_file;    // Header a preprocessed input attributes it to, empty otherwise
+    bool is_deprecated = false; // `[[deprecated]]`, carried over as `#[deprecated]`
+    std::string deprecation_note;  // Its message, escapes kept, empty if none
+    std::string doc_comment;       // C++ doc comment, carried over as `///` lines
+    std::string template_of;       // Function template this instantiates (`max` for `max_int`), empty otherwise
+    std::string template_args;     // Its template arguments as written, e.g. `int`
+    std::string generic_return_type;  // Return type before template substitution
+    bool has_c_linkage = false;    // Declared `extern "C"`, so its symbol is its plain name
 };
 
 /**
@@ -61,10 +107,344 @@ struct FFIClass {
     bool has_virtual_functions;
     bool is_polymorphic;
     bool is_abstract;
-    size_t size;                // Size in bytes
-    size_t alignment;           // Alignment requirement
+    bool factory_only;          // Every constructor is private or deleted
+    size_t pack;                // Maximum field alignment from `#pragma pack` or `packed`, 0 if natural
+    std::string link_name;      // Library the shims are linked from, empty for the module's library
+    std::string namespace_path; // Enclosing C++ namespaces, inline ones included, empty if global
+    std::string source_file;    // Header a preprocessed input attributes it to, empty otherwise
+    bool is_deprecated = false; // `class [[deprecated]] Name`
+    std::string deprecation_note;  // Its message, escapes kept, empty if none
+    std::string doc_comment;       // C++ doc comment, carried over as `///` lines
+    std::vector<std::string> base_classes;  // Public bases; their members are not part of the wrapper
+    bool is_template = false;      // Class template, bound as if it were a plain class
+    bool is_struct = false;        // Declared with `struct`, which MSVC symbols encode
+};
+
+/**
+ * @brief Represents an enum for FFI
+ */
+struct FFIEnum {
+    std::string name;           // Empty for anonymous enums
+    std::string underlying_type;  // C++ underlying type, "int" if unspecified
+    std::string namespace_path;   // Enclosing C++ namespaces, inline ones included, empty if global
+    std::string source_file;      // Header a preprocessed input attributes it to, empty otherwise
+    bool is_deprecated = false;   // `enum [[deprecated]] Name`
+    std::string deprecation_note; // Its message, escapes kept, empty if none
+    std::string doc_comment;      // C++ doc comment, carried over as `///` lines
+    std::vector<std::pair<std::string, std::string>> values;  // Enumerator and initializer (empty if implicit)
+};
+
+/**
+ * @brief Intrusive reference counting scheme of a class (e.g. COM AddRef/Release)
+ */
+struct RefCounting {
+    std::string incref;         // Method adding a reference
+    std::string decref;         // Method dropping a reference, frees at zero
+};
+
+/**
+ * @brief Callback registration wrapped to deliver events through a
+ *        tokio::sync::mpsc channel
+ */
+struct ChannelCallback {
+    std::string unregister;     // Function removing the callback, called when the receiver drops
+    size_t capacity = 64;       // Channel bound; events arriving while it is full are dropped
+};
+
+/**
+ * @brief Instantiations of a function template to bind, each through its
+ *        own shim, optionally unified behind a Rust trait
+ */
+struct TemplateInstances {
+    std::vector<std::string> instances;  // Template arguments of each, e.g. `int` or `int, double`
+    std::string trait;          // Trait implemented for each instance's type, empty for none
+};
+
+/**
+ * @brief Enum mirrored as a Rust error type implementing Display and
+ *        std::error::Error
+ */
+struct ErrorEnum {
+    std::string success;        // Enumerator meaning no error; functions returning the enum then return Result<(), E>
+    std::string to_string;      // `const char* f(E)` giving the message, empty prints the enumerator name
+};
+
+/**
+ * @brief Calling convention whose struct return rules the bindings follow
+ */
+enum class AbiTarget {
+    SysV64,     // x86-64 System V: Linux, macOS and the BSDs
+    Win64,      // x64 Windows
+    AArch64     // AAPCS64: 64-bit Arm Linux, Android and macOS
+};
+
+/**
+ * @brief One-time setup a library needs before use, and its teardown
+ */
+struct LibraryInit {
+    std::string function;       // `void f()` called once by the generated `init()`
+    std::string shutdown;       // `void f()` called by the generated `shutdown()`, empty for none
+    bool automatic = false;     // Call `init()` first in every wrapper that can be a first use
+};
+
+/**
+ * @brief Where a prebuilt library is installed, for the pkg-config and
+ *        CMake package files describing it
+ */
+struct PackageInfo {
+    std::string prefix = "/usr/local";  // Install prefix holding lib/ and include/
+    std::string version = "0.1.0";
+    std::string description;            // Empty for a generic one naming the library
+};
+
+/**
+ * @brief How wrappers expose parameters that have C++ default arguments
+ */
+enum class DefaultArgs {
+    Overloads,  // `add()` passing the defaults, plus `add_with(value)`
+    Option      // Single `add(value: Option<i32>)` falling back to the default
+};
+
+/**
+ * @brief How wrappers take `const char*` and `std::string` parameters
+ */
+enum class StringParams {
+    Str,        // `name: &str`
+    AsRef       // `name: impl AsRef<str>`, accepting String, &str, Cow<str>, ...
+};
+
+/**
+ * @brief How #[repr(C)] struct mirrors expose their fields
+ */
+enum class StructFields {
+    Public,     // `pub x: f32`
+    Accessors   // Private fields behind `new`, `x(&self)` and `set_x(&mut self, value)`
 };
 
+/**
+ * @brief Problem found while generating bindings, reported to the user
+ */
+struct FFIDiagnostic {
+    std::string symbol;         // C++ entity concerned, e.g. `Calculator::add`
+    std::string message;
+    SkipCategory category = SkipCategory::Other;  // For a skipped declaration, the kind of reason
+};
+
+/**
+ * @brief Options controlling FFI binding generation
+ */
+struct FFIOptions {
+    bool emit_tests = false;    // Append a #[cfg(test)] module of smoke tests
+    bool emit_toc = true;       // Lead with a comment listing every generated public item
+    bool emit_self_test = false;  // Append a runtime ABI round-trip harness against C++ checksum shims
+    bool emit_link_check = false; // Append a test per extern symbol so link errors name the binding
+    bool emit_relink_macro = false;  // Append a macro declaring the extern block again against another library name
+    bool preprocessed = false;  // Sources are preprocessed translation units with line markers
+    std::map<std::string, RefCounting> refcounted;  // Class name -> incref/decref methods
+    std::set<std::string> thread_safe;  // Classes whose singleton may be shared across threads (Send + Sync)
+    DefaultArgs default_args = DefaultArgs::Overloads;
+    StringParams string_params = StringParams::Str;
+    StructFields struct_fields = StructFields::Public;
+    bool checked_narrowing = false;  // Take i32 for i8/i16/u8/u16 parameters and convert with try_into
+    bool safety_comments = false;    // Precede each unsafe block with a `// SAFETY:` comment
+    bool thiserror = false;          // Derive thiserror::Error on error types when the crate's `thiserror` feature is on
+    std::string free_function;       // C function releasing owned `char*` returns, empty keeps raw pointers
+    bool strict = false;        // Fail generation instead of warning on correctness problems and skips
+    std::set<std::string> allowed_skips;  // Declarations known to be unbindable, `name` or `Class::method`
+    std::vector<std::string> include_patterns;  // Only bind names fully matching one of these, all if empty
+    std::vector<std::string> exclude_patterns;  // Never bind names fully matching one of these
+    std::vector<std::string> roots;  // Only bind these declarations and the types they reach, all if empty
+    size_t builder_min_setters = 0;  // Add a `<Class>Builder` to default-constructible classes with this many setters, 0 for none
+    std::vector<std::string> builder_setters;  // Only chain setters fully matching one of these (`Class::set_x`), all if empty
+    std::map<std::string, ChannelCallback> channel_callbacks;  // Registration function -> its channel wrapper
+    std::map<std::string, ErrorEnum> error_enums;  // Enum name -> how it maps to a Rust error
+    std::map<std::string, TemplateInstances> templates;  // Function template -> the instantiations to bind
+    std::map<std::string, std::string> typedefs;  // Alias -> the type it stands for, over the header's own typedefs
+    std::map<std::string, PointerOwnership> ownership;  // Function or `Class::method` -> who frees its returned pointer
+    std::map<std::string, std::vector<std::string>> derives;  // Struct -> #[derive] list replacing the inferred one
+    std::vector<std::string> path_params;  // Name words marking string parameters as filenames taken as `&Path`, none if empty
+    LibraryInit init;                // Once-guarded library initialization, none if `init.function` is empty
+    PackageInfo package;             // Install location described by the .pc and CMake package files
+    std::vector<AbiTarget> abi_targets = {AbiTarget::SysV64, AbiTarget::Win64, AbiTarget::AArch64};  // Targets the struct returns must suit
+};
+
+/**
+ * @brief A declaration the bindings cannot cover as written
+ */
+struct UnsupportedDeclaration {
+    std::string category;  // Kind of limitation the report groups by, e.g. "Templates"
+    std::string symbol;    // `name` or `Class::method`
+    std::string detail;    // Why this declaration in particular
+};
+
+/**
+ * @brief Which declarations of a set of headers the bindings cannot cover
+ */
+struct SupportReport {
+    size_t declarations = 0;  // Functions, classes, methods and named enums found
+    std::vector<UnsupportedDeclaration> unsupported;  // One entry per declaration
+};
+
+/**
+ * @brief Heading a category is reported under, e.g. "Templates"
+ */
+std::string skipCategoryName(SkipCategory category);
+
+/**
+ * @brief Plain text listing of a report, grouped by category with counts
+ */
+std::string formatSupportReport(const SupportReport& report);
+
+/**
+ * @brief Apply a JSON FFI configuration file on top of existing options
+ * @param json Configuration file contents
+ * @param options Options to update
+ * @throws std::runtime_error if the configuration is malformed
+ */
+void applyFFIConfig(const std::string& json, FFIOptions& options);
+
+/**
+ * @brief Whether the include/exclude patterns select a function or type
+ */
+bool isSelected(const std::string& name, const FFIOptions& options);
+
+/**
+ * @brief Rust source for a build script applying the include/exclude
+ *        patterns to a bindgen::Builder, so bindgen and these bindings
+ *        cover the same surface
+ * @param options Options holding the patterns
+ * @param library_name Name of the library
+ * @return Source of a `configure(bindgen::Builder) -> bindgen::Builder` function
+ */
+std::string generateBindgenConfig(const FFIOptions& options, const std::string& library_name);
+
+/**
+ * @brief pkg-config file for the prebuilt libraries, linking the same
+ *        names as the bindings' `#[link]` attributes
+ * @param options Options holding the package location and version
+ * @param library_names Linked libraries, the first naming the package
+ * @return Contents of `<library>.pc`
+ */
+std::string generatePkgConfig(const FFIOptions& options, const std::vector<std::string>& library_names);
+
+/**
+ * @brief CMake package configuration defining an imported target per
+ *        library the bindings link, `<package>::<library>`
+ * @param options Options holding the package location and version
+ * @param library_names Linked libraries, the first naming the package
+ * @return Contents of `<library>Config.cmake`
+ */
+std::string generateCMakeConfig(const FFIOptions& options, const std::vector<std::string>& library_names);
+
+// Naming shared by the Rust bindings and the C++ shim source, so both
+// sides of the extern "C" boundary agree on every symbol
+
+std::string toSnakeCase(const std::string& name);
+bool isConstructor(const FFIFunction& func);
+
+/**
+ * @brief `name` usable as a Rust identifier: keywords become raw
+ *        identifiers (`r#move`), and `self`, `Self`, `super` and `crate`,
+ *        which cannot be, get a trailing underscore
+ */
+std::string rustIdent(const std::string& name);
+
+/**
+ * @brief Rust type for a possibly nested C++ class, `Outer::Inner` becomes `OuterInner`
+ */
+std::string rustTypeName(const std::string& cpp_name);
+bool isDestructor(const FFIFunction& func);
+std::string paramName(const FFIParameter& param, size_t index);
+
+/**
+ * @brief Plain data classes are mirrored as #[repr(C)] structs,
+ *        everything else goes through the opaque pointer pattern
+ */
+bool isReprC(const FFIClass& cls);
+
+/**
+ * @brief `const char*` and `std::string` parameters cross the boundary as
+ *        NUL-terminated strings built from a Rust `&str`
+ */
+bool isStringParam(const FFIParameter& param);
+
+/**
+ * @brief `std::string_view` parameters cross the boundary as pointer +
+ *        length borrowed from the Rust string, without a copy
+ */
+bool isStringViewParam(const FFIParameter& param);
+
+/**
+ * @brief `std::chrono::duration<Rep, Period>` or one of its standard
+ *        typedefs, by value or `const&`; crosses the boundary as its count
+ */
+bool isDuration(const std::string& cpp_type);
+
+/**
+ * @brief C++ count type a duration crosses the boundary as, `int64_t` for
+ *        the standard typedefs
+ */
+std::string durationRep(const std::string& cpp_type);
+
+/**
+ * @brief `std::time::Duration` unit of a duration ("nanos", "micros",
+ *        "millis", "secs", "minutes" or "hours"), empty for custom periods
+ *        and non-integer counts, which stay raw counts
+ */
+std::string durationUnit(const std::string& cpp_type);
+
+/**
+ * @brief `T* f(..., size_t* count)`: an array return whose length C++
+ *        writes through the trailing out-parameter
+ */
+bool returnsCountedArray(const FFIFunction& func);
+
+/**
+ * @brief Whether the caller frees a returned pointer: static factories and
+ *        free functions hand out new memory, instance methods point into
+ *        their object, unless return_ownership says otherwise
+ */
+bool ownsReturnedPointer(const FFIFunction& func);
+
+/**
+ * @brief Name of the extern "C" shim for a function, method,
+ *        constructor (`<class>_new`), destructor (`<class>_delete`)
+ *        or comparison operator (`<class>_cmp`, `<class>_lt`, ...)
+ */
+std::string shimName(const FFIFunction& func);
+
+/**
+ * @brief Symbol a generated shim is defined under: free functions get a
+ *        `_shim` suffix, so the extern "C" definition never redeclares the
+ *        C++ function it wraps with a different return type
+ */
+std::string shimSymbol(const FFIFunction& func);
+
+/**
+ * @brief Whether a free function is called through a generated shim, or
+ *        bound directly because its signature is already plain C
+ * @param memory_structs Structs returned through an out-pointer, see memoryReturnedStructs
+ */
+bool needsShim(const FFIFunction& func, const std::set<std::string>& opaque_classes,
+               const std::set<std::string>& memory_structs);
+
+/**
+ * @brief Comparison operators (`operator<=>`, `operator<`, ...) are not
+ *        wrapped as methods but become PartialEq/PartialOrd/Ord impls
+ */
+bool isOperator(const FFIFunction& func);
+
+/**
+ * @brief Descriptor for an incref/decref method named in the configuration
+ */
+FFIFunction makeRefCountMethod(const std::string& class_name, const std::string& name);
+
+/**
+ * @brief Classes as the shims see them: overloaded constructors get
+ *        distinct names and configured incref/decref methods are added
+ */
+std::vector<FFIClass> prepareShimClasses(const std::vector<FFIClass>& classes, const FFIOptions& options);
+
 /**
  * @brief FFI compatibility analyzer
  *
@@ -73,7 +453,7 @@ struct FFIClass {
  */
 class FFIAnalyzer {
 public:
-    FFIAnalyzer() = default;
+    FFIAnalyzer() { initializeTypeMappings(); }
     ~FFIAnalyzer() = default;
 
     /**
@@ -90,6 +470,48 @@ public:
      */
     FFIClass analyzeClass(const std::string& class_decl);
 
+    /**
+     * @brief Build an FFI descriptor from a parsed IR function
+     * @param function Parsed function or method
+     * @param class_name Owning class, empty for free functions
+     * @return FFIFunction with compatibility information
+     */
+    FFIFunction analyzeFunction(const hybrid::Function& function, const std::string& class_name = "");
+
+    /**
+     * @brief Build the FFI descriptor of one instantiation of a free
+     *        function template, named after its arguments (`max_int`)
+     * @param function Parsed function template
+     * @param args Template arguments, comma-separated
+     * @return FFIFunction whose shim calls `max<int>(...)`
+     */
+    FFIFunction instantiateFunction(const hybrid::Function& function, const std::string& args);
+
+    /**
+     * @brief Typedefs to see through when mapping types
+     *
+     * Aliases of builtin scalars are replaced by what they stand for. The
+     * `<cstdint>` names (`int32_t`, `std::uint64_t`, `size_t`, ...) are never
+     * resolved: they keep their exact-width mapping whatever the header
+     * defines them as.
+     * @param aliases Alias name -> aliased type
+     */
+    void setTypeAliases(const std::map<std::string, std::string>& aliases) { type_aliases_ = aliases; }
+
+    /**
+     * @brief Build an FFI descriptor from a parsed IR class
+     * @param class_decl Parsed class or struct
+     * @return FFIClass with methods, fields and C layout
+     */
+    FFIClass analyzeClass(const hybrid::ClassDecl& class_decl);
+
+    /**
+     * @brief Build an FFI descriptor from a parsed IR enum
+     * @param enum_decl Parsed enum, possibly anonymous
+     * @return FFIEnum with its enumerators and underlying type
+     */
+    FFIEnum analyzeEnum(const hybrid::EnumDecl& enum_decl);
+
     /**
      * @brief Check if a C++ type is FFI-compatible
      * @param cpp_type C++ type to check
@@ -125,11 +547,32 @@ private:
     std::unordered_map<std::string, std::string> cpp_to_c_types_;
     std::unordered_map<std::string, std::string> cpp_to_rust_types_;
     std::unordered_map<std::string, std::string> cpp_to_go_types_;
+    std::map<std::string, std::string> type_aliases_;
+
+    /**
+     * @brief `cpp_type` with `std::`-qualified fixed-width names unqualified
+     *        and scalar aliases resolved
+     */
+    std::string canonicalType(const std::string& cpp_type) const;
 
     /**
      * @brief Initialize type mapping tables
      */
     void initializeTypeMappings();
+
+    /**
+     * @brief analyzeFunction with template parameters replaced by `bindings`
+     */
+    FFIFunction analyzeSignature(const hybrid::Function& function, const std::string& class_name,
+                                 const std::map<std::string, std::string>& bindings);
+
+    /**
+     * @brief Add static-method accessors for a public static data member
+     *
+     * `count` gets `count()`, plus `set_count(value)` unless it is const or
+     * held by pointer or reference.
+     */
+    void addStaticFieldAccessors(const hybrid::ClassDecl& class_decl, const hybrid::Variable& field, FFIClass& cls);
 };
 
 /**
@@ -138,6 +581,7 @@ private:
 class RustFFIGenerator {
 public:
     RustFFIGenerator() = default;
+    explicit RustFFIGenerator(const FFIOptions& options) : options_(options) {}
     ~RustFFIGenerator() = default;
 
     /**
@@ -157,22 +601,258 @@ public:
     /**
      * @brief Generate safe Rust wrapper for FFI function
      * @param func FFI function descriptor
+     * @param omit_defaults Leave out parameters with literal default arguments
+     * @param suffix Appended to the wrapper name (e.g. "_with")
      * @return Safe Rust wrapper code
      */
-    std::string generateSafeWrapper(const FFIFunction& func);
+    std::string generateSafeWrapper(const FFIFunction& func, bool omit_defaults = false,
+                                    const std::string& suffix = "");
+
+    /**
+     * @brief Diagnostics collected by the last generateModule call
+     */
+    const std::vector<FFIDiagnostic>& diagnostics() const { return diagnostics_; }
+
+    /**
+     * @brief Cross-check `self` receivers and extern `this` pointers in
+     *        generated code against the C++ const qualifiers
+     * @param rust_code Generated (possibly hand-edited) bindings
+     * @param classes Classes the bindings were generated from
+     * @return One diagnostic per mismatch
+     */
+    std::vector<FFIDiagnostic> checkConstness(const std::string& rust_code,
+                                              const std::vector<FFIClass>& classes) const;
 
     /**
      * @brief Generate complete Rust FFI module
      * @param functions List of FFI functions
      * @param classes List of FFI classes
      * @param library_name Name of the C++ library
+     * @param enums List of FFI enums
+     * @param callback_types Function pointer typedefs, named after the alias
      * @return Complete Rust module code
      */
     std::string generateModule(
         const std::vector<FFIFunction>& functions,
         const std::vector<FFIClass>& classes,
-        const std::string& library_name
+        const std::string& library_name,
+        const std::vector<FFIEnum>& enums = {},
+        const std::vector<FFIFunction>& callback_types = {}
     );
+
+    /**
+     * @brief C++ `<shim>_abi_checksum` functions the self-test harness calls
+     *
+     * Reads the types the preceding generateModule call resolved, so it must
+     * follow one over the same functions.
+     */
+    std::string generateSelfTestShims(const std::vector<FFIFunction>& functions);
+
+private:
+    FFIOptions options_;
+    FFIAnalyzer analyzer_;
+    std::set<std::string> repr_c_structs_;
+    std::set<std::string> opaque_classes_;
+    std::set<std::string> memory_structs_;  // Mirrored structs returned through an out-pointer
+    std::set<std::string> viewed_classes_;  // Opaque classes a borrowed pointer return hands out
+    std::map<std::string, std::string> enum_types_;  // Named enum -> Rust integer it crosses the ABI as
+    std::map<std::string, FFIFunction> callback_types_;  // Function pointer typedef -> its signature
+    std::set<std::string> channel_functions_;  // Registrations wrapped to return a channel receiver
+    std::map<std::string, ErrorEnum> error_enums_;  // Configured error enums, to_string resolved to its shim
+    std::map<std::string, std::string> template_traits_;  // Function template -> trait over its instances
+    std::map<std::string, std::vector<std::string>> struct_derives_;  // Mirrored struct -> its #[derive] list
+    std::string init_function_;      // Configured init function, if bound as one
+    std::string shutdown_function_;  // Configured shutdown function, if bound as one
+
+    struct GeneratedItem {
+        std::string kind;    // Table of contents group, e.g. "Functions"
+        std::string name;    // Rust item
+        std::string origin;  // C++ declaration it comes from
+    };
+    std::vector<GeneratedItem> generated_items_;  // Public items in emission order, for the table of contents
+    std::vector<FFIDiagnostic> diagnostics_;
+
+    std::string generateStructBinding(const FFIClass& cls);
+
+    /**
+     * @brief Derivable traits every field of a mirrored struct supports:
+     *        floats rule out Eq and Hash, pointers and callbacks compare
+     *        addresses so rule out all three comparisons, nested structs
+     *        pass on their own set
+     */
+    std::vector<std::string> inferDerives(const FFIClass& cls, const std::vector<FFIClass>& classes);
+    std::string generateTests(const std::vector<FFIClass>& classes, const std::vector<FFIEnum>& enums);
+
+    /**
+     * @brief `init()` calling the init function under a std::sync::Once,
+     *        and `shutdown()` if one is configured
+     */
+    std::string generateInit(const std::vector<FFIFunction>& functions);
+    std::string generateSelfTest(const std::vector<FFIFunction>& functions, const std::string& library_name);
+
+    /**
+     * @brief Why the self-test harness leaves `func` out, empty if it is exercised
+     */
+    std::string selfTestSkip(const FFIFunction& func);
+
+    std::string generateLinkCheck(const std::vector<std::string>& symbols);
+
+    /**
+     * @brief `relink_<library>!` re-declaring the extern blocks in a new
+     *        module, the module's own library replaced by the caller's
+     * @param blocks Library and declarations of each extern block, the module's own first
+     */
+    std::string generateRelinkMacro(const std::vector<std::pair<std::string, std::string>>& blocks,
+                                    const std::string& library_name);
+    std::string generateConstants(const FFIEnum& enum_decl);
+    std::string generateEnum(const FFIEnum& enum_decl);
+
+    /**
+     * @brief Leading comment listing the public items emitted so far,
+     *        grouped by kind in the order they were emitted
+     */
+    std::string generateToc() const;
+    std::string generateMethodWrapper(const FFIFunction& method, const FFIClass& cls,
+                                      bool omit_defaults = false, const std::string& suffix = "");
+    std::string generateSelfView(const FFIClass& cls);
+    std::string generateComparisons(const FFIClass& cls);
+
+    /**
+     * @brief `<Class>Builder` chaining the class's setters after its default
+     *        constructor, empty when the class does not qualify
+     */
+    std::string generateBuilder(const FFIClass& cls);
+
+    /**
+     * @brief Setters a builder chains: one-argument `void set_x(...)`
+     *        methods selected by builder_setters. Empty unless the class has
+     *        a public default constructor and at least builder_min_setters
+     */
+    std::vector<const FFIFunction*> builderSetters(const FFIClass& cls);
+
+    /**
+     * @brief Why `func` cannot be wrapped as configured in channel_callbacks,
+     *        empty if it can: one callback and one `void*` context parameter,
+     *        scalar or `const char*` events and an unregister function whose
+     *        arguments the receiver can supply
+     */
+    std::string channelProblem(const FFIFunction& func, const std::vector<FFIFunction>& functions);
+
+    /**
+     * @brief `<Func>Receiver` and the registration wrapper returning it; the
+     *        callback context is the boxed channel sender
+     */
+    std::string generateChannelWrapper(const FFIFunction& func, const std::vector<FFIFunction>& functions);
+
+    /**
+     * @brief Why the instances of a function template cannot share a trait,
+     *        empty if they can: one type argument each, mapping to distinct
+     *        Rust scalars, and otherwise scalar parameters without defaults
+     */
+    std::string templateTraitProblem(const std::string& name, const std::vector<FFIFunction>& functions);
+
+    /**
+     * @brief Trait implemented for each instance's type by calling its
+     *        wrapper, plus the generic function dispatching through it
+     */
+    std::string generateTemplateTrait(const std::string& name, const std::vector<FFIFunction>& functions);
+    std::string rustType(const std::string& cpp_type);
+    bool returnsSelfPointer(const FFIFunction& func, const FFIClass& cls) const;
+    bool isSingletonAccessor(const FFIFunction& func, const FFIClass& cls) const;
+    bool returnsBorrowed(const FFIFunction& func) const;
+
+    /**
+     * @brief Returns a `char*` the caller frees with the configured free function
+     */
+    bool ownsCString(const FFIFunction& func) const;
+
+    /**
+     * @brief Returns a `const char*` the library keeps, handed out as a `CStr`
+     */
+    bool borrowsCString(const FFIFunction& func) const;
+
+    /**
+     * @brief Returns a counted array of #[repr(C)] structs, wrapped as a slice or Vec
+     */
+    bool returnsStructArray(const FFIFunction& func) const;
+
+    /**
+     * @brief Function freeing an owned struct array: the configured free
+     *        function, else a generated `<shim>_free` calling delete[]
+     */
+    std::string arrayFreeFunction(const FFIFunction& func) const;
+    bool needsView(const FFIClass& cls) const;
+
+    /**
+     * @brief Bound opaque class whose pointer `func` returns without handing
+     *        over ownership, wrapped as a Drop-free `<Class>Ref`; empty otherwise
+     */
+    std::string viewedClass(const FFIFunction& func) const;
+    const RefCounting* refCounting(const FFIClass& cls) const;
+    std::string paramList(const FFIFunction& func, bool omit_defaults, std::string& args,
+                          std::vector<std::string>& locals, bool enum_outs = false);
+    bool isNarrowInteger(const FFIParameter& param);
+
+    /**
+     * @brief String parameter whose name has one of the configured path
+     *        words (`src_path`, `logFile`), taken as `&Path` so non-UTF-8
+     *        filenames pass through unchanged
+     */
+    bool isPathParam(const FFIParameter& param, size_t index) const;
+    bool narrowsIntegers(const FFIFunction& func);
+
+    /**
+     * @brief Body of a function or plain method wrapper: the FFI call,
+     *        converted for enum results, borrows and checked narrowing
+     * @param return_type Set to the wrapper's return type, empty for none
+     */
+    std::string plainBody(const FFIFunction& func, const std::string& call, const std::string& borrow,
+                          const std::string& indent, std::string& return_type);
+    bool isEnum(const std::string& cpp_type) const;
+    bool isBorrowable(const std::string& cpp_type);
+
+    /**
+     * @brief `// SAFETY:` line giving the invariant an unsafe block relies on,
+     *        empty unless safety_comments is set
+     */
+    std::string safety(const std::string& invariant, const std::string& indent) const;
+
+    /**
+     * @brief Why calling `func`'s extern from its wrapper is sound: the live
+     *        receiver and what each pointer argument points to
+     */
+    std::string callInvariant(const FFIFunction& func);
+
+    /**
+     * @brief Borrow type a `T&` / `const T&` return is wrapped as, tied to
+     *        the receiver or to the single reference argument
+     * @param owner Set to that argument's name, empty for the receiver
+     * @return Empty, after reporting a diagnostic, if there is no obvious
+     *         owner; the wrapper then returns the raw pointer
+     */
+    std::string referenceReturn(const FFIFunction& func, std::string& owner);
+    bool isEnumOutParam(const FFIParameter& param) const;
+
+    /**
+     * @brief Wrapper body converting enum returns and `Enum*` out-parameters
+     *        with TryFrom, empty if the function has neither
+     * @param return_type Set to the `Result<..., InvalidEnum>` the body returns
+     */
+    std::string enumResult(const FFIFunction& func, const std::string& call,
+                           const std::string& indent, std::string& return_type);
+    std::string rustDefault(const FFIParameter& param);
+    bool hasLiteralDefaults(const FFIFunction& func);
+
+    /**
+     * @brief Why the wrapper cannot leave a default argument out, empty
+     *        when every default is a literal
+     */
+    std::string defaultArgumentProblem(const FFIFunction& func);
+
+    /**
+     * @brief Warn about durations with custom periods, which stay raw tick counts
+     */
+    void checkDurations(const FFIFunction& func);
 };
 
 /**
@@ -218,6 +898,114 @@ public:
     );
 };
 
+/**
+ * @brief C++ ABI a mangled name is computed for
+ */
+enum class ManglingScheme {
+    Itanium,    // GCC and Clang on 64-bit Linux, macOS and the BSDs
+    Itanium32,  // GCC and Clang on 32-bit x86 and ARM
+    MSVC32,     // MSVC and clang-cl targeting x86
+    MSVC64      // MSVC and clang-cl targeting x64
+};
+
+/**
+ * @brief Linker symbols of C++ free functions, so a function without C
+ *        linkage can be bound without a shim
+ *
+ * Covers functions at namespace scope taking and returning builtin types,
+ * the <cstdint> typedefs, and the classes and enums of the headers, by
+ * value, pointer or lvalue reference. Templates, operators, function
+ * pointers, rvalue references and volatile are outside the subset.
+ */
+class NameMangler {
+public:
+    NameMangler(const std::vector<FFIClass>& classes, const std::vector<FFIEnum>& enums);
+
+    /**
+     * @brief Mangled name of a free function under one ABI
+     * @return The symbol, empty if the signature is outside the covered subset
+     */
+    std::string mangle(const FFIFunction& func, ManglingScheme scheme) const;
+
+    /**
+     * @brief Whether every scheme has a symbol for `func`
+     */
+    bool linksByMangledName(const FFIFunction& func) const;
+
+private:
+    struct NamedType {
+        std::vector<std::string> scope;  // Enclosing namespaces and classes, outermost first
+        char tag;                        // MSVC's 'U' struct, 'V' class or 'W' enum
+    };
+    std::map<std::string, NamedType> types_;  // Unqualified class and enum names
+};
+
+/**
+ * @brief Where a target returns a struct
+ */
+enum class ReturnConvention {
+    Registers,  // In registers, like a scalar
+    Memory      // Written through a hidden pointer to the caller's storage (sret)
+};
+
+/**
+ * @brief How a target returns a mirrored struct by value
+ *
+ * System V returns structs of up to 16 bytes without unaligned fields in
+ * registers, Windows x64 only those of 1, 2, 4 or 8 bytes, AArch64 those
+ * of up to 16 bytes and homogeneous aggregates of up to four floats or
+ * doubles. Structs of unknown layout count as Memory.
+ */
+ReturnConvention classifyReturn(const FFIClass& cls, AbiTarget target);
+
+/**
+ * @brief Mirrored structs one of the targets returns in memory
+ *
+ * Functions returning them by value get an explicit out-pointer instead,
+ * which the shim writes the result through, so no side relies on the
+ * other placing the hidden return pointer the same way.
+ */
+std::set<std::string> memoryReturnedStructs(const std::vector<FFIClass>& classes,
+                                            const std::vector<AbiTarget>& targets);
+
+/**
+ * @brief Struct `func` returns by value through an out-pointer, empty if
+ *        its return is not one of `memory_structs`
+ */
+std::string outPointerReturn(const FFIFunction& func, const std::set<std::string>& memory_structs);
+
+/**
+ * @brief Name of the out-pointer parameter, `out` unless a parameter has it
+ */
+std::string outPointerName(const FFIFunction& func);
+
+/**
+ * @brief Size in bytes of a scalar or pointer field on `target`, 0 for
+ *        anything else
+ *
+ * Pointers and `size_t` take the target's pointer size, and `long` is 4
+ * bytes on Windows but 8 on the LP64 targets.
+ */
+size_t scalarFieldSize(const FFIParameter& field, AbiTarget target);
+
+/**
+ * @brief C size and alignment of a struct
+ */
+struct StructLayout {
+    size_t size = 0;       // Size in bytes
+    size_t alignment = 0;  // Alignment requirement
+
+    bool operator==(const StructLayout& other) const {
+        return size == other.size && alignment == other.alignment;
+    }
+};
+
+/**
+ * @brief Layout of a struct's fields on `target`, both 0 when a field's
+ *        size is unknown
+ */
+StructLayout structLayout(const FFIClass& cls, AbiTarget target);
+
 /**
  * @brief C wrapper generator for C++ code
  *
@@ -227,6 +1015,7 @@ public:
 class CWrapperGenerator {
 public:
     CWrapperGenerator() = default;
+    explicit CWrapperGenerator(const FFIOptions& options) : options_(options) {}
     ~CWrapperGenerator() = default;
 
     /**
@@ -261,13 +1050,39 @@ public:
      * @param functions List of FFI functions
      * @param classes List of FFI classes
      * @param library_name Name of the library
+     * @param enums Enums the shims may name, brought into scope if namespaced
      * @return C implementation file content
      */
     std::string generateImplementation(
         const std::vector<FFIFunction>& functions,
         const std::vector<FFIClass>& classes,
-        const std::string& library_name
+        const std::string& library_name,
+        const std::vector<FFIEnum>& enums = {}
     );
+
+    /**
+     * @brief Shims the last generateImplementation call could not write,
+     *        `Class::method` and the reason
+     */
+    const std::vector<FFIDiagnostic>& diagnostics() const { return diagnostics_; }
+
+private:
+    FFIOptions options_;
+    std::vector<FFIDiagnostic> diagnostics_;
+    std::set<std::string> opaque_classes_;
+    std::set<std::string> struct_types_;  // Plain data classes, mirrored as #[repr(C)] structs
+    std::set<std::string> memory_structs_;  // Mirrored structs returned through an out-pointer
+
+    void collectOpaqueClasses(const std::vector<FFIClass>& classes);
+
+    /**
+     * @brief `void <shim>_free(const T*)` releasing an owned struct array
+     *        with delete[], empty when the free function is configured
+     *        or the function returns none
+     */
+    std::string arrayFreeShim(const FFIFunction& func) const;
+    std::string cParameter(const FFIParameter& param, size_t index, std::string& arg, std::string& reason,
+                           SkipCategory& category) const;
 };
 
 /**
@@ -276,6 +1091,7 @@ public:
 class FFIGenerator {
 public:
     FFIGenerator();
+    explicit FFIGenerator(const FFIOptions& options);
     ~FFIGenerator() = default;
 
     /**
@@ -291,6 +1107,32 @@ public:
         const std::string& target_lang
     );
 
+    /**
+     * @brief Generate one module binding several libraries, each with its
+     *        own `#[link]` extern block
+     * @param sources Link name and C++ header source of each library; the
+     *        first names the module
+     * @param target_lang Target language ("rust")
+     * @return FFI binding code
+     */
+    std::string generate(
+        const std::vector<std::pair<std::string, std::string>>& sources,
+        const std::string& target_lang
+    );
+
+    /**
+     * @brief Diagnostics from the last generate call
+     */
+    const std::vector<FFIDiagnostic>& diagnostics() const { return rust_generator_.diagnostics(); }
+
+    /**
+     * @brief Every declaration the Rust bindings and shims would skip or
+     *        bind incompletely, without failing on any of them
+     * @param sources Link name and C++ header source of each library
+     * @return Report with the reason for each declaration
+     */
+    SupportReport audit(const std::vector<std::pair<std::string, std::string>>& sources);
+
     /**
      * @brief Generate C wrapper layer
      * @param cpp_source C++ source code
@@ -307,6 +1149,11 @@ private:
     RustFFIGenerator rust_generator_;
     GoFFIGenerator go_generator_;
     CWrapperGenerator c_wrapper_generator_;
+    FFIOptions options_;
+
+    void analyze(const std::vector<std::pair<std::string, std::string>>& sources, std::vector<FFIFunction>& functions,
+                 std::vector<FFIClass>& classes, std::vector<FFIEnum>& enums,
+                 std::vector<FFIFunction>& callback_types);
 };
 
 } // namespace ffi
@@ -287,6 +287,14 @@ public:
 class Function {
 public:
     std::string name;
+    std::string namespace_path;  // Enclosing namespaces, inline ones included (`geo::v2`), empty if global
+    std::string source_file;     // Original file per a preprocessed input's line markers, empty otherwise
+    std::string library;         // Library of the header it came from, when FFI bindings span several
+    bool is_deprecated = false;  // `[[deprecated]]`
+    std::string deprecation_message;  // `[[deprecated("...")]]` text, escapes kept
+    std::string doc_comment;  // Preceding `///` or Doxygen block, comment markers removed
+    std::vector<std::string> annotations;  // Tags of preceding `// @tag` comments, e.g. `owns_return`
+    bool has_c_linkage = false;  // Declared `extern "C"` or inside an `extern "C" { }` block
     std::shared_ptr<Type> return_type;
     std::vector<Parameter> parameters;
     std::string body;
@@ -297,6 +305,11 @@ public:
     bool is_pure_virtual = false;
     bool is_constructor = false;
     bool is_destructor = false;
+    bool is_deleted = false;     // `= delete`
+
+    // Access level of a class member, free functions are public
+    enum class Access { Public, Protected, Private };
+    Access access = Access::Public;
 
     // Ownership analysis results
     std::vector<std::string> moved_params;
@@ -332,7 +345,14 @@ public:
 class ClassDecl {
 public:
     std::string name;
+    std::string namespace_path;  // Enclosing namespaces, inline ones included (`geo::v2`), empty if global
+    std::string source_file;     // Original file per a preprocessed input's line markers, empty otherwise
+    std::string library;         // Library of the header it came from, when FFI bindings span several
+    bool is_deprecated = false;  // `class [[deprecated]] Name`
+    std::string deprecation_message;  // `[[deprecated("...")]]` text, escapes kept
+    std::string doc_comment;  // Preceding `///` or Doxygen block, comment markers removed
     bool is_struct = false;
+    size_t pack = 0;             // `#pragma pack(N)` in effect, 1 for `__attribute__((packed))`, 0 if natural
 
     std::vector<Variable> fields;
     std::vector<Function> methods;
@@ -357,6 +377,27 @@ public:
     bool thread_safe = false;
 };
 
+/**
+ * Enum representation
+ */
+class EnumDecl {
+public:
+    struct Enumerator {
+        std::string name;
+        std::string value;           // Initializer as written, empty if implicit
+    };
+
+    std::string name;                // Empty for anonymous enums
+    std::string namespace_path;      // Enclosing namespaces, inline ones included, empty if global
+    std::string source_file;         // Original file per a preprocessed input's line markers, empty otherwise
+    bool is_deprecated = false;      // `enum [[deprecated]] Name`
+    std::string deprecation_message; // `[[deprecated("...")]]` text, escapes kept
+    std::string doc_comment;         // Preceding `///` or Doxygen block, comment markers removed
+    std::string underlying_type;     // Explicit `: type`, empty if unspecified
+    bool is_scoped = false;          // enum class / enum struct
+    std::vector<Enumerator> enumerators;
+};
+
 /**
  * Intermediate Representation
  * Contains parsed and analyzed C++ code in a language-neutral format
@@ -368,10 +409,18 @@ public:
     void addClass(const ClassDecl& class_decl);
     void addFunction(const Function& func);
     void addGlobalVariable(const Variable& var);
+    void addEnum(const EnumDecl& enum_decl);
+    void addFunctionPointerType(const Function& signature);
+    void addTypeAlias(const std::string& name, const std::string& underlying);
 
     const std::vector<ClassDecl>& getClasses() const { return classes_; }
     const std::vector<Function>& getFunctions() const { return functions_; }
     const std::vector<Variable>& getGlobalVariables() const { return global_vars_; }
+    const std::vector<EnumDecl>& getEnums() const { return enums_; }
+    // `typedef void (*Name)(int, void*)` / `using Name = ...`, named after the alias
+    const std::vector<Function>& getFunctionPointerTypes() const { return function_pointer_types_; }
+    // `typedef unsigned long Name;` / `using Name = ...`, alias name -> the type it names
+    const std::map<std::string, std::string>& getTypeAliases() const { return type_aliases_; }
 
     // Type lookup
     std::shared_ptr<Type> findType(const std::string& name) const;
@@ -381,9 +430,23 @@ private:
     std::vector<ClassDecl> classes_;
     std::vector<Function> functions_;
     std::vector<Variable> global_vars_;
+    std::vector<EnumDecl> enums_;
+    std::vector<Function> function_pointer_types_;
+    std::map<std::string, std::string> type_aliases_;
     std::map<std::string, std::shared_ptr<Type>> type_registry_;
 };
 
+/**
+ * Keep only the named declarations and everything they transitively
+ * reference through parameter, return, field and base class types
+ *
+ * @param ir Parsed declarations
+ * @param roots Function, class or enum names to start from
+ * @return IR holding the reachable declarations in their original order
+ * @throws std::invalid_argument if a root names no declaration
+ */
+IR reachableFrom(const IR& ir, const std::vector<std::string>& roots);
+
 } // namespace hybrid
 
 #endif // HYBRID_IR_H
@@ -28,6 +28,17 @@ public:
      * @return Intermediate representation of the parsed code
      */
     static IR parseString(const std::string& source);
+
+    /**
+     * Parse a preprocessed translation unit (`.i` / `.ii`)
+     *
+     * Line markers attribute each declaration to its original file;
+     * declarations from system headers are dropped.
+     *
+     * @param source Output of `g++ -E` or `clang++ -E`
+     * @return Intermediate representation of the user's declarations
+     */
+    static IR parsePreprocessed(const std::string& source);
 };
 
 } // namespace hybrid
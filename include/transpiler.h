@@ -1,16 +1,29 @@
 #ifndef HYBRID_TRANSPILER_H
 #define HYBRID_TRANSPILER_H
 
+#include <map>
 #include <string>
 #include <memory>
+#include <utility>
 #include <vector>
 
+namespace hybrid_transpiler {
+namespace ffi {
+struct FFIOptions;
+}
+}
+
 namespace hybrid {
 
 // Forward declarations
 class IR;
 class CodeGenerator;
 
+/**
+ * Transpiler version recorded in generated files
+ */
+constexpr const char* TRANSPILER_VERSION = "0.1.0";
+
 /**
  * Target language for transpilation
  */
@@ -30,15 +43,94 @@ struct TranspilerOptions {
     bool generate_tests = false;
     bool verbose = false;           // Verbose output
     bool quiet = false;             // Minimal output
+    std::string ffi_target;         // Generate FFI bindings instead ("rust"), empty to transpile
+    bool ffi_toc = true;            // Lead FFI output with a table of contents
+    bool ffi_tests = false;         // Append a #[cfg(test)] module of smoke tests to FFI output
+    bool ffi_self_test = false;     // Append a runtime ABI round-trip harness to FFI output
+    bool ffi_link_check = false;    // Append a test referencing every extern symbol to FFI output
+    bool ffi_relink_macro = false;  // Append a macro re-declaring the extern block under another link name
+    bool ffi_thiserror = false;     // Derive thiserror::Error on FFI error types behind a `thiserror` feature
+    std::string ffi_config_path;    // JSON file with per-type FFI settings, empty for none
+    std::string shim_source_path;   // Also write the C++ extern "C" shims here, empty for none
+    std::string bindgen_config_path;  // Also write a bindgen::Builder configuration here, empty for none
+    std::string pkg_config_path;    // Also write a pkg-config .pc file for the linked libraries, empty for none
+    std::string cmake_config_path;  // Also write a CMake package configuration for them, empty for none
+    bool strict = false;            // Treat FFI correctness warnings and skipped declarations as errors
+    std::vector<std::string> allowed_skips;  // Skipped declarations strict mode accepts
+    bool preprocessed = false;      // Inputs are preprocessed translation units (.i/.ii) with line markers
+    std::vector<std::string> roots; // Only emit these declarations and the types they reference, all if empty
+    bool stamp = true;              // Start output with a provenance comment (version, time, input hashes)
+    bool list_unsupported = false;  // Only report the declarations FFI bindings cannot cover, write nothing
     std::string output_path;
 };
 
+/**
+ * Build the provenance comment placed at the top of generated files
+ * @param inputs Path and contents of every input file
+ * @param timestamp UTC time to record (ISO 8601), empty to leave it out
+ * @return Comment block listing the transpiler version, time and SHA-256 of each input
+ */
+std::string generationStamp(const std::vector<std::pair<std::string, std::string>>& inputs,
+                            const std::string& timestamp);
+
+/**
+ * Destination of the files a transpiler run produces
+ */
+class CodeSink {
+public:
+    virtual ~CodeSink() = default;
+
+    /**
+     * Store one generated file
+     * @param path Path the file would be written to on disk
+     * @param contents Generated code
+     * @return true if stored, false otherwise
+     */
+    virtual bool write(const std::string& path, const std::string& contents) = 0;
+};
+
+/**
+ * Writes generated files to the filesystem, as the CLI does
+ */
+class FileSink : public CodeSink {
+public:
+    bool write(const std::string& path, const std::string& contents) override;
+};
+
+/**
+ * Keeps generated files in memory, for embedding the transpiler
+ */
+class MemorySink : public CodeSink {
+public:
+    bool write(const std::string& path, const std::string& contents) override;
+
+    /**
+     * Every file written so far, by path
+     */
+    const std::map<std::string, std::string>& files() const { return files_; }
+
+    /**
+     * Contents written to a path, empty if nothing was
+     */
+    std::string contents(const std::string& path) const;
+
+private:
+    std::map<std::string, std::string> files_;
+};
+
 /**
  * Main transpiler class
  */
 class Transpiler {
 public:
     explicit Transpiler(const TranspilerOptions& options);
+
+    /**
+     * Create a transpiler that emits through a caller-owned sink
+     * @param options Transpilation options
+     * @param sink Receives every generated file, must outlive the transpiler
+     */
+    Transpiler(const TranspilerOptions& options, CodeSink& sink);
     ~Transpiler();
 
     /**
@@ -50,6 +142,10 @@ public:
 
     /**
      * Transpile multiple C++ source files
+     *
+     * In FFI mode the headers are bound in one module, each input file
+     * naming the library its declarations are linked from.
+     *
      * @param input_paths Vector of paths to C++ source files
      * @return true if all successful, false otherwise
      */
@@ -60,14 +156,36 @@ public:
      */
     const std::string& getLastError() const { return last_error_; }
 
+    /**
+     * Get the warnings reported while generating output
+     */
+    const std::vector<std::string>& getWarnings() const { return warnings_; }
+
+    /**
+     * Get the unsupported-declaration report of a list_unsupported run
+     */
+    const std::string& getReport() const { return report_; }
+
 private:
     TranspilerOptions options_;
     std::unique_ptr<IR> ir_;
     std::unique_ptr<CodeGenerator> codegen_;
+    std::unique_ptr<CodeSink> file_sink_;  // Owned default sink, unset when the caller passes one
+    CodeSink* sink_;
     std::string last_error_;
+    std::vector<std::string> warnings_;
+    std::string report_;
 
+    void createCodeGenerator();
     bool parseSourceFile(const std::string& input_path);
-    bool generateCode(const std::string& output_path);
+    bool generateCode(const std::string& input_path, const std::string& output_path);
+    bool generateFFIBindings(const std::vector<std::string>& input_paths);
+    bool listUnsupported(const std::vector<std::string>& input_paths);
+    bool readFFISources(const std::vector<std::string>& input_paths,
+                        std::vector<std::pair<std::string, std::string>>& inputs,
+                        std::vector<std::pair<std::string, std::string>>& sources);
+    bool loadFFIOptions(hybrid_transpiler::ffi::FFIOptions& ffi_options);
+    std::string stampFor(const std::vector<std::pair<std::string, std::string>>& inputs) const;
 };
 
 } // namespace hybrid
@@ -77,7 +77,7 @@ void GoCodeGenerator::generateClass(const ClassDecl& class_decl) {
         // Find constructor or generate New function
         bool has_constructor = false;
         for (const auto& method : class_decl.methods) {
-            if (method.is_constructor) {
+            if (method.is_constructor && !method.is_deleted) {
                 has_constructor = true;
 
                 std::stringstream sig;
@@ -115,7 +115,7 @@ void GoCodeGenerator::generateClass(const ClassDecl& class_decl) {
 
         // Generate methods
         for (const auto& method : class_decl.methods) {
-            if (!method.is_constructor && !method.is_destructor) {
+            if (!method.is_constructor && !method.is_destructor && !method.is_deleted) {
                 generateFunction(method, struct_name);
                 writeLine("");
             }
@@ -431,6 +431,11 @@ std::string GoCodeGenerator::convertType(const std::shared_ptr<Type>& type) {
 std::string GoCodeGenerator::sanitizeName(const std::string& name) {
     std::string result = name;
 
+    // Nested classes (Outer::Inner) flatten to one identifier
+    for (size_t pos; result.compare(0, 5, "std::") != 0 && (pos = result.find("::")) != std::string::npos;) {
+        result.erase(pos, 2);
+    }
+
     // Handle Go keywords
     static const std::vector<std::string> keywords = {
         "break", "case", "chan", "const", "continue", "default", "defer",
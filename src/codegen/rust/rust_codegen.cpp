@@ -4,6 +4,44 @@
 
 namespace hybrid {
 
+std::vector<std::string> rustDocLines(const std::string& doc_comment) {
+    std::vector<std::string> lines;
+    std::stringstream doc(doc_comment);
+    bool in_code = false;
+    for (std::string line; std::getline(doc, line);) {
+        size_t start = line.find_first_not_of(" \t");
+        size_t end = line.find_last_not_of(" \t\r");
+        std::string text = start == std::string::npos ? "" : line.substr(start, end + 1 - start);
+        bool fence = text.compare(0, 3, "```") == 0 || text.compare(0, 3, "~~~") == 0;
+        bool opens = text.compare(0, 5, "@code") == 0 || text.compare(0, 5, "\\code") == 0;
+        bool closes = text.compare(0, 8, "@endcode") == 0 || text.compare(0, 8, "\\endcode") == 0;
+
+        if (!in_code && (opens || fence)) {
+            // C++ would not compile as a doctest, so the block is only shown
+            if (!lines.empty() && lines.back() != "///") {
+                lines.push_back("///");
+            }
+            lines.push_back("/// Original C++:");
+            lines.push_back("/// ```text");
+            in_code = true;
+        } else if (in_code && (closes || fence)) {
+            lines.push_back("/// ```");
+            in_code = false;
+        } else if (in_code) {
+            lines.push_back(line.empty() ? "///" : "/// " + line);
+        } else {
+            if (text.compare(0, 7, "@brief ") == 0 || text.compare(0, 7, "\\brief ") == 0) {
+                text = text.substr(7);
+            }
+            lines.push_back(text.empty() ? "///" : "/// " + text);
+        }
+    }
+    if (in_code) {
+        lines.push_back("/// ```");
+    }
+    return lines;
+}
+
 std::string RustCodeGenerator::generate(const IR& ir) {
     output_.str("");
     output_.clear();
@@ -34,6 +72,10 @@ std::string RustCodeGenerator::generate(const IR& ir) {
 }
 
 void RustCodeGenerator::generateClass(const ClassDecl& class_decl) {
+    for (const auto& line : rustDocLines(class_decl.doc_comment)) {
+        writeLine(line);
+    }
+
     // Generate struct definition with generics
     std::string struct_decl = "pub struct " + sanitizeName(class_decl.name);
 
@@ -79,6 +121,11 @@ void RustCodeGenerator::generateClass(const ClassDecl& class_decl) {
         indent();
 
         for (const auto& method : class_decl.methods) {
+            // `= delete` members do not exist in the translated type, and
+            // destructors become Drop
+            if (method.is_deleted || method.is_destructor) {
+                continue;
+            }
             generateFunction(method, true);  // true = is_method
             writeLine("");
         }
@@ -95,6 +142,10 @@ void RustCodeGenerator::generateClass(const ClassDecl& class_decl) {
 }
 
 void RustCodeGenerator::generateFunction(const Function& func, bool is_method) {
+    for (const auto& line : rustDocLines(func.doc_comment)) {
+        writeLine(line);
+    }
+
     // If function is async or coroutine, use async generation
     if (func.is_async || func.coroutine_info.is_coroutine) {
         generateAsyncFunction(func);
@@ -306,6 +357,10 @@ std::string RustCodeGenerator::convertType(const std::shared_ptr<Type>& type) {
             }
 
         case TypeKind::Array:
+            // `T[]` (e.g. std::unique_ptr<int[]>) or an extent the parser did not size
+            if (type->size_bytes == 0 || !type->element_type || type->element_type->size_bytes == 0) {
+                return "[" + convertType(type->element_type) + "]";
+            }
             return "[" + convertType(type->element_type) + "; " +
                    std::to_string(type->size_bytes / type->element_type->size_bytes) + "]";
 
@@ -482,6 +537,10 @@ std::string RustCodeGenerator::sanitizeName(const std::string& name) {
     for (size_t i = 0; i < name.size(); ++i) {
         char c = name[i];
 
+        // Nested classes (Outer::Inner) flatten to one identifier
+        if (c == ':' && name.compare(0, 5, "std::") != 0) {
+            continue;
+        }
         if (std::isupper(c) && i > 0 && !prev_upper) {
             result += '_';
             result += std::tolower(c);
@@ -0,0 +1,179 @@
+/**
+ * @file abi.cpp
+ * @brief Struct return conventions of the targets the bindings support
+ *
+ * A struct one target returns in memory crosses the boundary through an
+ * explicit out-pointer on every target, so the Rust side and the shim
+ * never disagree about where a large result lands.
+ */
+
+#include "ffi.h"
+#include <algorithm>
+#include <regex>
+#include <unordered_map>
+
+namespace hybrid_transpiler {
+namespace ffi {
+
+namespace {
+
+/**
+ * @brief Field type without cv-qualifiers
+ */
+std::string unqualified(const FFIParameter& field) {
+    static const std::regex qualifiers(R"(\b(const|volatile)\b\s*)");
+    std::string type = std::regex_replace(field.cpp_type, qualifiers, "");
+    size_t end = type.find_last_not_of(' ');
+    return end == std::string::npos ? "" : type.substr(0, end + 1);
+}
+
+/**
+ * @brief Bytes of pointers and of `long`: LLP64 on Windows, LP64 elsewhere
+ */
+struct DataModel {
+    size_t pointer;
+    size_t long_int;
+};
+
+DataModel dataModel(AbiTarget target) {
+    return target == AbiTarget::Win64 ? DataModel{8, 4} : DataModel{8, 8};
+}
+
+/**
+ * @brief Whether a field sits at an offset its natural alignment does not
+ *        divide, as `#pragma pack` allows
+ */
+bool hasUnalignedField(const FFIClass& cls, AbiTarget target) {
+    size_t offset = 0;
+    for (const auto& field : cls.fields) {
+        size_t size = scalarFieldSize(field, target);
+        size_t align = cls.pack == 0 ? size : std::min(size, cls.pack);
+        offset = (offset + align - 1) / align * align;
+        if (offset % size != 0) {
+            return true;
+        }
+        offset += size;
+    }
+    return false;
+}
+
+/**
+ * @brief AAPCS64 homogeneous floating-point aggregate: one to four
+ *        members, all `float` or all `double`
+ */
+bool isFloatAggregate(const FFIClass& cls) {
+    if (cls.fields.empty() || cls.fields.size() > 4) {
+        return false;
+    }
+    std::string first = unqualified(cls.fields.front());
+    return (first == "float" || first == "double") &&
+           std::all_of(cls.fields.begin(), cls.fields.end(), [&](const FFIParameter& field) {
+               return !field.is_pointer && !field.is_reference && unqualified(field) == first;
+           });
+}
+
+} // namespace
+
+size_t scalarFieldSize(const FFIParameter& field, AbiTarget target) {
+    static const std::unordered_map<std::string, size_t> fixed_sizes = {
+        {"bool", 1}, {"char", 1}, {"unsigned char", 1}, {"int8_t", 1}, {"uint8_t", 1},
+        {"short", 2}, {"unsigned short", 2}, {"int16_t", 2}, {"uint16_t", 2},
+        {"int", 4}, {"unsigned int", 4}, {"int32_t", 4}, {"uint32_t", 4}, {"float", 4},
+        {"long long", 8}, {"unsigned long long", 8}, {"int64_t", 8}, {"uint64_t", 8}, {"double", 8},
+    };
+
+    DataModel model = dataModel(target);
+    std::string type = unqualified(field);
+    if (field.is_pointer || field.is_reference || type == "size_t") {
+        return model.pointer;
+    }
+    if (type == "long" || type == "unsigned long") {
+        return model.long_int;
+    }
+    auto it = fixed_sizes.find(type);
+    return it == fixed_sizes.end() ? 0 : it->second;
+}
+
+StructLayout structLayout(const FFIClass& cls, AbiTarget target) {
+    size_t offset = 0;
+    size_t max_align = 1;
+    for (const auto& field : cls.fields) {
+        size_t size = scalarFieldSize(field, target);
+        if (size == 0) {
+            return {};
+        }
+
+        // Packing caps each field's alignment, never raises it
+        size_t align = cls.pack == 0 ? size : std::min(size, cls.pack);
+        offset = (offset + align - 1) / align * align;
+        offset += size;
+        max_align = std::max(max_align, align);
+    }
+    if (cls.fields.empty()) {
+        return {};
+    }
+    return {(offset + max_align - 1) / max_align * max_align, max_align};
+}
+
+ReturnConvention classifyReturn(const FFIClass& cls, AbiTarget target) {
+    size_t size = structLayout(cls, target).size;
+    if (size == 0) {
+        return ReturnConvention::Memory;
+    }
+    switch (target) {
+        case AbiTarget::SysV64:
+            // Two eightbytes at most, and each field must be aligned to be classified
+            return size <= 16 && !hasUnalignedField(cls, target) ? ReturnConvention::Registers
+                                                                 : ReturnConvention::Memory;
+        case AbiTarget::Win64:
+            return size == 1 || size == 2 || size == 4 || size == 8 ? ReturnConvention::Registers
+                                                                   : ReturnConvention::Memory;
+        case AbiTarget::AArch64:
+            // Aggregates of up to four floats come back in v0-v3, whatever their size
+            return size <= 16 || isFloatAggregate(cls) ? ReturnConvention::Registers
+                                                       : ReturnConvention::Memory;
+    }
+    return ReturnConvention::Memory;
+}
+
+std::set<std::string> memoryReturnedStructs(const std::vector<FFIClass>& classes,
+                                            const std::vector<AbiTarget>& targets) {
+    std::set<std::string> structs;
+    for (const auto& cls : classes) {
+        if (isReprC(cls) && std::any_of(targets.begin(), targets.end(), [&](AbiTarget target) {
+                return classifyReturn(cls, target) == ReturnConvention::Memory;
+            })) {
+            structs.insert(cls.name);
+        }
+    }
+    return structs;
+}
+
+std::string outPointerReturn(const FFIFunction& func, const std::set<std::string>& memory_structs) {
+    std::string type = func.return_type;
+    type.erase(0, type.find_first_not_of(' '));
+    if (type.compare(0, 6, "const ") == 0) {
+        type.erase(0, 6);
+    }
+    type.erase(type.find_last_not_of(' ') + 1);
+    return memory_structs.count(type) ? type : "";
+}
+
+std::string outPointerName(const FFIFunction& func) {
+    auto taken = [&](const std::string& name) {
+        for (size_t i = 0; i < func.parameters.size(); ++i) {
+            if (paramName(func.parameters[i], i) == name) {
+                return true;
+            }
+        }
+        return false;
+    };
+    std::string name = "out";
+    while (taken(name)) {
+        name += "_";
+    }
+    return name;
+}
+
+} // namespace ffi
+} // namespace hybrid_transpiler
@@ -0,0 +1,516 @@
+/**
+ * @file c_wrapper_gen.cpp
+ * @brief C++ shim source generator implementation
+ *
+ * Emits the `extern "C"` functions the Rust bindings link against:
+ * constructors, destructors and methods of wrapped classes become free
+ * functions taking the object as `void* self`.
+ */
+
+#include "ffi.h"
+#include <algorithm>
+#include <cctype>
+#include <sstream>
+#include <stdexcept>
+
+namespace hybrid_transpiler {
+namespace ffi {
+
+namespace {
+
+std::string trim(const std::string& str) {
+    size_t start = str.find_first_not_of(" \t\n\r");
+    if (start == std::string::npos) {
+        return "";
+    }
+    size_t end = str.find_last_not_of(" \t\n\r");
+    return str.substr(start, end - start + 1);
+}
+
+/**
+ * @brief A C++ type split into qualifiers and the named type
+ */
+struct TypeShape {
+    bool is_const = false;
+    bool is_pointer = false;
+    bool is_reference = false;
+    std::string base;
+};
+
+TypeShape shapeOf(const std::string& cpp_type) {
+    TypeShape shape;
+    shape.base = trim(cpp_type);
+    if (shape.base.find("const ") == 0) {
+        shape.is_const = true;
+        shape.base = trim(shape.base.substr(6));
+    }
+    if (!shape.base.empty() && (shape.base.back() == '*' || shape.base.back() == '&')) {
+        shape.is_pointer = shape.base.back() == '*';
+        shape.is_reference = !shape.is_pointer;
+        shape.base = trim(shape.base.substr(0, shape.base.size() - 1));
+    }
+    return shape;
+}
+
+bool isVoid(const std::string& cpp_type) {
+    std::string t = trim(cpp_type);
+    return t.empty() || t == "void";
+}
+
+bool isLibraryType(const std::string& base) {
+    return base.find("std::") != std::string::npos || base.find('<') != std::string::npos;
+}
+
+bool usesParams(const std::vector<FFIFunction>& functions, bool (*matches)(const FFIParameter&)) {
+    for (const auto& func : functions) {
+        for (const auto& param : func.parameters) {
+            if (matches(param)) {
+                return true;
+            }
+        }
+    }
+    return false;
+}
+
+bool isSpan(const FFIParameter& param) {
+    return !param.span_element.empty();
+}
+
+bool isDurationParam(const FFIParameter& param) {
+    return isDuration(param.cpp_type);
+}
+
+bool usesDurations(const std::vector<FFIFunction>& functions) {
+    return usesParams(functions, isDurationParam) ||
+           std::any_of(functions.begin(), functions.end(),
+                       [](const FFIFunction& func) { return isDuration(func.return_type); });
+}
+
+bool isStdString(const FFIParameter& param) {
+    return isStringParam(param) && param.cpp_type.find("std::string") != std::string::npos;
+}
+
+} // namespace
+
+bool needsShim(const FFIFunction& func, const std::set<std::string>& opaque_classes,
+               const std::set<std::string>& memory_structs) {
+    // A namespaced function has a C++ symbol even when its signature is
+    // plain, and a template instance has no symbol of its own name
+    bool needs_shim = !func.namespace_path.empty() || !func.template_of.empty();
+    for (const auto& param : func.parameters) {
+        TypeShape shape = shapeOf(param.cpp_type);
+        needs_shim = needs_shim || !param.span_element.empty() || shape.is_reference ||
+                     opaque_classes.count(shape.base) || shape.base == "std::string" ||
+                     shape.base == "std::string_view" || isDuration(param.cpp_type);
+    }
+    TypeShape ret = shapeOf(func.return_type);
+    return needs_shim || ret.is_reference || opaque_classes.count(ret.base) || isDuration(func.return_type) ||
+           !outPointerReturn(func, memory_structs).empty();
+}
+
+void CWrapperGenerator::collectOpaqueClasses(const std::vector<FFIClass>& classes) {
+    opaque_classes_.clear();
+    struct_types_.clear();
+    for (const auto& cls : classes) {
+        (isReprC(cls) ? struct_types_ : opaque_classes_).insert(cls.name);
+    }
+    memory_structs_ = memoryReturnedStructs(classes, options_.abi_targets);
+}
+
+std::string CWrapperGenerator::arrayFreeShim(const FFIFunction& func) const {
+    TypeShape ret = shapeOf(func.return_type);
+    if (!options_.free_function.empty() || !returnsCountedArray(func) || !struct_types_.count(ret.base) ||
+        !ownsReturnedPointer(func)) {
+        return "";
+    }
+    std::stringstream ss;
+    ss << "void " << shimName(func) << "_free(const " << ret.base << "* ptr) {\n";
+    ss << "    delete[] ptr;\n";
+    ss << "}\n";
+    return ss.str();
+}
+
+std::string CWrapperGenerator::cParameter(const FFIParameter& param, size_t index, std::string& arg,
+                                          std::string& reason, SkipCategory& category) const {
+    std::string name = paramName(param, index);
+
+    // The Rust side passes a slice as pointer + length
+    if (!param.span_element.empty()) {
+        std::string element = (param.span_const ? "const " : "") + param.span_element;
+        std::string extent = param.span_extent > 0 ? ", " + std::to_string(param.span_extent) : "";
+        arg = "std::span<" + element + extent + ">(" + name + ", " + name + "_len)";
+        return element + "* " + name + ", size_t " + name + "_len";
+    }
+
+    if (isStringViewParam(param)) {
+        arg = "std::string_view(" + name + ", " + name + "_len)";
+        return "const char* " + name + ", size_t " + name + "_len";
+    }
+
+    TypeShape shape = shapeOf(param.cpp_type);
+    if (isDuration(param.cpp_type)) {
+        arg = shape.base + "(" + name + ")";
+        return durationRep(param.cpp_type) + " " + name;
+    }
+
+    std::string qualifier = shape.is_const ? "const " : "";
+    if (shape.base == "std::string" && isStringParam(param)) {
+        arg = "std::string(" + name + ")";
+        return "const char* " + name;
+    }
+    if (opaque_classes_.count(shape.base)) {
+        if (!shape.is_pointer && !shape.is_reference) {
+            reason = "parameter `" + name + "` passes `" + shape.base + "` by value";
+            category = SkipCategory::ByValueClass;
+            return "";
+        }
+        std::string cast = "static_cast<" + qualifier + shape.base + "*>(" + name + ")";
+        arg = shape.is_reference ? "*" + cast : cast;
+        return qualifier + "void* " + name;
+    }
+    if (isLibraryType(shape.base)) {
+        reason = "parameter `" + name + "` has type `" + trim(param.cpp_type) + "`";
+        category = SkipCategory::StandardLibrary;
+        return "";
+    }
+
+    // References cross the boundary as pointers
+    if (shape.is_reference) {
+        arg = "*" + name;
+        return qualifier + shape.base + "* " + name;
+    }
+    arg = name;
+    return trim(param.cpp_type) + " " + name;
+}
+
+std::string CWrapperGenerator::generateFunctionWrapper(const FFIFunction& func) {
+    std::string name = shimSymbol(func);
+    std::string reason;
+    SkipCategory category = SkipCategory::Other;
+    std::string return_type = "void";
+    std::vector<std::string> params;
+    std::vector<std::string> args;
+
+    bool ctor = isConstructor(func);
+    bool dtor = isDestructor(func);
+    bool has_self = !func.class_name.empty() && !func.is_static && !ctor;
+    std::string self_qualifier = func.is_const ? "const " : "";
+    if (has_self) {
+        params.push_back(self_qualifier + "void* self");
+    }
+
+    for (size_t i = 0; i < func.parameters.size(); ++i) {
+        std::string arg;
+        std::string param = cParameter(func.parameters[i], i, arg, reason, category);
+        if (!reason.empty()) {
+            break;
+        }
+        params.push_back(param);
+        args.push_back(arg);
+    }
+
+    // Pointers and references to wrapped classes come back as void*
+    TypeShape ret = shapeOf(func.return_type);
+    bool address_of = false;
+    bool count_of = false;
+    std::string out_struct = outPointerReturn(func, memory_structs_);
+    bool three_way = func.name == "operator<=>";
+    if (ctor || three_way) {
+        return_type = ctor ? "void*" : "int32_t";
+    } else if (!isVoid(func.return_type) && reason.empty()) {
+        std::string qualifier = ret.is_const ? "const " : "";
+        if (isDuration(func.return_type)) {
+            // Returned as the tick count
+            return_type = durationRep(func.return_type);
+            count_of = true;
+        } else if (opaque_classes_.count(ret.base)) {
+            if (!ret.is_pointer && !ret.is_reference) {
+                reason = "returns `" + ret.base + "` by value";
+                category = SkipCategory::ByValueClass;
+            }
+            return_type = qualifier + "void*";
+        } else if (isLibraryType(ret.base)) {
+            reason = "returns `" + trim(func.return_type) + "`";
+            category = SkipCategory::StandardLibrary;
+        } else if (ret.is_reference) {
+            return_type = qualifier + ret.base + "*";
+        } else if (!out_struct.empty()) {
+            // Written into the caller's storage rather than returned
+            params.push_back(out_struct + "* " + outPointerName(func));
+        } else {
+            return_type = trim(func.return_type);
+        }
+        address_of = ret.is_reference;
+    }
+
+    std::string label = func.class_name.empty() ? func.name : func.class_name + "::" + func.name;
+    if (!reason.empty()) {
+        if (options_.strict && !options_.allowed_skips.count(label)) {
+            throw std::runtime_error("shim for " + label + " not generated: " + reason +
+                                     " (acknowledge it with --allow-skip)");
+        }
+        diagnostics_.push_back({label, reason, category});
+        return "// Not generated: " + label + " " + reason + ", write `" + name + "` by hand\n";
+    }
+
+    std::string arg_list;
+    for (const auto& arg : args) {
+        arg_list += (arg_list.empty() ? "" : ", ") + arg;
+    }
+
+    std::string call;
+    if (!func.static_field.empty()) {
+        call = func.class_name + "::" + func.static_field + (args.empty() ? "" : " = " + arg_list);
+    } else if (ctor) {
+        call = "new " + func.class_name + "(" + arg_list + ")";
+    } else if (dtor) {
+        call = "delete static_cast<" + func.class_name + "*>(self)";
+    } else if (has_self) {
+        call = "static_cast<" + self_qualifier + func.class_name + "*>(self)->" + func.name + "(" + arg_list + ")";
+    } else if (!func.class_name.empty()) {
+        call = func.class_name + "::" + func.name + "(" + arg_list + ")";
+    } else {
+        // Qualified in full, so a versioned `inline namespace` picks its own symbol
+        std::string scope = func.namespace_path.empty() ? "" : func.namespace_path + "::";
+        std::string callee = func.template_of.empty() ? func.name : func.template_of + "<" + func.template_args + ">";
+        call = "::" + scope + callee + "(" + arg_list + ")";
+    }
+
+    std::stringstream ss;
+    ss << return_type << " " << name << "(";
+    for (size_t i = 0; i < params.size(); ++i) {
+        ss << (i > 0 ? ", " : "") << params[i];
+    }
+    ss << ") {\n";
+    if (three_way) {
+        // Any ordering category compares against 0; unordered fails all three
+        ss << "    auto order = " << call << ";\n";
+        ss << "    return order < 0 ? -1 : order > 0 ? 1 : order == 0 ? 0 : 2;\n";
+    } else if (!out_struct.empty()) {
+        ss << "    new (" << outPointerName(func) << ") " << out_struct << "(" << call << ");\n";
+    } else if (return_type == "void") {
+        ss << "    " << call << ";\n";
+    } else {
+        ss << "    return " << (address_of ? "&" : "") << call << (count_of ? ".count()" : "") << ";\n";
+    }
+    ss << "}\n";
+    return ss.str();
+}
+
+std::string CWrapperGenerator::generateClassWrapper(const FFIClass& cls) {
+    std::stringstream ss;
+    ss << "// " << cls.name << "\n";
+    for (const auto* group : {&cls.methods, &cls.static_methods}) {
+        for (const auto& method : *group) {
+            if (!isDestructor(method)) {
+                ss << "\n" << generateFunctionWrapper(method);
+            }
+            std::string free_shim = arrayFreeShim(method);
+            if (!free_shim.empty()) {
+                ss << "\n" << free_shim;
+            }
+        }
+    }
+
+    // Refcounted objects free themselves when the last reference goes
+    if (!options_.refcounted.count(cls.name)) {
+        FFIFunction dtor{};
+        dtor.name = "~" + cls.name;
+        dtor.class_name = cls.name;
+        dtor.is_method = true;
+        ss << "\n" << generateFunctionWrapper(dtor);
+    }
+    return ss.str();
+}
+
+std::string CWrapperGenerator::generateHeader(
+    const std::vector<FFIFunction>& functions,
+    const std::vector<FFIClass>& classes,
+    const std::string& library_name
+) {
+    std::string implementation = generateImplementation(functions, classes, library_name);
+
+    std::string guard = library_name + "_shims_h";
+    for (char& c : guard) {
+        c = std::isalnum(static_cast<unsigned char>(c)) ? static_cast<char>(std::toupper(c)) : '_';
+    }
+
+    std::stringstream ss;
+    ss << "// Auto-generated C declarations for the " << library_name << " shims\n";
+    ss << "// Generated by Hybrid Transpiler\n\n";
+    ss << "#ifndef " << guard << "\n";
+    ss << "#define " << guard << "\n\n";
+    ss << "#include <stddef.h>\n";
+    ss << "#include <stdint.h>\n\n";
+    ss << "#ifdef __cplusplus\n";
+    ss << "extern \"C\" {\n";
+    ss << "#endif\n\n";
+
+    // Every shim definition starts on its own line with the signature
+    std::istringstream lines(implementation);
+    for (std::string line; std::getline(lines, line);) {
+        if (!line.empty() && line.back() == '{' && line.find('(') != std::string::npos) {
+            std::string declaration = line.substr(0, line.size() - 2);
+            size_t empty = declaration.find("()");
+            if (empty != std::string::npos) {
+                declaration.replace(empty, 2, "(void)");
+            }
+            ss << declaration << ";\n";
+        }
+    }
+
+    ss << "\n#ifdef __cplusplus\n";
+    ss << "}\n";
+    ss << "#endif\n\n";
+    ss << "#endif // " << guard << "\n";
+    return ss.str();
+}
+
+std::string CWrapperGenerator::generateImplementation(
+    const std::vector<FFIFunction>& functions,
+    const std::vector<FFIClass>& input_classes,
+    const std::string& library_name,
+    const std::vector<FFIEnum>& enums
+) {
+    std::vector<FFIClass> classes = prepareShimClasses(input_classes, options_);
+    collectOpaqueClasses(classes);
+    diagnostics_.clear();
+
+    // Shims already written in the input are not generated again
+    std::set<std::string> written;
+    for (const auto& func : functions) {
+        written.insert(shimName(func));
+    }
+
+    std::vector<FFIFunction> shimmed;
+    std::set<std::string> class_shims;
+    for (const auto& cls : classes) {
+        for (const auto* group : {&cls.methods, &cls.static_methods}) {
+            shimmed.insert(shimmed.end(), group->begin(), group->end());
+            for (const auto& method : *group) {
+                class_shims.insert(shimName(method));
+            }
+        }
+        if (!isReprC(cls) && !options_.refcounted.count(cls.name)) {
+            class_shims.insert(toSnakeCase(cls.name) + "_delete");
+        }
+    }
+
+    std::stringstream ss;
+    ss << "// Auto-generated C++ shims for " << library_name << "\n";
+    ss << "// Generated by Hybrid Transpiler\n";
+    ss << "//\n";
+    ss << "// Exports the functions declared in the Rust bindings' extern block.\n";
+    ss << "// Compile this file into lib" << library_name << " next to the C++ code.\n\n";
+    // A preprocessed input names the headers its declarations came from
+    std::vector<std::string> headers;
+    auto attribute = [&](const std::string& file) {
+        if (!file.empty() && std::find(headers.begin(), headers.end(), file) == headers.end()) {
+            headers.push_back(file);
+        }
+    };
+    for (const auto& func : functions) {
+        attribute(func.source_file);
+    }
+    for (const auto& cls : classes) {
+        attribute(cls.source_file);
+    }
+    for (const auto& enum_decl : enums) {
+        attribute(enum_decl.source_file);
+    }
+    if (headers.empty()) {
+        headers.push_back(library_name + ".h");
+    }
+    for (const auto& header : headers) {
+        ss << "#include \"" << header << "\"\n";
+    }
+    ss << "#include <cstddef>\n";
+    ss << "#include <cstdint>\n";
+    if (usesParams(functions, isSpan) || usesParams(shimmed, isSpan)) {
+        ss << "#include <span>\n";
+    }
+    if (usesParams(functions, isStdString) || usesParams(shimmed, isStdString)) {
+        ss << "#include <string>\n";
+    }
+    if (usesDurations(functions) || usesDurations(shimmed)) {
+        ss << "#include <chrono>\n";
+    }
+    if (usesParams(functions, isStringViewParam) || usesParams(shimmed, isStringViewParam)) {
+        ss << "#include <string_view>\n";
+    }
+    auto returns_out = [&](const FFIFunction& func) { return !outPointerReturn(func, memory_structs_).empty(); };
+    if (std::any_of(functions.begin(), functions.end(), returns_out) ||
+        std::any_of(shimmed.begin(), shimmed.end(), returns_out)) {
+        ss << "#include <new>\n";
+    }
+    ss << "\n";
+
+    // Shims spell types unqualified, as the Rust bindings name them
+    std::vector<std::string> usings;
+    for (const auto& cls : classes) {
+        if (!cls.namespace_path.empty() && cls.name.find("::") == std::string::npos) {
+            usings.push_back(cls.namespace_path + "::" + cls.name);
+        }
+    }
+    for (const auto& enum_decl : enums) {
+        if (!enum_decl.namespace_path.empty() && !enum_decl.name.empty()) {
+            usings.push_back(enum_decl.namespace_path + "::" + enum_decl.name);
+        }
+    }
+    for (const auto& name : usings) {
+        ss << "using ::" << name << ";\n";
+    }
+    if (!usings.empty()) {
+        ss << "\n";
+    }
+    ss << "extern \"C\" {\n";
+
+    for (const auto& cls : classes) {
+        if (isReprC(cls)) {
+            continue;
+        }
+
+        bool hand_written = false;
+        for (const auto* group : {&cls.methods, &cls.static_methods}) {
+            for (const auto& method : *group) {
+                hand_written = hand_written || written.count(shimName(method));
+            }
+        }
+        if (hand_written) {
+            ss << "\n// " << cls.name << ": shims already defined in the source\n";
+            continue;
+        }
+        ss << "\n" << generateClassWrapper(cls);
+    }
+
+    // Free functions only need a shim when their C signature differs,
+    // otherwise the bindings link the C++ function by its mangled name
+    NameMangler mangler(input_classes, enums);
+    std::vector<std::string> direct;
+    for (const auto& func : functions) {
+        if (!func.can_use_ffi || class_shims.count(shimName(func))) {
+            continue;
+        }
+        if (needsShim(func, opaque_classes_, memory_structs_)) {
+            ss << "\n" << generateFunctionWrapper(func);
+        } else if (!func.has_c_linkage && !mangler.linksByMangledName(func)) {
+            direct.push_back(func.name);
+        }
+        std::string free_shim = arrayFreeShim(func);
+        if (!free_shim.empty()) {
+            ss << "\n" << free_shim;
+        }
+    }
+    if (!direct.empty()) {
+        ss << "\n// Bound directly, declare these extern \"C\" in the library:\n";
+        for (const auto& name : direct) {
+            ss << "// - " << name << "\n";
+        }
+    }
+
+    ss << "\n} // extern \"C\"\n";
+    return ss.str();
+}
+
+} // namespace ffi
+} // namespace hybrid_transpiler
@@ -5,11 +5,114 @@
 
 #include "ffi.h"
 #include <algorithm>
+#include <cctype>
 #include <regex>
+#include <set>
 
 namespace hybrid_transpiler {
 namespace ffi {
 
+namespace {
+
+/**
+ * @brief Spell an IR type back as the C++ type it was parsed from
+ */
+std::string cppTypeName(const std::shared_ptr<hybrid::Type>& type) {
+    if (!type) {
+        return "void";
+    }
+    std::string name = type->name;
+    if (type->is_const && name.find("const") != 0) {
+        name = "const " + name;
+    }
+    return name;
+}
+
+/**
+ * @brief Who frees a returned pointer per a `// @owns_return` or
+ *        `// @borrows_return` annotation, else per the verb the name starts
+ *        with (`create_widget`, `findChild`); Inferred when neither says
+ */
+PointerOwnership returnOwnership(const hybrid::Function& function) {
+    for (const auto& tag : function.annotations) {
+        if (tag == "owns_return") {
+            return PointerOwnership::Owned;
+        }
+        if (tag == "borrows_return") {
+            return PointerOwnership::Borrowed;
+        }
+    }
+
+    static const std::set<std::string> owning = {"create", "new", "make", "alloc", "allocate", "dup", "clone", "copy"};
+    static const std::set<std::string> borrowing = {"get", "find", "lookup", "peek"};
+    std::string verb;
+    for (char c : function.name) {
+        if (c == '_' || (!verb.empty() && std::isupper(static_cast<unsigned char>(c)))) {
+            break;
+        }
+        verb += static_cast<char>(std::tolower(static_cast<unsigned char>(c)));
+    }
+    if (owning.count(verb)) {
+        return PointerOwnership::Owned;
+    }
+    return borrowing.count(verb) ? PointerOwnership::Borrowed : PointerOwnership::Inferred;
+}
+
+/**
+ * @brief Fixed-width and size typedefs of `<cstdint>` and `<cstddef>`,
+ *        mapped by name rather than by what a header declares them as
+ */
+const std::set<std::string>& cstdintTypedefs() {
+    static const std::set<std::string> names = {
+        "int8_t", "int16_t", "int32_t", "int64_t", "uint8_t", "uint16_t", "uint32_t", "uint64_t",
+        "intptr_t", "uintptr_t", "intmax_t", "uintmax_t", "size_t", "ptrdiff_t",
+    };
+    return names;
+}
+
+const std::regex& volatilePattern() {
+    static const std::regex pattern(R"(\bvolatile\b\s*)");
+    return pattern;
+}
+
+/**
+ * @brief Drop `volatile` qualifiers, which have no Rust or cgo spelling
+ */
+std::string removeVolatile(const std::string& cpp_type) {
+    std::string result = std::regex_replace(cpp_type, volatilePattern(), "");
+    size_t end = result.find_last_not_of(' ');
+    return end == std::string::npos ? "" : result.substr(0, end + 1);
+}
+
+bool isVolatile(const std::string& cpp_type) {
+    return std::regex_search(cpp_type, volatilePattern());
+}
+
+FFIParameter makeParameter(const std::string& name, const std::string& cpp_type) {
+    FFIParameter param{};
+    param.name = name;
+    param.cpp_type = cpp_type;
+    param.is_pointer = cpp_type.find('*') != std::string::npos;
+    param.is_reference = cpp_type.find('&') != std::string::npos;
+    param.is_const = cpp_type.find("const") == 0;
+    param.is_volatile = isVolatile(cpp_type);
+
+    // std::span<[const] T[, N]> is passed to the shim as pointer + length
+    static const std::regex span_pattern(
+        R"(^(?:const\s+)?std::span<\s*(const\s+)?([^,>]+?)\s*(?:,\s*([\w:]+)\s*)?>\s*&?$)");
+    std::smatch match;
+    if (std::regex_match(cpp_type, match, span_pattern)) {
+        param.span_element = match[2].str();
+        param.span_const = match[1].matched;
+        std::string extent = match[3].str();
+        bool fixed = !extent.empty() && std::all_of(extent.begin(), extent.end(), ::isdigit);
+        param.span_extent = fixed ? std::stoul(extent) : 0;
+    }
+    return param;
+}
+
+} // namespace
+
 void FFIAnalyzer::initializeTypeMappings() {
     // C++ to C type mappings
     cpp_to_c_types_ = {
@@ -36,6 +139,11 @@ void FFIAnalyzer::initializeTypeMappings() {
         {"uint32_t", "uint32_t"},
         {"uint64_t", "uint64_t"},
         {"size_t", "size_t"},
+        {"ptrdiff_t", "ptrdiff_t"},
+        {"intptr_t", "intptr_t"},
+        {"uintptr_t", "uintptr_t"},
+        {"intmax_t", "intmax_t"},
+        {"uintmax_t", "uintmax_t"},
         {"const char*", "const char*"},
         {"char*", "char*"},
     };
@@ -50,8 +158,9 @@ void FFIAnalyzer::initializeTypeMappings() {
         {"unsigned short", "u16"},
         {"int", "i32"},
         {"unsigned int", "u32"},
-        {"long", "i64"},
-        {"unsigned long", "u64"},
+        // 32 bits on Windows, 64 on other 64-bit targets
+        {"long", "std::ffi::c_long"},
+        {"unsigned long", "std::ffi::c_ulong"},
         {"long long", "i64"},
         {"unsigned long long", "u64"},
         {"float", "f32"},
@@ -65,6 +174,11 @@ void FFIAnalyzer::initializeTypeMappings() {
         {"uint32_t", "u32"},
         {"uint64_t", "u64"},
         {"size_t", "usize"},
+        {"ptrdiff_t", "isize"},
+        {"intptr_t", "isize"},
+        {"uintptr_t", "usize"},
+        {"intmax_t", "i64"},
+        {"uintmax_t", "u64"},
         {"const char*", "*const i8"},
         {"char*", "*mut i8"},
     };
@@ -94,6 +208,11 @@ void FFIAnalyzer::initializeTypeMappings() {
         {"uint32_t", "uint32"},
         {"uint64_t", "uint64"},
         {"size_t", "C.size_t"},
+        {"ptrdiff_t", "C.ptrdiff_t"},
+        {"intptr_t", "C.intptr_t"},
+        {"uintptr_t", "C.uintptr_t"},
+        {"intmax_t", "C.intmax_t"},
+        {"uintmax_t", "C.uintmax_t"},
         {"const char*", "*C.char"},
         {"char*", "*C.char"},
     };
@@ -110,18 +229,21 @@ FFIFunction FFIAnalyzer::analyzeFunction(const std::string& function_decl) {
     if (function_decl.find("throw") != std::string::npos) {
         func.can_use_ffi = false;
         func.reason = "Function may throw exceptions (not compatible with C ABI)";
+        func.skip_category = SkipCategory::Exceptions;
     }
 
     // Check for templates
     if (function_decl.find("template") != std::string::npos) {
         func.can_use_ffi = false;
         func.reason = "Template functions require monomorphization";
+        func.skip_category = SkipCategory::Templates;
     }
 
     // Check for non-POD types in signature
     if (function_decl.find("std::") != std::string::npos) {
         func.can_use_ffi = false;
         func.reason = "Uses C++ standard library types (not C ABI compatible)";
+        func.skip_category = SkipCategory::StandardLibrary;
     }
 
     return func;
@@ -138,6 +260,235 @@ FFIClass FFIAnalyzer::analyzeClass(const std::string& class_decl) {
     return cls;
 }
 
+FFIFunction FFIAnalyzer::analyzeFunction(const hybrid::Function& function, const std::string& class_name) {
+    return analyzeSignature(function, class_name, {});
+}
+
+FFIFunction FFIAnalyzer::instantiateFunction(const hybrid::Function& function, const std::string& args) {
+    // Split at top-level commas, `std::pair<int, int>` stays one argument
+    std::vector<std::string> values;
+    int depth = 0;
+    std::string current;
+    for (char c : args + ",") {
+        depth += c == '<' ? 1 : c == '>' ? -1 : 0;
+        if (c == ',' && depth == 0) {
+            size_t start = current.find_first_not_of(' ');
+            size_t end = current.find_last_not_of(' ');
+            values.push_back(start == std::string::npos ? "" : current.substr(start, end - start + 1));
+            current.clear();
+        } else {
+            current += c;
+        }
+    }
+
+    std::map<std::string, std::string> bindings;
+    for (size_t i = 0; i < values.size() && i < function.template_parameters.size(); ++i) {
+        bindings[function.template_parameters[i].name] = values[i];
+    }
+    FFIFunction func = analyzeSignature(function, "", bindings);
+
+    std::string suffix;
+    std::string joined;
+    for (const auto& value : values) {
+        std::string part = std::regex_replace(std::regex_replace(value, std::regex(R"(\bstd::)"), ""),
+                                              std::regex(R"([^A-Za-z0-9]+)"), "_");
+        part = std::regex_replace(part, std::regex("^_+|_+$"), "");
+        suffix += "_" + part;
+        joined += (joined.empty() ? "" : ", ") + value;
+    }
+    func.name = function.name + suffix;
+    func.template_of = function.name;
+    func.template_args = joined;
+    func.generic_return_type = cppTypeName(function.return_type);
+    for (size_t i = 0; i < func.parameters.size(); ++i) {
+        func.parameters[i].generic_type = cppTypeName(function.parameters[i].type);
+    }
+    if (values.size() != function.template_parameters.size()) {
+        func.can_use_ffi = false;
+        func.reason = "Instantiation <" + joined + "> needs " + std::to_string(function.template_parameters.size()) +
+                      " template arguments";
+        func.skip_category = SkipCategory::Templates;
+    }
+    return func;
+}
+
+FFIFunction FFIAnalyzer::analyzeSignature(const hybrid::Function& function, const std::string& class_name,
+                                          const std::map<std::string, std::string>& bindings) {
+    auto typeName = [&](const std::shared_ptr<hybrid::Type>& type) {
+        std::string name = canonicalType(cppTypeName(type));
+        for (const auto& binding : bindings) {
+            name = std::regex_replace(name, std::regex("\\b" + binding.first + "\\b"), binding.second);
+        }
+        return name;
+    };
+
+    FFIFunction func{};
+    func.name = function.name;
+    func.class_name = class_name;
+    func.namespace_path = function.namespace_path;
+    func.source_file = function.source_file;
+    func.is_deprecated = function.is_deprecated;
+    func.deprecation_note = function.deprecation_message;
+    func.doc_comment = function.doc_comment;
+    func.has_c_linkage = function.has_c_linkage;
+    func.is_method = !class_name.empty();
+    func.is_static = function.is_static;
+    func.is_const = function.is_const;
+    func.is_virtual = function.is_virtual;
+    func.can_use_ffi = true;
+
+    if (!function.is_constructor) {
+        func.return_type = typeName(function.return_type);
+        func.c_return_type = toCType(func.return_type);
+        if (func.return_type.find('*') != std::string::npos) {
+            func.return_ownership = returnOwnership(function);
+        }
+    }
+
+    bool uses_std = func.return_type.find("std::") != std::string::npos && !isDuration(func.return_type);
+    for (const auto& param : function.parameters) {
+        FFIParameter ffi_param = makeParameter(param.name, typeName(param.type));
+        ffi_param.c_type = toCType(ffi_param.cpp_type);
+        ffi_param.default_value = param.has_default ? param.default_value : "";
+        bool is_span = !ffi_param.span_element.empty() && ffi_param.span_element.find("std::") == std::string::npos;
+        // Strings are passed as NUL-terminated copies or pointer + length views
+        bool is_string = isStringParam(ffi_param) || isStringViewParam(ffi_param);
+        // Durations cross as their tick count
+        bool is_count = isDuration(ffi_param.cpp_type);
+        uses_std = uses_std ||
+                   (!is_span && !is_string && !is_count && ffi_param.cpp_type.find("std::") != std::string::npos);
+        func.parameters.push_back(ffi_param);
+    }
+
+    if (function.is_template && bindings.empty()) {
+        func.can_use_ffi = false;
+        func.reason = "Template functions require monomorphization";
+        func.skip_category = SkipCategory::Templates;
+    } else if (uses_std) {
+        func.can_use_ffi = false;
+        func.reason = "Uses C++ standard library types (not C ABI compatible)";
+        func.skip_category = SkipCategory::StandardLibrary;
+    }
+
+    return func;
+}
+
+FFIClass FFIAnalyzer::analyzeClass(const hybrid::ClassDecl& class_decl) {
+    FFIClass cls{};
+    cls.name = class_decl.name;
+    cls.namespace_path = class_decl.namespace_path;
+    cls.source_file = class_decl.source_file;
+    cls.is_deprecated = class_decl.is_deprecated;
+    cls.deprecation_note = class_decl.deprecation_message;
+    cls.doc_comment = class_decl.doc_comment;
+    cls.base_classes = class_decl.base_classes;
+    cls.is_template = class_decl.is_template;
+    cls.is_struct = class_decl.is_struct;
+    cls.pack = class_decl.pack;
+
+    bool has_constructor = false;
+    bool has_public_constructor = false;
+    for (const auto& method : class_decl.methods) {
+        // Every opaque wrapper frees through its `<class>_delete` shim
+        if (method.is_destructor) {
+            cls.has_virtual_functions = cls.has_virtual_functions || method.is_virtual;
+            continue;
+        }
+        bool reachable = method.access == hybrid::Function::Access::Public && !method.is_deleted;
+        if (method.is_constructor) {
+            has_constructor = true;
+            has_public_constructor = has_public_constructor || reachable;
+        }
+
+        // Shims can only call what is publicly reachable; a class whose
+        // constructors are all hidden is built through its static factories
+        if (!reachable) {
+            continue;
+        }
+
+        FFIFunction func = analyzeFunction(method, class_decl.name);
+        if (method.is_static) {
+            cls.static_methods.push_back(func);
+        } else {
+            cls.methods.push_back(func);
+        }
+
+        cls.has_virtual_functions = cls.has_virtual_functions || method.is_virtual;
+        cls.is_abstract = cls.is_abstract || method.is_pure_virtual;
+    }
+    cls.is_polymorphic = cls.has_virtual_functions;
+    cls.factory_only = has_constructor && !has_public_constructor;
+
+    for (const auto& field : class_decl.fields) {
+        if (field.is_static) {
+            addStaticFieldAccessors(class_decl, field, cls);
+            continue;
+        }
+        FFIParameter ffi_field = makeParameter(field.name, canonicalType(cppTypeName(field.type)));
+        ffi_field.c_type = toCType(ffi_field.cpp_type);
+        cls.fields.push_back(ffi_field);
+    }
+
+    return cls;
+}
+
+void FFIAnalyzer::addStaticFieldAccessors(const hybrid::ClassDecl& class_decl, const hybrid::Variable& field,
+                                          FFIClass& cls) {
+    for (const auto& section : class_decl.access_sections) {
+        bool listed = std::find(section.members.begin(), section.members.end(), field.name) != section.members.end();
+        if (listed && section.level != hybrid::ClassDecl::AccessSection::Public) {
+            return;
+        }
+    }
+
+    // Storing a pointer from Rust would outlive the value it points to
+    bool by_value = field.type && field.type->kind != hybrid::TypeKind::Pointer &&
+                    field.type->kind != hybrid::TypeKind::Reference;
+
+    // The class owns the variable, so a returned pointer is only borrowed
+    hybrid::Function getter;
+    getter.name = field.name;
+    getter.return_type = field.type;
+    if (by_value && field.type->is_const) {
+        getter.return_type = std::make_shared<hybrid::Type>(*field.type);
+        getter.return_type->is_const = false;
+    }
+    getter.is_static = true;
+    FFIFunction get = analyzeFunction(getter, class_decl.name);
+    get.static_field = field.name;
+    get.return_ownership = PointerOwnership::Borrowed;
+    cls.static_methods.push_back(get);
+
+    if (!by_value || field.type->is_const) {
+        return;
+    }
+    hybrid::Function setter;
+    setter.name = "set_" + field.name;
+    setter.is_static = true;
+    hybrid::Parameter value;
+    value.name = "value";
+    value.type = field.type;
+    setter.parameters.push_back(value);
+    FFIFunction set = analyzeFunction(setter, class_decl.name);
+    set.static_field = field.name;
+    cls.static_methods.push_back(set);
+}
+
+FFIEnum FFIAnalyzer::analyzeEnum(const hybrid::EnumDecl& enum_decl) {
+    FFIEnum ffi_enum;
+    ffi_enum.name = enum_decl.name;
+    ffi_enum.namespace_path = enum_decl.namespace_path;
+    ffi_enum.source_file = enum_decl.source_file;
+    ffi_enum.is_deprecated = enum_decl.is_deprecated;
+    ffi_enum.deprecation_note = enum_decl.deprecation_message;
+    ffi_enum.doc_comment = enum_decl.doc_comment;
+    ffi_enum.underlying_type = enum_decl.underlying_type.empty() ? "int" : enum_decl.underlying_type;
+    for (const auto& enumerator : enum_decl.enumerators) {
+        ffi_enum.values.emplace_back(enumerator.name, enumerator.value);
+    }
+    return ffi_enum;
+}
+
 bool FFIAnalyzer::isFFICompatible(const std::string& cpp_type) {
     // Remove const, volatile, etc.
     std::string clean_type = cpp_type;
@@ -147,7 +498,48 @@ bool FFIAnalyzer::isFFICompatible(const std::string& cpp_type) {
     return cpp_to_c_types_.find(clean_type) != cpp_to_c_types_.end();
 }
 
+std::string FFIAnalyzer::canonicalType(const std::string& cpp_type) const {
+    static const std::regex word(R"((std::)?\b([A-Za-z_]\w*)\b)");
+
+    // Follows alias chains to a builtin, leaving anything else as written
+    auto resolve = [&](const std::string& name) {
+        std::string type = name;
+        for (int depth = 0; depth < 16 && !cstdintTypedefs().count(type); ++depth) {
+            auto alias = type_aliases_.find(type);
+            if (alias == type_aliases_.end()) {
+                break;
+            }
+            type = alias->second.compare(0, 5, "std::") == 0 ? alias->second.substr(5) : alias->second;
+        }
+        return cpp_to_rust_types_.count(type) ? type : name;
+    };
+
+    std::string result;
+    auto last = cpp_type.cbegin();
+    for (std::sregex_iterator it(cpp_type.begin(), cpp_type.end(), word), end; it != end; ++it) {
+        const auto& match = *it;
+        result.append(last, match[0].first);
+        last = match[0].second;
+        std::string name = match[2].str();
+        bool nested = match.position(0) > 0 && cpp_type[match.position(0) - 1] == ':';
+        if (cstdintTypedefs().count(name) && !nested) {
+            result += name;
+        } else if (!match[1].matched && !nested) {
+            result += resolve(name);
+        } else {
+            result += match.str(0);
+        }
+    }
+    result.append(last, cpp_type.cend());
+    return result;
+}
+
 std::string FFIAnalyzer::toCType(const std::string& cpp_type) {
+    // Keep the qualifier so the C shim does not drop volatile accesses
+    if (cpp_type.find("volatile ") == 0) {
+        return "volatile " + toCType(cpp_type.substr(9));
+    }
+
     auto it = cpp_to_c_types_.find(cpp_type);
     if (it != cpp_to_c_types_.end()) {
         return it->second;
@@ -163,24 +555,39 @@ std::string FFIAnalyzer::toCType(const std::string& cpp_type) {
 }
 
 std::string FFIAnalyzer::toRustFFIType(const std::string& cpp_type) {
-    auto it = cpp_to_rust_types_.find(cpp_type);
+    // Rust has no volatile types; wrappers use read_volatile/write_volatile
+    std::string type = removeVolatile(cpp_type);
+
+    auto it = cpp_to_rust_types_.find(type);
     if (it != cpp_to_rust_types_.end()) {
         return it->second;
     }
 
-    // Handle pointers
-    if (cpp_type.find("*") != std::string::npos) {
-        bool is_const = (cpp_type.find("const") != std::string::npos);
+    // Handle pointers, keeping primitive pointees typed
+    size_t star = type.rfind('*');
+    if (star != std::string::npos) {
+        bool is_const = (type.find("const") != std::string::npos);
         bool is_mut = !is_const;
 
-        return is_mut ? "*mut std::ffi::c_void" : "*const std::ffi::c_void";
+        std::string pointee = type.substr(0, star);
+        if (pointee.find("const ") == 0) {
+            pointee = pointee.substr(6);
+        }
+        pointee = pointee.substr(0, pointee.find_last_not_of(' ') + 1);
+
+        std::string target = "std::ffi::c_void";
+        auto inner = cpp_to_rust_types_.find(pointee);
+        if (inner != cpp_to_rust_types_.end() && inner->second != "()") {
+            target = inner->second;
+        }
+        return (is_mut ? "*mut " : "*const ") + target;
     }
 
     return "*mut std::ffi::c_void";  // Fallback
 }
 
 std::string FFIAnalyzer::toGoFFIType(const std::string& cpp_type) {
-    auto it = cpp_to_go_types_.find(cpp_type);
+    auto it = cpp_to_go_types_.find(removeVolatile(cpp_type));
     if (it != cpp_to_go_types_.end()) {
         return it->second;
     }
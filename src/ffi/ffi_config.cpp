@@ -0,0 +1,648 @@
+/**
+ * @file ffi_config.cpp
+ * @brief JSON configuration for FFI binding generation
+ *
+ * Example:
+ * @code
+ * {
+ *   "refcounted": {
+ *     "IUnknown": { "incref": "AddRef", "decref": "Release" }
+ *   },
+ *   "default_arguments": "overloads",
+ *   "string_params": "as_ref",
+ *   "struct_fields": "accessors",
+ *   "checked_narrowing": true,
+ *   "safety_comments": true,
+ *   "free_function": "lib_free",
+ *   "include": ["calc_.*", "Calculator"],
+ *   "exclude": [".*_internal"],
+ *   "allow_skips": ["parse_config", "Logger::format"],
+ *   "thread_safe": ["Registry"],
+ *   "builders": { "min_setters": 3, "setters": ["Config::set_.*"] },
+ *   "channel_callbacks": {
+ *     "subscribe": { "unregister": "unsubscribe", "capacity": 64 }
+ *   },
+ *   "error_enums": {
+ *     "ErrorCode": { "success": "Ok", "to_string": "error_message" }
+ *   },
+ *   "templates": {
+ *     "max": { "instances": ["int", "double"], "trait": "Maxable" }
+ *   },
+ *   "typedefs": { "DWORD": "uint32_t" },
+ *   "ownership": { "Registry::find": "borrowed", "make_widget": "owned" },
+ *   "path_params": ["path", "file", "dir"],
+ *   "derives": { "Buffer": ["Debug", "Clone"] },
+ *   "init": { "function": "lib_init", "shutdown": "lib_shutdown", "automatic": true },
+ *   "abi_targets": ["x86_64-sysv", "aarch64"],
+ *   "package": { "prefix": "/opt/geo", "version": "2.1.0", "description": "Geometry kernel" }
+ * }
+ * @endcode
+ */
+
+#include "ffi.h"
+#include <algorithm>
+#include <cctype>
+#include <regex>
+#include <sstream>
+#include <stdexcept>
+
+namespace hybrid_transpiler {
+namespace ffi {
+
+namespace {
+
+/**
+ * @brief Parsed JSON value, objects keep their keys in file order
+ */
+struct JsonValue {
+    enum class Kind { Null, Bool, Number, String, Array, Object };
+
+    Kind kind = Kind::Null;
+    bool boolean = false;
+    double number = 0;
+    std::string string;
+    std::vector<JsonValue> array;
+    std::vector<std::pair<std::string, JsonValue>> object;
+};
+
+/**
+ * @brief Minimal recursive-descent JSON reader
+ */
+class JsonReader {
+public:
+    explicit JsonReader(const std::string& text) : text_(text) {}
+
+    JsonValue parse() {
+        JsonValue value = parseValue();
+        skipWhitespace();
+        if (pos_ != text_.size()) {
+            fail("unexpected trailing characters");
+        }
+        return value;
+    }
+
+private:
+    const std::string& text_;
+    size_t pos_ = 0;
+
+    [[noreturn]] void fail(const std::string& message) const {
+        throw std::runtime_error("Invalid FFI config at offset " + std::to_string(pos_) + ": " + message);
+    }
+
+    void skipWhitespace() {
+        while (pos_ < text_.size() && std::isspace(static_cast<unsigned char>(text_[pos_]))) {
+            pos_++;
+        }
+    }
+
+    void expect(char c) {
+        skipWhitespace();
+        if (pos_ >= text_.size() || text_[pos_] != c) {
+            fail(std::string("expected '") + c + "'");
+        }
+        pos_++;
+    }
+
+    bool consume(const std::string& word) {
+        if (text_.compare(pos_, word.size(), word) == 0) {
+            pos_ += word.size();
+            return true;
+        }
+        return false;
+    }
+
+    JsonValue parseValue() {
+        skipWhitespace();
+        if (pos_ >= text_.size()) {
+            fail("unexpected end of input");
+        }
+
+        JsonValue value;
+        char c = text_[pos_];
+        if (c == '{') {
+            value.kind = JsonValue::Kind::Object;
+            pos_++;
+            skipWhitespace();
+            if (pos_ < text_.size() && text_[pos_] == '}') {
+                pos_++;
+                return value;
+            }
+            do {
+                skipWhitespace();
+                std::string key = parseString();
+                expect(':');
+                value.object.emplace_back(key, parseValue());
+                skipWhitespace();
+            } while (pos_ < text_.size() && text_[pos_] == ',' && ++pos_);
+            expect('}');
+        } else if (c == '[') {
+            value.kind = JsonValue::Kind::Array;
+            pos_++;
+            skipWhitespace();
+            if (pos_ < text_.size() && text_[pos_] == ']') {
+                pos_++;
+                return value;
+            }
+            do {
+                value.array.push_back(parseValue());
+                skipWhitespace();
+            } while (pos_ < text_.size() && text_[pos_] == ',' && ++pos_);
+            expect(']');
+        } else if (c == '"') {
+            value.kind = JsonValue::Kind::String;
+            value.string = parseString();
+        } else if (consume("true")) {
+            value.kind = JsonValue::Kind::Bool;
+            value.boolean = true;
+        } else if (consume("false")) {
+            value.kind = JsonValue::Kind::Bool;
+        } else if (consume("null")) {
+            value.kind = JsonValue::Kind::Null;
+        } else if (c == '-' || std::isdigit(static_cast<unsigned char>(c))) {
+            size_t used = 0;
+            value.kind = JsonValue::Kind::Number;
+            value.number = std::stod(text_.substr(pos_), &used);
+            pos_ += used;
+        } else {
+            fail(std::string("unexpected character '") + c + "'");
+        }
+        return value;
+    }
+
+    std::string parseString() {
+        if (pos_ >= text_.size() || text_[pos_] != '"') {
+            fail("expected a string");
+        }
+        pos_++;
+
+        std::string result;
+        while (pos_ < text_.size() && text_[pos_] != '"') {
+            char c = text_[pos_++];
+            if (c == '\\' && pos_ < text_.size()) {
+                char escaped = text_[pos_++];
+                switch (escaped) {
+                    case 'n': result += '\n'; break;
+                    case 't': result += '\t'; break;
+                    case 'u': fail("\\u escapes are not supported");
+                    default: result += escaped; break;
+                }
+            } else {
+                result += c;
+            }
+        }
+        if (pos_ >= text_.size()) {
+            fail("unterminated string");
+        }
+        pos_++;
+        return result;
+    }
+};
+
+const JsonValue& requireKind(const JsonValue& value, JsonValue::Kind kind, const std::string& what) {
+    if (value.kind != kind) {
+        throw std::runtime_error("Invalid FFI config: " + what);
+    }
+    return value;
+}
+
+std::string requireString(const JsonValue& value, const std::string& what) {
+    return requireKind(value, JsonValue::Kind::String, what + " must be a string").string;
+}
+
+void applyRefcounted(const JsonValue& section, FFIOptions& options) {
+    requireKind(section, JsonValue::Kind::Object, "\"refcounted\" must map class names to objects");
+    for (const auto& entry : section.object) {
+        requireKind(entry.second, JsonValue::Kind::Object, "\"refcounted." + entry.first + "\" must be an object");
+
+        RefCounting scheme;
+        for (const auto& field : entry.second.object) {
+            std::string what = "\"refcounted." + entry.first + "." + field.first + "\"";
+            if (field.first == "incref") {
+                scheme.incref = requireString(field.second, what);
+            } else if (field.first == "decref") {
+                scheme.decref = requireString(field.second, what);
+            } else {
+                throw std::runtime_error("Invalid FFI config: unknown key " + what);
+            }
+        }
+        if (scheme.incref.empty() || scheme.decref.empty()) {
+            throw std::runtime_error("Invalid FFI config: \"refcounted." + entry.first +
+                                     "\" needs both \"incref\" and \"decref\"");
+        }
+        options.refcounted[entry.first] = scheme;
+    }
+}
+
+void applyChannelCallbacks(const JsonValue& section, FFIOptions& options) {
+    requireKind(section, JsonValue::Kind::Object, "\"channel_callbacks\" must map function names to objects");
+    for (const auto& entry : section.object) {
+        requireKind(entry.second, JsonValue::Kind::Object,
+                    "\"channel_callbacks." + entry.first + "\" must be an object");
+
+        ChannelCallback channel;
+        for (const auto& field : entry.second.object) {
+            std::string what = "\"channel_callbacks." + entry.first + "." + field.first + "\"";
+            if (field.first == "unregister") {
+                channel.unregister = requireString(field.second, what);
+            } else if (field.first == "capacity") {
+                const JsonValue& capacity = requireKind(field.second, JsonValue::Kind::Number,
+                                                        what + " must be a positive integer");
+                if (capacity.number < 1 ||
+                    capacity.number != static_cast<double>(static_cast<size_t>(capacity.number))) {
+                    throw std::runtime_error("Invalid FFI config: " + what + " must be a positive integer");
+                }
+                channel.capacity = static_cast<size_t>(capacity.number);
+            } else {
+                throw std::runtime_error("Invalid FFI config: unknown key " + what);
+            }
+        }
+        // Without unregistering, the C++ side could call into a freed sender
+        if (channel.unregister.empty()) {
+            throw std::runtime_error("Invalid FFI config: \"channel_callbacks." + entry.first +
+                                     "\" needs \"unregister\"");
+        }
+        options.channel_callbacks[entry.first] = channel;
+    }
+}
+
+void applyErrorEnums(const JsonValue& section, FFIOptions& options) {
+    requireKind(section, JsonValue::Kind::Object, "\"error_enums\" must map enum names to objects");
+    for (const auto& entry : section.object) {
+        requireKind(entry.second, JsonValue::Kind::Object, "\"error_enums." + entry.first + "\" must be an object");
+
+        ErrorEnum error;
+        for (const auto& field : entry.second.object) {
+            std::string what = "\"error_enums." + entry.first + "." + field.first + "\"";
+            if (field.first == "success") {
+                error.success = requireString(field.second, what);
+            } else if (field.first == "to_string") {
+                error.to_string = requireString(field.second, what);
+            } else {
+                throw std::runtime_error("Invalid FFI config: unknown key " + what);
+            }
+        }
+        options.error_enums[entry.first] = error;
+    }
+}
+
+void applyTemplates(const JsonValue& section, FFIOptions& options) {
+    requireKind(section, JsonValue::Kind::Object, "\"templates\" must map function templates to objects");
+    for (const auto& entry : section.object) {
+        requireKind(entry.second, JsonValue::Kind::Object, "\"templates." + entry.first + "\" must be an object");
+
+        TemplateInstances instances;
+        for (const auto& field : entry.second.object) {
+            std::string what = "\"templates." + entry.first + "." + field.first + "\"";
+            if (field.first == "instances") {
+                requireKind(field.second, JsonValue::Kind::Array, what + " must be an array of template arguments");
+                for (const auto& item : field.second.array) {
+                    instances.instances.push_back(requireString(item, what + " entries"));
+                }
+            } else if (field.first == "trait") {
+                instances.trait = requireString(field.second, what);
+            } else {
+                throw std::runtime_error("Invalid FFI config: unknown key " + what);
+            }
+        }
+        if (instances.instances.empty()) {
+            throw std::runtime_error("Invalid FFI config: \"templates." + entry.first + "\" needs \"instances\"");
+        }
+        options.templates[entry.first] = instances;
+    }
+}
+
+void applyTypedefs(const JsonValue& section, FFIOptions& options) {
+    requireKind(section, JsonValue::Kind::Object, "\"typedefs\" must map type names to the types they stand for");
+    for (const auto& entry : section.object) {
+        options.typedefs[entry.first] = requireString(entry.second, "\"typedefs." + entry.first + "\"");
+    }
+}
+
+void applyOwnership(const JsonValue& section, FFIOptions& options) {
+    requireKind(section, JsonValue::Kind::Object, "\"ownership\" must map functions to \"owned\" or \"borrowed\"");
+    for (const auto& entry : section.object) {
+        std::string what = "\"ownership." + entry.first + "\"";
+        std::string value = requireString(entry.second, what);
+        if (value == "owned") {
+            options.ownership[entry.first] = PointerOwnership::Owned;
+        } else if (value == "borrowed") {
+            options.ownership[entry.first] = PointerOwnership::Borrowed;
+        } else {
+            throw std::runtime_error("Invalid FFI config: " + what + " must be \"owned\" or \"borrowed\"");
+        }
+    }
+}
+
+void applyDerives(const JsonValue& section, FFIOptions& options) {
+    requireKind(section, JsonValue::Kind::Object, "\"derives\" must map struct names to arrays of traits");
+    for (const auto& entry : section.object) {
+        std::string what = "\"derives." + entry.first + "\"";
+        requireKind(entry.second, JsonValue::Kind::Array, what + " must be an array of traits");
+        std::vector<std::string>& traits = options.derives[entry.first];
+        traits.clear();
+        for (const auto& item : entry.second.array) {
+            traits.push_back(requireString(item, what + " entries"));
+        }
+    }
+}
+
+void applyPathParams(const JsonValue& section, FFIOptions& options) {
+    options.path_params.clear();
+    if (section.kind == JsonValue::Kind::Bool) {
+        if (section.boolean) {
+            options.path_params = {"path", "file", "filename", "filepath"};
+        }
+        return;
+    }
+    requireKind(section, JsonValue::Kind::Array, "\"path_params\" must be true, false or an array of name words");
+    for (const auto& item : section.array) {
+        std::string word = requireString(item, "\"path_params\" entries");
+        std::transform(word.begin(), word.end(), word.begin(),
+                       [](unsigned char c) { return static_cast<char>(std::tolower(c)); });
+        options.path_params.push_back(word);
+    }
+}
+
+void applyPatterns(const JsonValue& section, const std::string& key, std::vector<std::string>& patterns) {
+    requireKind(section, JsonValue::Kind::Array, "\"" + key + "\" must be an array of regular expressions");
+    for (const auto& item : section.array) {
+        std::string pattern = requireString(item, "\"" + key + "\" entries");
+        try {
+            std::regex compiled(pattern);
+        } catch (const std::regex_error&) {
+            throw std::runtime_error("Invalid FFI config: \"" + key + "\" pattern \"" + pattern +
+                                     "\" is not a valid regular expression");
+        }
+        patterns.push_back(pattern);
+    }
+}
+
+void applyBuilders(const JsonValue& section, FFIOptions& options) {
+    requireKind(section, JsonValue::Kind::Object, "\"builders\" must be an object");
+    options.builder_min_setters = 1;
+    for (const auto& field : section.object) {
+        if (field.first == "min_setters") {
+            const JsonValue& count = requireKind(field.second, JsonValue::Kind::Number,
+                                                 "\"builders.min_setters\" must be a positive integer");
+            if (count.number < 1 || count.number != static_cast<double>(static_cast<size_t>(count.number))) {
+                throw std::runtime_error("Invalid FFI config: \"builders.min_setters\" must be a positive integer");
+            }
+            options.builder_min_setters = static_cast<size_t>(count.number);
+        } else if (field.first == "setters") {
+            applyPatterns(field.second, "builders.setters", options.builder_setters);
+        } else {
+            throw std::runtime_error("Invalid FFI config: unknown key \"builders." + field.first + "\"");
+        }
+    }
+}
+
+void applyInit(const JsonValue& section, FFIOptions& options) {
+    requireKind(section, JsonValue::Kind::Object, "\"init\" must be an object");
+    for (const auto& field : section.object) {
+        if (field.first == "function") {
+            options.init.function = requireString(field.second, "\"init.function\"");
+        } else if (field.first == "shutdown") {
+            options.init.shutdown = requireString(field.second, "\"init.shutdown\"");
+        } else if (field.first == "automatic") {
+            options.init.automatic =
+                requireKind(field.second, JsonValue::Kind::Bool, "\"init.automatic\" must be true or false").boolean;
+        } else {
+            throw std::runtime_error("Invalid FFI config: unknown key \"init." + field.first + "\"");
+        }
+    }
+    if (options.init.function.empty()) {
+        throw std::runtime_error("Invalid FFI config: \"init.function\" must name the init function");
+    }
+}
+
+void applyPackage(const JsonValue& section, FFIOptions& options) {
+    requireKind(section, JsonValue::Kind::Object, "\"package\" must be an object");
+    for (const auto& field : section.object) {
+        if (field.first == "prefix") {
+            options.package.prefix = requireString(field.second, "\"package.prefix\"");
+        } else if (field.first == "version") {
+            options.package.version = requireString(field.second, "\"package.version\"");
+        } else if (field.first == "description") {
+            options.package.description = requireString(field.second, "\"package.description\"");
+        } else {
+            throw std::runtime_error("Invalid FFI config: unknown key \"package." + field.first + "\"");
+        }
+    }
+    if (options.package.prefix.empty()) {
+        throw std::runtime_error("Invalid FFI config: \"package.prefix\" must not be empty");
+    }
+}
+
+void applyAbiTargets(const JsonValue& section, FFIOptions& options) {
+    static const std::map<std::string, AbiTarget> targets = {
+        {"x86_64-sysv", AbiTarget::SysV64},
+        {"x86_64-windows", AbiTarget::Win64},
+        {"aarch64", AbiTarget::AArch64},
+    };
+    const std::string what = "\"abi_targets\" must be an array of \"x86_64-sysv\", \"x86_64-windows\" or \"aarch64\"";
+    requireKind(section, JsonValue::Kind::Array, what);
+    options.abi_targets.clear();
+    for (const auto& item : section.array) {
+        auto target = targets.find(requireString(item, "\"abi_targets\" entries"));
+        if (target == targets.end()) {
+            throw std::runtime_error("Invalid FFI config: " + what);
+        }
+        options.abi_targets.push_back(target->second);
+    }
+}
+
+/**
+ * @brief Rust string literal for a pattern
+ */
+std::string rustString(const std::string& text) {
+    std::string literal = "\"";
+    for (char c : text) {
+        if (c == '\\' || c == '"') {
+            literal += '\\';
+        }
+        literal += c;
+    }
+    return literal + "\"";
+}
+
+} // namespace
+
+void applyFFIConfig(const std::string& json, FFIOptions& options) {
+    JsonValue root = JsonReader(json).parse();
+    requireKind(root, JsonValue::Kind::Object, "top level must be an object");
+
+    for (const auto& section : root.object) {
+        if (section.first == "refcounted") {
+            applyRefcounted(section.second, options);
+        } else if (section.first == "default_arguments") {
+            std::string mode = requireString(section.second, "\"default_arguments\"");
+            if (mode == "overloads") {
+                options.default_args = DefaultArgs::Overloads;
+            } else if (mode == "option") {
+                options.default_args = DefaultArgs::Option;
+            } else {
+                throw std::runtime_error("Invalid FFI config: \"default_arguments\" must be \"overloads\" or \"option\"");
+            }
+        } else if (section.first == "string_params") {
+            std::string style = requireString(section.second, "\"string_params\"");
+            if (style == "str") {
+                options.string_params = StringParams::Str;
+            } else if (style == "as_ref") {
+                options.string_params = StringParams::AsRef;
+            } else {
+                throw std::runtime_error("Invalid FFI config: \"string_params\" must be \"str\" or \"as_ref\"");
+            }
+        } else if (section.first == "struct_fields") {
+            std::string style = requireString(section.second, "\"struct_fields\"");
+            if (style == "public") {
+                options.struct_fields = StructFields::Public;
+            } else if (style == "accessors") {
+                options.struct_fields = StructFields::Accessors;
+            } else {
+                throw std::runtime_error("Invalid FFI config: \"struct_fields\" must be \"public\" or \"accessors\"");
+            }
+        } else if (section.first == "checked_narrowing") {
+            options.checked_narrowing =
+                requireKind(section.second, JsonValue::Kind::Bool, "\"checked_narrowing\" must be true or false").boolean;
+        } else if (section.first == "safety_comments") {
+            options.safety_comments =
+                requireKind(section.second, JsonValue::Kind::Bool, "\"safety_comments\" must be true or false").boolean;
+        } else if (section.first == "free_function") {
+            options.free_function = requireString(section.second, "\"free_function\"");
+        } else if (section.first == "include") {
+            applyPatterns(section.second, section.first, options.include_patterns);
+        } else if (section.first == "exclude") {
+            applyPatterns(section.second, section.first, options.exclude_patterns);
+        } else if (section.first == "builders") {
+            applyBuilders(section.second, options);
+        } else if (section.first == "channel_callbacks") {
+            applyChannelCallbacks(section.second, options);
+        } else if (section.first == "error_enums") {
+            applyErrorEnums(section.second, options);
+        } else if (section.first == "templates") {
+            applyTemplates(section.second, options);
+        } else if (section.first == "typedefs") {
+            applyTypedefs(section.second, options);
+        } else if (section.first == "ownership") {
+            applyOwnership(section.second, options);
+        } else if (section.first == "derives") {
+            applyDerives(section.second, options);
+        } else if (section.first == "path_params") {
+            applyPathParams(section.second, options);
+        } else if (section.first == "init") {
+            applyInit(section.second, options);
+        } else if (section.first == "abi_targets") {
+            applyAbiTargets(section.second, options);
+        } else if (section.first == "package") {
+            applyPackage(section.second, options);
+        } else if (section.first == "allow_skips") {
+            requireKind(section.second, JsonValue::Kind::Array, "\"allow_skips\" must be an array of names");
+            for (const auto& item : section.second.array) {
+                options.allowed_skips.insert(requireString(item, "\"allow_skips\" entries"));
+            }
+        } else if (section.first == "thread_safe") {
+            requireKind(section.second, JsonValue::Kind::Array, "\"thread_safe\" must be an array of class names");
+            for (const auto& item : section.second.array) {
+                options.thread_safe.insert(requireString(item, "\"thread_safe\" entries"));
+            }
+        } else {
+            throw std::runtime_error("Invalid FFI config: unknown key \"" + section.first + "\"");
+        }
+    }
+}
+
+bool isSelected(const std::string& name, const FFIOptions& options) {
+    // Whole-name matches, the same semantics bindgen gives its patterns
+    auto matches = [&](const std::string& pattern) { return std::regex_match(name, std::regex(pattern)); };
+    bool included = options.include_patterns.empty() ||
+                    std::any_of(options.include_patterns.begin(), options.include_patterns.end(), matches);
+    return included && std::none_of(options.exclude_patterns.begin(), options.exclude_patterns.end(), matches);
+}
+
+std::string generateBindgenConfig(const FFIOptions& options, const std::string& library_name) {
+    std::stringstream ss;
+    ss << "// Auto-generated bindgen configuration for " << library_name << "\n";
+    ss << "// Generated by Hybrid Transpiler\n";
+    ss << "//\n";
+    ss << "// Restricts bindgen to the include/exclude patterns used for the C++\n";
+    ss << "// bindings. In build.rs:\n";
+    ss << "//\n";
+    ss << "//     let builder = configure(bindgen::Builder::default().header(\"" << library_name << ".h\"));\n\n";
+    ss << "pub fn configure(builder: bindgen::Builder) -> bindgen::Builder {\n";
+    ss << "    builder";
+    for (const auto& pattern : options.include_patterns) {
+        ss << "\n        .allowlist_function(" << rustString(pattern) << ")";
+        ss << "\n        .allowlist_type(" << rustString(pattern) << ")";
+        ss << "\n        .allowlist_var(" << rustString(pattern) << ")";
+    }
+    for (const auto& pattern : options.exclude_patterns) {
+        ss << "\n        .blocklist_item(" << rustString(pattern) << ")";
+    }
+    ss << "\n}\n";
+    return ss.str();
+}
+
+std::string generatePkgConfig(const FFIOptions& options, const std::vector<std::string>& library_names) {
+    const std::string& name = library_names.front();
+    std::stringstream ss;
+    ss << "# Auto-generated pkg-config file for " << name << "\n";
+    ss << "# Generated by Hybrid Transpiler\n\n";
+    ss << "prefix=" << options.package.prefix << "\n";
+    ss << "libdir=${prefix}/lib\n";
+    ss << "includedir=${prefix}/include\n\n";
+    ss << "Name: " << name << "\n";
+    ss << "Description: "
+       << (options.package.description.empty() ? "C++ library " + name + " with generated Rust bindings"
+                                                : options.package.description)
+       << "\n";
+    ss << "Version: " << options.package.version << "\n";
+    // The libraries the bindings' #[link(name = "...")] attributes name
+    ss << "Libs: -L${libdir}";
+    for (const auto& library : library_names) {
+        ss << " -l" << library;
+    }
+    ss << "\n";
+    ss << "Cflags: -I${includedir}\n";
+    return ss.str();
+}
+
+std::string generateCMakeConfig(const FFIOptions& options, const std::vector<std::string>& library_names) {
+    const std::string& name = library_names.front();
+    const std::string& prefix = options.package.prefix;
+    std::stringstream ss;
+    ss << "# Auto-generated CMake package configuration for " << name << "\n";
+    ss << "# Generated by Hybrid Transpiler\n";
+    ss << "#\n";
+    ss << "# Imported targets for the libraries the Rust bindings link, so C++ code\n";
+    ss << "# links the same ones:\n";
+    ss << "#\n";
+    ss << "#     find_package(" << name << " CONFIG REQUIRED)\n";
+    ss << "#     target_link_libraries(app PRIVATE " << name << "::" << name << ")\n\n";
+    ss << "set(" << name << "_VERSION \"" << options.package.version << "\")\n";
+    ss << "set(" << name << "_INCLUDE_DIRS \"" << prefix << "/include\")\n";
+    ss << "set(" << name << "_LIBRARIES)\n";
+    for (const auto& library : library_names) {
+        const std::string target = name + "::" + library;
+        const std::string variable = name + "_" + library + "_LIBRARY";
+        ss << "\n";
+        ss << "if(NOT TARGET " << target << ")\n";
+        ss << "    find_library(" << variable << " NAMES " << library << " HINTS \"" << prefix
+           << "/lib\" NO_DEFAULT_PATH)\n";
+        ss << "    if(NOT " << variable << ")\n";
+        ss << "        set(" << name << "_FOUND FALSE)\n";
+        ss << "        set(" << name << "_NOT_FOUND_MESSAGE \"lib" << library << " not found in " << prefix
+           << "/lib\")\n";
+        ss << "        return()\n";
+        ss << "    endif()\n";
+        ss << "    add_library(" << target << " UNKNOWN IMPORTED)\n";
+        ss << "    set_target_properties(" << target << " PROPERTIES\n";
+        ss << "        IMPORTED_LOCATION \"${" << variable << "}\"\n";
+        ss << "        INTERFACE_INCLUDE_DIRECTORIES \"${" << name << "_INCLUDE_DIRS}\")\n";
+        ss << "endif()\n";
+        ss << "list(APPEND " << name << "_LIBRARIES " << target << ")\n";
+    }
+    return ss.str();
+}
+
+} // namespace ffi
+} // namespace hybrid_transpiler
@@ -0,0 +1,288 @@
+/**
+ * @file ffi_generator.cpp
+ * @brief FFI generation coordinator implementation
+ */
+
+#include "ffi.h"
+#include "parser.h"
+#include <algorithm>
+#include <map>
+#include <set>
+#include <sstream>
+#include <stdexcept>
+
+namespace hybrid_transpiler {
+namespace ffi {
+
+FFIGenerator::FFIGenerator() : FFIGenerator(FFIOptions{}) {}
+
+FFIGenerator::FFIGenerator(const FFIOptions& options)
+    : rust_generator_(options), c_wrapper_generator_(options), options_(options) {}
+
+void FFIGenerator::analyze(const std::vector<std::pair<std::string, std::string>>& sources,
+                           std::vector<FFIFunction>& functions, std::vector<FFIClass>& classes,
+                           std::vector<FFIEnum>& enums, std::vector<FFIFunction>& callback_types) {
+    // Every declaration remembers the library whose header declared it
+    hybrid::IR ir;
+    for (const auto& source : sources) {
+        hybrid::IR parsed = options_.preprocessed ? hybrid::Parser::parsePreprocessed(source.second)
+                                                  : hybrid::Parser::parseString(source.second);
+        for (auto function : parsed.getFunctions()) {
+            function.library = source.first;
+            ir.addFunction(function);
+        }
+        for (auto class_decl : parsed.getClasses()) {
+            class_decl.library = source.first;
+            ir.addClass(class_decl);
+        }
+        for (const auto& enum_decl : parsed.getEnums()) {
+            ir.addEnum(enum_decl);
+        }
+        for (const auto& signature : parsed.getFunctionPointerTypes()) {
+            ir.addFunctionPointerType(signature);
+        }
+        for (const auto& alias : parsed.getTypeAliases()) {
+            ir.addTypeAlias(alias.first, alias.second);
+        }
+    }
+    if (!options_.roots.empty()) {
+        ir = hybrid::reachableFrom(ir, options_.roots);
+    }
+
+    // Configured typedefs win over what the headers say
+    std::map<std::string, std::string> aliases = options_.typedefs;
+    aliases.insert(ir.getTypeAliases().begin(), ir.getTypeAliases().end());
+    analyzer_.setTypeAliases(aliases);
+
+    for (const auto& function : ir.getFunctions()) {
+        if (!isSelected(function.name, options_)) {
+            continue;
+        }
+        // A configured template is bound once per instantiation instead
+        auto instances = options_.templates.find(function.name);
+        if (function.is_template && instances != options_.templates.end()) {
+            for (const auto& args : instances->second.instances) {
+                functions.push_back(analyzer_.instantiateFunction(function, args));
+                functions.back().link_name = function.library;
+            }
+            continue;
+        }
+        functions.push_back(analyzer_.analyzeFunction(function));
+        functions.back().link_name = function.library;
+    }
+
+    for (const auto& class_decl : ir.getClasses()) {
+        if (isSelected(class_decl.name, options_)) {
+            classes.push_back(analyzer_.analyzeClass(class_decl));
+            classes.back().link_name = class_decl.library;
+        }
+    }
+
+    // Configured ownership overrides what the function shape suggests
+    auto own = [&](FFIFunction& func, const std::string& key) {
+        auto it = options_.ownership.find(key);
+        if (it != options_.ownership.end()) {
+            func.return_ownership = it->second;
+        }
+    };
+    for (auto& func : functions) {
+        own(func, func.name);
+    }
+    for (auto& cls : classes) {
+        for (auto* group : {&cls.methods, &cls.static_methods}) {
+            for (auto& method : *group) {
+                own(method, cls.name + "::" + method.name);
+            }
+        }
+    }
+
+    // Anonymous enums only group constants and are always kept
+    for (const auto& enum_decl : ir.getEnums()) {
+        if (enum_decl.name.empty() || isSelected(enum_decl.name, options_)) {
+            enums.push_back(analyzer_.analyzeEnum(enum_decl));
+        }
+    }
+
+    for (const auto& signature : ir.getFunctionPointerTypes()) {
+        callback_types.push_back(analyzer_.analyzeFunction(signature));
+    }
+}
+
+std::string FFIGenerator::generate(
+    const std::string& cpp_source,
+    const std::string& library_name,
+    const std::string& target_lang
+) {
+    std::vector<FFIFunction> functions;
+    std::vector<FFIClass> classes;
+    std::vector<FFIEnum> enums;
+    std::vector<FFIFunction> callback_types;
+    analyze({{"", cpp_source}}, functions, classes, enums, callback_types);
+
+    if (target_lang == "rust") {
+        return rust_generator_.generateModule(functions, classes, library_name, enums, callback_types);
+    }
+
+    throw std::invalid_argument("Unsupported FFI target: " + target_lang);
+}
+
+std::string FFIGenerator::generate(
+    const std::vector<std::pair<std::string, std::string>>& sources,
+    const std::string& target_lang
+) {
+    if (sources.empty()) {
+        throw std::invalid_argument("No FFI sources given");
+    }
+    std::vector<FFIFunction> functions;
+    std::vector<FFIClass> classes;
+    std::vector<FFIEnum> enums;
+    std::vector<FFIFunction> callback_types;
+    analyze(sources, functions, classes, enums, callback_types);
+
+    if (target_lang == "rust") {
+        return rust_generator_.generateModule(functions, classes, sources.front().first, enums, callback_types);
+    }
+
+    throw std::invalid_argument("Unsupported FFI target: " + target_lang);
+}
+
+std::pair<std::string, std::string> FFIGenerator::generateCWrapper(
+    const std::string& cpp_source,
+    const std::string& library_name
+) {
+    std::vector<FFIFunction> functions;
+    std::vector<FFIClass> classes;
+    std::vector<FFIEnum> enums;
+    std::vector<FFIFunction> callback_types;
+    analyze({{"", cpp_source}}, functions, classes, enums, callback_types);
+
+    std::string implementation =
+        c_wrapper_generator_.generateImplementation(functions, classes, library_name, enums);
+    if (options_.emit_self_test) {
+        // The harness calls checksum twins of the functions it exercises,
+        // which only the Rust side knows after planning the module
+        RustFFIGenerator rust(options_);
+        rust.generateModule(functions, classes, library_name, enums, callback_types);
+        implementation += "\n" + rust.generateSelfTestShims(functions);
+    }
+
+    return {
+        c_wrapper_generator_.generateHeader(functions, classes, library_name),
+        implementation,
+    };
+}
+
+SupportReport FFIGenerator::audit(const std::vector<std::pair<std::string, std::string>>& sources) {
+    // Skips are collected instead of failing the run
+    FFIOptions lenient = options_;
+    lenient.strict = false;
+    FFIGenerator generator(lenient);
+
+    std::vector<FFIFunction> functions;
+    std::vector<FFIClass> classes;
+    std::vector<FFIEnum> enums;
+    std::vector<FFIFunction> callback_types;
+    generator.analyze(sources, functions, classes, enums, callback_types);
+
+    SupportReport report;
+    std::set<std::string> reported;
+    auto add = [&](const std::string& category, const std::string& symbol, const std::string& detail) {
+        if (reported.insert(symbol).second) {
+            report.unsupported.push_back({category, symbol, detail});
+        }
+    };
+
+    for (const auto& cls : classes) {
+        report.declarations += 1 + cls.methods.size() + cls.static_methods.size();
+        if (cls.is_template) {
+            add(skipCategoryName(SkipCategory::Templates), cls.name, "class template, the shims cannot name it without template arguments");
+            for (const auto* group : {&cls.methods, &cls.static_methods}) {
+                for (const auto& method : *group) {
+                    reported.insert(cls.name + "::" + method.name);
+                }
+            }
+        } else if (cls.base_classes.size() > 1) {
+            std::string bases;
+            for (const auto& base : cls.base_classes) {
+                bases += (bases.empty() ? "" : ", ") + base;
+            }
+            add(skipCategoryName(SkipCategory::MultipleInheritance), cls.name, "derives from " + bases + ", only its own members are bound");
+        }
+        for (const auto* group : {&cls.methods, &cls.static_methods}) {
+            for (const auto& method : *group) {
+                if (!method.can_use_ffi) {
+                    add(skipCategoryName(method.skip_category), cls.name + "::" + method.name, method.reason);
+                }
+            }
+        }
+    }
+    for (const auto& func : functions) {
+        report.declarations++;
+        if (!func.can_use_ffi) {
+            add(skipCategoryName(func.skip_category), func.name, func.reason);
+        }
+    }
+    for (const auto& enum_decl : enums) {
+        report.declarations += enum_decl.name.empty() ? 0 : 1;
+    }
+
+    // The generators find what the analysis alone does not, such as
+    // self-embedding structs and shims that cannot be written
+    const std::string skipped = "skipped: ";
+    generator.rust_generator_.generateModule(functions, classes, "audit", enums, callback_types);
+    for (const auto& diagnostic : generator.rust_generator_.diagnostics()) {
+        if (diagnostic.message.compare(0, skipped.size(), skipped) == 0) {
+            std::string reason = diagnostic.message.substr(skipped.size());
+            add(skipCategoryName(diagnostic.category), diagnostic.symbol, reason);
+        }
+    }
+    generator.c_wrapper_generator_.generateImplementation(functions, classes, "audit", enums);
+    for (const auto& diagnostic : generator.c_wrapper_generator_.diagnostics()) {
+        add(skipCategoryName(diagnostic.category), diagnostic.symbol, diagnostic.message);
+    }
+    return report;
+}
+
+std::string skipCategoryName(SkipCategory category) {
+    switch (category) {
+        case SkipCategory::Templates: return "Templates";
+        case SkipCategory::StandardLibrary: return "Standard library types";
+        case SkipCategory::Exceptions: return "Exceptions";
+        case SkipCategory::SelfEmbedding: return "Self-embedding structs";
+        case SkipCategory::ByValueClass: return "Classes passed by value";
+        case SkipCategory::MultipleInheritance: return "Multiple inheritance";
+        case SkipCategory::Other: break;
+    }
+    return "Other";
+}
+
+std::string formatSupportReport(const SupportReport& report) {
+    std::stringstream ss;
+    if (report.unsupported.empty()) {
+        ss << "All " << report.declarations << " declarations are supported\n";
+        return ss.str();
+    }
+    ss << report.unsupported.size() << " of " << report.declarations << " declarations are not supported\n";
+
+    // Categories in the order they first appear
+    std::vector<std::pair<std::string, std::vector<const UnsupportedDeclaration*>>> groups;
+    for (const auto& entry : report.unsupported) {
+        auto group = std::find_if(groups.begin(), groups.end(),
+                                  [&](const auto& g) { return g.first == entry.category; });
+        if (group == groups.end()) {
+            groups.push_back({entry.category, {}});
+            group = groups.end() - 1;
+        }
+        group->second.push_back(&entry);
+    }
+    for (const auto& group : groups) {
+        ss << "\n" << group.first << " (" << group.second.size() << "):\n";
+        for (const auto* entry : group.second) {
+            ss << "  " << entry->symbol << ": " << entry->detail << "\n";
+        }
+    }
+    return ss.str();
+}
+
+} // namespace ffi
+} // namespace hybrid_transpiler
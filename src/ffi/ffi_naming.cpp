@@ -0,0 +1,304 @@
+/**
+ * @file ffi_naming.cpp
+ * @brief Shim naming shared by the FFI generators
+ */
+
+#include "ffi.h"
+#include <algorithm>
+#include <cctype>
+#include <map>
+#include <set>
+
+namespace hybrid_transpiler {
+namespace ffi {
+
+namespace {
+
+/**
+ * @brief Give overloaded constructors distinct shim names
+ *
+ * The default constructor keeps `<class>_new`, the others become
+ * `<class>_new_with_<params>` so each maps to its own Rust function.
+ */
+void disambiguateConstructors(FFIClass& cls) {
+    size_t ctor_count = 0;
+    for (const auto& method : cls.methods) {
+        if (isConstructor(method)) {
+            ctor_count++;
+        }
+    }
+    if (ctor_count < 2) {
+        return;
+    }
+
+    for (auto& method : cls.methods) {
+        if (!isConstructor(method) || method.parameters.empty() || !method.c_name.empty()) {
+            continue;
+        }
+        method.c_name = toSnakeCase(cls.name) + "_new_with";
+        for (size_t i = 0; i < method.parameters.size(); ++i) {
+            method.c_name += "_" + paramName(method.parameters[i], i);
+        }
+    }
+}
+
+/**
+ * @brief Identifier for an overloaded comparison operator, empty otherwise
+ */
+std::string operatorSuffix(const std::string& name) {
+    static const std::map<std::string, std::string> suffixes = {
+        {"operator<=>", "cmp"}, {"operator==", "eq"}, {"operator!=", "ne"},
+        {"operator<", "lt"}, {"operator<=", "le"}, {"operator>", "gt"}, {"operator>=", "ge"},
+    };
+    auto it = suffixes.find(name);
+    return it == suffixes.end() ? "" : it->second;
+}
+
+/**
+ * @brief `std::chrono::...` type without const or reference, empty for other types
+ */
+std::string chronoType(const std::string& cpp_type) {
+    size_t start = cpp_type.find_first_not_of(" \t");
+    if (start == std::string::npos) {
+        return "";
+    }
+    std::string type = cpp_type.substr(start);
+    if (type.compare(0, 6, "const ") == 0) {
+        type = type.substr(type.find_first_not_of(' ', 6));
+    }
+    while (!type.empty() && (type.back() == '&' || type.back() == ' ')) {
+        type.pop_back();
+    }
+    return type.compare(0, 13, "std::chrono::") == 0 ? type : "";
+}
+
+// Rust Duration unit of each standard period typedef
+const std::map<std::string, std::string>& durationTypedefs() {
+    static const std::map<std::string, std::string> typedefs = {
+        {"std::chrono::nanoseconds", "nanos"}, {"std::chrono::microseconds", "micros"},
+        {"std::chrono::milliseconds", "millis"}, {"std::chrono::seconds", "secs"},
+        {"std::chrono::minutes", "minutes"}, {"std::chrono::hours", "hours"},
+    };
+    return typedefs;
+}
+
+// Strict and reserved keywords of Rust 2021, which C++ allows as identifiers
+bool isRustKeyword(const std::string& name) {
+    static const std::set<std::string> keywords = {
+        "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+        "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+        "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub",
+        "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "try",
+        "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+    };
+    return keywords.count(name) > 0;
+}
+
+} // namespace
+
+std::string rustIdent(const std::string& name) {
+    if (!isRustKeyword(name)) {
+        return name;
+    }
+    // These four cannot be raw identifiers
+    if (name == "self" || name == "Self" || name == "super" || name == "crate") {
+        return name + "_";
+    }
+    return "r#" + name;
+}
+
+std::string toSnakeCase(const std::string& name) {
+    std::string result;
+    for (size_t i = 0; i < name.size(); ++i) {
+        char c = name[i];
+        // Nested class scopes join with an underscore like words do
+        if (c == ':') {
+            continue;
+        }
+        if (std::isupper(static_cast<unsigned char>(c))) {
+            if (i > 0 && !std::isupper(static_cast<unsigned char>(name[i - 1])) && name[i - 1] != '_') {
+                result += '_';
+            }
+            result += static_cast<char>(std::tolower(static_cast<unsigned char>(c)));
+        } else {
+            result += c;
+        }
+    }
+    return result;
+}
+
+bool isConstructor(const FFIFunction& func) {
+    // Constructors of nested classes carry the unqualified name
+    size_t scope = func.class_name.rfind("::");
+    return !func.class_name.empty() &&
+           func.name == (scope == std::string::npos ? func.class_name : func.class_name.substr(scope + 2));
+}
+
+std::string rustTypeName(const std::string& cpp_name) {
+    std::string name = cpp_name;
+    for (size_t pos; (pos = name.find("::")) != std::string::npos;) {
+        name.erase(pos, 2);
+    }
+    return name;
+}
+
+bool isDestructor(const FFIFunction& func) {
+    return !func.name.empty() && func.name[0] == '~';
+}
+
+std::string paramName(const FFIParameter& param, size_t index) {
+    if (param.name.empty()) {
+        return "arg" + std::to_string(index);
+    }
+    // Shared with the C++ shim, so a keyword gets a suffix rather than `r#`
+    std::string name = toSnakeCase(param.name);
+    return isRustKeyword(name) ? name + "_" : name;
+}
+
+bool isReprC(const FFIClass& cls) {
+    return cls.methods.empty() && cls.static_methods.empty() && !cls.fields.empty();
+}
+
+bool isStringParam(const FFIParameter& param) {
+    std::string type = param.cpp_type;
+    type.erase(std::remove(type.begin(), type.end(), ' '), type.end());
+    return type == "constchar*" || type == "std::string" || type == "conststd::string" ||
+           type == "conststd::string&";
+}
+
+bool isStringViewParam(const FFIParameter& param) {
+    std::string type = param.cpp_type;
+    type.erase(std::remove(type.begin(), type.end(), ' '), type.end());
+    return type == "std::string_view" || type == "conststd::string_view" || type == "conststd::string_view&";
+}
+
+bool isDuration(const std::string& cpp_type) {
+    std::string type = chronoType(cpp_type);
+    return durationTypedefs().count(type) || type.compare(0, 22, "std::chrono::duration<") == 0;
+}
+
+std::string durationRep(const std::string& cpp_type) {
+    std::string type = chronoType(cpp_type);
+    if (durationTypedefs().count(type)) {
+        return "int64_t";
+    }
+    size_t start = type.find('<') + 1;
+    size_t end = type.find_first_of(",>", start);
+    std::string rep = type.substr(start, end - start);
+    rep.erase(0, rep.find_first_not_of(' '));
+    rep.erase(rep.find_last_not_of(' ') + 1);
+    return rep;
+}
+
+std::string durationUnit(const std::string& cpp_type) {
+    std::string type = chronoType(cpp_type);
+    auto typedef_unit = durationTypedefs().find(type);
+    if (typedef_unit != durationTypedefs().end()) {
+        return typedef_unit->second;
+    }
+
+    // duration<Rep, Period> with an integer count and a standard period
+    static const std::set<std::string> integers = {
+        "short", "int", "long", "long long", "unsigned", "unsigned int", "unsigned long", "unsigned long long",
+        "int8_t", "int16_t", "int32_t", "int64_t", "uint8_t", "uint16_t", "uint32_t", "uint64_t", "size_t",
+    };
+    static const std::map<std::string, std::string> periods = {
+        {"", "secs"}, {"std::nano", "nanos"}, {"std::micro", "micros"}, {"std::milli", "millis"},
+        {"std::ratio<1>", "secs"}, {"std::ratio<1,1>", "secs"}, {"std::ratio<60>", "minutes"},
+        {"std::ratio<3600>", "hours"},
+    };
+    if (!integers.count(durationRep(cpp_type))) {
+        return "";
+    }
+    size_t comma = type.find(',');
+    std::string period = comma == std::string::npos ? "" : type.substr(comma + 1, type.size() - comma - 2);
+    period.erase(std::remove(period.begin(), period.end(), ' '), period.end());
+    auto unit = periods.find(period);
+    return unit == periods.end() ? "" : unit->second;
+}
+
+bool returnsCountedArray(const FFIFunction& func) {
+    std::string type = func.return_type;
+    type.erase(std::remove(type.begin(), type.end(), ' '), type.end());
+    if (func.parameters.empty() || type.empty() || type.back() != '*' || type.find('*') != type.size() - 1 ||
+        type == "void*" || type == "constvoid*") {
+        return false;
+    }
+    std::string count = func.parameters.back().cpp_type;
+    count.erase(std::remove(count.begin(), count.end(), ' '), count.end());
+    return count == "size_t*" || count == "std::size_t*";
+}
+
+bool ownsReturnedPointer(const FFIFunction& func) {
+    switch (func.return_ownership) {
+        case PointerOwnership::Owned:
+            return true;
+        case PointerOwnership::Borrowed:
+            return false;
+        case PointerOwnership::Inferred:
+        default:
+            return func.class_name.empty() || func.is_static;
+    }
+}
+
+std::string shimName(const FFIFunction& func) {
+    if (!func.c_name.empty()) {
+        return func.c_name;
+    }
+    if (func.class_name.empty()) {
+        return func.name;
+    }
+
+    std::string prefix = toSnakeCase(func.class_name) + "_";
+    if (isConstructor(func)) {
+        return prefix + "new";
+    }
+    if (isDestructor(func)) {
+        return prefix + "delete";
+    }
+    std::string op = operatorSuffix(func.name);
+    return prefix + (op.empty() ? toSnakeCase(func.name) : op);
+}
+
+bool isOperator(const FFIFunction& func) {
+    return func.name.compare(0, 8, "operator") == 0 && !operatorSuffix(func.name).empty();
+}
+
+std::string shimSymbol(const FFIFunction& func) {
+    // Template instances (`max_int`) already differ from the template's name
+    bool wraps_namesake = func.class_name.empty() && func.c_name.empty() && func.template_of.empty();
+    return wraps_namesake ? func.name + "_shim" : shimName(func);
+}
+
+FFIFunction makeRefCountMethod(const std::string& class_name, const std::string& name) {
+    FFIFunction method{};
+    method.name = name;
+    method.class_name = class_name;
+    method.return_type = "void";
+    method.is_method = true;
+    method.can_use_ffi = true;
+    return method;
+}
+
+std::vector<FFIClass> prepareShimClasses(const std::vector<FFIClass>& input_classes, const FFIOptions& options) {
+    std::vector<FFIClass> classes = input_classes;
+    for (auto& cls : classes) {
+        disambiguateConstructors(cls);
+
+        // Refcounting methods need shims even when the header hides them
+        auto refcount = options.refcounted.find(cls.name);
+        if (refcount != options.refcounted.end()) {
+            for (const std::string& method_name : {refcount->second.incref, refcount->second.decref}) {
+                bool declared = std::any_of(cls.methods.begin(), cls.methods.end(),
+                    [&](const FFIFunction& method) { return method.name == method_name; });
+                if (!declared) {
+                    cls.methods.push_back(makeRefCountMethod(cls.name, method_name));
+                }
+            }
+        }
+    }
+    return classes;
+}
+
+} // namespace ffi
+} // namespace hybrid_transpiler
@@ -0,0 +1,528 @@
+/**
+ * @file mangle.cpp
+ * @brief Itanium and MSVC symbol names of C++ free functions
+ *
+ * A free function whose signature is already plain C is bound without a
+ * shim. Unless the header gives it C linkage, the extern block links it
+ * through the `link_name` computed here for each ABI.
+ */
+
+#include "ffi.h"
+#include <algorithm>
+#include <cctype>
+
+namespace hybrid_transpiler {
+namespace ffi {
+
+namespace {
+
+/**
+ * @brief A parameter or return type as written: the named type and the
+ *        pointers and reference applied to it, innermost first
+ */
+struct SpelledType {
+    struct Layer {
+        bool is_reference = false;
+        bool is_const = false;   // `* const`, never set on references
+    };
+
+    bool valid = false;          // false for anything outside the subset
+    bool is_builtin = false;
+    bool base_const = false;
+    std::string base;            // Builtin spelled canonically (`unsigned long`) or a type name
+    std::vector<std::string> qualified;  // Named types: scopes and name, outermost first
+    char tag = 'V';              // Named types: MSVC's 'U' struct, 'V' class or 'W' enum
+    std::vector<Layer> layers;
+
+    /**
+     * @brief Const-ness of the type with `level` layers applied
+     */
+    bool constAt(size_t level) const {
+        return level == 0 ? base_const : layers[level - 1].is_const;
+    }
+};
+
+std::vector<std::string> splitScope(const std::string& path) {
+    std::vector<std::string> parts;
+    size_t start = 0;
+    while (start < path.size()) {
+        size_t end = path.find("::", start);
+        end = end == std::string::npos ? path.size() : end;
+        if (end > start) {
+            parts.push_back(path.substr(start, end - start));
+        }
+        start = end + 2;
+    }
+    return parts;
+}
+
+bool isIdentifier(const std::string& name) {
+    return !name.empty() && !std::isdigit(static_cast<unsigned char>(name[0])) &&
+           std::all_of(name.begin(), name.end(),
+                       [](unsigned char c) { return std::isalnum(c) || c == '_'; });
+}
+
+/**
+ * @brief Builtin named by a run of type specifiers in any order
+ *        (`long unsigned int` is `unsigned long`), empty if they name none
+ */
+std::string builtinType(const std::vector<std::string>& words) {
+    static const std::vector<std::string> standalone = {"void", "bool", "float", "wchar_t", "char16_t", "char32_t"};
+    size_t is_signed = 0, is_unsigned = 0, shorts = 0, longs = 0, ints = 0, chars = 0, doubles = 0;
+    for (const auto& word : words) {
+        if (std::find(standalone.begin(), standalone.end(), word) != standalone.end()) {
+            return words.size() == 1 ? word : "";
+        }
+        is_signed += word == "signed";
+        is_unsigned += word == "unsigned";
+        shorts += word == "short";
+        longs += word == "long";
+        ints += word == "int";
+        chars += word == "char";
+        doubles += word == "double";
+    }
+    if (is_signed + is_unsigned + shorts + longs + ints + chars + doubles != words.size() ||
+        is_signed + is_unsigned > 1 || ints > 1 || chars > 1 || doubles > 1) {
+        return "";
+    }
+    if (doubles) {
+        bool plain = words.size() == 1 + longs && longs <= 1;
+        return plain ? (longs ? "long double" : "double") : "";
+    }
+    if (chars) {
+        if (words.size() != 1 + is_signed + is_unsigned) {
+            return "";
+        }
+        return is_unsigned ? "unsigned char" : is_signed ? "signed char" : "char";
+    }
+    if ((shorts && longs) || shorts > 1 || longs > 2) {
+        return "";
+    }
+    std::string integer = shorts ? "short" : longs == 2 ? "long long" : longs ? "long" : "int";
+    return is_unsigned ? "unsigned " + integer : integer;
+}
+
+/**
+ * @brief Tokens of a C++ type; `valid` stays false for templates, arrays,
+ *        function types, rvalue references and volatile
+ */
+SpelledType spell(const std::string& cpp_type) {
+    SpelledType type;
+    std::vector<std::string> tokens;
+    for (size_t i = 0; i < cpp_type.size();) {
+        unsigned char c = static_cast<unsigned char>(cpp_type[i]);
+        if (std::isspace(c)) {
+            ++i;
+        } else if (c == '*' || c == '&') {
+            tokens.emplace_back(1, static_cast<char>(c));
+            ++i;
+        } else if (std::isalnum(c) || c == '_' || c == ':') {
+            size_t end = i;
+            while (end < cpp_type.size() &&
+                   (std::isalnum(static_cast<unsigned char>(cpp_type[end])) || cpp_type[end] == '_' ||
+                    cpp_type[end] == ':')) {
+                ++end;
+            }
+            tokens.push_back(cpp_type.substr(i, end - i));
+            i = end;
+        } else {
+            return type;
+        }
+    }
+
+    std::vector<std::string> words;
+    size_t i = 0;
+    for (; i < tokens.size() && tokens[i] != "*" && tokens[i] != "&"; ++i) {
+        if (tokens[i] == "const") {
+            type.base_const = true;
+        } else {
+            words.push_back(tokens[i]);
+        }
+    }
+    for (; i < tokens.size(); ++i) {
+        bool after_reference = !type.layers.empty() && type.layers.back().is_reference;
+        if ((tokens[i] == "*" || tokens[i] == "&") && !after_reference) {
+            type.layers.push_back({tokens[i] == "&", false});
+        } else if (tokens[i] == "const" && !type.layers.empty() && !after_reference) {
+            type.layers.back().is_const = true;
+        } else {
+            return type;
+        }
+    }
+
+    type.base = builtinType(words);
+    type.is_builtin = !type.base.empty();
+    if (!type.is_builtin && words.size() == 1) {
+        type.base = words[0].compare(0, 2, "::") == 0 ? words[0].substr(2) : words[0];
+    }
+    type.valid = !type.base.empty() && type.base != "volatile";
+    return type;
+}
+
+/**
+ * @brief Builtin a <cstdint> typedef stands for under an ABI: 64-bit
+ *        Itanium assumes LP64, where only the 64-bit typedefs differ between
+ *        glibc (`long`) and Darwin (`long long`) and are left out, and
+ *        32-bit Itanium ILP32, where both spell them `long long`.
+ *        Empty for other names and for those left out.
+ */
+std::string cstdintType(const std::string& name, ManglingScheme scheme) {
+    static const std::map<std::string, std::string> common = {
+        {"int8_t", "signed char"}, {"uint8_t", "unsigned char"},
+        {"int16_t", "short"},      {"uint16_t", "unsigned short"},
+        {"int32_t", "int"},        {"uint32_t", "unsigned int"},
+    };
+    static const std::map<std::string, std::string> lp64 = {
+        {"size_t", "unsigned long"}, {"ptrdiff_t", "long"}, {"intptr_t", "long"}, {"uintptr_t", "unsigned long"},
+    };
+    static const std::map<std::string, std::string> ilp32 = {
+        {"size_t", "unsigned int"}, {"ptrdiff_t", "int"}, {"intptr_t", "int"}, {"uintptr_t", "unsigned int"},
+        {"int64_t", "long long"},   {"uint64_t", "unsigned long long"},
+        {"intmax_t", "long long"},  {"uintmax_t", "unsigned long long"},
+    };
+    static const std::map<std::string, std::string> msvc = {
+        {"int64_t", "long long"}, {"uint64_t", "unsigned long long"},
+        {"intmax_t", "long long"}, {"uintmax_t", "unsigned long long"},
+    };
+    static const std::map<std::string, std::string> msvc32 = {
+        {"size_t", "unsigned int"}, {"ptrdiff_t", "int"}, {"intptr_t", "int"}, {"uintptr_t", "unsigned int"},
+    };
+    static const std::map<std::string, std::string> msvc64 = {
+        {"size_t", "unsigned long long"}, {"ptrdiff_t", "long long"},
+        {"intptr_t", "long long"},        {"uintptr_t", "unsigned long long"},
+    };
+
+    std::string plain = name.compare(0, 5, "std::") == 0 ? name.substr(5) : name;
+    std::vector<const std::map<std::string, std::string>*> tables = {&common};
+    if (scheme == ManglingScheme::Itanium) {
+        tables.push_back(&lp64);
+    } else if (scheme == ManglingScheme::Itanium32) {
+        tables.push_back(&ilp32);
+    } else {
+        tables.push_back(&msvc);
+        tables.push_back(scheme == ManglingScheme::MSVC64 ? &msvc64 : &msvc32);
+    }
+    for (const auto* table : tables) {
+        auto it = table->find(plain);
+        if (it != table->end()) {
+            return it->second;
+        }
+    }
+    return "";
+}
+
+std::string itaniumBuiltin(const std::string& builtin) {
+    static const std::map<std::string, std::string> codes = {
+        {"void", "v"}, {"bool", "b"}, {"char", "c"}, {"signed char", "a"}, {"unsigned char", "h"},
+        {"short", "s"}, {"unsigned short", "t"}, {"int", "i"}, {"unsigned int", "j"},
+        {"long", "l"}, {"unsigned long", "m"}, {"long long", "x"}, {"unsigned long long", "y"},
+        {"float", "f"}, {"double", "d"}, {"long double", "e"},
+        {"wchar_t", "w"}, {"char16_t", "Ds"}, {"char32_t", "Di"},
+    };
+    return codes.at(builtin);
+}
+
+std::string msvcBuiltin(const std::string& builtin) {
+    static const std::map<std::string, std::string> codes = {
+        {"void", "X"}, {"bool", "_N"}, {"char", "D"}, {"signed char", "C"}, {"unsigned char", "E"},
+        {"short", "F"}, {"unsigned short", "G"}, {"int", "H"}, {"unsigned int", "I"},
+        {"long", "J"}, {"unsigned long", "K"}, {"long long", "_J"}, {"unsigned long long", "_K"},
+        {"float", "M"}, {"double", "N"}, {"long double", "O"},
+        {"wchar_t", "_W"}, {"char16_t", "_S"}, {"char32_t", "_U"},
+    };
+    return codes.at(builtin);
+}
+
+/**
+ * @brief Itanium encoding with its substitution table, so repeated
+ *        components become `S_`, `S0_`, ...
+ */
+class ItaniumEncoder {
+public:
+    std::string function(const std::vector<std::string>& scope, const std::string& name,
+                         const std::vector<SpelledType>& params) {
+        std::vector<std::string> components = scope;
+        components.push_back(name);
+        std::string out = "_Z" + nested(components, false);
+        if (params.empty()) {
+            return out + "v";
+        }
+        for (const auto& param : params) {
+            // Top-level const is not part of the signature
+            out += type(param, param.layers.size(), false);
+        }
+        return out;
+    }
+
+private:
+    std::vector<std::string> candidates_;  // Unsubstituted encodings, in the order they complete
+
+    std::string substitution(const std::string& key) const {
+        auto it = std::find(candidates_.begin(), candidates_.end(), key);
+        if (it == candidates_.end()) {
+            return "";
+        }
+        size_t index = static_cast<size_t>(it - candidates_.begin());
+        if (index == 0) {
+            return "S_";
+        }
+        std::string digits;
+        for (size_t n = index - 1; ; n /= 36) {
+            digits.insert(digits.begin(), "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ"[n % 36]);
+            if (n < 36) {
+                break;
+            }
+        }
+        return "S" + digits + "_";
+    }
+
+    static std::string source(const std::string& id) {
+        return std::to_string(id.size()) + id;
+    }
+
+    static std::string joined(const std::vector<std::string>& components, size_t count) {
+        std::string key;
+        for (size_t i = 0; i < count; ++i) {
+            key += "::" + components[i];
+        }
+        return key;
+    }
+
+    /**
+     * @brief `N...E` for scoped names; every scope is a candidate, and so
+     *        is the whole name when it names a type
+     */
+    std::string nested(const std::vector<std::string>& components, bool is_type) {
+        size_t seen = 0;
+        std::string out;
+        for (size_t n = components.size() - (is_type ? 0 : 1); n > 0; --n) {
+            std::string ref = substitution(joined(components, n));
+            if (!ref.empty()) {
+                if (n == components.size()) {
+                    return ref;
+                }
+                out = ref;
+                seen = n;
+                break;
+            }
+        }
+        for (size_t i = seen; i < components.size(); ++i) {
+            out += source(components[i]);
+            if (i + 1 < components.size() || is_type) {
+                candidates_.push_back(joined(components, i + 1));
+            }
+        }
+        return components.size() == 1 ? out : "N" + out + "E";
+    }
+
+    static std::string key(const SpelledType& t, size_t level, bool with_const) {
+        std::string inner = level == 0
+            ? (t.is_builtin ? itaniumBuiltin(t.base) : joined(t.qualified, t.qualified.size()))
+            : (t.layers[level - 1].is_reference ? "R" : "P") + key(t, level - 1, t.constAt(level - 1));
+        return (with_const ? "K" : "") + inner;
+    }
+
+    std::string type(const SpelledType& t, size_t level, bool with_const) {
+        if (level == 0 && !with_const) {
+            return t.is_builtin ? itaniumBuiltin(t.base) : nested(t.qualified, true);
+        }
+        std::string full = key(t, level, with_const);
+        std::string ref = substitution(full);
+        if (!ref.empty()) {
+            return ref;
+        }
+        std::string out = with_const ? "K" + type(t, level, false)
+                                     : (t.layers[level - 1].is_reference ? "R" : "P") +
+                                       type(t, level - 1, t.constAt(level - 1));
+        candidates_.push_back(full);
+        return out;
+    }
+};
+
+/**
+ * @brief MSVC encoding with its two back-reference tables: name fragments,
+ *        and argument types longer than one character
+ */
+class MsvcEncoder {
+public:
+    explicit MsvcEncoder(bool x64) : x64_(x64) {}
+
+    std::string function(const std::vector<std::string>& scope, const std::string& name,
+                         const SpelledType& result, const std::vector<SpelledType>& params) {
+        std::vector<std::string> components = scope;
+        components.push_back(name);
+        // `YA`: a free function with the __cdecl calling convention
+        std::string out = "?" + qualified(components) + "YA";
+
+        if (result.layers.empty() && result.is_builtin) {
+            if (result.base_const && result.base != "void") {
+                out += "?B";
+            }
+            out += msvcBuiltin(result.base);
+        } else if (result.layers.empty()) {
+            // Classes and enums are returned with their qualifiers spelled out
+            out += std::string("?") + (result.base_const ? "B" : "A") + named(result);
+        } else {
+            out += type(result, result.layers.size());
+        }
+
+        if (params.empty()) {
+            return out + "XZ";
+        }
+        for (const auto& param : params) {
+            // Only types encoded in more than one character are remembered,
+            // and a repeat is matched by type before its names are written
+            bool single = param.layers.empty() && param.is_builtin && msvcBuiltin(param.base).size() == 1;
+            std::string key = single ? "" : argumentKey(param);
+            auto it = std::find(arguments_.begin(), arguments_.end(), key);
+            if (!single && it != arguments_.end()) {
+                out += std::to_string(it - arguments_.begin());
+                continue;
+            }
+            if (!single && arguments_.size() < 10) {
+                arguments_.push_back(key);
+            }
+            // By-value const is dropped, a const pointer keeps its `Q`
+            out += param.layers.empty() ? (param.is_builtin ? msvcBuiltin(param.base) : named(param))
+                                        : type(param, param.layers.size());
+        }
+        return out + "@Z";
+    }
+
+private:
+    bool x64_;
+    std::vector<std::string> names_;
+    std::vector<std::string> arguments_;  // Argument types as argumentKey spells them
+
+    static std::string argumentKey(const SpelledType& t) {
+        std::string key = t.is_builtin ? t.base : std::string(1, t.tag);
+        for (const auto& component : t.qualified) {
+            key += "::" + component;
+        }
+        key += t.layers.empty() || !t.base_const ? "" : " const";
+        for (const auto& layer : t.layers) {
+            key += layer.is_reference ? "&" : layer.is_const ? "* const" : "*";
+        }
+        return key;
+    }
+
+    std::string fragment(const std::string& id) {
+        auto it = std::find(names_.begin(), names_.end(), id);
+        if (it != names_.end()) {
+            return std::to_string(it - names_.begin());
+        }
+        if (names_.size() < 10) {
+            names_.push_back(id);
+        }
+        return id + "@";
+    }
+
+    /**
+     * @brief Name, then its scopes innermost first, closed by `@`
+     */
+    std::string qualified(const std::vector<std::string>& components) {
+        std::string out;
+        for (auto it = components.rbegin(); it != components.rend(); ++it) {
+            out += fragment(*it);
+        }
+        return out + "@";
+    }
+
+    std::string named(const SpelledType& t) {
+        return (t.tag == 'W' ? std::string("W4") : std::string(1, t.tag)) + qualified(t.qualified);
+    }
+
+    /**
+     * @brief Pointer or reference with `level` layers: its own kind and
+     *        constness, `E` for 64-bit, then the pointee's cv and the pointee
+     */
+    std::string type(const SpelledType& t, size_t level) {
+        const auto& layer = t.layers[level - 1];
+        std::string out = layer.is_reference ? "A" : layer.is_const ? "Q" : "P";
+        out += x64_ ? "E" : "";
+        out += t.constAt(level - 1) ? "B" : "A";
+        if (level == 1) {
+            return out + (t.is_builtin ? msvcBuiltin(t.base) : named(t));
+        }
+        return out + type(t, level - 1);
+    }
+};
+
+} // namespace
+
+NameMangler::NameMangler(const std::vector<FFIClass>& classes, const std::vector<FFIEnum>& enums) {
+    for (const auto& cls : classes) {
+        std::vector<std::string> scope = splitScope(cls.namespace_path);
+        std::vector<std::string> name = splitScope(cls.name);
+        scope.insert(scope.end(), name.begin(), name.end() - 1);
+        types_[name.back()] = {scope, cls.is_struct ? 'U' : 'V'};
+    }
+    for (const auto& ffi_enum : enums) {
+        if (!ffi_enum.name.empty()) {
+            types_[ffi_enum.name] = {splitScope(ffi_enum.namespace_path), 'W'};
+        }
+    }
+}
+
+std::string NameMangler::mangle(const FFIFunction& func, ManglingScheme scheme) const {
+    if (!func.class_name.empty() || !func.template_of.empty() || !isIdentifier(func.name)) {
+        return "";
+    }
+    std::vector<std::string> scope = splitScope(func.namespace_path);
+    if (!scope.empty() && scope.front() == "std") {
+        return "";
+    }
+
+    auto resolve = [&](const std::string& cpp_type, SpelledType& type) {
+        type = spell(cpp_type.empty() ? "void" : cpp_type);
+        if (!type.valid || type.is_builtin) {
+            return type.valid;
+        }
+        std::string builtin = cstdintType(type.base, scheme);
+        if (!builtin.empty()) {
+            type.base = builtin;
+            type.is_builtin = true;
+            return true;
+        }
+        // Written as `geo::Vec` or just `Vec`, the qualifiers must close its scope
+        std::vector<std::string> written = splitScope(type.base);
+        auto named = written.empty() ? types_.end() : types_.find(written.back());
+        if (named == types_.end() || written.size() - 1 > named->second.scope.size() ||
+            !std::equal(written.begin(), written.end() - 1, named->second.scope.end() - (written.size() - 1))) {
+            return false;
+        }
+        type.qualified = named->second.scope;
+        type.qualified.push_back(written.back());
+        type.tag = named->second.tag;
+        return true;
+    };
+
+    SpelledType result;
+    if (!resolve(func.return_type, result)) {
+        return "";
+    }
+    std::vector<SpelledType> params(func.parameters.size());
+    for (size_t i = 0; i < func.parameters.size(); ++i) {
+        if (!resolve(func.parameters[i].cpp_type, params[i]) ||
+            (params[i].is_builtin && params[i].base == "void" && params[i].layers.empty())) {
+            return "";
+        }
+    }
+
+    if (scheme == ManglingScheme::Itanium || scheme == ManglingScheme::Itanium32) {
+        return ItaniumEncoder().function(scope, func.name, params);
+    }
+    return MsvcEncoder(scheme == ManglingScheme::MSVC64).function(scope, func.name, result, params);
+}
+
+bool NameMangler::linksByMangledName(const FFIFunction& func) const {
+    for (auto scheme : {ManglingScheme::Itanium, ManglingScheme::Itanium32, ManglingScheme::MSVC32, ManglingScheme::MSVC64}) {
+        if (mangle(func, scheme).empty()) {
+            return false;
+        }
+    }
+    return true;
+}
+
+} // namespace ffi
+} // namespace hybrid_transpiler
@@ -0,0 +1,3318 @@
+/**
+ * @file rust_ffi_gen.cpp
+ * @brief Rust FFI bindings generator implementation
+ *
+ * Emits an `extern "C"` block for the C shims plus safe Rust wrappers,
+ * using the opaque pointer pattern for C++ classes.
+ */
+
+#include "ffi.h"
+#include "codegen.h"
+#include <algorithm>
+#include <cctype>
+#include <regex>
+#include <sstream>
+#include <stdexcept>
+#include <unordered_map>
+#include <utility>
+
+namespace hybrid_transpiler {
+namespace ffi {
+
+namespace {
+
+std::string trim(const std::string& str) {
+    size_t start = str.find_first_not_of(" \t\n\r");
+    if (start == std::string::npos) {
+        return "";
+    }
+    size_t end = str.find_last_not_of(" \t\n\r");
+    return str.substr(start, end - start + 1);
+}
+
+bool isVoid(const std::string& cpp_type) {
+    std::string t = trim(cpp_type);
+    return t.empty() || t == "void";
+}
+
+/**
+ * @brief Named type without const, pointer or reference qualifiers
+ */
+std::string baseType(const std::string& cpp_type) {
+    std::string base = trim(cpp_type);
+    if (base.find("const ") == 0) {
+        base = trim(base.substr(6));
+    }
+    while (!base.empty() && (base.back() == '*' || base.back() == '&')) {
+        base = trim(base.substr(0, base.size() - 1));
+    }
+    return base;
+}
+
+/**
+ * @brief Tick count of a `std::time::Duration`, saturating at the count type's maximum
+ */
+std::string durationToCount(const std::string& value, const std::string& unit, const std::string& count_type) {
+    std::string ticks = unit == "minutes" ? value + ".as_secs() / 60"
+                      : unit == "hours"   ? value + ".as_secs() / 3600"
+                                          : value + ".as_" + unit + "()";
+    return count_type + "::try_from(" + ticks + ").unwrap_or(" + count_type + "::MAX)";
+}
+
+/**
+ * @brief `std::time::Duration` from a tick count; it is unsigned, so negative counts become zero
+ */
+std::string countToDuration(const std::string& value, const std::string& unit) {
+    std::string ticks = "u64::try_from(" + value + ").unwrap_or(0)";
+    if (unit == "minutes" || unit == "hours") {
+        return "std::time::Duration::from_secs(" + ticks + ".saturating_mul(" + (unit == "minutes" ? "60" : "3600") + "))";
+    }
+    return "std::time::Duration::from_" + unit + "(" + ticks + ")";
+}
+
+/**
+ * @brief Chain of by-value fields leading from `from` back into `target`,
+ *        empty when `target` is only reached through pointers
+ */
+std::string embeddingCycle(const std::string& target, const FFIClass& from, const std::vector<FFIClass>& classes,
+                           std::set<std::string>& visited) {
+    visited.insert(from.name);
+    for (const auto& field : from.fields) {
+        if (field.is_pointer || field.is_reference) {
+            continue;
+        }
+        std::string link = "`" + from.name + "::" + field.name + "`";
+        std::string type = baseType(field.cpp_type);
+        if (type == target) {
+            return link;
+        }
+        for (const auto& next : classes) {
+            if (next.name == type && isReprC(next) && !visited.count(type)) {
+                std::string rest = embeddingCycle(target, next, classes, visited);
+                if (!rest.empty()) {
+                    return link + " -> " + rest;
+                }
+            }
+        }
+    }
+    return "";
+}
+
+bool isConstPointer(const std::string& cpp_type) {
+    return trim(cpp_type).find("const") == 0;
+}
+
+bool isGetter(const FFIFunction& method) {
+    return method.is_const && !method.is_static && method.parameters.empty() &&
+           !isVoid(method.return_type) && method.return_type.find('*') == std::string::npos;
+}
+
+/**
+ * @brief `watch_progress` / `onEvent` as a type name, `WatchProgress` / `OnEvent`
+ */
+std::string pascalCase(const std::string& name) {
+    std::string result;
+    bool upper = true;
+    for (char c : name) {
+        if (c == '_') {
+            upper = true;
+            continue;
+        }
+        result += upper ? static_cast<char>(std::toupper(static_cast<unsigned char>(c))) : c;
+        upper = false;
+    }
+    return result;
+}
+
+/**
+ * @brief `void*`, however it is spaced
+ */
+bool isVoidPointer(const std::string& cpp_type) {
+    std::string type = cpp_type;
+    type.erase(std::remove(type.begin(), type.end(), ' '), type.end());
+    return type == "void*";
+}
+
+/**
+ * @brief Statements run before the FFI call, one per line
+ */
+std::string indented(const std::vector<std::string>& lines, const std::string& indent) {
+    std::string text;
+    for (const auto& line : lines) {
+        text += indent + line + "\n";
+    }
+    return text;
+}
+
+/**
+ * @brief The C++ doc comment as `///` lines, followed by a `///` separator
+ *        when generated documentation comes after it
+ */
+std::string docComment(const std::string& doc_comment, const std::string& indent, bool separate = false) {
+    std::string text;
+    for (const auto& line : hybrid::rustDocLines(doc_comment)) {
+        text += indent + line + "\n";
+    }
+    return text.empty() || !separate ? text : text + indent + "///\n";
+}
+
+/**
+ * @brief `# Panics` section for a wrapper whose locals convert strings to
+ *        C strings, empty when none does
+ */
+std::string panicsDoc(const std::vector<std::string>& locals, const std::string& indent) {
+    static const std::regex nul(R"(expect\("`((?:r#)?\w+)` contains a NUL byte"\))");
+    static const std::regex unicode(R"(expect\("`((?:r#)?\w+)` is not valid Unicode"\))");
+    std::vector<std::string> names;
+    std::set<std::string> non_unix;
+    std::smatch match;
+    for (const auto& local : locals) {
+        if (std::regex_search(local, match, unicode)) {
+            non_unix.insert(match[1]);
+        }
+        if (std::regex_search(local, match, nul) &&
+            std::find(names.begin(), names.end(), match[1].str()) == names.end()) {
+            names.push_back(match[1]);
+        }
+    }
+    std::string lines;
+    for (const auto& name : names) {
+        lines += indent + "/// If `" + name + "` contains a NUL byte" +
+                 (non_unix.count(name) ? ", or is not valid Unicode on targets other than Unix" : "") + ".\n";
+    }
+    return lines.empty() ? "" : indent + "/// # Panics\n" + indent + "///\n" + lines;
+}
+
+/**
+ * @brief `# Safety` section for a wrapper whose parameters include raw
+ *        pointers, empty when there are none
+ *
+ * Nothing in Rust can check what C++ does through such a pointer, so the
+ * wrapper is an `unsafe fn` and the caller vouches for each one.
+ */
+std::string pointerSafety(const std::string& params, const std::string& symbol, const std::string& indent) {
+    static const std::regex raw(R"((?:^|, )((?:r#)?\w+): \*(const|mut) )");
+    std::string lines;
+    for (std::sregex_iterator it(params.begin(), params.end(), raw), end; it != end; ++it) {
+        std::string access = (*it)[2] == "const" ? "reads" : "reads and writes";
+        lines += indent + "/// `" + (*it)[1].str() + "` must be valid for the " + access + " `" + symbol +
+                 "` makes through it.\n";
+    }
+    return lines.empty() ? "" : indent + "/// # Safety\n" + indent + "///\n" + lines;
+}
+
+/**
+ * @brief Whether extern declarations name a type of the parent module:
+ *        `c_void`, or a CamelCase struct, enum or callback alias
+ *
+ * Decides if `use super::*;` would be used. String literals such as the
+ * mangled link names are not looked at.
+ */
+bool namesParentTypes(const std::string& declarations) {
+    static const std::regex strings(R"("[^"]*")");
+    static const std::regex type(R"(\b(c_void|[A-Z]\w*)\b)");
+    std::string code = std::regex_replace(declarations, strings, "\"\"");
+    for (std::sregex_iterator it(code.begin(), code.end(), type), end; it != end; ++it) {
+        if ((*it)[1] != "Option") {
+            return true;
+        }
+    }
+    return false;
+}
+
+/**
+ * @brief `#[deprecated]` line mirroring a C++ `[[deprecated]]`, empty otherwise
+ *
+ * C++ and Rust string escapes agree, so the note is copied as written.
+ */
+std::string deprecatedAttribute(bool is_deprecated, const std::string& note, const std::string& indent) {
+    if (!is_deprecated) {
+        return "";
+    }
+    return indent + (note.empty() ? "#[deprecated]" : "#[deprecated(note = \"" + note + "\")]") + "\n";
+}
+
+/**
+ * @brief Whether any wrapped item carries `#[deprecated]`
+ */
+bool anyDeprecated(const std::vector<FFIFunction>& functions, const std::vector<FFIClass>& classes,
+                   const std::vector<FFIEnum>& enums) {
+    auto deprecated = [](const FFIFunction& f) { return f.is_deprecated; };
+    if (std::any_of(functions.begin(), functions.end(), deprecated) ||
+        std::any_of(enums.begin(), enums.end(), [](const FFIEnum& e) { return e.is_deprecated; })) {
+        return true;
+    }
+    return std::any_of(classes.begin(), classes.end(), [&](const FFIClass& cls) {
+        return cls.is_deprecated || std::any_of(cls.methods.begin(), cls.methods.end(), deprecated) ||
+               std::any_of(cls.static_methods.begin(), cls.static_methods.end(), deprecated);
+    });
+}
+
+/**
+ * @brief `#[cfg]` predicate selecting the Rust targets that follow an ABI
+ */
+std::string targetCfg(AbiTarget target) {
+    switch (target) {
+        case AbiTarget::SysV64:
+            return "all(target_arch = \"x86_64\", not(windows))";
+        case AbiTarget::Win64:
+            return "all(target_arch = \"x86_64\", windows)";
+        case AbiTarget::AArch64:
+            return "target_arch = \"aarch64\"";
+    }
+    return "";
+}
+
+/**
+ * @brief C++ declaration a function wrapper comes from, as the table of
+ *        contents names it: `int32_t add(int32_t, int32_t)`
+ */
+std::string declarationOf(const FFIFunction& func) {
+    std::string source = func.template_of.empty() ? func.name : func.template_of + "<" + func.template_args + ">";
+    std::string origin = (isVoid(func.return_type) ? "void" : trim(func.return_type)) + " " + source + "(";
+    for (size_t i = 0; i < func.parameters.size(); ++i) {
+        if (i > 0) origin += ", ";
+        origin += trim(func.parameters[i].cpp_type);
+    }
+    return origin + ")";
+}
+
+/**
+ * @brief Body shared by every wrapper that returns a possibly-null
+ *        pointer to an instance of the receiver's class
+ */
+std::string nullCheckedReturn(const std::string& call, const std::string& wrap,
+                              bool const_ptr, const std::string& indent, const std::string& safety) {
+    std::stringstream ss;
+    ss << safety;
+    ss << indent << "let ptr = unsafe { " << call << " };\n";
+    ss << indent << "if ptr.is_null() {\n";
+    ss << indent << "    None\n";
+    ss << indent << "} else {\n";
+    ss << indent << "    Some(" << wrap << "(ptr" << (const_ptr ? ".cast_mut()" : "") << "))\n";
+    ss << indent << "}\n";
+    return ss.str();
+}
+
+/**
+ * @brief Sentinel argument for the ABI self-test, empty if the Rust type
+ *        is not a scalar the harness knows how to exercise
+ *
+ * Values are negative or have high bits set so that sign extension and
+ * truncation mistakes change the checksum.
+ */
+std::string abiSentinel(const std::string& rust_type, size_t index) {
+    static const std::unordered_map<std::string, long long> signed_bases = {
+        {"i8", -0x25}, {"i16", -0x1234}, {"i32", -0x123456}, {"i64", -0x123456789abcLL},
+        {"std::ffi::c_long", -0x123456},
+    };
+    static const std::unordered_map<std::string, unsigned long long> unsigned_bases = {
+        {"u8", 0xa5}, {"u16", 0xa5a5}, {"u32", 0xa5a5a5a5}, {"u64", 0xa5a5a5a5a5a5a5a5ULL}, {"usize", 0xa5a5a5a5},
+        {"std::ffi::c_ulong", 0xa5a5a5a5},
+    };
+
+    std::stringstream ss;
+    auto s = signed_bases.find(rust_type);
+    if (s != signed_bases.end()) {
+        ss << "-0x" << std::hex << -(s->second - static_cast<long long>(index));
+        return ss.str();
+    }
+    auto u = unsigned_bases.find(rust_type);
+    if (u != unsigned_bases.end()) {
+        ss << "0x" << std::hex << (u->second - index);
+        return ss.str();
+    }
+    if (rust_type == "f32" || rust_type == "f64") {
+        ss << (rust_type == "f32" ? 1.5 : -2.25) + static_cast<double>(index);
+        std::string value = ss.str();
+        return value.find('.') == std::string::npos ? value + ".0" : value;
+    }
+    if (rust_type == "bool") {
+        return index % 2 == 0 ? "true" : "false";
+    }
+    return "";
+}
+
+/**
+ * @brief Rust expression widening a scalar to the u64 the checksum hashes
+ */
+std::string abiBits(const std::string& expr, const std::string& rust_type) {
+    if (rust_type == "f32" || rust_type == "f64") {
+        return expr + ".to_bits() as u64";
+    }
+    return expr + " as u64";
+}
+
+} // namespace
+
+std::string RustFFIGenerator::rustType(const std::string& cpp_type) {
+    // Durations cross as their tick count
+    if (isDuration(cpp_type)) {
+        return rustType(durationRep(cpp_type));
+    }
+
+    // Structs mirrored in Rust are passed by value or by typed pointer
+    std::string base = trim(cpp_type);
+    for (size_t pos; (pos = base.find("volatile ")) != std::string::npos;) {
+        base.erase(pos, 9);
+    }
+    bool is_const = base.find("const") == 0;
+    if (is_const) {
+        base = trim(base.substr(5));
+    }
+    bool is_pointer = !base.empty() && base.back() == '*';
+    if (is_pointer) {
+        base = trim(base.substr(0, base.size() - 1));
+    }
+    // The shims pass references to plain values as pointers
+    if (!is_pointer && isBorrowable(cpp_type)) {
+        std::string pointee = rustType(baseType(base));
+        return (is_const ? "*const " : "*mut ") + pointee;
+    }
+    if (repr_c_structs_.count(base)) {
+        return is_pointer ? (is_const ? "*const " : "*mut ") + rustTypeName(base) : rustTypeName(base);
+    }
+    // Enums cross the boundary as their underlying integer
+    auto enum_repr = enum_types_.find(base);
+    if (enum_repr != enum_types_.end()) {
+        return is_pointer ? (is_const ? "*const " : "*mut ") + enum_repr->second : enum_repr->second;
+    }
+    // C function pointers may be null
+    auto callback = callback_types_.find(base);
+    if (callback != callback_types_.end() && !is_pointer) {
+        std::string fn = "Option<unsafe extern \"C\" fn(";
+        for (size_t i = 0; i < callback->second.parameters.size(); ++i) {
+            fn += (i > 0 ? ", " : "") + rustType(callback->second.parameters[i].cpp_type);
+        }
+        fn += ")";
+        if (!isVoid(callback->second.return_type)) {
+            fn += " -> " + rustType(callback->second.return_type);
+        }
+        return fn + ">";
+    }
+
+    std::string mapped = analyzer_.toRustFFIType(is_pointer ? trim(cpp_type) : base);
+
+    // The generated module imports c_void, keep signatures short
+    const std::string full = "std::ffi::c_void";
+    size_t pos = mapped.find(full);
+    if (pos != std::string::npos) {
+        mapped.replace(pos, full.size(), "c_void");
+    }
+    return mapped;
+}
+
+bool RustFFIGenerator::returnsSelfPointer(const FFIFunction& func, const FFIClass& cls) const {
+    std::string type = trim(func.return_type);
+    if (type.find("const") == 0) {
+        type = trim(type.substr(5));
+    }
+    if (type.empty() || type.back() != '*') {
+        return false;
+    }
+    type.pop_back();
+    return trim(type) == cls.name;
+}
+
+bool RustFFIGenerator::isSingletonAccessor(const FFIFunction& func, const FFIClass& cls) const {
+    // `static Cls& instance()`: the reference outlives every caller
+    if (!func.is_static) {
+        return false;
+    }
+    std::string type = trim(func.return_type);
+    if (type.find("const") == 0) {
+        type = trim(type.substr(5));
+    }
+    if (type.empty() || type.back() != '&') {
+        return false;
+    }
+    type.pop_back();
+    return trim(type) == cls.name;
+}
+
+bool RustFFIGenerator::returnsBorrowed(const FFIFunction& func) const {
+    switch (func.return_ownership) {
+        case PointerOwnership::Owned:
+            return false;
+        case PointerOwnership::Borrowed:
+            return true;
+        case PointerOwnership::Inferred:
+        default:
+            // Instance accessors like `Node* parent()` point into an
+            // existing object graph; static factories hand out new objects
+            return !func.is_static;
+    }
+}
+
+bool RustFFIGenerator::ownsCString(const FFIFunction& func) const {
+    std::string type = trim(func.return_type);
+    type.erase(std::remove(type.begin(), type.end(), ' '), type.end());
+    // Free functions have no object the buffer could belong to
+    return !options_.free_function.empty() && type == "char*" && ownsReturnedPointer(func);
+}
+
+bool RustFFIGenerator::borrowsCString(const FFIFunction& func) const {
+    std::string type = trim(func.return_type);
+    type.erase(std::remove(type.begin(), type.end(), ' '), type.end());
+    // One declared owned stays a raw pointer for the caller to free
+    return type == "constchar*" && func.return_ownership != PointerOwnership::Owned;
+}
+
+bool RustFFIGenerator::returnsStructArray(const FFIFunction& func) const {
+    return returnsCountedArray(func) && repr_c_structs_.count(baseType(func.return_type));
+}
+
+std::string RustFFIGenerator::arrayFreeFunction(const FFIFunction& func) const {
+    return options_.free_function.empty() ? shimName(func) + "_free" : options_.free_function;
+}
+
+const RefCounting* RustFFIGenerator::refCounting(const FFIClass& cls) const {
+    auto it = options_.refcounted.find(cls.name);
+    return it == options_.refcounted.end() ? nullptr : &it->second;
+}
+
+bool RustFFIGenerator::needsView(const FFIClass& cls) const {
+    return viewed_classes_.count(cls.name) > 0;
+}
+
+std::string RustFFIGenerator::viewedClass(const FFIFunction& func) const {
+    std::string type = trim(func.return_type);
+    if (type.find("const") == 0) {
+        type = trim(type.substr(5));
+    }
+    if (type.empty() || type.back() != '*') {
+        return "";
+    }
+    type = trim(type.substr(0, type.size() - 1));
+    return opaque_classes_.count(type) && !ownsReturnedPointer(func) ? type : "";
+}
+
+bool RustFFIGenerator::isBorrowable(const std::string& cpp_type) {
+    std::string type = trim(cpp_type);
+    if (type.empty() || type.back() != '&' || type.find_first_of("*&") != type.size() - 1) {
+        return false;
+    }
+    std::string base = baseType(type);
+    if (repr_c_structs_.count(base) || enum_types_.count(base)) {
+        return true;
+    }
+    std::string mapped = analyzer_.toRustFFIType(base);
+    return mapped != "()" && mapped.find('*') == std::string::npos;
+}
+
+std::string RustFFIGenerator::safety(const std::string& invariant, const std::string& indent) const {
+    return options_.safety_comments ? indent + "// SAFETY: " + invariant + "\n" : "";
+}
+
+std::string RustFFIGenerator::callInvariant(const FFIFunction& func) {
+    // Mirrors how paramList passes each argument
+    std::vector<std::string> facts;
+    if (!func.class_name.empty() && !func.is_static && !isConstructor(func)) {
+        facts.push_back("`self.ptr` points to a live " + rustTypeName(func.class_name));
+    }
+    for (size_t i = 0; i < func.parameters.size(); ++i) {
+        const auto& param = func.parameters[i];
+        std::string name = "`" + paramName(param, i) + "`";
+        if (i + 1 == func.parameters.size() && returnsStructArray(func)) {
+            facts.push_back(name + " is a local the shim writes the length to");
+        } else if (!durationUnit(param.cpp_type).empty()) {
+            continue;
+        } else if (isEnumOutParam(param)) {
+            facts.push_back(name + " is a local the shim writes to");
+        } else if (isBorrowable(param.cpp_type)) {
+            facts.push_back(name + " is a reference, so non-null and aligned");
+        } else if (!param.span_element.empty()) {
+            facts.push_back(name + "'s pointer and length come from one slice");
+        } else if (isStringParam(param)) {
+            facts.push_back(name + " is a NUL-terminated copy that outlives the call");
+        } else if (isStringViewParam(param)) {
+            facts.push_back(name + "'s pointer and length come from one string");
+        } else if (rustType(param.cpp_type).find('*') == 0) {
+            facts.push_back(name + " is forwarded as the caller's raw pointer");
+        }
+    }
+
+    if (facts.empty()) {
+        return "`" + shimName(func) + "` takes " + (func.parameters.empty() ? "no arguments" : "only plain values");
+    }
+    std::string joined;
+    for (const auto& fact : facts) {
+        joined += (joined.empty() ? "" : "; ") + fact;
+    }
+    return joined;
+}
+
+std::string RustFFIGenerator::referenceReturn(const FFIFunction& func, std::string& owner) {
+    owner.clear();
+    if (!isBorrowable(func.return_type) || enum_types_.count(baseType(func.return_type))) {
+        return "";
+    }
+    bool mutable_return = !isConstPointer(func.return_type);
+    std::string target = rustType(baseType(func.return_type));
+    std::string symbol = func.class_name.empty() ? func.name : func.class_name + "::" + func.name;
+
+    bool has_receiver = !func.class_name.empty() && !func.is_static && !isConstructor(func);
+    std::string reason;
+    if (has_receiver) {
+        // Elision ties the borrow to the receiver
+        if (!mutable_return || !func.is_const) {
+            return std::string(mutable_return ? "&mut " : "&") + target;
+        }
+        reason = "returns a mutable reference from a const method";
+    } else {
+        // Otherwise the only reference argument must be what the result points into
+        std::vector<size_t> candidates;
+        for (size_t i = 0; i < func.parameters.size(); ++i) {
+            if (isBorrowable(func.parameters[i].cpp_type)) {
+                candidates.push_back(i);
+            }
+        }
+        if (candidates.size() == 1) {
+            const auto& param = func.parameters[candidates[0]];
+            if (!mutable_return || !param.is_const) {
+                owner = paramName(param, candidates[0]);
+                return std::string(mutable_return ? "&'a mut " : "&'a ") + target;
+            }
+            reason = "returns a mutable reference into the const argument `" + paramName(param, candidates[0]) + "`";
+        } else {
+            reason = "returns a reference with no obvious owner";
+        }
+    }
+
+    FFIDiagnostic diagnostic{symbol, reason + "; the wrapper returns a raw pointer"};
+    bool reported = std::any_of(diagnostics_.begin(), diagnostics_.end(), [&](const FFIDiagnostic& d) {
+        return d.symbol == diagnostic.symbol && d.message == diagnostic.message;
+    });
+    if (!reported) {
+        diagnostics_.push_back(diagnostic);
+    }
+    return "";
+}
+
+bool RustFFIGenerator::isEnum(const std::string& cpp_type) const {
+    std::string type = trim(cpp_type);
+    return type.find_first_of("*&") == std::string::npos && enum_types_.count(baseType(type));
+}
+
+bool RustFFIGenerator::isEnumOutParam(const FFIParameter& param) const {
+    std::string type = trim(param.cpp_type);
+    return !param.is_const && !type.empty() && type.back() == '*' &&
+           type.find('*') == type.size() - 1 && enum_types_.count(baseType(type));
+}
+
+std::string RustFFIGenerator::generateFunctionBinding(const FFIFunction& func) {
+    std::stringstream ss;
+    ss << "pub fn " << rustIdent(shimName(func)) << "(";
+
+    bool first = true;
+    if (!func.class_name.empty() && !func.is_static && !isConstructor(func)) {
+        ss << "this: " << (func.is_const ? "*const c_void" : "*mut c_void");
+        first = false;
+    }
+
+    for (size_t i = 0; i < func.parameters.size(); ++i) {
+        const auto& param = func.parameters[i];
+        if (!first) ss << ", ";
+        first = false;
+
+        if (!param.span_element.empty()) {
+            // The shim rebuilds the span as std::span<T[, N]>(ptr, len)
+            std::string element = rustType(param.span_element);
+            ss << paramName(param, i) << ": " << (param.span_const ? "*const " : "*mut ") << element
+               << ", " << paramName(param, i) << "_len: usize";
+            continue;
+        }
+        if (isStringViewParam(param)) {
+            // The shim rebuilds the std::string_view over the Rust bytes
+            ss << paramName(param, i) << ": *const u8, " << paramName(param, i) << "_len: usize";
+            continue;
+        }
+
+        // The shim builds a std::string from the NUL-terminated copy
+        std::string type = isStringParam(param) ? "*const i8"
+                         : param.rust_type.empty() ? rustType(param.cpp_type) : param.rust_type;
+        ss << paramName(param, i) << ": " << type;
+    }
+
+    std::string out_struct = outPointerReturn(func, memory_structs_);
+    if (!out_struct.empty()) {
+        // The shim writes the result into the caller's storage
+        ss << (first ? "" : ", ") << outPointerName(func) << ": *mut " << rustType(out_struct);
+    }
+    ss << ")";
+
+    if (isConstructor(func)) {
+        ss << " -> *mut c_void";
+    } else if (func.name == "operator<=>") {
+        // The shim folds the ordering category into -1, 0, 1 (2 = unordered)
+        ss << " -> i32";
+    } else if (!isVoid(func.return_type) && out_struct.empty()) {
+        ss << " -> " << rustType(func.return_type);
+    }
+
+    ss << ";";
+    return ss.str();
+}
+
+std::string RustFFIGenerator::rustDefault(const FFIParameter& param) {
+    static const std::regex integer(R"(([-+]?)(0[xX][0-9a-fA-F]+|[0-9]+)[uUlL]*)");
+    static const std::regex floating(R"(([-+]?)([0-9]*)\.([0-9]*)([eE][-+]?[0-9]+)?[fFlL]?)");
+    static const std::regex character(R"('([^'\\])')");
+    // Escapes Rust spells the same, a NUL would end the C string early
+    static const std::regex string(R"re("(?:[^"\\]|\\[nrt\\"'])*")re");
+
+    std::string value = trim(param.default_value);
+    std::string type = param.rust_type.empty() ? rustType(param.cpp_type) : param.rust_type;
+    bool is_float = type == "f32" || type == "f64";
+    std::smatch match;
+
+    if (value == "true" || value == "false") {
+        return type == "bool" ? value : "";
+    }
+    if (value == "nullptr" || value == "NULL") {
+        if (type.find("*const") == 0) return "std::ptr::null()";
+        if (type.find("*mut") == 0) return "std::ptr::null_mut()";
+        return "";
+    }
+    if (std::regex_match(value, match, integer) && type.find('*') == std::string::npos) {
+        std::string literal = (match[1] == "-" ? "-" : "") + match[2].str();
+        bool hex = match[2].str().size() > 1 && (match[2].str()[1] == 'x' || match[2].str()[1] == 'X');
+        if (is_float) {
+            return hex ? "" : literal + ".0";
+        }
+        return type == "bool" ? "" : literal;
+    }
+    if (std::regex_match(value, match, floating) && is_float && (match[2].length() > 0 || match[3].length() > 0)) {
+        return (match[1] == "-" ? "-" : "") + (match[2].length() > 0 ? match[2].str() : "0") + "." +
+               (match[3].length() > 0 ? match[3].str() : "0") + match[4].str();
+    }
+    if (std::regex_match(value, match, character) && (type == "i8" || type == "u8")) {
+        return "b'" + match[1].str() + "'" + (type == "i8" ? " as i8" : "");
+    }
+    if (std::regex_match(value, match, string) && param.rust_type.empty() &&
+        (isStringParam(param) || isStringViewParam(param))) {
+        return value;
+    }
+    return "";
+}
+
+bool RustFFIGenerator::hasLiteralDefaults(const FFIFunction& func) {
+    return std::any_of(func.parameters.begin(), func.parameters.end(),
+        [this](const FFIParameter& param) { return !rustDefault(param).empty(); });
+}
+
+std::string RustFFIGenerator::defaultArgumentProblem(const FFIFunction& func) {
+    for (size_t i = 0; i < func.parameters.size(); ++i) {
+        const auto& param = func.parameters[i];
+        if (!param.default_value.empty() && rustDefault(param).empty()) {
+            return "default argument `" + paramName(param, i) + " = " + trim(param.default_value) +
+                   "` is not a literal; callers must pass it explicitly";
+        }
+    }
+    return "";
+}
+
+void RustFFIGenerator::checkDurations(const FFIFunction& func) {
+    std::string symbol = func.class_name.empty() ? func.name : func.class_name + "::" + func.name;
+    auto check = [&](const std::string& cpp_type, const std::string& what) {
+        if (isDuration(cpp_type) && durationUnit(cpp_type).empty()) {
+            diagnostics_.push_back({symbol, what + " `" + trim(cpp_type) + "` has no std::time::Duration unit; "
+                                    "it is passed as the raw " + rustType(cpp_type) + " tick count"});
+        }
+    };
+    for (size_t i = 0; i < func.parameters.size(); ++i) {
+        check(func.parameters[i].cpp_type, "parameter `" + paramName(func.parameters[i], i) + "`");
+    }
+    check(func.return_type, "return type");
+}
+
+std::string RustFFIGenerator::paramList(const FFIFunction& func, bool omit_defaults, std::string& args,
+                                        std::vector<std::string>& locals, bool enum_outs) {
+    std::string sig;
+    for (size_t i = 0; i < func.parameters.size(); ++i) {
+        const auto& param = func.parameters[i];
+        std::string name = paramName(param, i);
+        std::string type = param.rust_type.empty() ? rustType(param.cpp_type) : param.rust_type;
+        std::string fallback = rustDefault(param);
+
+        std::string arg = name;
+        if (enum_outs && i + 1 == func.parameters.size() && returnsStructArray(func)) {
+            // C++ writes the element count, plainBody turns it into the slice length
+            locals.push_back("let mut " + name + ": usize = 0;");
+            arg = "&mut " + name;
+        } else if (param.rust_type.empty() && !durationUnit(param.cpp_type).empty()) {
+            sig += std::string(sig.empty() ? "" : ", ") + name + ": std::time::Duration";
+            arg = durationToCount(name, durationUnit(param.cpp_type), type);
+        } else if (enum_outs && isEnumOutParam(param)) {
+            // Written by C++ into a local, validated by enumResult
+            arg = "&mut " + name;
+        } else if (param.rust_type.empty() && isBorrowable(param.cpp_type)) {
+            // References coerce to the pointers the shim expects
+            sig += std::string(sig.empty() ? "" : ", ") + name + ": " + (param.is_const ? "&" : "&mut ") +
+                   rustType(baseType(param.cpp_type));
+        } else if (param.rust_type.empty() && isEnum(param.cpp_type)) {
+            sig += std::string(sig.empty() ? "" : ", ") + name + ": " + baseType(param.cpp_type);
+            arg = name + " as " + type;
+        } else if (!param.span_element.empty()) {
+            // Fixed-extent spans take an array so the length is checked at compile time
+            std::string element = rustType(param.span_element);
+            std::string slice = param.span_extent > 0
+                ? "[" + element + "; " + std::to_string(param.span_extent) + "]"
+                : "[" + element + "]";
+            sig += std::string(sig.empty() ? "" : ", ") + name + ": " + (param.span_const ? "&" : "&mut ") + slice;
+            arg = name + (param.span_const ? ".as_ptr(), " : ".as_mut_ptr(), ") + name + ".len()";
+        } else if (!fallback.empty() && omit_defaults) {
+            // String literals are written as C strings, views as pointer and length
+            arg = isStringViewParam(param) ? fallback + ".as_ptr(), " + fallback + ".len()"
+                  : isStringParam(param) ? "c" + fallback + ".as_ptr().cast()"
+                  : fallback;
+        } else if (isPathParam(param, i)) {
+            // Unix paths are arbitrary bytes, going through &str would reject or mangle them
+            bool as_ref = options_.string_params == StringParams::AsRef;
+            std::string path = as_ref ? "impl AsRef<std::path::Path>" : "&std::path::Path";
+            if (!fallback.empty() && options_.default_args == DefaultArgs::Option) {
+                sig += std::string(sig.empty() ? "" : ", ") + name + ": Option<" + path + ">";
+                locals.push_back("let " + name + " = " + name + (as_ref ? ".as_ref().map_or(" : ".unwrap_or(") +
+                                 "std::path::Path::new(" + fallback + ")" + (as_ref ? ", AsRef::as_ref" : "") + ");");
+            } else {
+                sig += std::string(sig.empty() ? "" : ", ") + name + ": " + path;
+                if (as_ref) {
+                    locals.push_back("let " + name + " = " + name + ".as_ref();");
+                }
+            }
+            locals.push_back("#[cfg(unix)]");
+            locals.push_back("let " + name + " = std::ffi::CString::new(std::os::unix::ffi::OsStrExt::as_bytes(" +
+                             name + ".as_os_str())).expect(\"`" + name + "` contains a NUL byte\");");
+            // The narrow Windows APIs take the ANSI code page, only Unicode paths convert reliably
+            locals.push_back("#[cfg(not(unix))]");
+            locals.push_back("let " + name + " = std::ffi::CString::new(" + name + ".to_str().expect(\"`" + name +
+                             "` is not valid Unicode\")).expect(\"`" + name + "` contains a NUL byte\");");
+            arg = name + ".as_ptr().cast()";
+        } else if (isStringParam(param)) {
+            // C++ reads up to the first NUL, so an interior one would silently truncate
+            bool as_ref = options_.string_params == StringParams::AsRef;
+            std::string text = as_ref ? "impl AsRef<str>" : "&str";
+            if (!fallback.empty() && options_.default_args == DefaultArgs::Option) {
+                sig += std::string(sig.empty() ? "" : ", ") + name + ": Option<" + text + ">";
+                locals.push_back("let " + name + " = " + name + (as_ref ? ".as_ref().map_or(" : ".unwrap_or(") +
+                                 fallback + (as_ref ? ", AsRef::as_ref" : "") + ");");
+                as_ref = false;
+            } else {
+                sig += std::string(sig.empty() ? "" : ", ") + name + ": " + text;
+            }
+            locals.push_back("let " + name + " = std::ffi::CString::new(" + name + (as_ref ? ".as_ref()" : "") +
+                             ").expect(\"`" + name + "` contains a NUL byte\");");
+            // c_char is u8 on some targets, the extern takes *const i8
+            arg = name + ".as_ptr().cast()";
+        } else if (isStringViewParam(param)) {
+            // Borrowed for the call, so no copy; a view needs no NUL and may hold any bytes
+            bool as_ref = options_.string_params == StringParams::AsRef;
+            std::string view = as_ref ? "impl AsRef<[u8]>" : "&str";
+            if (!fallback.empty() && options_.default_args == DefaultArgs::Option) {
+                sig += std::string(sig.empty() ? "" : ", ") + name + ": Option<" + view + ">";
+                locals.push_back("let " + name + " = " + name + (as_ref ? ".as_ref().map_or(" : ".unwrap_or(") +
+                                 fallback + (as_ref ? ".as_bytes(), AsRef::as_ref" : "") + ");");
+            } else {
+                sig += std::string(sig.empty() ? "" : ", ") + name + ": " + view;
+                if (as_ref) {
+                    locals.push_back("let " + name + " = " + name + ".as_ref();");
+                }
+            }
+            arg = name + ".as_ptr(), " + name + ".len()";
+        } else if (!fallback.empty() && options_.default_args == DefaultArgs::Option) {
+            sig += std::string(sig.empty() ? "" : ", ") + name + ": Option<" + type + ">";
+            arg = name + ".unwrap_or(" + fallback + ")";
+        } else if (enum_outs && isNarrowInteger(param) && narrowsIntegers(func)) {
+            // Checked in the wrapper instead of truncating at the call site
+            sig += std::string(sig.empty() ? "" : ", ") + name + ": i32";
+            locals.push_back("let " + name + ": " + type + " = " + name + ".try_into()?;");
+        } else {
+            sig += std::string(sig.empty() ? "" : ", ") + name + ": " + type;
+        }
+        args += std::string(args.empty() ? "" : ", ") + arg;
+    }
+    return sig;
+}
+
+std::string RustFFIGenerator::enumResult(const FFIFunction& func, const std::string& call,
+                                         const std::string& indent, std::string& return_type) {
+    // Enum values are read back as integers, C++ may store anything in them
+    std::vector<std::string> types;
+    std::vector<std::string> values;
+    if (isEnum(func.return_type)) {
+        types.push_back(baseType(func.return_type));
+        values.push_back(baseType(func.return_type) + "::try_from(ret)?");
+    } else if (!isVoid(func.return_type)) {
+        types.push_back(rustType(func.return_type));
+        values.push_back("ret");
+    }
+
+    std::stringstream locals;
+    bool has_outs = false;
+    for (size_t i = 0; i < func.parameters.size(); ++i) {
+        const auto& param = func.parameters[i];
+        if (!isEnumOutParam(param)) {
+            continue;
+        }
+        has_outs = true;
+        std::string name = paramName(param, i);
+        std::string enum_name = baseType(param.cpp_type);
+        locals << indent << "let mut " << name << ": " << enum_types_.at(enum_name) << " = 0;\n";
+        types.push_back(enum_name);
+        values.push_back(enum_name + "::try_from(" + name + ")?");
+    }
+
+    if (!has_outs && !isEnum(func.return_type)) {
+        return "";
+    }
+    auto error = isEnum(func.return_type) ? error_enums_.find(types[0]) : error_enums_.end();
+    if (!has_outs && error != error_enums_.end() && !error->second.success.empty()) {
+        // The success enumerator carries no information, every other one is the error
+        return_type = "Result<(), " + types[0] + ">";
+        return safety(callInvariant(func), indent) +
+               indent + "let ret = unsafe { " + call + " };\n" +
+               indent + "match " + types[0] + "::try_from(ret) {\n" +
+               indent + "    Ok(" + types[0] + "::" + error->second.success + ") => Ok(()),\n" +
+               indent + "    Ok(code) => Err(code),\n" +
+               indent + "    Err(invalid) => panic!(\"{}\", invalid),\n" +
+               indent + "}\n";
+    }
+    if (!has_outs) {
+        return_type = "Result<" + types[0] + ", InvalidEnum>";
+        return safety(callInvariant(func), indent) + indent + types[0] + "::try_from(unsafe { " + call + " })\n";
+    }
+
+    auto tuple = [](const std::vector<std::string>& items) {
+        std::string joined;
+        for (const auto& item : items) {
+            joined += (joined.empty() ? "" : ", ") + item;
+        }
+        return items.size() == 1 ? joined : "(" + joined + ")";
+    };
+
+    std::stringstream ss;
+    ss << locals.str();
+    ss << safety(callInvariant(func), indent);
+    if (isVoid(func.return_type)) {
+        ss << indent << "unsafe { " << call << " };\n";
+    } else {
+        ss << indent << "let ret = unsafe { " << call << " };\n";
+    }
+    if (values.size() == 1) {
+        // A lone conversion already is the Result
+        std::string conversion = values[0];
+        ss << indent << conversion.substr(0, conversion.size() - 1) << "\n";
+    } else {
+        ss << indent << "Ok(" << tuple(values) << ")\n";
+    }
+    return_type = "Result<" + tuple(types) + ", InvalidEnum>";
+    return ss.str();
+}
+
+bool RustFFIGenerator::isPathParam(const FFIParameter& param, size_t index) const {
+    if (!param.rust_type.empty() || !isStringParam(param) || options_.path_params.empty()) {
+        return false;
+    }
+    // Words of a snake_case or camelCase name, lowercased: `logFile` -> log, file
+    std::string name = paramName(param, index);
+    std::vector<std::string> words(1);
+    for (size_t i = 0; i < name.size(); ++i) {
+        unsigned char c = static_cast<unsigned char>(name[i]);
+        bool boundary = c == '_' || (std::isupper(c) && i > 0 && std::islower(static_cast<unsigned char>(name[i - 1])));
+        if (boundary && !words.back().empty()) {
+            words.emplace_back();
+        }
+        if (c != '_') {
+            words.back() += static_cast<char>(std::tolower(c));
+        }
+    }
+    return std::any_of(words.begin(), words.end(), [&](const std::string& word) {
+        return std::find(options_.path_params.begin(), options_.path_params.end(), word) != options_.path_params.end();
+    });
+}
+
+bool RustFFIGenerator::isNarrowInteger(const FFIParameter& param) {
+    static const std::set<std::string> narrow = {"i8", "i16", "u8", "u16"};
+    return param.rust_type.empty() && param.span_element.empty() && !isEnum(param.cpp_type) &&
+           narrow.count(rustType(param.cpp_type));
+}
+
+bool RustFFIGenerator::narrowsIntegers(const FFIFunction& func) {
+    if (!options_.checked_narrowing || isEnum(func.return_type)) {
+        return false;
+    }
+    // Enum conversions already own the Result error type
+    bool narrows = false;
+    for (const auto& param : func.parameters) {
+        if (isEnumOutParam(param)) {
+            return false;
+        }
+        narrows = narrows || isNarrowInteger(param);
+    }
+    return narrows;
+}
+
+std::string RustFFIGenerator::plainBody(const FFIFunction& func, const std::string& call, const std::string& borrow,
+                                        const std::string& indent, std::string& return_type) {
+    std::string body = enumResult(func, call, indent, return_type);
+    if (!body.empty()) {
+        return body;
+    }
+
+    bool narrows = narrowsIntegers(func);
+    if (ownsCString(func)) {
+        // The copy is taken before the library's allocator reclaims the buffer
+        auto wrap = [&](const std::string& value) { return narrows ? "Ok(" + value + ")" : value; };
+        return_type = narrows ? "Result<Option<String>, std::num::TryFromIntError>" : "Option<String>";
+        return safety(callInvariant(func), indent) +
+               indent + "let ptr = unsafe { " + call + " };\n" +
+               indent + "if ptr.is_null() {\n" +
+               indent + "    return " + wrap("None") + ";\n" +
+               indent + "}\n" +
+               safety("`ptr` is non-null and points to a NUL-terminated string", indent) +
+               indent + "let text = unsafe { std::ffi::CStr::from_ptr(ptr.cast()) }.to_string_lossy().into_owned();\n" +
+               safety("`ptr` came from the library and is released once, after the copy", indent) +
+               indent + "unsafe { ffi::" + rustIdent(options_.free_function) + "(ptr.cast()) };\n" +
+               indent + wrap("Some(text)") + "\n";
+    }
+
+    if (borrowsCString(func)) {
+        // Methods tie the string to the object, anything else to the library
+        auto wrap = [&](const std::string& value) { return narrows ? "Ok(" + value + ")" : value; };
+        bool has_receiver = !func.class_name.empty() && !func.is_static;
+        return_type = std::string("Option<&") + (has_receiver ? "" : "'static ") + "std::ffi::CStr>";
+        if (narrows) {
+            return_type = "Result<" + return_type + ", std::num::TryFromIntError>";
+        }
+        return safety(callInvariant(func), indent) +
+               indent + "let ptr = unsafe { " + call + " };\n" +
+               indent + "if ptr.is_null() {\n" +
+               indent + "    return " + wrap("None") + ";\n" +
+               indent + "}\n" +
+               safety(std::string("`ptr` is non-null and points to a NUL-terminated string ") +
+                      (has_receiver ? "that lives as long as `self`" : "C++ never frees"), indent) +
+               indent + wrap("Some(unsafe { std::ffi::CStr::from_ptr(ptr.cast()) })") + "\n";
+    }
+
+    if (returnsStructArray(func)) {
+        auto wrap = [&](const std::string& value) { return narrows ? "Ok(" + value + ")" : value; };
+        std::string element = rustType(baseType(func.return_type));
+        std::string count = paramName(func.parameters.back(), func.parameters.size() - 1);
+        bool owned = ownsReturnedPointer(func);
+        bool has_receiver = !func.class_name.empty() && !func.is_static;
+        // Borrowed arrays live as long as the object holding them, owned ones
+        // are copied out before going back to the library
+        return_type = owned ? "Vec<" + element + ">" : std::string(has_receiver ? "&[" : "&'static [") + element + "]";
+        if (narrows) {
+            return_type = "Result<" + return_type + ", std::num::TryFromIntError>";
+        }
+        std::string body = safety(callInvariant(func), indent) +
+                           indent + "let ptr = unsafe { " + call + " };\n" +
+                           indent + "if ptr.is_null() {\n" +
+                           indent + "    return " + wrap(owned ? "Vec::new()" : "&[]") + ";\n" +
+                           indent + "}\n";
+        std::string elements = "`ptr` is non-null and points to `" + count + "` elements";
+        if (!owned) {
+            return body + safety(elements + (has_receiver ? " that live as long as `self`" : " C++ never frees"), indent) +
+                   indent + wrap("unsafe { std::slice::from_raw_parts(ptr, " + count + ") }") + "\n";
+        }
+        std::string release = options_.free_function.empty() ? "ptr" : "ptr as *mut c_void";
+        return body +
+               safety(elements, indent) +
+               indent + "let items = unsafe { std::slice::from_raw_parts(ptr, " + count + ") }.to_vec();\n" +
+               safety("`ptr` came from the library and is released once, after the copy", indent) +
+               indent + "unsafe { ffi::" + rustIdent(arrayFreeFunction(func)) + "(" + release + ") };\n" +
+               indent + wrap("items") + "\n";
+    }
+
+    std::string out_struct = outPointerReturn(func, memory_structs_);
+    if (!out_struct.empty()) {
+        std::string out = outPointerName(func);
+        return_type = rustType(out_struct);
+        std::string out_call = call.substr(0, call.size() - 1) + (call[call.size() - 2] == '(' ? "" : ", ") +
+                               out + ".as_mut_ptr())";
+        std::string value = "unsafe { " + out + ".assume_init() }";
+        if (narrows) {
+            return_type = "Result<" + return_type + ", std::num::TryFromIntError>";
+            value = "Ok(" + value + ")";
+        }
+        return indent + "let mut " + out + " = std::mem::MaybeUninit::<" + rustType(out_struct) + ">::uninit();\n" +
+               safety(callInvariant(func) + "; `" + out + "` has room for the result", indent) +
+               indent + "unsafe { " + out_call + " };\n" +
+               safety("the shim wrote the result through `" + out + "`", indent) +
+               indent + value + "\n";
+    }
+
+    std::string expr = "unsafe { " + call + " }";
+    std::string invariant = callInvariant(func);
+    if (!borrow.empty()) {
+        return_type = borrow;
+        invariant += "; the returned C++ reference is never null";
+        expr = "unsafe { " + std::string(borrow.find("mut ") == std::string::npos ? "&*" : "&mut *") + call + " }";
+    } else if (!durationUnit(func.return_type).empty()) {
+        return_type = "std::time::Duration";
+        expr = countToDuration(expr, durationUnit(func.return_type));
+    } else if (!isVoid(func.return_type)) {
+        return_type = rustType(func.return_type);
+    }
+    if (narrows && return_type.empty()) {
+        // The call is a statement, wrapping `()` in Ok(..) trips clippy's unit_arg
+        return_type = "Result<(), std::num::TryFromIntError>";
+        return safety(invariant, indent) + indent + expr + ";\n" + indent + "Ok(())\n";
+    }
+    if (narrows) {
+        return_type = "Result<" + return_type + ", std::num::TryFromIntError>";
+        expr = "Ok(" + expr + ")";
+    }
+    return safety(invariant, indent) + indent + expr + "\n";
+}
+
+std::string RustFFIGenerator::generateSafeWrapper(const FFIFunction& func, bool omit_defaults,
+                                                  const std::string& suffix) {
+    std::stringstream sig;
+    std::string args;
+    std::vector<std::string> locals;
+
+    std::string owner;
+    std::string borrow = referenceReturn(func, owner);
+    std::string params = paramList(func, omit_defaults, args, locals, true);
+    if (!init_function_.empty() && options_.init.automatic) {
+        locals.insert(locals.begin(), "init();");
+    }
+    if (!owner.empty()) {
+        // Name the lifetime of the argument the result borrows from
+        size_t pos = params.find(owner + ": &");
+        params.insert(pos + owner.size() + 3, "'a ");
+    }
+    std::string pointers = pointerSafety(params, func.name, "");
+    generated_items_.push_back({"Functions", toSnakeCase(func.name) + suffix, declarationOf(func)});
+    sig << (pointers.empty() ? "pub fn " : "pub unsafe fn ") << rustIdent(toSnakeCase(func.name) + suffix)
+        << (owner.empty() ? "" : "<'a>") << "(";
+    sig << params;
+    sig << ")";
+
+    std::string return_type;
+    std::string call = "ffi::" + rustIdent(shimName(func)) + "(" + args + ")";
+    std::string body;
+    std::string viewed = viewedClass(func);
+    if (!viewed.empty()) {
+        // Borrowed from C++ state that outlives any Rust caller
+        return_type = "Option<" + rustTypeName(viewed) + "Ref<'static>>";
+        body = nullCheckedReturn(call, rustTypeName(viewed) + "Ref::from_raw", isConstPointer(func.return_type),
+                                 "    ", safety(callInvariant(func), "    "));
+    } else {
+        body = plainBody(func, call, borrow, "    ", return_type);
+    }
+    if (!return_type.empty()) {
+        sig << " -> " << return_type;
+    }
+
+    std::string sections = panicsDoc(locals, "");
+    sections += (sections.empty() || pointers.empty() ? "" : "///\n") + pointers;
+    std::stringstream ss;
+    ss << docComment(func.doc_comment, "", !sections.empty()) << sections;
+    ss << deprecatedAttribute(func.is_deprecated, func.deprecation_note, "");
+    ss << sig.str() << " {\n";
+    ss << indented(locals, "    ");
+    ss << body;
+    ss << "}\n";
+    return ss.str();
+}
+
+std::string RustFFIGenerator::generateMethodWrapper(const FFIFunction& method, const FFIClass& cls,
+                                                    bool omit_defaults, const std::string& suffix) {
+    std::stringstream sig;
+    std::string args;
+    std::vector<std::string> locals;
+    std::stringstream ss;
+    const std::string name = rustTypeName(cls.name);
+
+    bool ctor = isConstructor(method);
+    bool has_receiver = !method.is_static && !ctor;
+
+    // Only plain methods read enum out-parameters back or return borrows
+    bool plain = !ctor && !isSingletonAccessor(method, cls) && !returnsSelfPointer(method, cls) &&
+                 viewedClass(method).empty();
+    std::string owner;
+    std::string borrow = plain ? referenceReturn(method, owner) : "";
+
+    if (ctor) {
+        // Overloaded constructors get their suffix from the shim name
+        std::string shim = shimName(method);
+        std::string prefix = toSnakeCase(name) + "_";
+        sig << (shim.find(prefix) == 0 ? shim.substr(prefix.size()) : "new") << suffix << "(";
+    } else {
+        sig << rustIdent(toSnakeCase(method.name) + suffix) << (owner.empty() ? "" : "<'a>") << "(";
+    }
+
+    if (has_receiver) {
+        sig << (method.is_const ? "&self" : "&mut self");
+        args = "self.ptr";
+    }
+
+    std::string params = paramList(method, omit_defaults, args, locals, plain);
+    if (!init_function_.empty() && options_.init.automatic && !has_receiver) {
+        // Objects only come from wrappers that initialized the library already
+        locals.insert(locals.begin(), "init();");
+    }
+    if (!owner.empty()) {
+        size_t pos = params.find(owner + ": &");
+        params.insert(pos + owner.size() + 3, "'a ");
+    }
+    sig << (has_receiver && !params.empty() ? ", " : "") << params << ")";
+    std::string pointers = pointerSafety(params, cls.name + "::" + method.name, "    ");
+    std::string signature = (pointers.empty() ? "pub fn " : "pub unsafe fn ") + sig.str();
+
+    std::string call = "ffi::" + rustIdent(shimName(method)) + "(" + args + ")";
+    std::string prelude = indented(locals, "        ");
+    std::string sections = panicsDoc(locals, "    ");
+    sections += (sections.empty() || pointers.empty() ? "" : "    ///\n") + pointers;
+    ss << docComment(method.doc_comment, "    ", !sections.empty()) << sections;
+    ss << deprecatedAttribute(method.is_deprecated, method.deprecation_note, "    ");
+
+    if (ctor) {
+        ss << "    " << signature << " -> Self {\n";
+        ss << prelude;
+        ss << safety(callInvariant(method), "        ");
+        ss << "        let ptr = unsafe { " << call << " };\n";
+        ss << "        " << name << " { ptr }\n";
+        ss << "    }\n";
+    } else if (isSingletonAccessor(method, cls)) {
+        // Statics are never dropped, so the singleton is never freed from Rust
+        bool shared = options_.thread_safe.count(cls.name) > 0;
+        ss << "    " << signature << " -> " << (shared ? "&'static " + name : name + "Ref<'static>") << " {\n";
+        ss << prelude;
+        if (shared) {
+            ss << "        struct Instance(" << name << ");\n";
+            std::string shared = "the class is configured thread-safe, so its methods may run concurrently";
+            if (options_.safety_comments) {
+                ss << safety(shared, "        ");
+                ss << "        unsafe impl Send for Instance {}\n";
+                ss << safety(shared, "        ");
+            } else {
+                ss << "        // The class is configured thread-safe, so its methods may run concurrently\n";
+                ss << "        unsafe impl Send for Instance {}\n";
+            }
+            ss << "        unsafe impl Sync for Instance {}\n";
+            ss << "        static INSTANCE: std::sync::OnceLock<Instance> = std::sync::OnceLock::new();\n";
+            ss << safety(callInvariant(method), "        ");
+            ss << "        &INSTANCE.get_or_init(|| Instance(" << name << " { ptr: unsafe { " << call << " } })).0\n";
+        } else {
+            // Nothing says the C++ side tolerates other threads, so each
+            // thread keeps the pointer and hands out views that cannot leave it
+            ss << "        thread_local! {\n";
+            ss << safety(callInvariant(method), "            ");
+            ss << "            static INSTANCE: *mut c_void = unsafe { " << call << " };\n";
+            ss << "        }\n";
+            ss << "        " << name << "Ref::from_raw(INSTANCE.with(|ptr| *ptr))\n";
+        }
+        ss << "    }\n";
+    } else if (!viewedClass(method).empty()) {
+        // Without a receiver nothing in Rust owns the object the view borrows
+        std::string view = rustTypeName(viewedClass(method)) + "Ref";
+        ss << "    " << signature << " -> Option<" << view << (has_receiver ? "<'_>" : "<'static>") << "> {\n";
+        ss << prelude;
+        ss << nullCheckedReturn(call, view + "::from_raw", isConstPointer(method.return_type), "        ",
+                                safety(callInvariant(method), "        "));
+        ss << "    }\n";
+    } else if (returnsSelfPointer(method, cls)) {
+        bool const_ptr = isConstPointer(method.return_type);
+        ss << "    " << signature << " -> Option<Self> {\n";
+        ss << prelude;
+        ss << safety(callInvariant(method), "        ");
+        ss << "        let ptr = unsafe { " << call << " };\n";
+        ss << "        if ptr.is_null() {\n";
+        ss << "            None\n";
+        ss << "        } else {\n";
+        ss << "            Some(" << name << " { ptr" << (const_ptr ? ": ptr.cast_mut()" : "") << " })\n";
+        ss << "        }\n";
+        ss << "    }\n";
+    } else {
+        std::string return_type;
+        std::string body = plainBody(method, call, borrow, "        ", return_type);
+        ss << "    " << signature;
+        if (!return_type.empty()) {
+            ss << " -> " << return_type;
+        }
+        ss << " {\n";
+        ss << prelude;
+        ss << body;
+        ss << "    }\n";
+    }
+
+    return ss.str();
+}
+
+std::string RustFFIGenerator::generateSelfView(const FFIClass& cls) {
+    const std::string name = rustTypeName(cls.name);
+    const std::string view = name + "Ref";
+    std::stringstream ss;
+    generated_items_.push_back({"Opaque wrappers", view, "borrowed " + cls.name + "*"});
+
+    ss << "/// Borrowed view of a " << name << " owned by another object; never freed from Rust\n";
+    ss << "#[derive(Clone, Copy)]\n";
+    ss << "pub struct " << view << "<'a> {\n";
+    ss << "    ptr: *mut c_void,\n";
+    ss << "    _marker: std::marker::PhantomData<&'a " << name << ">,\n";
+    ss << "}\n\n";
+
+    ss << "impl<'a> " << view << "<'a> {\n";
+    ss << "    fn from_raw(ptr: *mut c_void) -> Self {\n";
+    ss << "        " << view << " { ptr, _marker: std::marker::PhantomData }\n";
+    ss << "    }\n";
+
+    // Re-expose const borrowed accessors by value so chains such as
+    // `node.parent()?.parent()` keep the original lifetime
+    for (const auto& method : cls.methods) {
+        if (isConstructor(method) || method.is_static || !method.is_const) {
+            continue;
+        }
+        if (!returnsSelfPointer(method, cls) || !returnsBorrowed(method)) {
+            continue;
+        }
+
+        std::stringstream sig;
+        std::stringstream args;
+        sig << "pub fn " << rustIdent(toSnakeCase(method.name)) << "(self";
+        args << "self.ptr";
+        for (size_t i = 0; i < method.parameters.size(); ++i) {
+            const auto& param = method.parameters[i];
+            std::string type = param.rust_type.empty() ? rustType(param.cpp_type) : param.rust_type;
+            sig << ", " << paramName(param, i) << ": " << type;
+            args << ", " << paramName(param, i);
+        }
+        sig << ") -> Option<" << view << "<'a>>";
+
+        ss << "\n";
+        ss << "    " << sig.str() << " {\n";
+        ss << nullCheckedReturn("ffi::" + rustIdent(shimName(method)) + "(" + args.str() + ")",
+                                view + "::from_raw", isConstPointer(method.return_type), "        ",
+                                safety(callInvariant(method), "        "));
+        ss << "    }\n";
+    }
+    ss << "}\n\n";
+
+    ss << "impl std::ops::Deref for " << view << "<'_> {\n";
+    ss << "    type Target = " << name << ";\n\n";
+    ss << "    fn deref(&self) -> &" << name << " {\n";
+    ss << "        // " << (options_.safety_comments ? "SAFETY: " : "") << name
+       << " is repr(transparent) over the same pointer, and the\n";
+    ss << "        // view never owns it, so Drop is not run on the borrowed object\n";
+    ss << "        unsafe { &*std::ptr::addr_of!(self.ptr).cast::<" << name << ">() }\n";
+    ss << "    }\n";
+    ss << "}\n";
+
+    return ss.str();
+}
+
+std::vector<std::string> RustFFIGenerator::inferDerives(const FFIClass& cls, const std::vector<FFIClass>& classes) {
+    static const std::set<std::string> integers = {"i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16",
+                                                   "u32", "u64", "u128", "usize", "bool", "char",
+                                                   "std::ffi::c_long", "std::ffi::c_ulong"};
+    std::set<std::string> allowed = {"Debug", "Clone", "Copy", "PartialEq", "Eq", "Hash"};
+    auto remove = [&](std::initializer_list<const char*> traits) {
+        for (const char* trait : traits) {
+            allowed.erase(trait);
+        }
+    };
+    for (const auto& field : cls.fields) {
+        std::string type = rustType(field.cpp_type);
+        auto nested = std::find_if(classes.begin(), classes.end(), [&](const FFIClass& other) {
+            return isReprC(other) && rustTypeName(other.name) == type;
+        });
+        if (integers.count(type)) {
+            continue;
+        } else if (type == "f32" || type == "f64") {
+            remove({"Eq", "Hash"});
+        } else if (type.find('*') == 0 || type.find("Option<") == 0) {
+            // Equal addresses say nothing about equal pointees
+            remove({"PartialEq", "Eq", "Hash"});
+        } else if (nested != classes.end()) {
+            auto configured = options_.derives.find(nested->name);
+            std::vector<std::string> inner =
+                configured != options_.derives.end() ? configured->second : inferDerives(*nested, classes);
+            for (auto it = allowed.begin(); it != allowed.end();) {
+                it = std::find(inner.begin(), inner.end(), *it) == inner.end() ? allowed.erase(it) : std::next(it);
+            }
+        } else {
+            remove({"PartialEq", "Eq", "Hash"});
+        }
+    }
+
+    std::vector<std::string> derives;
+    for (const char* trait : {"Debug", "Clone", "Copy", "PartialEq", "Eq", "Hash"}) {
+        if (allowed.count(trait)) {
+            derives.push_back(trait);
+        }
+    }
+    return derives;
+}
+
+std::string RustFFIGenerator::generateStructBinding(const FFIClass& cls) {
+    std::stringstream ss;
+
+    bool has_volatile = false;
+    for (const auto& field : cls.fields) {
+        has_volatile = has_volatile || field.is_volatile;
+    }
+
+    ss << docComment(cls.doc_comment, "", true);
+    ss << "/// FFI-compatible mirror of " << cls.name << "\n";
+    if (cls.pack == 0) {
+        ss << "#[repr(C)]\n";
+    } else if (cls.pack == 1) {
+        ss << "#[repr(C, packed)]\n";
+    } else {
+        ss << "#[repr(C, packed(" << cls.pack << "))]\n";
+    }
+    const auto& derives = struct_derives_[cls.name];
+    if (!has_volatile && !derives.empty()) {
+        // Derived impls would read volatile fields with plain loads
+        ss << "#[derive(";
+        for (size_t i = 0; i < derives.size(); ++i) {
+            ss << (i > 0 ? ", " : "") << derives[i];
+        }
+        ss << ")]\n";
+    }
+    // References into a packed struct may be unaligned, so its fields are
+    // private and only copied in and out through accessors
+    bool packed = cls.pack != 0;
+    bool accessors = options_.struct_fields == StructFields::Accessors || packed;
+    ss << deprecatedAttribute(cls.is_deprecated, cls.deprecation_note, "");
+    generated_items_.push_back({"Structs", rustTypeName(cls.name), "struct " + cls.name});
+    ss << "pub struct " << rustTypeName(cls.name) << " {\n";
+    for (const auto& field : cls.fields) {
+        bool is_public = !field.is_volatile && !accessors;
+        ss << "    " << (is_public ? "pub " : "") << rustIdent(field.name) << ": " << rustType(field.cpp_type) << ",\n";
+    }
+    ss << "}\n";
+
+    if (!has_volatile && !accessors) {
+        return ss.str();
+    }
+
+    // Volatile fields (e.g. memory-mapped registers) are private and only
+    // reachable through accessors the compiler cannot elide or merge. In
+    // accessor mode every field is private and built through `new`
+    ss << "\n";
+    ss << "impl " << rustTypeName(cls.name) << " {\n";
+    bool first = true;
+    if (accessors) {
+        ss << "    pub fn new(";
+        for (size_t i = 0; i < cls.fields.size(); ++i) {
+            if (i > 0) ss << ", ";
+            ss << rustIdent(cls.fields[i].name) << ": " << rustType(cls.fields[i].cpp_type);
+        }
+        ss << ") -> Self {\n";
+        ss << "        Self {";
+        for (size_t i = 0; i < cls.fields.size(); ++i) {
+            ss << (i > 0 ? ", " : " ") << cls.fields[i].name;
+        }
+        ss << (cls.fields.empty() ? "}\n" : " }\n");
+        ss << "    }\n";
+        first = false;
+    }
+    for (const auto& field : cls.fields) {
+        if (!field.is_volatile && !accessors) {
+            continue;
+        }
+        std::string getter = toSnakeCase(field.name);
+        std::string setter = "set_" + getter;
+        getter = rustIdent(getter);
+        std::string type = rustType(field.cpp_type);
+
+        if (!first) ss << "\n";
+        first = false;
+        if (packed) {
+            // Rust has no unaligned volatile access
+            if (field.is_volatile) {
+                diagnostics_.push_back({cls.name + "::" + field.name,
+                                        "volatile field of a packed struct is accessed with plain unaligned loads and stores"});
+            }
+            std::string invariant = "`self." + rustIdent(field.name) + "` is a live field of this struct; "
+                                    "the unaligned access tolerates its packed offset";
+            ss << "    pub fn " << getter << "(&self) -> " << type << " {\n";
+            ss << safety(invariant, "        ");
+            ss << "        unsafe { std::ptr::addr_of!(self." << rustIdent(field.name) << ").read_unaligned() }\n";
+            ss << "    }\n";
+        } else if (field.is_volatile) {
+            ss << "    pub fn " << getter << "(&self) -> " << type << " {\n";
+            ss << safety("`self." + rustIdent(field.name) + "` is a live, aligned field of this struct", "        ");
+            ss << "        unsafe { std::ptr::read_volatile(std::ptr::addr_of!(self." << rustIdent(field.name) << ")) }\n";
+            ss << "    }\n";
+        } else {
+            // Scalars and pointers are returned by value, nested structs by reference
+            bool by_reference = repr_c_structs_.count(trim(field.cpp_type)) > 0;
+            ss << "    pub fn " << getter << "(&self) -> " << (by_reference ? "&" : "") << type << " {\n";
+            ss << "        " << (by_reference ? "&" : "") << "self." << rustIdent(field.name) << "\n";
+            ss << "    }\n";
+        }
+        if (!field.is_const) {
+            ss << "\n";
+            ss << "    pub fn " << setter << "(&mut self, value: " << type << ") {\n";
+            if (packed) {
+                ss << safety("`self." + rustIdent(field.name) + "` is a live field of this struct; "
+                             "the unaligned access tolerates its packed offset", "        ");
+                ss << "        unsafe { std::ptr::addr_of_mut!(self." << rustIdent(field.name) << ").write_unaligned(value) }\n";
+            } else if (field.is_volatile) {
+                ss << safety("`self." + rustIdent(field.name) + "` is a live, aligned field of this struct", "        ");
+                ss << "        unsafe { std::ptr::write_volatile(std::ptr::addr_of_mut!(self." << rustIdent(field.name) << "), value) }\n";
+            } else {
+                ss << "        self." << rustIdent(field.name) << " = value;\n";
+            }
+            ss << "    }\n";
+        }
+    }
+    ss << "}\n";
+
+    return ss.str();
+}
+
+std::string RustFFIGenerator::generateTests(const std::vector<FFIClass>& classes, const std::vector<FFIEnum>& enums) {
+    std::stringstream ss;
+
+    ss << "#[cfg(test)]\n";
+    ss << "mod tests {\n";
+    std::streampos body_at = ss.tellp();
+
+    if (!init_function_.empty()) {
+        // Racing first calls must all return after the one that runs the init function
+        ss << "\n";
+        ss << "    #[test]\n";
+        ss << "    fn init_runs_once() {\n";
+        ss << "        let threads: Vec<_> = (0..8).map(|_| std::thread::spawn(init)).collect();\n";
+        ss << "        for thread in threads {\n";
+        ss << "            thread.join().unwrap();\n";
+        ss << "        }\n";
+        ss << "        assert!(INIT.is_completed());\n";
+        ss << "    }\n";
+    }
+
+    for (const auto& cls : classes) {
+        const std::string test_prefix = toSnakeCase(cls.name);
+
+        if (isReprC(cls)) {
+            std::vector<std::pair<AbiTarget, StructLayout>> layouts;
+            for (AbiTarget target : options_.abi_targets) {
+                layouts.emplace_back(target, structLayout(cls, target));
+            }
+            if (layouts.empty() || layouts.front().second.size == 0) {
+                continue;
+            }
+            // `long` makes the layout differ between Windows and the LP64 targets
+            bool same = std::all_of(layouts.begin(), layouts.end(), [&](const std::pair<AbiTarget, StructLayout>& l) {
+                return l.second == layouts.front().second;
+            });
+            auto asserts = [&](const StructLayout& layout, const std::string& indent) {
+                ss << indent << "assert_eq!(std::mem::size_of::<" << rustTypeName(cls.name) << ">(), " << layout.size
+                   << ");\n";
+                ss << indent << "assert_eq!(std::mem::align_of::<" << rustTypeName(cls.name) << ">(), "
+                   << layout.alignment << ");\n";
+            };
+            ss << "\n";
+            ss << "    #[test]\n";
+            ss << "    fn " << test_prefix << "_layout() {\n";
+            if (same) {
+                asserts(layouts.front().second, "        ");
+            }
+            for (size_t i = 0; !same && i < layouts.size(); ++i) {
+                ss << "        #[cfg(" << targetCfg(layouts[i].first) << ")]\n";
+                ss << "        {\n";
+                asserts(layouts[i].second, "            ");
+                ss << "        }\n";
+            }
+            ss << "    }\n";
+            continue;
+        }
+
+        for (const auto& method : cls.static_methods) {
+            if (!isSingletonAccessor(method, cls) || !method.parameters.empty()) {
+                continue;
+            }
+            std::string accessor = rustIdent(toSnakeCase(method.name));
+            ss << "\n";
+            ss << "    #[test]\n";
+            ss << "    fn " << test_prefix << "_" << accessor << "_is_shared() {\n";
+            ss << "        let first = " << rustTypeName(cls.name) << "::" << accessor << "();\n";
+            ss << "        let second = " << rustTypeName(cls.name) << "::" << accessor << "();\n";
+            if (options_.thread_safe.count(cls.name)) {
+                ss << "        assert!(std::ptr::eq(first, second));\n";
+            }
+            ss << "        assert_eq!(first.ptr, second.ptr);\n";
+            ss << "    }\n";
+        }
+
+        // Construct through the constructor with the fewest arguments,
+        // as long as every argument has a Default value in Rust
+        const FFIFunction* ctor = nullptr;
+        for (const auto& method : cls.methods) {
+            if (!isConstructor(method)) {
+                continue;
+            }
+            bool defaultable = true;
+            for (const auto& param : method.parameters) {
+                std::string type = param.rust_type.empty() ? rustType(param.cpp_type) : param.rust_type;
+                defaultable = defaultable && !isEnum(param.cpp_type) &&
+                              (type.find('*') == std::string::npos || isStringParam(param) ||
+                               isStringViewParam(param));
+            }
+            if (defaultable && (!ctor || method.parameters.size() < ctor->parameters.size())) {
+                ctor = &method;
+            }
+        }
+        // Factory-only classes are built through a factory without arguments
+        const FFIFunction* factory = nullptr;
+        for (const auto& method : cls.static_methods) {
+            if (!factory && method.parameters.empty() && returnsSelfPointer(method, cls) && !returnsBorrowed(method)) {
+                factory = &method;
+            }
+        }
+        if (!ctor && factory) {
+            ss << "\n";
+            ss << "    #[test]\n";
+            ss << "    fn " << test_prefix << "_smoke() {\n";
+            ss << "        let _obj = " << rustTypeName(cls.name) << "::" << rustIdent(toSnakeCase(factory->name))
+               << "().expect(\"factory returned null\");\n";
+            ss << "    }\n";
+            continue;
+        }
+        if (!ctor) {
+            ss << "\n";
+            ss << "    // " << cls.name << ": no constructor callable with default arguments\n";
+            continue;
+        }
+
+        std::string ctor_name = shimName(*ctor);
+        std::string prefix = test_prefix + "_";
+        ctor_name = ctor_name.find(prefix) == 0 ? ctor_name.substr(prefix.size()) : "new";
+
+        std::vector<std::string> getters;
+        for (const auto& method : cls.methods) {
+            if (!isConstructor(method) && isGetter(method)) {
+                getters.push_back(rustIdent(toSnakeCase(method.name)));
+            }
+        }
+
+        std::string construct = rustTypeName(cls.name) + "::" + ctor_name + "(";
+        bool omitted = options_.default_args == DefaultArgs::Overloads;
+        bool first_arg = true;
+        for (size_t i = 0; i < ctor->parameters.size(); ++i) {
+            const auto& param = ctor->parameters[i];
+            if (omitted && !rustDefault(param).empty()) {
+                continue;
+            }
+            if (!first_arg) construct += ", ";
+            first_arg = false;
+            // An `impl AsRef` parameter needs its type named
+            bool as_ref = options_.string_params == StringParams::AsRef;
+            std::string none = !as_ref ? "None"
+                               : isPathParam(param, i) ? "None::<&std::path::Path>"
+                               : isStringParam(param) ? "None::<&str>"
+                               : isStringViewParam(param) ? "None::<&[u8]>"
+                               : "None";
+            construct += !rustDefault(param).empty() ? none
+                         : isPathParam(param, i) ? "std::path::Path::new(\"\")"
+                         : isStringParam(param) || isStringViewParam(param) ? "\"\""
+                         : "Default::default()";
+        }
+        construct += ")";
+
+        ss << "\n";
+        ss << "    #[test]\n";
+        ss << "    fn " << test_prefix << "_smoke() {\n";
+        ss << "        let " << (getters.empty() ? "_obj" : "obj") << " = " << construct << ";\n";
+        for (const auto& getter : getters) {
+            ss << "        let _ = obj." << getter << "(); // TODO: assert the expected value\n";
+        }
+        ss << "    }\n";
+
+        // Ordered wrappers must sort through the comparison shim
+        bool ordered = std::any_of(cls.methods.begin(), cls.methods.end(), [](const FFIFunction& method) {
+            return method.name == "operator<" ||
+                   (method.name == "operator<=>" && method.return_type.find("partial_ordering") == std::string::npos);
+        });
+        if (ordered) {
+            ss << "\n";
+            ss << "    #[test]\n";
+            ss << "    fn " << test_prefix << "_sorts() {\n";
+            ss << "        let mut items = vec![" << construct << ", " << construct << ", " << construct << "];\n";
+            ss << "        items.sort();\n";
+            ss << "        assert!(items.windows(2).all(|pair| pair[0] <= pair[1]));\n";
+            ss << "    }\n";
+        }
+    }
+
+    // Error enums must propagate through `?` into a boxed error
+    for (const auto& enum_decl : enums) {
+        auto error = error_enums_.find(enum_decl.name);
+        if (error == error_enums_.end()) {
+            continue;
+        }
+        auto failure = std::find_if(enum_decl.values.begin(), enum_decl.values.end(),
+                                    [&](const std::pair<std::string, std::string>& value) {
+                                        return value.first != error->second.success;
+                                    });
+        if (failure == enum_decl.values.end()) {
+            continue;
+        }
+        std::string value = enum_decl.name + "::" + failure->first;
+        ss << "\n";
+        ss << "    #[test]\n";
+        ss << "    fn " << toSnakeCase(enum_decl.name) << "_is_std_error() {\n";
+        ss << "        fn fail() -> Result<(), Box<dyn std::error::Error>> {\n";
+        ss << "            let result: Result<(), " << enum_decl.name << "> = Err(" << value << ");\n";
+        ss << "            result?;\n";
+        ss << "            Ok(())\n";
+        ss << "        }\n";
+        ss << "        let err = fail().unwrap_err();\n";
+        ss << "        assert!(!err.to_string().is_empty());\n";
+        ss << "        assert_eq!(err.downcast_ref::<" << enum_decl.name << ">(), Some(&" << value << "));\n";
+        ss << "    }\n";
+        if (!options_.thiserror) {
+            continue;
+        }
+        ss << "\n";
+        ss << "    #[cfg(feature = \"thiserror\")]\n";
+        ss << "    #[test]\n";
+        ss << "    fn " << toSnakeCase(enum_decl.name) << "_converts_from_thiserror() {\n";
+        ss << "        #[derive(Debug, thiserror::Error)]\n";
+        ss << "        enum AppError {\n";
+        ss << "            #[error(\"C++ call failed\")]\n";
+        ss << "            Cpp(#[from] " << enum_decl.name << "),\n";
+        ss << "        }\n";
+        ss << "        fn fail() -> Result<(), AppError> {\n";
+        ss << "            let result: Result<(), " << enum_decl.name << "> = Err(" << value << ");\n";
+        ss << "            result?;\n";
+        ss << "            Ok(())\n";
+        ss << "        }\n";
+        ss << "        let err = fail().unwrap_err();\n";
+        ss << "        assert!(matches!(err, AppError::Cpp(" << value << ")));\n";
+        ss << "        let source = std::error::Error::source(&err).unwrap();\n";
+        ss << "        assert_eq!(source.to_string(), " << value << ".to_string());\n";
+        ss << "    }\n";
+    }
+
+    ss << "}\n";
+    // Every test uses the bindings, comments alone name nothing from them
+    std::string tests = ss.str();
+    size_t body = static_cast<size_t>(body_at);
+    if (tests.find("#[test]", body) != std::string::npos) {
+        tests.insert(body, "    use super::*;\n");
+    } else if (tests.compare(body, 1, "\n") == 0) {
+        tests.erase(body, 1);
+    }
+    return tests;
+}
+
+std::string RustFFIGenerator::generateLinkCheck(const std::vector<std::string>& symbols) {
+    std::stringstream ss;
+    ss << "// Link check: each test below takes the address of one extern symbol.\n";
+    ss << "// When the linked library lacks a symbol (e.g. an older version than\n";
+    ss << "// the header), the linker reports it as undefined and names the\n";
+    ss << "// referencing `link_check::<symbol>` test.\n";
+    ss << "#[cfg(test)]\n";
+    ss << "mod link_check {\n";
+    for (size_t i = 0; i < symbols.size(); ++i) {
+        const std::string& symbol = symbols[i];
+        ss << (i == 0 ? "    use super::*;\n\n" : "\n");
+        ss << "    #[test]\n";
+        ss << "    fn " << rustIdent(symbol) << "() {\n";
+        ss << "        assert!(!std::hint::black_box(ffi::" << rustIdent(symbol) << " as *const ()).is_null());\n";
+        ss << "    }\n";
+    }
+    ss << "}\n";
+    return ss.str();
+}
+
+std::string RustFFIGenerator::generateInit(const std::vector<FFIFunction>& functions) {
+    auto find = [&](const std::string& name) -> const FFIFunction& {
+        return *std::find_if(functions.begin(), functions.end(), [&](const FFIFunction& f) { return f.name == name; });
+    };
+    auto shim = [&](const std::string& name) { return shimName(find(name)); };
+    generated_items_.push_back({"Functions", "init", declarationOf(find(init_function_))});
+
+    std::stringstream ss;
+    ss << "static INIT: std::sync::Once = std::sync::Once::new();\n\n";
+    ss << "/// Initializes the library through `" << init_function_ << "`, the first time only\n";
+    ss << "///\n";
+    ss << "/// Calls racing the first one, from any thread, wait until it has finished.\n";
+    if (options_.init.automatic) {
+        ss << "/// Every wrapper calls this first, so calling it up front is optional.\n";
+    }
+    ss << "pub fn init() {\n";
+    ss << "    INIT.call_once(|| {\n";
+    ss << safety("the library has not been initialized yet", "        ");
+    ss << "        unsafe { ffi::" << rustIdent(shim(init_function_)) << "() }\n";
+    ss << "    });\n";
+    ss << "}\n";
+    if (shutdown_function_.empty()) {
+        return ss.str();
+    }
+
+    generated_items_.push_back({"Functions", "shutdown", declarationOf(find(shutdown_function_))});
+    ss << "\n";
+    ss << "/// Shuts the library down through `" << shutdown_function_ << "`, if [`init`] ran\n";
+    ss << "///\n";
+    ss << "/// # Safety\n";
+    ss << "///\n";
+    ss << "/// Nothing may use the library afterwards, from any thread: [`init`] never\n";
+    ss << "/// runs twice, so the library stays shut down.\n";
+    ss << "pub unsafe fn shutdown() {\n";
+    ss << "    static SHUTDOWN: std::sync::Once = std::sync::Once::new();\n";
+    ss << "    if INIT.is_completed() {\n";
+    ss << "        SHUTDOWN.call_once(|| {\n";
+    ss << safety("the library was initialized, and the caller guarantees it is no longer in use", "            ");
+    ss << "            unsafe { ffi::" << rustIdent(shim(shutdown_function_)) << "() }\n";
+    ss << "        });\n";
+    ss << "    }\n";
+    ss << "}\n";
+    return ss.str();
+}
+
+std::string RustFFIGenerator::generateRelinkMacro(const std::vector<std::pair<std::string, std::string>>& blocks,
+                                                  const std::string& library_name) {
+    const std::string name = "relink_" + toSnakeCase(library_name);
+
+    std::stringstream ss;
+    ss << "/// Declares the extern functions of these bindings again, in a new module\n";
+    ss << "/// linked against another build of " << library_name << ":\n";
+    ss << "///\n";
+    ss << "/// ```ignore\n";
+    ss << "/// mod debug {\n";
+    ss << "///     use super::*; // The bindings' types\n";
+    ss << "///     " << name << "!(pub ffi, \"" << library_name << "_debug\");\n";
+    ss << "/// }\n";
+    ss << "/// mod asan {\n";
+    ss << "///     use super::*;\n";
+    ss << "///     " << name << "!(pub ffi, \"" << library_name << "_asan\");\n";
+    ss << "/// }\n";
+    ss << "/// ```\n";
+    ss << "///\n";
+    ss << "/// The signatures are the same as in the bindings' own extern block, so the\n";
+    ss << "/// builds must be ABI-compatible. Symbol names do not change either: a binary\n";
+    ss << "/// linking two builds gets each symbol from whichever the linker sees first.\n";
+    generated_items_.push_back({"Macros", name, "extern blocks of " + library_name});
+    ss << "#[macro_export]\n";
+    ss << "macro_rules! " << name << " {\n";
+    ss << "    ($vis:vis $module:ident, $link:literal) => {\n";
+    ss << "        $vis mod $module {\n";
+    ss << "            #![allow(dead_code, unused_imports)]\n";
+    ss << "            use super::*;\n";
+    ss << "            use std::ffi::c_void;\n";
+    for (size_t i = 0; i < blocks.size(); ++i) {
+        if (i > 0 && blocks[i].second.empty()) {
+            continue;
+        }
+        ss << "\n";
+        ss << "            #[link(name = " << (i == 0 ? "$link" : "\"" + blocks[i].first + "\"") << ")]\n";
+        ss << "            extern \"C\" {\n";
+        std::stringstream lines(blocks[i].second);
+        for (std::string line; std::getline(lines, line);) {
+            ss << "        " << line << "\n";
+        }
+        ss << "            }\n";
+    }
+    ss << "        }\n";
+    ss << "    };\n";
+    ss << "}\n";
+    return ss.str();
+}
+
+std::string RustFFIGenerator::selfTestSkip(const FFIFunction& func) {
+    if (!func.can_use_ffi) {
+        return "no binding generated";
+    }
+    for (size_t i = 0; i < func.parameters.size(); ++i) {
+        const auto& param = func.parameters[i];
+        std::string type = param.rust_type.empty() ? rustType(param.cpp_type) : param.rust_type;
+        if (abiSentinel(type, 0).empty() || isEnum(param.cpp_type)) {
+            return "parameter `" + paramName(param, i) + "` is not a scalar";
+        }
+    }
+    if (isEnum(func.return_type)) {
+        return "returns the enum `" + trim(func.return_type) + "`";
+    }
+    if (!isVoid(func.return_type) && abiSentinel(rustType(func.return_type), 0).empty()) {
+        return "returns a non-scalar `" + trim(func.return_type) + "`";
+    }
+    return "";
+}
+
+std::string RustFFIGenerator::generateSelfTestShims(const std::vector<FFIFunction>& functions) {
+    std::vector<const FFIFunction*> exercised;
+    for (const auto& func : functions) {
+        if (selfTestSkip(func).empty()) {
+            exercised.push_back(&func);
+        }
+    }
+    if (exercised.empty()) {
+        return "";
+    }
+
+    std::stringstream ss;
+    ss << "// ABI self-test twins: each calls its function with the arguments it\n";
+    ss << "// received and hashes them and the result, for the Rust harness to\n";
+    ss << "// compare against the hash of what it sent\n\n";
+    ss << "#include <cstring>\n";
+    ss << "#include <initializer_list>\n\n";
+    ss << "namespace {\n\n";
+    ss << "uint64_t abi_bits(double v) { uint64_t b; std::memcpy(&b, &v, 8); return b; }\n";
+    ss << "uint64_t abi_bits(float v) { uint32_t b; std::memcpy(&b, &v, 4); return b; }\n";
+    ss << "template <typename T> uint64_t abi_bits(T v) { return static_cast<uint64_t>(v); }\n\n";
+    ss << "// FNV-1a over the widened values, mirrored by checksum in Rust\n";
+    ss << "uint64_t abi_checksum(std::initializer_list<uint64_t> values) {\n";
+    ss << "    uint64_t h = 0xcbf29ce484222325ull;\n";
+    ss << "    for (uint64_t v : values) h = (h ^ v) * 0x100000001b3ull;\n";
+    ss << "    return h;\n";
+    ss << "}\n\n";
+    ss << "} // namespace\n\n";
+    ss << "extern \"C\" {\n";
+    for (const auto* func : exercised) {
+        std::string params;
+        std::string args;
+        std::string bits;
+        for (size_t i = 0; i < func->parameters.size(); ++i) {
+            std::string name = paramName(func->parameters[i], i);
+            params += (i > 0 ? ", " : "") + trim(func->parameters[i].cpp_type) + " " + name;
+            args += (i > 0 ? ", " : "") + name;
+            bits += (i > 0 ? ", " : "") + std::string("abi_bits(") + name + ")";
+        }
+        // Qualified in full, as the shims call it
+        std::string scope = func->namespace_path.empty() ? "" : func->namespace_path + "::";
+        std::string callee = func->template_of.empty() ? func->name : func->template_of + "<" + func->template_args + ">";
+        std::string call = "::" + scope + callee + "(" + args + ")";
+        ss << "\n";
+        ss << "uint64_t " << shimName(*func) << "_abi_checksum(" << params << ") {\n";
+        if (isVoid(func->return_type)) {
+            ss << "    " << call << ";\n";
+        } else {
+            bits += std::string(bits.empty() ? "" : ", ") + "abi_bits(" + call + ")";
+        }
+        ss << "    return abi_checksum({" << bits << "});\n";
+        ss << "}\n";
+    }
+    ss << "\n} // extern \"C\"\n";
+    return ss.str();
+}
+
+std::string RustFFIGenerator::generateSelfTest(const std::vector<FFIFunction>& functions,
+                                               const std::string& library_name) {
+    std::vector<const FFIFunction*> exercised;
+    std::vector<std::pair<std::string, std::string>> skipped;
+    for (const auto& func : functions) {
+        std::string reason = selfTestSkip(func);
+        if (reason.empty()) {
+            exercised.push_back(&func);
+        } else {
+            skipped.emplace_back(func.name, reason);
+        }
+    }
+
+    std::stringstream ss;
+    ss << "// Runtime ABI round trip: every exercised function has a C++ twin\n";
+    ss << "// `<name>_abi_checksum` that calls it with the same arguments and hashes\n";
+    ss << "// what it received and returned, which must match the Rust-side hash.\n";
+    ss << "// The twins are written into the shim source (`--emit-shim-source`).\n";
+    ss << "// Functions are assumed deterministic; class methods are not exercised.\n";
+    if (!skipped.empty()) {
+        ss << "//\n";
+        ss << "// Not exercised:\n";
+        for (const auto& entry : skipped) {
+            ss << "// - " << entry.first << ": " << entry.second << "\n";
+        }
+    }
+
+    ss << "#[cfg(test)]\n";
+    ss << "mod abi_self_test {\n";
+
+    if (!exercised.empty()) {
+        ss << "    use super::*;\n";
+        ss << "\n";
+        ss << "    #[link(name = \"" << library_name << "\")]\n";
+        ss << "    extern \"C\" {\n";
+        for (const auto* func : exercised) {
+            ss << "        fn " << shimName(*func) << "_abi_checksum(";
+            for (size_t i = 0; i < func->parameters.size(); ++i) {
+                const auto& param = func->parameters[i];
+                std::string type = param.rust_type.empty() ? rustType(param.cpp_type) : param.rust_type;
+                ss << (i > 0 ? ", " : "") << paramName(param, i) << ": " << type;
+            }
+            ss << ") -> u64;\n";
+        }
+        ss << "    }\n";
+        ss << "\n";
+        ss << "    // FNV-1a over the widened values, mirrored by abi_checksum in C++\n";
+        ss << "    fn checksum(values: &[u64]) -> u64 {\n";
+        ss << "        values.iter().fold(0xcbf2_9ce4_8422_2325, |h, v| (h ^ v).wrapping_mul(0x100_0000_01b3))\n";
+        ss << "    }\n";
+    }
+
+    for (const auto* func : exercised) {
+        std::string args;
+        std::string bits;
+        ss << "\n";
+        ss << "    #[test]\n";
+        ss << "    fn " << toSnakeCase(func->name) << "_abi_round_trip() {\n";
+        for (size_t i = 0; i < func->parameters.size(); ++i) {
+            const auto& param = func->parameters[i];
+            std::string name = paramName(param, i);
+            std::string type = param.rust_type.empty() ? rustType(param.cpp_type) : param.rust_type;
+            ss << "        let " << name << ": " << type << " = " << abiSentinel(type, i) << ";\n";
+            args += (i > 0 ? ", " : "") + name;
+            bits += (i > 0 ? ", " : "") + abiBits(name, type);
+        }
+        ss << safety(callInvariant(*func), "        ");
+        if (isVoid(func->return_type)) {
+            ss << "        unsafe { ffi::" << rustIdent(shimName(*func)) << "(" << args << ") };\n";
+        } else {
+            ss << "        let ret = unsafe { ffi::" << rustIdent(shimName(*func)) << "(" << args << ") };\n";
+            bits += std::string(bits.empty() ? "" : ", ") + abiBits("ret", rustType(func->return_type));
+        }
+        ss << "        let expected = checksum(&[" << bits << "]);\n";
+        ss << safety("`" + shimName(*func) + "_abi_checksum` takes the same plain values", "        ");
+        ss << "        assert_eq!(unsafe { " << shimName(*func) << "_abi_checksum(" << args << ") }, expected);\n";
+        ss << "    }\n";
+    }
+
+    ss << "}\n";
+    return ss.str();
+}
+
+std::string RustFFIGenerator::generateConstants(const FFIEnum& enum_decl) {
+    static const std::regex literal_suffix(R"(\b(0[xX][0-9a-fA-F]+|[0-9]+)[uUlL]+\b)");
+
+    std::stringstream ss;
+    std::string type = rustType(enum_decl.underlying_type);
+    std::string previous;
+
+    ss << "// Constants from an anonymous " << enum_decl.underlying_type << " enum\n";
+    for (const auto& value : enum_decl.values) {
+        std::string init;
+        if (!value.second.empty()) {
+            init = std::regex_replace(value.second, literal_suffix, "$1");
+            std::replace(init.begin(), init.end(), '~', '!');
+        } else {
+            // Implicit enumerators continue from the previous one
+            init = previous.empty() ? "0" : previous + " + 1";
+        }
+        ss << "pub const " << rustIdent(value.first) << ": " << type << " = " << init << ";\n";
+        generated_items_.push_back({"Constants", value.first,
+                                    "enum { " + value.first + (value.second.empty() ? "" : " = " + value.second) + " }"});
+        previous = rustIdent(value.first);
+    }
+    return ss.str();
+}
+
+std::string RustFFIGenerator::generateEnum(const FFIEnum& enum_decl) {
+    static const std::regex literal_suffix(R"(\b(0[xX][0-9a-fA-F]+|[0-9]+)[uUlL]+\b)");
+
+    const std::string name = enum_decl.name;
+    const std::string repr = enum_types_.at(name);
+    std::stringstream ss;
+
+    ss << docComment(enum_decl.doc_comment, "", true);
+    ss << "/// Mirror of the C++ enum " << name << "; integers from C++ go through TryFrom\n";
+    ss << "#[repr(" << repr << ")]\n";
+    ss << "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\n";
+    auto error = error_enums_.find(name);
+    // Messages come from the to_string function when there is one, so the
+    // derive only supplies the Error impl
+    bool error_messages = options_.thiserror && error != error_enums_.end() && error->second.to_string.empty();
+    if (options_.thiserror && error != error_enums_.end()) {
+        ss << "#[cfg_attr(feature = \"thiserror\", derive(thiserror::Error))]\n";
+    }
+    ss << deprecatedAttribute(enum_decl.is_deprecated, enum_decl.deprecation_note, "");
+    generated_items_.push_back({"Enums", name, "enum " + name});
+    ss << "pub enum " << name << " {\n";
+    for (const auto& value : enum_decl.values) {
+        if (error_messages) {
+            ss << "    #[cfg_attr(feature = \"thiserror\", error(\"" << value.first << "\"))]\n";
+        }
+        ss << "    " << rustIdent(value.first);
+        if (!value.second.empty()) {
+            std::string init = std::regex_replace(value.second, literal_suffix, "$1");
+            std::replace(init.begin(), init.end(), '~', '!');
+            ss << " = " << init;
+        }
+        ss << ",\n";
+    }
+    ss << "}\n\n";
+
+    ss << "impl TryFrom<" << repr << "> for " << name << " {\n";
+    ss << "    type Error = InvalidEnum;\n\n";
+    ss << "    fn try_from(value: " << repr << ") -> Result<Self, InvalidEnum> {\n";
+    ss << "        match value {\n";
+    for (const auto& value : enum_decl.values) {
+        ss << "            v if v == " << name << "::" << rustIdent(value.first) << " as " << repr << " => Ok("
+           << name << "::" << rustIdent(value.first) << "),\n";
+    }
+    ss << "            _ => Err(InvalidEnum { type_name: \"" << name << "\", value: value as i64 }),\n";
+    ss << "        }\n";
+    ss << "    }\n";
+    ss << "}\n";
+
+    if (error == error_enums_.end()) {
+        return ss.str();
+    }
+    ss << "\n";
+    if (error_messages) {
+        ss << "#[cfg(not(feature = \"thiserror\"))]\n";
+    }
+    ss << "impl std::fmt::Display for " << name << " {\n";
+    ss << "    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {\n";
+    if (error->second.to_string.empty()) {
+        ss << "        write!(f, \"{:?}\", self)\n";
+    } else {
+        ss << safety("`" + error->second.to_string + "` takes only plain values", "        ");
+        ss << "        let ptr = unsafe { ffi::" << rustIdent(error->second.to_string) << "(*self as " << repr << ") };\n";
+        ss << "        if ptr.is_null() {\n";
+        ss << "            return write!(f, \"{:?}\", self);\n";
+        ss << "        }\n";
+        ss << safety("`ptr` is non-null and points to a NUL-terminated string", "        ");
+        ss << "        f.write_str(&unsafe { std::ffi::CStr::from_ptr(ptr.cast()) }.to_string_lossy())\n";
+    }
+    ss << "    }\n";
+    ss << "}\n\n";
+    if (options_.thiserror) {
+        ss << "#[cfg(not(feature = \"thiserror\"))]\n";
+    }
+    ss << "impl std::error::Error for " << name << " {}\n";
+    return ss.str();
+}
+
+std::string RustFFIGenerator::generateToc() const {
+    static const std::vector<std::string> kinds = {
+        "Functions", "Structs", "Enums", "Constants", "Opaque wrappers", "Traits", "Macros",
+    };
+
+    std::stringstream ss;
+    ss << "//! Generated items:\n";
+    for (const auto& kind : kinds) {
+        bool listed = false;
+        for (const auto& item : generated_items_) {
+            if (item.kind != kind) {
+                continue;
+            }
+            if (!listed) {
+                ss << "//!\n";
+                ss << "//! " << kind << ":\n";
+                listed = true;
+            }
+            ss << "//! - `" << item.name << "` from `" << item.origin << "`\n";
+        }
+    }
+    return ss.str();
+}
+
+std::string RustFFIGenerator::generateClassBinding(const FFIClass& cls) {
+    if (isReprC(cls)) {
+        return generateStructBinding(cls);
+    }
+
+    std::stringstream ss;
+    const std::string name = rustTypeName(cls.name);
+
+    bool needs_view = needsView(cls);
+
+    ss << docComment(cls.doc_comment, "", true);
+    ss << "/// Safe wrapper for " << cls.name << " class\n";
+    if (cls.factory_only) {
+        ss << "///\n";
+        ss << "/// The C++ constructors are not public; create instances through the static factories.\n";
+    }
+    if (needs_view) {
+        ss << "#[repr(transparent)]\n";
+    }
+    ss << deprecatedAttribute(cls.is_deprecated, cls.deprecation_note, "");
+    generated_items_.push_back({"Opaque wrappers", name, "class " + cls.name});
+    ss << "pub struct " << name << " {\n";
+    ss << "    ptr: *mut c_void,\n";
+    ss << "}\n\n";
+
+    const RefCounting* refcount = refCounting(cls);
+
+    ss << "impl " << name << " {\n";
+    bool first = true;
+    for (const auto* group : {&cls.methods, &cls.static_methods}) {
+        for (const auto& method : *group) {
+            if (isDestructor(method) || isOperator(method)) {
+                continue;
+            }
+            // Reference counts are only managed through Clone and Drop
+            if (refcount && (method.name == refcount->incref || method.name == refcount->decref)) {
+                continue;
+            }
+            if (!first) ss << "\n";
+            first = false;
+            if (options_.default_args == DefaultArgs::Overloads && hasLiteralDefaults(method)) {
+                ss << generateMethodWrapper(method, cls, true) << "\n";
+                ss << generateMethodWrapper(method, cls, false, "_with");
+            } else {
+                ss << generateMethodWrapper(method, cls);
+            }
+        }
+    }
+    ss << "}\n\n";
+
+    // A `new()` taking nothing is what Default would do
+    bool default_new = std::any_of(cls.methods.begin(), cls.methods.end(), [&](const FFIFunction& method) {
+        bool omitted = options_.default_args == DefaultArgs::Overloads;
+        bool no_args = std::all_of(method.parameters.begin(), method.parameters.end(),
+                                   [&](const FFIParameter& param) { return omitted && !rustDefault(param).empty(); });
+        return isConstructor(method) && no_args && shimName(method) == toSnakeCase(name) + "_new";
+    });
+    if (default_new) {
+        ss << "impl Default for " << name << " {\n";
+        ss << "    fn default() -> Self {\n";
+        ss << "        Self::new()\n";
+        ss << "    }\n";
+        ss << "}\n\n";
+    }
+
+    std::string release = toSnakeCase(name) + "_delete";
+    if (refcount) {
+        FFIFunction incref = makeRefCountMethod(cls.name, refcount->incref);
+        FFIFunction decref = makeRefCountMethod(cls.name, refcount->decref);
+        release = shimName(decref);
+
+        // Clones share the C++ object, each one holding its own reference
+        ss << "impl Clone for " << name << " {\n";
+        ss << "    fn clone(&self) -> Self {\n";
+        ss << safety("`self.ptr` points to a live " + name + "; the clone's Drop releases the new reference",
+                     "        ");
+        ss << "        unsafe {\n";
+        ss << "            ffi::" << rustIdent(shimName(incref)) << "(self.ptr);\n";
+        ss << "        }\n";
+        ss << "        " << name << " { ptr: self.ptr }\n";
+        ss << "    }\n";
+        ss << "}\n\n";
+    }
+
+    ss << "impl Drop for " << name << " {\n";
+    ss << "    fn drop(&mut self) {\n";
+    ss << safety(std::string("`self.ptr` points to a live ") + name + " and " +
+                 (refcount ? "this wrapper's reference" : "the object") + " is released exactly once", "        ");
+    ss << "        unsafe {\n";
+    ss << "            ffi::" << rustIdent(release) << "(self.ptr);\n";
+    ss << "        }\n";
+    ss << "    }\n";
+    ss << "}\n";
+
+    std::string comparisons = generateComparisons(cls);
+    if (!comparisons.empty()) {
+        ss << "\n" << comparisons;
+    }
+
+    if (needs_view) {
+        ss << "\n" << generateSelfView(cls);
+    }
+
+    std::string builder = generateBuilder(cls);
+    if (!builder.empty()) {
+        ss << "\n" << builder;
+    }
+
+    return ss.str();
+}
+
+std::vector<const FFIFunction*> RustFFIGenerator::builderSetters(const FFIClass& cls) {
+    if (options_.builder_min_setters == 0 || cls.factory_only || cls.is_abstract || refCounting(cls)) {
+        return {};
+    }
+    bool default_constructible = false;
+    for (const auto& method : cls.methods) {
+        default_constructible = default_constructible || (isConstructor(method) && method.parameters.empty());
+    }
+    if (!default_constructible) {
+        return {};
+    }
+
+    std::vector<const FFIFunction*> setters;
+    for (const auto& method : cls.methods) {
+        std::string chained = toSnakeCase(method.name);
+        bool named_setter = chained.compare(0, 4, "set_") == 0 && chained != "set_new" && chained != "set_build";
+        if (!named_setter || isConstructor(method) || method.is_const || method.parameters.size() != 1 ||
+            !isVoid(method.return_type) || isEnumOutParam(method.parameters[0]) || narrowsIntegers(method)) {
+            continue;
+        }
+        // An unsafe setter cannot be chained from a safe builder
+        std::string args;
+        std::vector<std::string> locals;
+        if (!pointerSafety(paramList(method, false, args, locals, true), method.name, "").empty()) {
+            continue;
+        }
+        std::string qualified = cls.name + "::" + method.name;
+        bool selected = options_.builder_setters.empty() ||
+                        std::any_of(options_.builder_setters.begin(), options_.builder_setters.end(),
+                                    [&](const std::string& pattern) { return std::regex_match(qualified, std::regex(pattern)); });
+        if (selected) {
+            setters.push_back(&method);
+        }
+    }
+    if (setters.size() < options_.builder_min_setters) {
+        return {};
+    }
+    return setters;
+}
+
+std::string RustFFIGenerator::generateBuilder(const FFIClass& cls) {
+    std::vector<const FFIFunction*> setters = builderSetters(cls);
+    if (setters.empty()) {
+        return "";
+    }
+
+    const std::string name = rustTypeName(cls.name);
+    const std::string builder = name + "Builder";
+    std::string constructor = "new";
+    for (const auto& method : cls.methods) {
+        if (isConstructor(method) && method.parameters.empty()) {
+            std::string shim = shimName(method);
+            std::string prefix = toSnakeCase(name) + "_";
+            constructor = shim.find(prefix) == 0 ? shim.substr(prefix.size()) : "new";
+        }
+    }
+
+    std::stringstream ss;
+    generated_items_.push_back({"Opaque wrappers", builder, "setters of " + cls.name});
+    ss << "/// Builder for " << name << ": chains its setters after the default constructor\n";
+    ss << "pub struct " << builder << " {\n";
+    ss << "    inner: " << name << ",\n";
+    ss << "}\n\n";
+
+    ss << "impl " << builder << " {\n";
+    bool first = true;
+    for (const auto* setter : setters) {
+        // Same parameter as the setter wrapper, which does the conversions
+        std::string args;
+        std::vector<std::string> locals;
+        std::string params = paramList(*setter, false, args, locals, true);
+        std::string wrapper = toSnakeCase(setter->name);
+        if (options_.default_args == DefaultArgs::Overloads && hasLiteralDefaults(*setter)) {
+            wrapper += "_with";
+        }
+
+        ss << (first ? "" : "\n");
+        first = false;
+        ss << panicsDoc(locals, "    ");
+        ss << "    pub fn " << rustIdent(toSnakeCase(setter->name).substr(4)) << "(mut self, " << params << ") -> Self {\n";
+        ss << "        self.inner." << rustIdent(wrapper) << "(" << paramName(setter->parameters[0], 0) << ");\n";
+        ss << "        self\n";
+        ss << "    }\n";
+    }
+    ss << "\n";
+    ss << "    pub fn build(self) -> " << name << " {\n";
+    ss << "        self.inner\n";
+    ss << "    }\n";
+    ss << "}\n\n";
+
+    // Started only from here, so the builder has no `new` of its own
+    ss << "impl " << name << " {\n";
+    ss << "    pub fn builder() -> " << builder << " {\n";
+    ss << "        " << builder << " { inner: " << name << "::" << constructor << "() }\n";
+    ss << "    }\n";
+    ss << "}\n";
+    return ss.str();
+}
+
+std::string RustFFIGenerator::channelProblem(const FFIFunction& func, const std::vector<FFIFunction>& functions) {
+    static const std::set<std::string> scalars = {
+        "i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64", "isize", "usize", "f32", "f64", "bool",
+        "std::ffi::c_long", "std::ffi::c_ulong",
+    };
+
+    const FFIFunction* signature = nullptr;
+    size_t contexts = 0;
+    for (const auto& param : func.parameters) {
+        auto callback = callback_types_.find(trim(param.cpp_type));
+        if (callback != callback_types_.end()) {
+            if (signature) {
+                return "takes more than one callback";
+            }
+            signature = &callback->second;
+        } else if (isVoidPointer(param.cpp_type)) {
+            contexts++;
+        } else if (!scalars.count(rustType(param.cpp_type))) {
+            return "parameter `" + param.name + "` is not a scalar";
+        }
+    }
+    if (!signature || contexts != 1) {
+        return "needs exactly one function pointer typedef parameter and one `void*` context";
+    }
+    if (!isVoid(signature->return_type)) {
+        return "callback `" + signature->name + "` returns a value";
+    }
+
+    // Events are copied out before the callback returns
+    size_t user_data = 0;
+    for (const auto& param : signature->parameters) {
+        if (isVoidPointer(param.cpp_type)) {
+            user_data++;
+        } else if (trim(param.cpp_type) != "const char*" && !scalars.count(rustType(param.cpp_type))) {
+            return "callback `" + signature->name + "` passes `" + trim(param.cpp_type) +
+                   "`, only scalars and `const char*` can be sent";
+        }
+    }
+    if (user_data != 1) {
+        return "callback `" + signature->name + "` needs exactly one `void*` user data parameter";
+    }
+
+    const std::string& unregister = options_.channel_callbacks.at(func.name).unregister;
+    auto remove = std::find_if(functions.begin(), functions.end(),
+                               [&](const FFIFunction& f) { return f.name == unregister && f.can_use_ffi; });
+    if (remove == functions.end()) {
+        return "unregister function `" + unregister + "` is not bound";
+    }
+    for (const auto& param : remove->parameters) {
+        bool matched = callback_types_.count(trim(param.cpp_type)) || isVoidPointer(param.cpp_type) ||
+                       (!isVoid(func.return_type) && trim(param.cpp_type) == trim(func.return_type));
+        for (const auto& arg : func.parameters) {
+            matched = matched || (arg.name == param.name && trim(arg.cpp_type) == trim(param.cpp_type));
+        }
+        if (!matched) {
+            return "`" + unregister + "` parameter `" + param.name +
+                   "` is neither the callback, the context, the returned handle nor a registration argument";
+        }
+    }
+    return "";
+}
+
+std::string RustFFIGenerator::generateChannelWrapper(const FFIFunction& func,
+                                                     const std::vector<FFIFunction>& functions) {
+    const ChannelCallback& channel = options_.channel_callbacks.at(func.name);
+    const FFIFunction& unregister = *std::find_if(functions.begin(), functions.end(),
+                                                  [&](const FFIFunction& f) { return f.name == channel.unregister; });
+    std::string callback_type;
+    for (const auto& param : func.parameters) {
+        if (callback_types_.count(trim(param.cpp_type))) {
+            callback_type = trim(param.cpp_type);
+        }
+    }
+    const FFIFunction& signature = callback_types_.at(callback_type);
+
+    const std::string wrapper = rustIdent(toSnakeCase(func.name));
+    const std::string receiver = pascalCase(func.name) + "Receiver";
+    const std::string trampoline = toSnakeCase(func.name) + "_trampoline";
+
+    // One event field per callback parameter except the user data
+    std::vector<std::string> fields;
+    std::vector<std::string> conversions;
+    std::string user_data;
+    std::string trampoline_params;
+    for (size_t i = 0; i < signature.parameters.size(); ++i) {
+        const auto& param = signature.parameters[i];
+        std::string name = paramName(param, i);
+        trampoline_params += (i > 0 ? ", " : "") + name + ": " + rustType(param.cpp_type);
+        if (isVoidPointer(param.cpp_type)) {
+            user_data = name;
+        } else if (trim(param.cpp_type) == "const char*") {
+            fields.push_back("String");
+            conversions.push_back("    let " + name + " = if " + name + ".is_null() {\n"
+                                  "        String::new()\n"
+                                  "    } else {\n" +
+                                  safety("`" + name + "` is a NUL-terminated string valid during the callback",
+                                         "        ") +
+                                  "        unsafe { std::ffi::CStr::from_ptr(" + name +
+                                  ") }.to_string_lossy().into_owned()\n"
+                                  "    };\n");
+        } else {
+            fields.push_back(rustType(param.cpp_type));
+        }
+    }
+    std::string event;
+    std::string send;
+    for (size_t i = 0, field = 0; i < signature.parameters.size(); ++i) {
+        if (!isVoidPointer(signature.parameters[i].cpp_type)) {
+            send += (field++ > 0 ? ", " : "") + paramName(signature.parameters[i], i);
+        }
+    }
+    for (size_t i = 0; i < fields.size(); ++i) {
+        event += (i > 0 ? ", " : "") + fields[i];
+    }
+    if (fields.size() != 1) {
+        event = "(" + event + ")";
+        send = "(" + send + ")";
+    }
+    const std::string sender_type = "tokio::sync::mpsc::Sender<" + event + ">";
+
+    // Registration arguments the unregister call needs again are kept
+    std::vector<std::pair<std::string, std::string>> kept;
+    std::string params;
+    std::string register_args;
+    for (size_t i = 0; i < func.parameters.size(); ++i) {
+        const auto& param = func.parameters[i];
+        std::string name = paramName(param, i);
+        register_args += i > 0 ? ", " : "";
+        if (callback_types_.count(trim(param.cpp_type))) {
+            register_args += "Some(" + trampoline + ")";
+        } else if (isVoidPointer(param.cpp_type)) {
+            register_args += "sender.cast()";
+        } else {
+            params += (params.empty() ? "" : ", ") + name + ": " + rustType(param.cpp_type);
+            register_args += name;
+            for (const auto& other : unregister.parameters) {
+                if (other.name == param.name && trim(other.cpp_type) == trim(param.cpp_type)) {
+                    kept.emplace_back(name, rustType(param.cpp_type));
+                }
+            }
+        }
+    }
+    bool keeps_handle = false;
+    std::string unregister_args;
+    for (size_t i = 0; i < unregister.parameters.size(); ++i) {
+        const auto& param = unregister.parameters[i];
+        unregister_args += i > 0 ? ", " : "";
+        bool by_name = std::any_of(kept.begin(), kept.end(),
+                                   [&](const std::pair<std::string, std::string>& k) { return k.first == paramName(param, i); });
+        if (callback_types_.count(trim(param.cpp_type))) {
+            unregister_args += "Some(" + trampoline + ")";
+        } else if (isVoidPointer(param.cpp_type)) {
+            unregister_args += "self.sender.cast()";
+        } else if (by_name) {
+            unregister_args += "self." + paramName(param, i);
+        } else {
+            unregister_args += "self.handle";
+            keeps_handle = true;
+        }
+    }
+
+    std::stringstream ss;
+    ss << "/// Events from the callback registered by `" << wrapper << "`; unregistered with `"
+       << channel.unregister << "` when dropped\n";
+    ss << "///\n";
+    ss << "/// Derefs to the channel's receiver: `while let Some(event) = events.recv().await`.\n";
+    ss << "/// Events arriving while " << channel.capacity << " are queued are dropped.\n";
+    generated_items_.push_back({"Opaque wrappers", receiver, "callbacks of " + func.name});
+    ss << "pub struct " << receiver << " {\n";
+    ss << "    receiver: tokio::sync::mpsc::Receiver<" << event << ">,\n";
+    ss << "    sender: *mut " << sender_type << ",\n";
+    if (keeps_handle) {
+        ss << "    handle: " << rustType(func.return_type) << ",\n";
+    }
+    for (const auto& field : kept) {
+        ss << "    " << field.first << ": " << field.second << ",\n";
+    }
+    ss << "}\n\n";
+
+    ss << "// " << (options_.safety_comments ? "SAFETY: " : "")
+       << "the sender is only touched by the trampoline, which tokio's Sender allows\n";
+    ss << "// from any thread, and freed once the callback is unregistered\n";
+    ss << "unsafe impl Send for " << receiver << " {}\n\n";
+
+    ss << "extern \"C\" fn " << trampoline << "(" << trampoline_params << ") {\n";
+    ss << safety("`" + user_data + "` is the sender boxed by `" + wrapper + "`, alive until unregistered", "    ");
+    ss << "    let sender = unsafe { &*" << user_data << ".cast::<" << sender_type << ">() };\n";
+    for (const auto& conversion : conversions) {
+        ss << conversion;
+    }
+    ss << "    // A full or closed channel drops the event instead of blocking the C++ thread\n";
+    ss << "    let _ = sender.try_send(" << send << ");\n";
+    ss << "}\n\n";
+
+    ss << "/// Registers a callback through `" << func.name << "` and returns the receiver of its events\n";
+    generated_items_.push_back({"Functions", toSnakeCase(func.name), declarationOf(func)});
+    ss << "pub fn " << wrapper << "(" << params << ") -> " << receiver << " {\n";
+    if (!init_function_.empty() && options_.init.automatic) {
+        ss << "    init();\n";
+    }
+    ss << "    let (sender, receiver) = tokio::sync::mpsc::channel(" << channel.capacity << ");\n";
+    ss << "    let sender = Box::into_raw(Box::new(sender));\n";
+    ss << safety("`sender` stays alive until the receiver unregisters the trampoline", "    ");
+    ss << "    " << (keeps_handle ? "let handle = " : "") << "unsafe { ffi::" << rustIdent(shimName(func)) << "("
+       << register_args << ") };\n";
+    ss << "    " << receiver << " { receiver, sender";
+    if (keeps_handle) {
+        ss << ", handle";
+    }
+    for (const auto& field : kept) {
+        ss << ", " << field.first;
+    }
+    ss << " }\n";
+    ss << "}\n\n";
+
+    ss << "impl std::ops::Deref for " << receiver << " {\n";
+    ss << "    type Target = tokio::sync::mpsc::Receiver<" << event << ">;\n\n";
+    ss << "    fn deref(&self) -> &Self::Target {\n";
+    ss << "        &self.receiver\n";
+    ss << "    }\n";
+    ss << "}\n\n";
+    ss << "impl std::ops::DerefMut for " << receiver << " {\n";
+    ss << "    fn deref_mut(&mut self) -> &mut Self::Target {\n";
+    ss << "        &mut self.receiver\n";
+    ss << "    }\n";
+    ss << "}\n\n";
+
+    ss << "impl Drop for " << receiver << " {\n";
+    ss << "    fn drop(&mut self) {\n";
+    ss << "        // " << (options_.safety_comments ? "SAFETY: " : "")
+       << "once unregistered the trampoline no longer runs, so the sender can be freed\n";
+    ss << "        unsafe {\n";
+    ss << "            ffi::" << rustIdent(shimName(unregister)) << "(" << unregister_args << ");\n";
+    ss << "            drop(Box::from_raw(self.sender));\n";
+    ss << "        }\n";
+    ss << "    }\n";
+    ss << "}\n";
+    return ss.str();
+}
+
+std::string RustFFIGenerator::generateComparisons(const FFIClass& cls) {
+    const FFIFunction* three_way = nullptr;
+    const FFIFunction* less = nullptr;
+    const FFIFunction* equal = nullptr;
+    for (const auto& method : cls.methods) {
+        if (!isOperator(method) || method.parameters.size() != 1) {
+            continue;
+        }
+        if (method.name == "operator<=>") {
+            three_way = &method;
+        } else if (method.name == "operator<") {
+            less = &method;
+        } else if (method.name == "operator==") {
+            equal = &method;
+        }
+    }
+    if (!three_way && !less && !equal) {
+        return "";
+    }
+
+    const std::string name = rustTypeName(cls.name);
+    const std::string ordering = "std::cmp::Ordering";
+    bool partial = three_way && three_way->return_type.find("partial_ordering") != std::string::npos;
+    std::string both_live = safety("`self.ptr` and `other.ptr` point to live " + name + "s", "        ");
+    std::stringstream ss;
+
+    ss << "impl PartialEq for " << name << " {\n";
+    ss << "    fn eq(&self, other: &Self) -> bool {\n";
+    if (equal) {
+        ss << both_live;
+        ss << "        unsafe { ffi::" << rustIdent(shimName(*equal)) << "(self.ptr, other.ptr) }\n";
+    } else if (partial) {
+        ss << "        self.partial_cmp(other) == Some(" << ordering << "::Equal)\n";
+    } else {
+        ss << "        self.cmp(other) == " << ordering << "::Equal\n";
+    }
+    ss << "    }\n";
+    ss << "}\n";
+
+    if (!three_way && !less) {
+        return ss.str();
+    }
+    ss << "\n";
+
+    if (partial) {
+        ss << "impl PartialOrd for " << name << " {\n";
+        ss << "    fn partial_cmp(&self, other: &Self) -> Option<" << ordering << "> {\n";
+        ss << both_live;
+        ss << "        match unsafe { ffi::" << rustIdent(shimName(*three_way)) << "(self.ptr, other.ptr) } {\n";
+        ss << "            -1 => Some(" << ordering << "::Less),\n";
+        ss << "            0 => Some(" << ordering << "::Equal),\n";
+        ss << "            1 => Some(" << ordering << "::Greater),\n";
+        ss << "            _ => None,\n";
+        ss << "        }\n";
+        ss << "    }\n";
+        ss << "}\n";
+        return ss.str();
+    }
+
+    ss << "impl Eq for " << name << " {}\n\n";
+    ss << "impl PartialOrd for " << name << " {\n";
+    ss << "    fn partial_cmp(&self, other: &Self) -> Option<" << ordering << "> {\n";
+    ss << "        Some(self.cmp(other))\n";
+    ss << "    }\n";
+    ss << "}\n\n";
+
+    ss << "impl Ord for " << name << " {\n";
+    ss << "    fn cmp(&self, other: &Self) -> " << ordering << " {\n";
+    if (three_way) {
+        ss << both_live;
+        ss << "        unsafe { ffi::" << rustIdent(shimName(*three_way)) << "(self.ptr, other.ptr) }.cmp(&0)\n";
+    } else if (options_.safety_comments) {
+        // An `else if unsafe` condition has no line of its own for the comment
+        std::string lt = "ffi::" + rustIdent(shimName(*less));
+        ss << both_live;
+        ss << "        if unsafe { " << lt << "(self.ptr, other.ptr) } {\n";
+        ss << "            return " << ordering << "::Less;\n";
+        ss << "        }\n";
+        ss << both_live;
+        ss << "        if unsafe { " << lt << "(other.ptr, self.ptr) } {\n";
+        ss << "            " << ordering << "::Greater\n";
+        ss << "        } else {\n";
+        ss << "            " << ordering << "::Equal\n";
+        ss << "        }\n";
+    } else {
+        // Only operator< is available: equal means neither is less
+        std::string lt = "ffi::" + rustIdent(shimName(*less));
+        ss << "        if unsafe { " << lt << "(self.ptr, other.ptr) } {\n";
+        ss << "            " << ordering << "::Less\n";
+        ss << "        } else if unsafe { " << lt << "(other.ptr, self.ptr) } {\n";
+        ss << "            " << ordering << "::Greater\n";
+        ss << "        } else {\n";
+        ss << "            " << ordering << "::Equal\n";
+        ss << "        }\n";
+    }
+    ss << "    }\n";
+    ss << "}\n";
+    return ss.str();
+}
+
+std::vector<FFIDiagnostic> RustFFIGenerator::checkConstness(const std::string& rust_code,
+                                                            const std::vector<FFIClass>& input_classes) const {
+    // Shim name -> C++ method, for every method called through a receiver
+    std::vector<FFIClass> classes = prepareShimClasses(input_classes, options_);
+    std::map<std::string, std::pair<std::string, const FFIFunction*>> methods;
+    for (const auto& cls : classes) {
+        if (isReprC(cls)) {
+            continue;
+        }
+        const RefCounting* refcount = refCounting(cls);
+        for (const auto& method : cls.methods) {
+            if (isConstructor(method) || isDestructor(method) || method.is_static) {
+                continue;
+            }
+            // Clone and Drop adjust the count through a shared handle by design
+            if (refcount && (method.name == refcount->incref || method.name == refcount->decref)) {
+                continue;
+            }
+            methods[shimName(method)] = {cls.name + "::" + method.name, &method};
+        }
+    }
+
+    static const std::regex extern_this(R"(pub fn (\w+)\(this: \*(const|mut) c_void)");
+    static const std::regex receiver(R"(\bfn (?:r#)?\w+(?:<[^>]*>)?\((&mut self|&self|self)\b)");
+    static const std::regex call(R"(ffi::(\w+)\(self\.ptr\b)");
+
+    std::vector<FFIDiagnostic> mismatches;
+    std::string current_receiver;
+    std::istringstream lines(rust_code);
+    for (std::string line; std::getline(lines, line);) {
+        std::smatch match;
+        if (std::regex_search(line, match, extern_this)) {
+            auto it = methods.find(match[1].str());
+            bool const_this = match[2].str() == "const";
+            if (it != methods.end() && const_this != it->second.second->is_const) {
+                mismatches.push_back({it->second.first,
+                    "extern `" + it->first + "` declares `this: *" + match[2].str() +
+                    " c_void`, but the C++ method is " + (const_this ? "not const" : "const")});
+            }
+            continue;
+        }
+
+        if (std::regex_search(line, match, receiver)) {
+            current_receiver = match[1].str();
+        }
+        // By-value receivers are views or consume the handle, either is sound
+        if (current_receiver == "self" || !std::regex_search(line, match, call)) {
+            continue;
+        }
+        auto it = methods.find(match[1].str());
+        bool shared = current_receiver == "&self";
+        if (it != methods.end() && shared != it->second.second->is_const) {
+            mismatches.push_back({it->second.first,
+                "`" + current_receiver + "` wrapper calls `" + it->first + "`, but the C++ method is " +
+                (shared ? "not const" : "const")});
+        }
+    }
+    return mismatches;
+}
+
+std::string RustFFIGenerator::templateTraitProblem(const std::string& name, const std::vector<FFIFunction>& functions) {
+    static const std::set<std::string> scalars = {
+        "i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64", "isize", "usize", "f32", "f64", "bool",
+        "std::ffi::c_long", "std::ffi::c_ulong",
+    };
+
+    std::set<std::string> types;
+    size_t count = 0;
+    for (const auto& func : functions) {
+        if (func.template_of != name) {
+            continue;
+        }
+        if (!func.can_use_ffi) {
+            return "instance " + func.name + " is skipped";
+        }
+        if (func.template_args.find(',') != std::string::npos) {
+            return "instance " + func.name + " has several template arguments";
+        }
+        std::string type = rustType(func.template_args);
+        if (isEnum(func.template_args) || !scalars.count(type)) {
+            return "`" + func.template_args + "` is not a number or bool";
+        }
+        if (!types.insert(type).second) {
+            return "two instances are both `" + type + "` in Rust";
+        }
+        if (hasLiteralDefaults(func) || narrowsIntegers(func)) {
+            return "instance " + func.name + " does not take its parameters as written";
+        }
+        for (const auto& param : func.parameters) {
+            bool generic = param.generic_type != param.cpp_type;
+            if ((generic && trim(param.cpp_type) != trim(func.template_args)) ||
+                (!generic && (isEnum(param.cpp_type) || !scalars.count(rustType(param.cpp_type))))) {
+                return "parameter `" + param.name + "` is not a scalar or the template argument by value";
+            }
+        }
+        bool generic = func.generic_return_type != func.return_type;
+        if ((generic && trim(func.return_type) != trim(func.template_args)) ||
+            (!generic && !isVoid(func.return_type) &&
+             (isEnum(func.return_type) || !scalars.count(rustType(func.return_type))))) {
+            return "the return type is not a scalar or the template argument by value";
+        }
+        count++;
+    }
+    return count == 0 ? "no instances are bound" : "";
+}
+
+std::string RustFFIGenerator::generateTemplateTrait(const std::string& name, const std::vector<FFIFunction>& functions) {
+    std::vector<const FFIFunction*> instances;
+    for (const auto& func : functions) {
+        if (func.template_of == name) {
+            instances.push_back(&func);
+        }
+    }
+    const std::string trait = template_traits_.at(name);
+    const std::string method = rustIdent(toSnakeCase(name));
+    const FFIFunction& first = *instances.front();
+
+    // Parameters of the template argument's type become `Self` / `T`
+    auto signature = [&](const std::string& generic) {
+        std::string params;
+        for (size_t i = 0; i < first.parameters.size(); ++i) {
+            const auto& param = first.parameters[i];
+            std::string type = param.generic_type != param.cpp_type ? generic : rustType(param.cpp_type);
+            params += (params.empty() ? "" : ", ") + paramName(param, i) + ": " + type;
+        }
+        std::string ret = first.generic_return_type != first.return_type ? generic
+                        : isVoid(first.return_type) ? "" : rustType(first.return_type);
+        return "(" + params + ")" + (ret.empty() ? "" : " -> " + ret);
+    };
+    std::string args;
+    for (size_t i = 0; i < first.parameters.size(); ++i) {
+        args += (args.empty() ? "" : ", ") + paramName(first.parameters[i], i);
+    }
+
+    std::stringstream ss;
+    ss << "/// Types the C++ template `" << name << "` is instantiated for; [`" << method
+       << "`] calls the matching instance\n";
+    generated_items_.push_back({"Traits", trait, "template " + name});
+    ss << "pub trait " << trait << " {\n";
+    ss << "    fn " << method << signature("Self") << ";\n";
+    ss << "}\n";
+    for (const auto* instance : instances) {
+        ss << "\n";
+        ss << "impl " << trait << " for " << rustType(instance->template_args) << " {\n";
+        ss << "    fn " << method << signature("Self") << " {\n";
+        ss << "        " << toSnakeCase(instance->name) << "(" << args << ")\n";
+        ss << "    }\n";
+        ss << "}\n";
+    }
+    ss << "\n";
+    ss << "/// `" << name << "` for any type implementing [`" << trait << "`]\n";
+    generated_items_.push_back({"Functions", toSnakeCase(name), "template " + name});
+    ss << "pub fn " << method << "<T: " << trait << ">" << signature("T") << " {\n";
+    ss << "    T::" << method << "(" << args << ")\n";
+    ss << "}\n";
+    return ss.str();
+}
+
+std::string RustFFIGenerator::generateModule(
+    const std::vector<FFIFunction>& functions,
+    const std::vector<FFIClass>& input_classes,
+    const std::string& library_name,
+    const std::vector<FFIEnum>& enums,
+    const std::vector<FFIFunction>& callback_types
+) {
+    std::stringstream ss;
+
+    std::vector<FFIClass> classes = prepareShimClasses(input_classes, options_);
+
+    // A struct containing itself by value has no finite size, in C++ or
+    // Rust; self-reference has to go through a pointer
+    std::vector<std::pair<std::string, std::string>> recursive_structs;
+    for (const auto& cls : classes) {
+        std::set<std::string> visited;
+        std::string cycle = isReprC(cls) ? embeddingCycle(cls.name, cls, classes, visited) : "";
+        if (!cycle.empty()) {
+            recursive_structs.emplace_back(cls.name, "embeds itself by value through " + cycle + ", use a pointer");
+        }
+    }
+    for (const auto& entry : recursive_structs) {
+        classes.erase(std::remove_if(classes.begin(), classes.end(),
+                                     [&](const FFIClass& cls) { return cls.name == entry.first; }),
+                      classes.end());
+    }
+
+    repr_c_structs_.clear();
+    enum_types_.clear();
+    callback_types_.clear();
+    diagnostics_.clear();
+    generated_items_.clear();
+    for (const auto& signature : callback_types) {
+        callback_types_[signature.name] = signature;
+    }
+    for (const auto& enum_decl : enums) {
+        // Rust enums need at least one variant to take a repr
+        if (!enum_decl.name.empty() && !enum_decl.values.empty()) {
+            enum_types_[enum_decl.name] = rustType(enum_decl.underlying_type);
+        }
+    }
+    opaque_classes_.clear();
+    viewed_classes_.clear();
+    memory_structs_ = memoryReturnedStructs(classes, options_.abi_targets);
+    std::set<std::string> class_shims;
+    for (const auto& cls : classes) {
+        if (isReprC(cls)) {
+            repr_c_structs_.insert(cls.name);
+            continue;
+        }
+        opaque_classes_.insert(cls.name);
+        for (const auto* group : {&cls.methods, &cls.static_methods}) {
+            for (const auto& method : *group) {
+                class_shims.insert(shimName(method));
+            }
+        }
+        if (!refCounting(cls)) {
+            class_shims.insert(toSnakeCase(cls.name) + "_delete");
+        }
+    }
+
+    for (const auto& func : functions) {
+        viewed_classes_.insert(viewedClass(func));
+    }
+    for (const auto& cls : classes) {
+        for (const auto* group : {&cls.methods, &cls.static_methods}) {
+            for (const auto& method : *group) {
+                viewed_classes_.insert(viewedClass(method));
+            }
+        }
+        // Singletons not shared between threads are handed out as views
+        for (const auto& method : cls.static_methods) {
+            if (opaque_classes_.count(cls.name) && isSingletonAccessor(method, cls) &&
+                !options_.thread_safe.count(cls.name)) {
+                viewed_classes_.insert(cls.name);
+            }
+        }
+    }
+    viewed_classes_.erase("");
+
+    struct_derives_.clear();
+    for (const auto& cls : classes) {
+        if (isReprC(cls)) {
+            auto configured = options_.derives.find(cls.name);
+            struct_derives_[cls.name] =
+                configured != options_.derives.end() ? configured->second : inferDerives(cls, classes);
+        }
+    }
+
+    // Hand-written shims for wrapped classes are already declared above
+    std::vector<FFIFunction> free_functions;
+    for (const auto& func : functions) {
+        if (!class_shims.count(shimName(func))) {
+            free_functions.push_back(func);
+        }
+    }
+
+    // Skipped declarations leave the bindings incomplete; under strict
+    // only the acknowledged ones are accepted
+    std::string unacknowledged;
+    std::set<std::string> skipped;
+    auto skip = [&](const std::string& symbol, const std::string& reason, SkipCategory category) {
+        if (options_.allowed_skips.count(symbol) || !skipped.insert(symbol).second) {
+            return;
+        }
+        if (options_.strict) {
+            unacknowledged += "\n  " + symbol + ": " + reason;
+        } else {
+            diagnostics_.push_back({symbol, "skipped: " + reason, category});
+        }
+    };
+    for (const auto& entry : recursive_structs) {
+        skip(entry.first, entry.second, SkipCategory::SelfEmbedding);
+    }
+    for (const auto& func : free_functions) {
+        if (!func.can_use_ffi) {
+            skip(func.name, func.reason, func.skip_category);
+        } else if (!defaultArgumentProblem(func).empty()) {
+            // The wrapper is still generated, taking the argument
+            skip(func.name, defaultArgumentProblem(func), SkipCategory::Other);
+        }
+    }
+    for (const auto& cls : classes) {
+        for (const auto* group : {&cls.methods, &cls.static_methods}) {
+            for (const auto& method : *group) {
+                if (!defaultArgumentProblem(method).empty()) {
+                    skip(cls.name + "::" + method.name, defaultArgumentProblem(method), SkipCategory::Other);
+                }
+            }
+        }
+    }
+
+    // Methods and static fields with no shim are still declared in the
+    // extern block, so they are reported whether or not the shim source
+    // is written
+    FFIOptions lenient = options_;
+    lenient.strict = false;
+    CWrapperGenerator shims(lenient);
+    shims.generateImplementation(functions, input_classes, library_name, enums);
+    for (const auto& diagnostic : shims.diagnostics()) {
+        skip(diagnostic.symbol, diagnostic.message, diagnostic.category);
+    }
+    if (!unacknowledged.empty()) {
+        throw std::runtime_error("skipped declarations (acknowledge them with --allow-skip):" + unacknowledged);
+    }
+
+    for (const auto& func : free_functions) {
+        checkDurations(func);
+    }
+
+    // Registrations that cannot be wrapped keep their plain wrapper
+    channel_functions_.clear();
+    for (const auto& entry : options_.channel_callbacks) {
+        auto func = std::find_if(free_functions.begin(), free_functions.end(),
+                                 [&](const FFIFunction& f) { return f.name == entry.first && f.can_use_ffi; });
+        std::string problem = func == free_functions.end() ? "no bound function has that name"
+                                                           : channelProblem(*func, free_functions);
+        if (problem.empty()) {
+            channel_functions_.insert(entry.first);
+        } else {
+            diagnostics_.push_back({entry.first, "not wrapped as a channel: " + problem});
+        }
+    }
+    for (const auto& cls : classes) {
+        for (const auto* group : {&cls.methods, &cls.static_methods}) {
+            for (const auto& method : *group) {
+                checkDurations(method);
+            }
+        }
+    }
+
+    // Instances whose signatures cannot share a trait keep only their own wrappers
+    template_traits_.clear();
+    for (const auto& entry : options_.templates) {
+        if (entry.second.trait.empty()) {
+            continue;
+        }
+        std::string problem = templateTraitProblem(entry.first, free_functions);
+        if (problem.empty()) {
+            template_traits_[entry.first] = entry.second.trait;
+        } else {
+            diagnostics_.push_back({entry.first, "no " + entry.second.trait + " trait: " + problem});
+        }
+    }
+
+    // Misconfigured error enums fall back to what the enum supports: names
+    // for Display, or plain TryFrom results
+    error_enums_.clear();
+    for (const auto& entry : options_.error_enums) {
+        auto enum_decl = std::find_if(enums.begin(), enums.end(),
+                                      [&](const FFIEnum& e) { return e.name == entry.first; });
+        if (enum_decl == enums.end() || !enum_types_.count(entry.first)) {
+            diagnostics_.push_back({entry.first, "not an error enum: no bound enum has that name"});
+            continue;
+        }
+        ErrorEnum error = entry.second;
+        if (!error.success.empty() &&
+            std::none_of(enum_decl->values.begin(), enum_decl->values.end(),
+                         [&](const std::pair<std::string, std::string>& value) { return value.first == error.success; })) {
+            diagnostics_.push_back({entry.first, "success value " + error.success + " is not an enumerator"});
+            error.success.clear();
+        }
+        if (!error.to_string.empty()) {
+            auto func = std::find_if(free_functions.begin(), free_functions.end(),
+                                     [&](const FFIFunction& f) { return f.name == error.to_string && f.can_use_ffi; });
+            if (func == free_functions.end() || trim(func->return_type) != "const char*" ||
+                func->parameters.size() != 1 || !isEnum(func->parameters[0].cpp_type) ||
+                baseType(func->parameters[0].cpp_type) != entry.first) {
+                diagnostics_.push_back({entry.first, "to_string " + error.to_string + " is not a bound `const char* " +
+                                                         error.to_string + "(" + entry.first + ")`; Display prints enumerator names"});
+                error.to_string.clear();
+            } else {
+                error.to_string = shimName(*func);
+            }
+        }
+        error_enums_[entry.first] = error;
+    }
+
+    // Only a `void f()` can be run once and forgotten about
+    init_function_.clear();
+    shutdown_function_.clear();
+    auto lifecycle = [&](const std::string& name, const std::string& role) {
+        auto func = std::find_if(free_functions.begin(), free_functions.end(),
+                                 [&](const FFIFunction& f) { return f.name == name && f.can_use_ffi; });
+        std::string problem = func == free_functions.end() ? "no bound function has that name"
+                            : !func->parameters.empty() || !isVoid(func->return_type)
+                                ? "only a `void " + name + "()` can be called without arguments and ignored"
+                                : "";
+        if (!problem.empty()) {
+            diagnostics_.push_back({name, "not the " + role + " function: " + problem});
+        }
+        return problem.empty() ? name : "";
+    };
+    if (!options_.init.function.empty()) {
+        init_function_ = lifecycle(options_.init.function, "init");
+    }
+    if (!init_function_.empty() && !options_.init.shutdown.empty()) {
+        shutdown_function_ = lifecycle(options_.init.shutdown, "shutdown");
+    }
+
+    ss << "// Auto-generated Rust FFI bindings for " << library_name << "\n";
+    ss << "// Generated by Hybrid Transpiler\n\n";
+    // Listed once everything below has been emitted
+    std::streampos toc_at = ss.tellp();
+    if (anyDeprecated(free_functions, classes, enums)) {
+        // The module's own impls and conversions name the deprecated items;
+        // only callers outside it should see the warning
+        ss << "#![allow(deprecated)]\n\n";
+    }
+    // Imported once the rest of the module shows it is needed
+    std::streampos imports_at = ss.tellp();
+
+    // Each library gets its own #[link] block, the module's library first
+    std::vector<std::pair<std::string, std::string>> blocks = {{library_name, ""}};
+    auto declare = [&](const std::string& link_name, const std::string& declaration) {
+        std::string library = link_name.empty() ? library_name : link_name;
+        auto block = std::find_if(blocks.begin(), blocks.end(),
+                                  [&](const std::pair<std::string, std::string>& b) { return b.first == library; });
+        if (block == blocks.end()) {
+            block = blocks.insert(blocks.end(), {library, ""});
+        }
+        block->second += "        " + declaration + "\n";
+    };
+
+    // Functions bound without a shim link by their mangled C++ name,
+    // unless the header gives them C linkage
+    NameMangler mangler(input_classes, enums);
+    auto link_names = [&](const FFIFunction& func) {
+        static const std::vector<std::pair<ManglingScheme, std::string>> targets = {
+            {ManglingScheme::Itanium, "all(not(target_env = \"msvc\"), target_pointer_width = \"64\")"},
+            {ManglingScheme::Itanium32, "all(not(target_env = \"msvc\"), target_pointer_width = \"32\")"},
+            {ManglingScheme::MSVC64, "all(target_env = \"msvc\", target_pointer_width = \"64\")"},
+            {ManglingScheme::MSVC32, "all(target_env = \"msvc\", target_pointer_width = \"32\")"},
+        };
+        std::string attributes;
+        if (func.has_c_linkage || needsShim(func, opaque_classes_, memory_structs_)) {
+            return attributes;
+        }
+        for (const auto& target : targets) {
+            std::string symbol = mangler.mangle(func, target.first);
+            if (!symbol.empty()) {
+                attributes += "#[cfg_attr(" + target.second + ", link_name = \"" + symbol + "\")]\n        ";
+            }
+        }
+        return attributes;
+    };
+
+    std::vector<std::string> linked;
+    bool uses_free_function = false;
+    bool declares_free = false;
+    std::string free_link;  // Declared with the first library handing out memory
+    for (const auto& func : free_functions) {
+        if (func.can_use_ffi) {
+            std::string attributes = link_names(func);
+            std::string rust_name = rustIdent(shimName(func));
+            if (needsShim(func, opaque_classes_, memory_structs_) && shimSymbol(func) != shimName(func)) {
+                attributes = "#[link_name = \"" + shimSymbol(func) + "\"]\n        ";
+            } else if (attributes.empty() && rust_name != shimName(func) && rust_name.compare(0, 2, "r#") != 0) {
+                // `self_` and friends still link against the C symbol
+                attributes = "#[link_name = \"" + shimName(func) + "\"]\n        ";
+            }
+            declare(func.link_name, attributes + generateFunctionBinding(func));
+            linked.push_back(shimName(func));
+            if (ownsCString(func) && !uses_free_function) {
+                free_link = func.link_name;
+            }
+            uses_free_function = uses_free_function || ownsCString(func);
+            declares_free = declares_free || shimName(func) == options_.free_function;
+        }
+    }
+    for (const auto& cls : classes) {
+        for (const auto* group : {&cls.methods, &cls.static_methods}) {
+            for (const auto& method : *group) {
+                if (!isReprC(cls) && ownsCString(method) && !uses_free_function) {
+                    free_link = cls.link_name;
+                    uses_free_function = true;
+                }
+            }
+        }
+    }
+    // Owned struct arrays go back through the free function or a delete[] shim
+    std::vector<std::pair<FFIFunction, std::string>> array_returns;
+    for (const auto& func : free_functions) {
+        if (func.can_use_ffi && returnsStructArray(func) && ownsReturnedPointer(func)) {
+            array_returns.emplace_back(func, func.link_name);
+        }
+    }
+    for (const auto& cls : classes) {
+        for (const auto* group : {&cls.methods, &cls.static_methods}) {
+            for (const auto& method : *group) {
+                if (!isReprC(cls) && returnsStructArray(method) && ownsReturnedPointer(method)) {
+                    array_returns.emplace_back(method, cls.link_name);
+                }
+            }
+        }
+    }
+    if (!array_returns.empty() && !options_.free_function.empty() && !uses_free_function) {
+        free_link = array_returns.front().second;
+        uses_free_function = true;
+    }
+    if (uses_free_function && !declares_free) {
+        declare(free_link, "pub fn " + options_.free_function + "(ptr: *mut c_void);");
+        linked.push_back(options_.free_function);
+    }
+    for (const auto& entry : array_returns) {
+        if (options_.free_function.empty()) {
+            declare(entry.second, "pub fn " + arrayFreeFunction(entry.first) + "(ptr: *const " +
+                                  rustType(baseType(entry.first.return_type)) + ");");
+            linked.push_back(arrayFreeFunction(entry.first));
+        }
+    }
+
+    for (const auto& cls : classes) {
+        if (isReprC(cls)) {
+            continue;
+        }
+        for (const auto* group : {&cls.methods, &cls.static_methods}) {
+            for (const auto& method : *group) {
+                if (!isDestructor(method)) {
+                    declare(cls.link_name, generateFunctionBinding(method));
+                    linked.push_back(shimName(method));
+                }
+            }
+        }
+
+        // Refcounted objects free themselves when the last reference goes
+        if (refCounting(cls)) {
+            continue;
+        }
+
+        FFIFunction dtor{};
+        dtor.name = "~" + cls.name;
+        dtor.class_name = cls.name;
+        dtor.is_method = true;
+        declare(cls.link_name, generateFunctionBinding(dtor));
+        linked.push_back(shimName(dtor));
+    }
+
+    // Extern declarations live in a private module so that safe wrappers
+    // can reuse the C function names
+    ss << "mod ffi {\n";
+    bool uses_parent = std::any_of(blocks.begin(), blocks.end(), [](const std::pair<std::string, std::string>& b) {
+        return namesParentTypes(b.second);
+    });
+    if (uses_parent) {
+        ss << "    use super::*;\n";
+    }
+    for (size_t i = 0; i < blocks.size(); ++i) {
+        // The module's own block stays even when empty, other libraries only appear with symbols
+        if (i > 0 && blocks[i].second.empty()) {
+            continue;
+        }
+        if (i > 0 || uses_parent) {
+            ss << "\n";
+        }
+        ss << "    #[link(name = \"" << blocks[i].first << "\")]\n";
+        ss << "    extern \"C\" {\n";
+        ss << blocks[i].second;
+        ss << "    }\n";
+    }
+    ss << "}\n";
+    if (options_.emit_relink_macro) {
+        ss << "\n" << generateRelinkMacro(blocks, library_name);
+    }
+
+    // Named enums are types of their own, anonymous ones only group constants
+    for (const auto& enum_decl : enums) {
+        if (enum_decl.name.empty()) {
+            ss << "\n" << generateConstants(enum_decl);
+        }
+    }
+    if (!enum_types_.empty()) {
+        ss << "\n";
+        ss << "/// Integer received from C++ that is not an enumerator of the expected enum\n";
+        ss << "#[derive(Debug, Clone, Copy, PartialEq, Eq)]\n";
+        const std::string without_thiserror = options_.thiserror ? "#[cfg(not(feature = \"thiserror\"))]\n" : "";
+        if (options_.thiserror) {
+            ss << "#[cfg_attr(feature = \"thiserror\", derive(thiserror::Error), "
+               << "error(\"{value} is not a valid {type_name}\"))]\n";
+        }
+        generated_items_.push_back({"Structs", "InvalidEnum", "integers outside a named enum"});
+        ss << "pub struct InvalidEnum {\n";
+        ss << "    pub type_name: &'static str,\n";
+        ss << "    pub value: i64,\n";
+        ss << "}\n\n";
+        ss << without_thiserror;
+        ss << "impl std::fmt::Display for InvalidEnum {\n";
+        ss << "    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {\n";
+        ss << "        write!(f, \"{} is not a valid {}\", self.value, self.type_name)\n";
+        ss << "    }\n";
+        ss << "}\n\n";
+        ss << without_thiserror;
+        ss << "impl std::error::Error for InvalidEnum {}\n";
+    }
+    for (const auto& enum_decl : enums) {
+        if (enum_types_.count(enum_decl.name)) {
+            ss << "\n" << generateEnum(enum_decl);
+        }
+    }
+
+    for (const auto& entry : recursive_structs) {
+        ss << "\n// Skipped " << entry.first << ": " << entry.second << "\n";
+    }
+    for (const auto& cls : classes) {
+        ss << "\n" << generateClassBinding(cls);
+    }
+
+    if (!init_function_.empty()) {
+        ss << "\n" << generateInit(free_functions);
+    }
+    for (const auto& func : free_functions) {
+        // The free function takes pointers the wrappers own, so it stays in `mod ffi`
+        if (func.name == init_function_ || func.name == shutdown_function_ || func.name == options_.free_function) {
+            continue;
+        }
+        ss << "\n";
+        if (channel_functions_.count(func.name)) {
+            ss << generateChannelWrapper(func, free_functions);
+        } else if (func.can_use_ffi && options_.default_args == DefaultArgs::Overloads && hasLiteralDefaults(func)) {
+            ss << generateSafeWrapper(func, true) << "\n";
+            ss << generateSafeWrapper(func, false, "_with");
+        } else if (func.can_use_ffi) {
+            ss << generateSafeWrapper(func);
+        } else {
+            ss << "// Skipped " << func.name << ": " << func.reason << "\n";
+        }
+    }
+    for (const auto& entry : template_traits_) {
+        ss << "\n" << generateTemplateTrait(entry.first, free_functions);
+    }
+
+    if (options_.emit_tests) {
+        ss << "\n" << generateTests(classes, enums);
+    }
+
+    if (options_.emit_self_test) {
+        ss << "\n" << generateSelfTest(free_functions, library_name);
+    }
+    if (options_.emit_link_check) {
+        ss << "\n" << generateLinkCheck(linked);
+    }
+
+    std::string code = ss.str();
+    static const std::regex c_void(R"(\bc_void\b)");
+    if (std::regex_search(code.begin() + imports_at, code.end(), c_void)) {
+        code.insert(static_cast<size_t>(imports_at), "use std::ffi::c_void;\n\n");
+    }
+    if (options_.emit_toc) {
+        code.insert(static_cast<size_t>(toc_at), generateToc() + "\n");
+    }
+
+    // Receivers must agree with the C++ const qualifiers, or `&self` could
+    // reach code that mutates the object
+    for (const auto& mismatch : checkConstness(code, classes)) {
+        if (options_.strict) {
+            throw std::runtime_error("const mismatch in " + mismatch.symbol + ": " + mismatch.message);
+        }
+        diagnostics_.push_back(mismatch);
+    }
+    return code;
+}
+
+} // namespace ffi
+} // namespace hybrid_transpiler
@@ -1,4 +1,7 @@
 #include "ir.h"
+#include <regex>
+#include <set>
+#include <stdexcept>
 
 namespace hybrid {
 
@@ -19,6 +22,25 @@ void IR::addGlobalVariable(const Variable& var) {
     global_vars_.push_back(var);
 }
 
+void IR::addEnum(const EnumDecl& enum_decl) {
+    enums_.push_back(enum_decl);
+
+    // Anonymous enums only declare constants, named ones are types
+    if (!enum_decl.name.empty()) {
+        auto type = std::make_shared<Type>(TypeKind::Enum);
+        type->name = enum_decl.name;
+        registerType(enum_decl.name, type);
+    }
+}
+
+void IR::addFunctionPointerType(const Function& signature) {
+    function_pointer_types_.push_back(signature);
+}
+
+void IR::addTypeAlias(const std::string& name, const std::string& underlying) {
+    type_aliases_[name] = underlying;
+}
+
 std::shared_ptr<Type> IR::findType(const std::string& name) const {
     auto it = type_registry_.find(name);
     if (it != type_registry_.end()) {
@@ -31,4 +53,126 @@ void IR::registerType(const std::string& name, std::shared_ptr<Type> type) {
     type_registry_[name] = type;
 }
 
+namespace {
+
+/**
+ * Every identifier a type spells out, including template arguments
+ */
+void collectTypeNames(const std::shared_ptr<Type>& type, std::vector<std::string>& names) {
+    if (!type) {
+        return;
+    }
+    static const std::regex identifier(R"([A-Za-z_]\w*(?:::[A-Za-z_]\w*)*)");
+    for (std::sregex_iterator it(type->name.begin(), type->name.end(), identifier), end; it != end; ++it) {
+        names.push_back(it->str());
+    }
+    collectTypeNames(type->element_type, names);
+    for (const auto& arg : type->template_args) {
+        collectTypeNames(arg, names);
+    }
+}
+
+void collectFunctionTypes(const Function& func, std::vector<std::string>& names) {
+    collectTypeNames(func.return_type, names);
+    for (const auto& param : func.parameters) {
+        collectTypeNames(param.type, names);
+    }
+}
+
+} // namespace
+
+IR reachableFrom(const IR& ir, const std::vector<std::string>& roots) {
+    std::set<std::string> types;
+    for (const auto& cls : ir.getClasses()) {
+        types.insert(cls.name);
+    }
+    for (const auto& enum_decl : ir.getEnums()) {
+        if (!enum_decl.name.empty()) {
+            types.insert(enum_decl.name);
+        }
+    }
+
+    // Nested types are often spelled without their scope inside the outer class
+    auto resolve = [&](const std::string& name) -> std::vector<std::string> {
+        if (types.count(name)) {
+            return {name};
+        }
+        std::vector<std::string> matches;
+        for (const auto& type : types) {
+            if (type.size() > name.size() + 2 && type.compare(type.size() - name.size(), name.size(), name) == 0 &&
+                type.compare(type.size() - name.size() - 2, 2, "::") == 0) {
+                matches.push_back(type);
+            }
+        }
+        return matches;
+    };
+
+    std::set<std::string> kept_functions;
+    std::set<std::string> kept_types;
+    std::vector<std::string> pending;
+    for (const auto& root : roots) {
+        bool is_function = false;
+        for (const auto& func : ir.getFunctions()) {
+            if (func.name == root) {
+                is_function = true;
+                collectFunctionTypes(func, pending);
+            }
+        }
+        if (is_function) {
+            kept_functions.insert(root);
+        } else if (types.count(root)) {
+            pending.push_back(root);
+        } else {
+            throw std::invalid_argument("Unknown root '" + root + "': no function, class or enum has that name");
+        }
+    }
+
+    while (!pending.empty()) {
+        std::string name = pending.back();
+        pending.pop_back();
+        for (const auto& type : resolve(name)) {
+            if (!kept_types.insert(type).second) {
+                continue;
+            }
+            for (const auto& cls : ir.getClasses()) {
+                if (cls.name != type) {
+                    continue;
+                }
+                for (const auto& field : cls.fields) {
+                    collectTypeNames(field.type, pending);
+                }
+                for (const auto& method : cls.methods) {
+                    collectFunctionTypes(method, pending);
+                }
+                pending.insert(pending.end(), cls.base_classes.begin(), cls.base_classes.end());
+            }
+        }
+    }
+
+    IR pruned;
+    for (const auto& cls : ir.getClasses()) {
+        if (kept_types.count(cls.name)) {
+            pruned.addClass(cls);
+        }
+    }
+    for (const auto& func : ir.getFunctions()) {
+        if (kept_functions.count(func.name)) {
+            pruned.addFunction(func);
+        }
+    }
+    for (const auto& enum_decl : ir.getEnums()) {
+        if (kept_types.count(enum_decl.name)) {
+            pruned.addEnum(enum_decl);
+        }
+    }
+    // Signatures declare no symbols, kept ones only describe parameter types
+    for (const auto& signature : ir.getFunctionPointerTypes()) {
+        pruned.addFunctionPointerType(signature);
+    }
+    for (const auto& alias : ir.getTypeAliases()) {
+        pruned.addTypeAlias(alias.first, alias.second);
+    }
+    return pruned;
+}
+
 } // namespace hybrid
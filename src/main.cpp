@@ -1,4 +1,5 @@
 #include "transpiler.h"
+#include <algorithm>
 #include <iostream>
 #include <string>
 #include <vector>
@@ -18,7 +19,8 @@ void printUsage(const char* program_name) {
     std::cout << "Usage: " << program_name << " [options]\n\n";
 
     std::cout << "Options:\n";
-    std::cout << "  -i, --input <file>      Input C++ source file (required)\n";
+    std::cout << "  -i, --input <file>      Input C++ source file (required, repeat with --ffi\n";
+    std::cout << "                          to bind several libraries in one module)\n";
     std::cout << "  -o, --output <file>     Output file path (auto-generated if omitted)\n";
     std::cout << "  -t, --target <lang>     Target language: rust, go [default: rust]\n";
     std::cout << "  -O, --opt-level <N>     Optimization level 0-3 [default: 0]\n";
@@ -27,6 +29,36 @@ void printUsage(const char* program_name) {
     std::cout << "  --no-safety-checks      Disable safety checks\n";
     std::cout << "  --no-comments           Don't preserve comments\n";
     std::cout << "  --gen-tests             Generate test cases\n";
+    std::cout << "  --ffi <lang>            Generate FFI bindings instead of transpiling: rust\n";
+    std::cout << "  --emit-tests            With --ffi, append smoke tests for each wrapper\n";
+    std::cout << "  --no-toc                With --ffi, omit the table of contents comment\n";
+    std::cout << "  --self-test             With --ffi, add runtime ABI checks against C++ checksum shims\n";
+    std::cout << "  --link-check            With --ffi, add a test per extern symbol so a library missing\n";
+    std::cout << "                          one fails to link with the binding's name\n";
+    std::cout << "  --relink-macro          With --ffi, add a macro declaring the extern block again\n";
+    std::cout << "                          against a differently named build of the library\n";
+    std::cout << "  --thiserror             With --ffi, derive thiserror::Error on error types when the\n";
+    std::cout << "                          crate's `thiserror` feature is enabled\n";
+    std::cout << "  --ffi-config <file>     With --ffi, read per-type settings from a JSON file\n";
+    std::cout << "  --emit-bindgen <file>   With --ffi, also write a bindgen::Builder configuration\n";
+    std::cout << "                          using the config's include/exclude patterns\n";
+    std::cout << "  --emit-pkg-config <file>\n";
+    std::cout << "                          With --ffi, also write a pkg-config file for the linked libraries\n";
+    std::cout << "  --emit-cmake <file>     With --ffi, also write a CMake package configuration for them\n";
+    std::cout << "  --strict                With --ffi, fail on const mismatches and skipped declarations\n";
+    std::cout << "                          instead of warning\n";
+    std::cout << "  --allow-skip <name>     With --strict, accept that <name> (or Class::method) is skipped\n";
+    std::cout << "  --emit-shim-source <file>\n";
+    std::cout << "                          With --ffi, also write the C++ extern \"C\" shims to <file>\n";
+    std::cout << "  --preprocessed          Inputs are preprocessed translation units (g++ -E output, .i/.ii):\n";
+    std::cout << "                          parse them as expanded, skip system-header declarations and\n";
+    std::cout << "                          attribute the rest to the files named by their line markers\n";
+    std::cout << "  --list-unsupported      Print the declarations FFI bindings cannot cover, grouped by\n";
+    std::cout << "                          reason, and exit without writing output (reads --ffi-config)\n";
+    std::cout << "  --roots <names>         Only emit these comma-separated functions, classes or enums\n";
+    std::cout << "                          and the types they reference\n";
+    std::cout << "  --no-stamp              Don't start output with the provenance comment\n";
+    std::cout << "                          (set SOURCE_DATE_EPOCH to pin its timestamp instead)\n";
     std::cout << "  --verbose               Enable verbose output\n";
     std::cout << "  --quiet                 Minimal output (errors only)\n";
     std::cout << "  -h, --help              Show this help message\n";
@@ -46,6 +78,8 @@ void printUsage(const char* program_name) {
     std::cout << "  " << program_name << " -i example.cpp --quiet\n\n";
     std::cout << "  # Generate with test cases\n";
     std::cout << "  " << program_name << " -i vector.cpp --gen-tests\n\n";
+    std::cout << "  # Rust FFI bindings with smoke tests\n";
+    std::cout << "  " << program_name << " -i mylib.cpp --ffi rust --emit-tests -o mylib_ffi.rs\n\n";
 
     std::cout << "Supported C++ Features:\n";
     std::cout << "  • Classes, methods, constructors\n";
@@ -61,7 +95,7 @@ void printUsage(const char* program_name) {
 }
 
 void printVersion() {
-    std::cout << "Hybrid Transpiler v0.1.0\n";
+    std::cout << "Hybrid Transpiler v" << hybrid::TRANSPILER_VERSION << "\n";
     std::cout << "Built with LLVM/Clang support\n\n";
     std::cout << "Supported targets:\n";
     std::cout << "  • Rust (edition 2021)\n";
@@ -77,7 +111,6 @@ int main(int argc, char* argv[]) {
     }
 
     hybrid::TranspilerOptions options;
-    std::string input_file;
     std::vector<std::string> input_files;
 
     // Parse command line arguments
@@ -92,7 +125,7 @@ int main(int argc, char* argv[]) {
             return 0;
         } else if (arg == "-i" || arg == "--input") {
             if (i + 1 < argc) {
-                input_file = argv[++i];
+                input_files.push_back(argv[++i]);
             } else {
                 std::cerr << "Error: --input requires a file path\n";
                 std::cerr << "Usage: " << argv[0] << " --input <file.cpp> --output <file.rs> --target rust\n";
@@ -164,6 +197,101 @@ int main(int argc, char* argv[]) {
             options.preserve_comments = false;
         } else if (arg == "--gen-tests") {
             options.generate_tests = true;
+        } else if (arg == "--emit-tests") {
+            options.ffi_tests = true;
+        } else if (arg == "--no-stamp") {
+            options.stamp = false;
+        } else if (arg == "--no-toc") {
+            options.ffi_toc = false;
+        } else if (arg == "--self-test") {
+            options.ffi_self_test = true;
+        } else if (arg == "--link-check") {
+            options.ffi_link_check = true;
+        } else if (arg == "--relink-macro") {
+            options.ffi_relink_macro = true;
+        } else if (arg == "--thiserror") {
+            options.ffi_thiserror = true;
+        } else if (arg == "--preprocessed") {
+            options.preprocessed = true;
+        } else if (arg == "--list-unsupported") {
+            options.list_unsupported = true;
+        } else if (arg == "--ffi-config") {
+            if (i + 1 < argc) {
+                options.ffi_config_path = argv[++i];
+            } else {
+                std::cerr << "Error: --ffi-config requires a file path\n";
+                std::cerr << "See '" << argv[0] << " --help' for more information.\n";
+                return 1;
+            }
+        } else if (arg == "--strict") {
+            options.strict = true;
+        } else if (arg == "--allow-skip") {
+            if (i + 1 < argc) {
+                options.allowed_skips.push_back(argv[++i]);
+            } else {
+                std::cerr << "Error: --allow-skip requires a declaration name\n";
+                std::cerr << "See '" << argv[0] << " --help' for more information.\n";
+                return 1;
+            }
+        } else if (arg == "--roots") {
+            if (i + 1 < argc) {
+                std::string roots = argv[++i];
+                for (size_t start = 0, comma; start <= roots.size(); start = comma + 1) {
+                    comma = std::min(roots.find(',', start), roots.size());
+                    if (comma > start) {
+                        options.roots.push_back(roots.substr(start, comma - start));
+                    }
+                }
+            } else {
+                std::cerr << "Error: --roots requires a comma-separated list of names\n";
+                std::cerr << "See '" << argv[0] << " --help' for more information.\n";
+                return 1;
+            }
+        } else if (arg == "--emit-bindgen") {
+            if (i + 1 < argc) {
+                options.bindgen_config_path = argv[++i];
+            } else {
+                std::cerr << "Error: --emit-bindgen requires a file path\n";
+                std::cerr << "See '" << argv[0] << " --help' for more information.\n";
+                return 1;
+            }
+        } else if (arg == "--emit-pkg-config") {
+            if (i + 1 < argc) {
+                options.pkg_config_path = argv[++i];
+            } else {
+                std::cerr << "Error: --emit-pkg-config requires a file path\n";
+                std::cerr << "See '" << argv[0] << " --help' for more information.\n";
+                return 1;
+            }
+        } else if (arg == "--emit-cmake") {
+            if (i + 1 < argc) {
+                options.cmake_config_path = argv[++i];
+            } else {
+                std::cerr << "Error: --emit-cmake requires a file path\n";
+                std::cerr << "See '" << argv[0] << " --help' for more information.\n";
+                return 1;
+            }
+        } else if (arg == "--emit-shim-source") {
+            if (i + 1 < argc) {
+                options.shim_source_path = argv[++i];
+            } else {
+                std::cerr << "Error: --emit-shim-source requires a file path\n";
+                std::cerr << "See '" << argv[0] << " --help' for more information.\n";
+                return 1;
+            }
+        } else if (arg == "--ffi") {
+            if (i + 1 < argc) {
+                options.ffi_target = argv[++i];
+                if (options.ffi_target != "rust") {
+                    std::cerr << "Error: Unsupported FFI target '" << options.ffi_target << "'\n";
+                    std::cerr << "Supported FFI targets: rust\n";
+                    return 1;
+                }
+            } else {
+                std::cerr << "Error: --ffi requires a language (rust)\n";
+                std::cerr << "See '" << argv[0] << " --help' for more information.\n";
+                return 1;
+            }
         } else if (arg == "--verbose") {
             options.verbose = true;
         } else if (arg == "--quiet") {
@@ -188,22 +316,45 @@ int main(int argc, char* argv[]) {
     }
 
     // Validate inputs
-    if (input_file.empty()) {
+    if (input_files.empty()) {
         std::cerr << "Error: No input file specified\n";
         std::cerr << "You must provide an input file with -i or --input\n";
         std::cerr << "Example: " << argv[0] << " -i example.cpp\n";
         std::cerr << "Run '" << argv[0] << " --help' for usage information.\n";
         return 1;
     }
-
-    // Check if input file exists
-    std::ifstream test_file(input_file);
-    if (!test_file.good()) {
-        std::cerr << "Error: Input file not found: " << input_file << "\n";
-        std::cerr << "Please check the file path and try again.\n";
+    if (input_files.size() > 1 && options.ffi_target.empty() && !options.list_unsupported) {
+        std::cerr << "Error: Several input files are only supported with --ffi\n";
+        std::cerr << "Each library's declarations get their own #[link] block in one module.\n";
         return 1;
     }
-    test_file.close();
+    if (options.ffi_tests && options.ffi_target.empty()) {
+        std::cerr << "Error: --emit-tests only applies to --ffi bindings\n";
+        std::cerr << "Use --gen-tests to generate test cases for transpiled code.\n";
+        return 1;
+    }
+    const std::string& input_file = input_files.front();
+
+    // Check if the input files exist
+    for (const auto& path : input_files) {
+        std::ifstream test_file(path);
+        if (!test_file.good()) {
+            std::cerr << "Error: Input file not found: " << path << "\n";
+            std::cerr << "Please check the file path and try again.\n";
+            return 1;
+        }
+    }
+
+    // The audit is informational: it reads the inputs and writes nothing
+    if (options.list_unsupported) {
+        hybrid::Transpiler transpiler(options);
+        if (!transpiler.transpileBatch(input_files)) {
+            std::cerr << "Error: " << transpiler.getLastError() << "\n";
+            return 1;
+        }
+        std::cout << transpiler.getReport();
+        return 0;
+    }
 
     // Auto-generate output filename if not specified
     if (options.output_path.empty()) {
@@ -241,7 +392,17 @@ int main(int argc, char* argv[]) {
 
     hybrid::Transpiler transpiler(options);
 
-    if (!transpiler.transpile(input_file)) {
+    bool success = input_files.size() == 1
+        ? transpiler.transpile(input_file)
+        : transpiler.transpileBatch(input_files);
+
+    if (!options.quiet) {
+        for (const auto& warning : transpiler.getWarnings()) {
+            std::cerr << "Warning: " << warning << "\n";
+        }
+    }
+
+    if (!success) {
         std::cerr << "Error: Transpilation failed\n";
         std::cerr << transpiler.getLastError() << "\n";
         return 1;
@@ -11,4 +11,8 @@ IR Parser::parseString(const std::string& source) {
     return SimpleCppParser::parseString(source);
 }
 
+IR Parser::parsePreprocessed(const std::string& source) {
+    return SimpleCppParser::parsePreprocessed(source);
+}
+
 } // namespace hybrid
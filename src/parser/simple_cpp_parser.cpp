@@ -7,6 +7,7 @@
  */
 
 #include "ir.h"
+#include <algorithm>
 #include <regex>
 #include <fstream>
 #include <sstream>
@@ -36,12 +37,70 @@ public:
      * Parse C++ source string and build IR
      */
     static IR parseString(const std::string& source) {
+        return parse(source, {});
+    }
+
+    /**
+     * Parse a preprocessed translation unit (`g++ -E` output), attributing
+     * each declaration to the file its line markers name
+     *
+     * System headers (marker flag 3) and the compiler's `<built-in>` and
+     * `<command-line>` regions are dropped, so only the user's own
+     * declarations are bound.
+     */
+    static IR parsePreprocessed(const std::string& source) {
+        static const std::regex marker("^#\\s*(?:line\\s+)?\\d+\\s+\"([^\"]*)\"((?:\\s+\\d+)*)\\s*$");
+
+        std::string text = SimpleCppParser("").removeComments(source);
+        std::string code;
+        std::vector<FileRegion> files;
+        bool dropped = false;
+        std::stringstream lines(text);
+        std::string line;
+        while (std::getline(lines, line)) {
+            std::smatch match;
+            if (!std::regex_match(line, match, marker)) {
+                code += (dropped ? "" : line) + "\n";
+                continue;
+            }
+            std::string file = match[1].str();
+            std::stringstream flags(match[2].str());
+            bool system = false;
+            for (int flag; flags >> flag;) {
+                system = system || flag == 3;
+            }
+            dropped = system || (!file.empty() && file[0] == '<');
+            if (!dropped && (files.empty() || files.back().file != file)) {
+                files.push_back({code.size(), file});
+            }
+            code += "\n";
+        }
+        return parse(code, files);
+    }
+
+private:
+    /**
+     * Where a preprocessed input switches to another original file
+     */
+    struct FileRegion {
+        size_t begin = 0;
+        std::string file;
+    };
+
+    static IR parse(const std::string& source, const std::vector<FileRegion>& files) {
         IR ir;
         SimpleCppParser parser(source);
+        parser.files_ = files;
 
         // Parse namespaces and extract content
+        parser.namespaces_ = findNamespaces(parser.removeComments(source));
         std::string processed = parser.processNamespaces(source);
         parser.source_ = processed;
+        parser.pack_pragmas_ = findPackPragmas(parser.removeComments(processed));
+        stripComments(processed, &parser.doc_comments_, &parser.annotations_);
+
+        // Alias templates first: they are expanded wherever types are parsed
+        parser.parseAliasTemplates();
 
         // Parse all classes in the source
         parser.parseClasses(ir);
@@ -49,14 +108,67 @@ public:
         // Parse all structs in the source
         parser.parseStructs(ir);
 
+        // Parse namespace-level enums
+        parser.parseEnums(ir);
+
+        // Parse function pointer typedefs
+        parser.parseFunctionPointerTypes(ir);
+
+        // Parse typedefs of plain types
+        parser.parseTypeAliases(ir);
+
         // Parse standalone functions
         parser.parseStandaloneFunctions(ir);
 
         return ir;
     }
 
-private:
+    /**
+     * A namespace body, located by brace matching
+     */
+    struct NamespaceScope {
+        std::string name;    // `a::b` for a nested namespace definition
+        bool is_inline = false;
+        size_t open = 0;     // Position of `{`
+        size_t close = 0;    // Position of the matching `}`
+    };
+
+    /**
+     * A `#pragma pack` directive: `pack(N)`, `pack(push, N)`, `pack(pop)` or `pack()`
+     */
+    struct PackPragma {
+        size_t pos = 0;
+        bool push = false;
+        bool pop = false;
+        size_t value = 0;    // 0 restores natural alignment
+    };
+
+    /**
+     * A documentation comment (`///`, `//!` or a Doxygen block) starting its
+     * line, positioned in the comment-free code
+     */
+    struct DocComment {
+        size_t pos = 0;      // Where the comment was removed
+        size_t limit = 0;    // First `;`, `{`, `}` or `#` after it; a declaration starting before owns the comment
+        std::string text;    // Without the comment markers, one line per source line
+    };
+
+    /**
+     * A `template<class T> using Name = ...;` declaration
+     */
+    struct AliasTemplate {
+        std::vector<std::string> parameters;  // Type parameter names, in order
+        std::string underlying;               // Aliased type, spelled with the parameters
+    };
+
     std::string source_;
+    std::vector<NamespaceScope> namespaces_;  // Outer scopes before the ones they contain
+    std::vector<PackPragma> pack_pragmas_;    // In source order
+    std::vector<FileRegion> files_;           // In source order, empty unless preprocessed
+    std::vector<DocComment> doc_comments_;    // In source order
+    std::vector<DocComment> annotations_;     // `// @tag` comments in source order, tags without the `@`
+    std::map<std::string, AliasTemplate> alias_templates_;  // By alias name
+    int alias_depth_ = 0;                     // Alias templates being expanded by parseType
 
     explicit SimpleCppParser(const std::string& source) : source_(source) {}
 
@@ -64,96 +176,319 @@ private:
      * Remove C++ comments from source
      */
     std::string removeComments(const std::string& code) const {
-        std::string result = code;
-
-        // Remove single-line comments
-        result = std::regex_replace(result, std::regex("//[^\n]*"), "");
+        return stripComments(code, nullptr);
+    }
 
-        // Remove multi-line comments
-        result = std::regex_replace(result, std::regex("/\\*.*?\\*/", std::regex::ECMAScript), "");
+    /**
+     * Comment-free `code`, collecting its doc comments into `docs` and its
+     * annotation comments into `annotations` if given; consecutive `///`
+     * lines form one comment
+     */
+    static std::string stripComments(const std::string& code, std::vector<DocComment>* docs,
+                                     std::vector<DocComment>* annotations = nullptr) {
+        std::string result;
+        result.reserve(code.size());
+        size_t i = 0;
+        while (i < code.size()) {
+            bool line = code.compare(i, 2, "//") == 0;
+            if (!line && code.compare(i, 2, "/*") != 0) {
+                result += code[i++];
+                continue;
+            }
+            size_t end = line ? code.find('\n', i) : code.find("*/", i + 2);
+            if (end == std::string::npos && !line) {
+                // An unterminated block comment is left as written
+                result += code.substr(i);
+                break;
+            }
+            end = end == std::string::npos ? code.size() : end + (line ? 0 : 2);
+            std::string comment = code.substr(i, end - i);
+            size_t line_start = result.find_last_of('\n');
+            line_start = line_start == std::string::npos ? 0 : line_start + 1;
+            bool own_line = result.find_first_not_of(" \t\r", line_start) == std::string::npos;
+            if (docs && own_line) {
+                collectDocComment(comment, result, *docs);
+            }
+            if (annotations && own_line) {
+                collectAnnotation(comment, result, *annotations);
+            }
+            i = end;
+        }
 
+        for (auto* comments : {docs, annotations}) {
+            if (!comments) {
+                continue;
+            }
+            for (auto& doc : *comments) {
+                doc.limit = std::min(result.find_first_of(";{}#", doc.pos), result.size());
+            }
+        }
         return result;
     }
 
     /**
-     * Parse all class declarations
+     * Add `comment` to `annotations` if it is a plain comment holding only
+     * `@tag`s, such as `// @owns_return`
      */
-    void parseClasses(IR& ir) {
-        std::string cleaned = removeComments(source_);
-
-        // Regex to match class declarations
-        // Matches: class ClassName { ... };
-        std::regex class_pattern(
-            R"(class\s+(\w+)\s*(?::\s*public\s+(\w+(?:\s*,\s*\w+)*))?\s*\{([^}]*(?:\{[^}]*\}[^}]*)*)\};)",
-            std::regex::ECMAScript
-        );
+    static void collectAnnotation(const std::string& comment, const std::string& result,
+                                  std::vector<DocComment>& annotations) {
+        static const std::regex tags(R"(^(?://|/\*)\s*((?:@\w+\s*)+)(?:\*/)?$)");
+        std::smatch match;
+        if (!std::regex_match(comment, match, tags)) {
+            return;
+        }
+        std::string text = std::regex_replace(match[1].str(), std::regex(R"(@|\s+$)"), "");
+        annotations.push_back({result.size(), 0, std::regex_replace(text, std::regex(R"(\s+)"), " ")});
+    }
 
-        auto classes_begin = std::sregex_iterator(cleaned.begin(), cleaned.end(), class_pattern);
-        auto classes_end = std::sregex_iterator();
+    /**
+     * Add `comment`, removed at the end of `result`, to `docs` if it is a
+     * doc comment; trailing member docs (`///<`) and file docs are not
+     */
+    static void collectDocComment(const std::string& comment, const std::string& result,
+                                  std::vector<DocComment>& docs) {
+        static const std::regex file_command(R"([@\\]file\b)");
+        bool line = comment.compare(0, 2, "//") == 0;
+        bool doc = line ? (comment.compare(0, 3, "///") == 0 && comment.compare(0, 4, "////") != 0) ||
+                              comment.compare(0, 3, "//!") == 0
+                        : (comment.compare(0, 3, "/**") == 0 && comment != "/**/") || comment.compare(0, 3, "/*!") == 0;
+        if (!doc || (comment.size() > 3 && comment[3] == '<') || std::regex_search(comment, file_command)) {
+            return;
+        }
 
-        for (std::sregex_iterator i = classes_begin; i != classes_end; ++i) {
-            std::smatch match = *i;
+        std::vector<std::string> lines;
+        if (line) {
+            std::string text = comment.substr(3);
+            lines.push_back(!text.empty() && text[0] == ' ' ? text.substr(1) : text);
+        } else {
+            std::stringstream body(comment.substr(3, comment.size() - 5));
+            for (std::string text; std::getline(body, text);) {
+                size_t start = text.find_first_not_of(" \t");
+                text = start == std::string::npos ? "" : text.substr(start);
+                if (!text.empty() && text[0] == '*') {
+                    text = text.size() > 1 && text[1] == ' ' ? text.substr(2) : text.substr(1);
+                }
+                lines.push_back(text);
+            }
+            auto blank = [](const std::string& l) { return l.find_first_not_of(" \t\r") == std::string::npos; };
+            while (!lines.empty() && blank(lines.back())) {
+                lines.pop_back();
+            }
+            while (!lines.empty() && blank(lines.front())) {
+                lines.erase(lines.begin());
+            }
+        }
 
-            ClassDecl class_decl;
-            class_decl.name = match[1].str();
-            class_decl.is_struct = false;
+        std::string text;
+        for (const auto& l : lines) {
+            text += (text.empty() ? "" : "\n") + l;
+        }
+        bool continues = line && !docs.empty() && docs.back().pos <= result.size() &&
+                         result.find_first_not_of(" \t\r\n", docs.back().pos) == std::string::npos;
+        if (continues) {
+            docs.back().text += "\n" + text;
+        } else {
+            docs.push_back({result.size(), 0, text});
+        }
+    }
 
-            // Parse base classes if present
-            if (match[2].matched) {
-                parseBaseClasses(match[2].str(), class_decl);
+    /**
+     * Annotation tags before the declaration starting at `pos` in the
+     * comment-free source
+     */
+    std::vector<std::string> annotationsAt(size_t pos) const {
+        std::vector<std::string> tags;
+        for (const auto& annotation : annotations_) {
+            if (annotation.pos <= pos && pos < annotation.limit) {
+                std::stringstream words(annotation.text);
+                for (std::string tag; words >> tag;) {
+                    tags.push_back(tag);
+                }
             }
+        }
+        return tags;
+    }
 
-            // Parse class body
-            std::string body = match[3].str();
-            parseClassBody(body, class_decl);
-
-            ir.addClass(class_decl);
+    /**
+     * Doc comment of the declaration starting at `pos` in the comment-free source
+     */
+    std::string docAt(size_t pos) const {
+        for (auto it = doc_comments_.rbegin(); it != doc_comments_.rend(); ++it) {
+            if (it->pos <= pos) {
+                return pos < it->limit ? it->text : "";
+            }
         }
+        return "";
     }
 
+    // One `[[...]]` attribute specifier; string literals may contain `]`
+    static constexpr const char* ATTRIBUTE_PATTERN = R"(\[\[(?:[^\]"]|"(?:[^"\\]|\\.)*")*\]\])";
+
     /**
-     * Parse all struct declarations
+     * A class or struct definition, located by brace matching
      */
-    void parseStructs(IR& ir) {
-        std::string cleaned = removeComments(source_);
+    struct Definition {
+        bool is_struct = false;
+        std::string name;
+        std::string bases;   // Base list after the `:`, access specifiers included, empty if none
+        std::string template_params;  // Inside a preceding `template<...>`, empty if not a template
+        std::string body;    // Between the braces, nested definitions included
+        size_t body_begin = 0;  // Offset of the body in the searched code
+        size_t begin = 0;
+        size_t end = 0;      // One past the closing `;`
+        bool packed = false; // `__attribute__((packed))` before the name or after the body
+        std::string attributes;  // `[[...]]` and `__attribute__((...))` between the keyword and the name
+    };
 
-        // Regex to match struct declarations
-        // Matches: struct StructName { ... };
-        std::regex struct_pattern(
-            R"(struct\s+(\w+)\s*(?::\s*public\s+(\w+(?:\s*,\s*\w+)*))?\s*\{([^}]*(?:\{[^}]*\}[^}]*)*)\};)",
-            std::regex::ECMAScript
-        );
+    /**
+     * Outermost class and struct definitions in `code`; nested ones stay in the body
+     */
+    static std::vector<Definition> findDefinitions(const std::string& code) {
+        static const std::regex header(
+            "\\b(class|struct)\\s+((?:" + std::string(ATTRIBUTE_PATTERN) + "\\s*|__attribute__\\s*\\(\\(\\s*packed\\s*\\)\\)\\s*)*)"
+            R"((\w+)\s*(?::\s*([\w\s,:<>]+?))?\s*\{)");
+        static const std::regex template_head(R"(\btemplate\s*<([^>]*)>\s*$)");
+        static const std::regex packed(R"(__attribute__\s*\(\(\s*packed\s*\)\))");
+        static const std::regex trailing_packed(R"(^\s*__attribute__\s*\(\(\s*packed\s*\)\))");
+
+        std::vector<Definition> found;
+        std::smatch match;
+        size_t from = 0;
+        while (std::regex_search(code.begin() + from, code.end(), match, header)) {
+            size_t begin = from + match.position(0);
+            size_t open = begin + match.length(0) - 1;
+
+            size_t depth = 0;
+            size_t close = std::string::npos;
+            for (size_t i = open; i < code.size(); ++i) {
+                if (code[i] == '{') {
+                    depth++;
+                } else if (code[i] == '}' && --depth == 0) {
+                    close = i;
+                    break;
+                }
+            }
+            size_t after = close == std::string::npos ? close : close + 1;
+            std::smatch attribute;
+            bool packed_after = after != std::string::npos &&
+                std::regex_search(code.begin() + after, code.end(), attribute, trailing_packed);
+            if (packed_after) {
+                after += attribute.length(0);
+            }
+            size_t semicolon = after == std::string::npos ? after : code.find_first_not_of(" \t\r\n", after);
+            if (followsEnumKeyword(match) || semicolon == std::string::npos || code[semicolon] != ';') {
+                from = open + 1;
+                continue;
+            }
 
-        auto structs_begin = std::sregex_iterator(cleaned.begin(), cleaned.end(), struct_pattern);
-        auto structs_end = std::sregex_iterator();
+            Definition definition;
+            definition.is_struct = match[1].str() == "struct";
+            definition.name = match[3].str();
+            definition.bases = match[4].str();
+            definition.attributes = match[2].str();
+            std::smatch head;
+            std::string before = code.substr(begin > 200 ? begin - 200 : 0, begin > 200 ? 200 : begin);
+            if (std::regex_search(before, head, template_head)) {
+                definition.template_params = head[1].str();
+            }
+            definition.packed = std::regex_search(definition.attributes, packed) || packed_after;
+            definition.body = code.substr(open + 1, close - open - 1);
+            definition.body_begin = open + 1;
+            definition.begin = begin;
+            definition.end = semicolon + 1;
+            found.push_back(definition);
+            from = definition.end;
+        }
+        return found;
+    }
 
-        for (std::sregex_iterator i = structs_begin; i != structs_end; ++i) {
-            std::smatch match = *i;
+    /**
+     * Add a definition and its public nested classes, named `Outer::Inner`;
+     * `offset` locates the searched code in the comment-free source
+     */
+    void addDefinition(IR& ir, const Definition& definition, const std::string& scope,
+                       const std::string& namespace_path, size_t pragma_pack, const std::string& source_file,
+                       size_t offset) {
+        ClassDecl class_decl;
+        class_decl.name = scope.empty() ? definition.name : scope + "::" + definition.name;
+        class_decl.is_struct = definition.is_struct;
+        class_decl.namespace_path = namespace_path;
+        class_decl.pack = definition.packed ? 1 : pragma_pack;
+        class_decl.source_file = source_file;
+        class_decl.is_deprecated = deprecation(definition.attributes, class_decl.deprecation_message);
+        class_decl.doc_comment = docAt(offset + definition.begin);
+        size_t body_offset = offset + definition.body_begin;
+
+        // Parse base classes if present
+        if (!definition.bases.empty()) {
+            parseBaseClasses(definition.bases, definition.is_struct, class_decl);
+        }
+        if (!definition.template_params.empty()) {
+            class_decl.is_template = true;
+            class_decl.template_parameters = typeParameters(definition.template_params);
+        }
+
+        // Nested definitions are not members of the enclosing class
+        std::vector<Definition> nested = findDefinitions(definition.body);
+        std::string body = definition.body;
+        for (auto it = nested.rbegin(); it != nested.rend(); ++it) {
+            body.replace(it->begin, it->end - it->begin, std::string(it->end - it->begin, ' '));
+        }
 
-            ClassDecl struct_decl;
-            struct_decl.name = match[1].str();
-            struct_decl.is_struct = true;  // Mark as struct
+        // Parse body (default access is public for structs)
+        if (definition.is_struct) {
+            parseStructBody(body, class_decl, body_offset);
+        } else {
+            parseClassBody(body, class_decl, body_offset);
+        }
+        ir.addClass(class_decl);
 
-            // Parse base classes if present
-            if (match[2].matched) {
-                parseBaseClasses(match[2].str(), struct_decl);
+        // Only public nested classes are reachable from a shim
+        std::regex access_pattern(R"((private|protected|public)\s*:)");
+        for (const auto& inner : nested) {
+            std::string access = definition.is_struct ? "public" : "private";
+            std::string before = definition.body.substr(0, inner.begin);
+            for (std::sregex_iterator it(before.begin(), before.end(), access_pattern), end; it != end; ++it) {
+                access = (*it)[1].str();
             }
+            if (access == "public") {
+                addDefinition(ir, inner, class_decl.name, namespace_path, pragma_pack, source_file, body_offset);
+            }
+        }
+    }
 
-            // Parse struct body (default access is public for structs)
-            std::string body = match[3].str();
-            parseStructBody(body, struct_decl);
+    /**
+     * Parse all class declarations
+     */
+    void parseClasses(IR& ir) {
+        for (const auto& definition : findDefinitions(removeComments(source_))) {
+            if (!definition.is_struct) {
+                addDefinition(ir, definition, "", namespaceAt(definition.begin), packAt(definition.begin),
+                              fileAt(definition.begin), 0);
+            }
+        }
+    }
 
-            ir.addClass(struct_decl);
+    /**
+     * Parse all struct declarations
+     */
+    void parseStructs(IR& ir) {
+        for (const auto& definition : findDefinitions(removeComments(source_))) {
+            if (definition.is_struct) {
+                addDefinition(ir, definition, "", namespaceAt(definition.begin), packAt(definition.begin),
+                              fileAt(definition.begin), 0);
+            }
         }
     }
 
     /**
      * Parse struct body (fields and methods) - defaults to public
      */
-    void parseStructBody(const std::string& body, ClassDecl& struct_decl) {
+    void parseStructBody(const std::string& body, ClassDecl& struct_decl, size_t offset) {
         // Split by access specifiers
         std::vector<std::string> sections;
         std::vector<std::string> access_levels;
+        std::vector<size_t> offsets;  // Where each section starts in the comment-free source
 
         std::regex access_pattern(R"((private|protected|public)\s*:)");
 
@@ -168,6 +503,7 @@ private:
             if (!section_content.empty()) {
                 sections.push_back(section_content);
                 access_levels.push_back(current_access);
+                offsets.push_back(offset + last_pos);
             }
 
             current_access = (*it)[1].str();
@@ -178,17 +514,120 @@ private:
         if (last_pos < body.length()) {
             sections.push_back(body.substr(last_pos));
             access_levels.push_back(current_access);
+            offsets.push_back(offset + last_pos);
         }
 
         // If no access specifier found, treat entire body as public
         if (sections.empty()) {
             sections.push_back(body);
             access_levels.push_back("public");
+            offsets.push_back(offset);
         }
 
         // Parse each section
         for (size_t i = 0; i < sections.size(); ++i) {
-            parseSection(sections[i], access_levels[i], struct_decl);
+            parseSection(sections[i], access_levels[i], struct_decl, offsets[i]);
+        }
+    }
+
+    /**
+     * `enum class Name { ... };` also matches the class and struct patterns
+     */
+    /**
+     * `[[...]]` attribute specifiers directly before `pos`, in source order
+     */
+    static std::string attributesBefore(const std::string& code, size_t pos) {
+        std::string attributes;
+        size_t end = pos == 0 ? std::string::npos : code.find_last_not_of(" \t\r\n", pos - 1);
+        while (end != std::string::npos && end >= 3 && code.compare(end - 1, 2, "]]") == 0) {
+            size_t open = code.rfind("[[", end - 2);
+            if (open == std::string::npos) {
+                break;
+            }
+            attributes = code.substr(open, end + 1 - open) + attributes;
+            end = open == 0 ? std::string::npos : code.find_last_not_of(" \t\r\n", open - 1);
+        }
+        return attributes;
+    }
+
+    /**
+     * Whether `attributes` holds `[[deprecated]]` or `[[deprecated("why")]]`;
+     * the message is the string literal's contents, escapes kept
+     */
+    static bool deprecation(const std::string& attributes, std::string& message) {
+        static const std::regex deprecated(
+            "\\[\\[\\s*deprecated\\s*(?:\\(\\s*\"((?:[^\"\\\\]|\\\\.)*)\"\\s*\\))?\\s*\\]\\]");
+        std::smatch match;
+        if (!std::regex_search(attributes, match, deprecated)) {
+            return false;
+        }
+        message = match[1].str();
+        return true;
+    }
+
+    static bool followsEnumKeyword(const std::smatch& match) {
+        std::string prefix = match.prefix().str();
+        size_t end = prefix.find_last_not_of(" \t\n\r");
+        return end != std::string::npos && end >= 3 && prefix.compare(end - 3, 4, "enum") == 0;
+    }
+
+    /**
+     * Parse enum declarations outside of classes, including anonymous ones
+     */
+    void parseEnums(IR& ir) {
+        std::string cleaned = removeComments(source_);
+
+        // Enums nested in classes are not namespace-level declarations
+        std::vector<std::pair<size_t, size_t>> class_ranges;
+        for (const auto& definition : findDefinitions(cleaned)) {
+            class_ranges.emplace_back(definition.begin, definition.end);
+        }
+
+        // Pattern: enum [class|struct] [Name] [: type] { A = 1, B, ... };
+        std::regex enum_pattern(
+            "\\benum\\b\\s*(?:(class|struct)\\s+)?((?:" + std::string(ATTRIBUTE_PATTERN) + "\\s*)*)"
+            R"((\w+)?\s*(?::\s*([\w\s]+?)\s*)?\{([^}]*)\}\s*;)",
+            std::regex::ECMAScript
+        );
+
+        auto enums_begin = std::sregex_iterator(cleaned.begin(), cleaned.end(), enum_pattern);
+        auto enums_end = std::sregex_iterator();
+
+        for (std::sregex_iterator it = enums_begin; it != enums_end; ++it) {
+            std::smatch match = *it;
+
+            size_t pos = match.position(0);
+            bool nested = std::any_of(class_ranges.begin(), class_ranges.end(),
+                [pos](const std::pair<size_t, size_t>& range) { return pos > range.first && pos < range.second; });
+            if (nested) {
+                continue;
+            }
+
+            EnumDecl enum_decl;
+            enum_decl.is_scoped = match[1].matched;
+            enum_decl.name = match[3].str();
+            enum_decl.namespace_path = namespaceAt(pos);
+            enum_decl.source_file = fileAt(pos);
+            enum_decl.is_deprecated = deprecation(match[2].str(), enum_decl.deprecation_message);
+            enum_decl.doc_comment = docAt(pos);
+            enum_decl.underlying_type = trim(match[4].str());
+
+            std::stringstream body(match[5].str());
+            std::string item;
+            while (std::getline(body, item, ',')) {
+                item = trim(item);
+                if (item.empty()) continue;
+
+                EnumDecl::Enumerator enumerator;
+                size_t eq = item.find('=');
+                enumerator.name = trim(item.substr(0, eq));
+                if (eq != std::string::npos) {
+                    enumerator.value = trim(item.substr(eq + 1));
+                }
+                enum_decl.enumerators.push_back(enumerator);
+            }
+
+            ir.addEnum(enum_decl);
         }
     }
 
@@ -198,21 +637,24 @@ private:
     void parseStandaloneFunctions(IR& ir) {
         std::string cleaned = removeComments(source_);
 
-        // First, remove class/struct definitions to avoid matching methods
-        cleaned = std::regex_replace(cleaned,
-            std::regex(R"((class|struct)\s+\w+\s*(?::\s*public\s+\w+(?:\s*,\s*\w+)*)?\s*\{[^}]*(?:\{[^}]*\}[^}]*)*\};)"),
-            "");
+        // First, blank class/struct definitions to avoid matching methods
+        std::vector<Definition> definitions = findDefinitions(cleaned);
+        for (auto it = definitions.rbegin(); it != definitions.rend(); ++it) {
+            cleaned.replace(it->begin, it->end - it->begin, std::string(it->end - it->begin, ' '));
+        }
 
         // Pattern for standalone functions:
-        // [template<...>] [inline] [static] return_type function_name(params) [const] { body }
+        // [template<...>] [inline] [static] [const] return_type function_name(params) [const] { body }
         // or declarations: return_type function_name(params);
+        // A default argument may be a call, `int n = compute()`
         std::regex func_pattern(
-            R"((?:template\s*<[^>]*>\s*)?(?:inline\s+|static\s+|extern\s+)*(?:auto|void|bool|char|short|int|long|float|double|size_t|std::\w+(?:<[^>]*>)?|\w+)\s*[*&]?\s+([a-zA-Z_]\w*)\s*\(([^)]*)\)\s*(?:const\s*)?(?:->[\s\w:*&<>]+\s*)?(?:\{([^}]*(?:\{[^}]*\}[^}]*)*)\}|;))",
+            R"((?:template\s*<[^>]*>\s*)?(?:inline\s+|static\s+|extern\s+)*(?:const\s+)?(?:auto|void|bool|char|short|int|long|float|double|size_t|std::(?:\w+::)*\w+(?:<[^>]*>)?|\w+(?:<[^>]*>)?)\s*[*&]?\s+([a-zA-Z_]\w*)\s*\(([^()]*(?:=\s*[\w:<>]*\s*\([^()]*\)[^()]*)*)\)\s*(?:const\s*)?(?:->[\s\w:*&<>]+\s*)?(?:\{([^}]*(?:\{[^}]*\}[^}]*)*)\}|;))",
             std::regex::ECMAScript
         );
 
         auto funcs_begin = std::sregex_iterator(cleaned.begin(), cleaned.end(), func_pattern);
         auto funcs_end = std::sregex_iterator();
+        std::vector<std::pair<size_t, size_t>> c_blocks = findCLinkageBlocks(cleaned);
 
         for (std::sregex_iterator it = funcs_begin; it != funcs_end; ++it) {
             std::smatch match = *it;
@@ -221,13 +663,35 @@ private:
             std::string full_match = match.str();
             std::string func_name = match[1].str();
 
-            // Skip if it's a class method implementation (contains ::)
-            if (full_match.find("::") != std::string::npos) {
+            // Skip if it's a class method implementation (Name::method), std:: types are fine
+            size_t name_offset = static_cast<size_t>(match.position(1) - match.position(0));
+            if (name_offset >= 2 && full_match.compare(name_offset - 2, 2, "::") == 0) {
                 continue;
             }
 
             Function func;
             func.name = func_name;
+            func.namespace_path = namespaceAt(match.position(1));
+            func.source_file = fileAt(match.position(1));
+            func.is_deprecated = deprecation(attributesBefore(cleaned, match.position(0)), func.deprecation_message);
+            func.doc_comment = docAt(match.position(0));
+            func.annotations = annotationsAt(match.position(0));
+
+            // `extern "C"` before the declaration stops the match at the string
+            static const std::regex c_linkage(R"(\bextern\s*"C"\s*$)");
+            size_t pos = match.position(0);
+            size_t lookback = std::min<size_t>(pos, 32);
+            func.has_c_linkage = std::regex_search(cleaned.substr(pos - lookback, lookback), c_linkage) ||
+                                 std::any_of(c_blocks.begin(), c_blocks.end(), [&](const auto& block) {
+                                     return pos > block.first && pos < block.second;
+                                 });
+
+            static const std::regex template_head(R"(^\s*template\s*<([^>]*)>)");
+            std::smatch head;
+            if (std::regex_search(full_match, head, template_head)) {
+                func.is_template = true;
+                func.template_parameters = typeParameters(head[1].str());
+            }
 
             // Extract return type from the match
             std::string prefix = match.prefix().str();
@@ -258,45 +722,327 @@ private:
         }
     }
 
+    /**
+     * Parse `typedef R (*Name)(params);` and `using Name = R (*)(params);`
+     */
+    void parseFunctionPointerTypes(IR& ir) {
+        static const std::regex typedef_pattern(
+            R"(\btypedef\s+([\w:<>\s*&]+?)\s*\(\s*\*\s*(\w+)\s*\)\s*\(([^)]*)\)\s*;)");
+        static const std::regex using_pattern(
+            R"(\busing\s+(\w+)\s*=\s*([\w:<>\s*&]+?)\s*\(\s*\*\s*\)\s*\(([^)]*)\)\s*;)");
+
+        std::string cleaned = removeComments(source_);
+        std::vector<std::pair<size_t, Function>> found;
+        for (const auto* pattern : {&typedef_pattern, &using_pattern}) {
+            bool is_using = pattern == &using_pattern;
+            for (std::sregex_iterator it(cleaned.begin(), cleaned.end(), *pattern), end; it != end; ++it) {
+                Function signature;
+                signature.name = (*it)[is_using ? 1 : 2].str();
+                signature.return_type = parseType(trim((*it)[is_using ? 2 : 1].str()));
+                signature.namespace_path = namespaceAt(it->position(0));
+                signature.source_file = fileAt(it->position(0));
+                std::string params_str = trim((*it)[3].str());
+                if (!params_str.empty() && params_str != "void") {
+                    parseParameters(params_str, signature);
+                }
+                found.emplace_back(it->position(0), signature);
+            }
+        }
+
+        // Keep declaration order across both spellings
+        std::sort(found.begin(), found.end(),
+                  [](const auto& a, const auto& b) { return a.first < b.first; });
+        for (const auto& entry : found) {
+            ir.addFunctionPointerType(entry.second);
+        }
+    }
+
+    /**
+     * Parse `typedef unsigned long Name;` and `using Name = unsigned long;`
+     *
+     * Only aliases of a single, possibly multi-word, type name are kept;
+     * pointers, templates and function types do not match.
+     */
+    void parseTypeAliases(IR& ir) {
+        static const std::regex typedef_pattern(R"(\btypedef\s+((?:[\w:]+\s+)*?[\w:]+)\s+(\w+)\s*;)");
+        static const std::regex using_pattern(R"(\busing\s+(\w+)\s*=\s*((?:[\w:]+\s+)*?[\w:]+)\s*;)");
+
+        std::string cleaned = removeComments(source_);
+        for (const auto* pattern : {&typedef_pattern, &using_pattern}) {
+            bool is_using = pattern == &using_pattern;
+            for (std::sregex_iterator it(cleaned.begin(), cleaned.end(), *pattern), end; it != end; ++it) {
+                std::string underlying = std::regex_replace((*it)[is_using ? 2 : 1].str(), std::regex(R"(\s+)"), " ");
+                ir.addTypeAlias((*it)[is_using ? 1 : 2].str(), underlying);
+            }
+        }
+    }
+
+    /**
+     * Parse `template<class T> using Name = std::vector<T>;`
+     *
+     * Uses such as `Name<int>` are expanded by parseType, so they resolve
+     * like the type they stand for.
+     */
+    void parseAliasTemplates() {
+        static const std::regex alias_pattern(R"(\btemplate\s*<([^>]*)>\s*using\s+(\w+)\s*=\s*([^;]+);)");
+
+        std::string cleaned = removeComments(source_);
+        for (std::sregex_iterator it(cleaned.begin(), cleaned.end(), alias_pattern), end; it != end; ++it) {
+            AliasTemplate alias;
+            for (const auto& parameter : typeParameters((*it)[1].str())) {
+                alias.parameters.push_back(parameter.name);
+            }
+            alias.underlying = trim((*it)[3].str());
+            alias_templates_[(*it)[2].str()] = alias;
+        }
+    }
+
+    /**
+     * `Name<args>` with the alias template's parameters replaced by the
+     * arguments, or empty if `type_str` does not use an alias template
+     */
+    std::string expandAliasTemplate(const std::string& type_str) const {
+        static const std::regex use_pattern(R"(^(\w+)\s*<(.*)>$)");
+        std::smatch match;
+        if (!std::regex_match(type_str, match, use_pattern)) {
+            return "";
+        }
+        auto alias = alias_templates_.find(match[1].str());
+        if (alias == alias_templates_.end()) {
+            return "";
+        }
+
+        // Split the arguments at top-level commas
+        std::vector<std::string> args;
+        std::string list = match[2].str();
+        int depth = 0;
+        size_t start = 0;
+        for (size_t i = 0; i <= list.size(); ++i) {
+            if (i == list.size() || (list[i] == ',' && depth == 0)) {
+                args.push_back(trim(list.substr(start, i - start)));
+                start = i + 1;
+            } else if (list[i] == '<') {
+                depth++;
+            } else if (list[i] == '>') {
+                depth--;
+            }
+        }
+        if (args.size() != alias->second.parameters.size()) {
+            return "";
+        }
+
+        // Substitute whole identifiers only, so `T` leaves `Type` alone
+        std::string expanded;
+        const std::string& underlying = alias->second.underlying;
+        for (size_t i = 0; i < underlying.size();) {
+            if (!std::isalpha(static_cast<unsigned char>(underlying[i])) && underlying[i] != '_') {
+                expanded += underlying[i++];
+                continue;
+            }
+            size_t end = i;
+            while (end < underlying.size() &&
+                   (std::isalnum(static_cast<unsigned char>(underlying[end])) || underlying[end] == '_')) {
+                end++;
+            }
+            std::string word = underlying.substr(i, end - i);
+            const auto& parameters = alias->second.parameters;
+            auto parameter = std::find(parameters.begin(), parameters.end(), word);
+            expanded += parameter == parameters.end() ? word : args[parameter - parameters.begin()];
+            i = end;
+        }
+        return expanded;
+    }
+
+    /**
+     * Named namespace bodies in `code`, inline ones (`inline namespace v1`) included
+     */
+    static std::vector<NamespaceScope> findNamespaces(const std::string& code) {
+        static const std::regex opening(R"(\b(inline\s+)?namespace\s+(\w+(?:\s*::\s*\w+)*)\s*\{)");
+
+        std::vector<NamespaceScope> found;
+        for (std::sregex_iterator it(code.begin(), code.end(), opening), end; it != end; ++it) {
+            NamespaceScope scope;
+            scope.name = std::regex_replace((*it)[2].str(), std::regex(R"(\s+)"), "");
+            scope.is_inline = (*it)[1].matched;
+            scope.open = it->position(0) + it->length(0) - 1;
+            scope.close = code.size();
+
+            size_t depth = 0;
+            for (size_t i = scope.open; i < code.size(); ++i) {
+                if (code[i] == '{') {
+                    depth++;
+                } else if (code[i] == '}' && --depth == 0) {
+                    scope.close = i;
+                    break;
+                }
+            }
+            found.push_back(scope);
+        }
+        return found;
+    }
+
+    /**
+     * Braces of the `extern "C" { ... }` blocks in `code`, including the
+     * `#ifdef __cplusplus` guarded ones
+     */
+    static std::vector<std::pair<size_t, size_t>> findCLinkageBlocks(const std::string& code) {
+        static const std::regex opening(R"(\bextern\s*"C"\s*\{)");
+
+        std::vector<std::pair<size_t, size_t>> found;
+        for (std::sregex_iterator it(code.begin(), code.end(), opening), end; it != end; ++it) {
+            size_t open = it->position(0) + it->length(0) - 1;
+            size_t close = code.size();
+            size_t depth = 0;
+            for (size_t i = open; i < code.size(); ++i) {
+                if (code[i] == '{') {
+                    depth++;
+                } else if (code[i] == '}' && --depth == 0) {
+                    close = i;
+                    break;
+                }
+            }
+            found.emplace_back(open, close);
+        }
+        return found;
+    }
+
+    /**
+     * Namespaces enclosing `pos`, outermost first, e.g. `geo::v2`
+     *
+     * Inline namespaces are kept: they are transparent to C++ name lookup
+     * but part of the symbol the linker resolves.
+     */
+    std::string namespaceAt(size_t pos) const {
+        std::string path;
+        for (const auto& scope : namespaces_) {
+            if (pos > scope.open && pos < scope.close) {
+                path += (path.empty() ? "" : "::") + scope.name;
+            }
+        }
+        return path;
+    }
+
+    /**
+     * `#pragma pack` directives in `code`
+     */
+    static std::vector<PackPragma> findPackPragmas(const std::string& code) {
+        static const std::regex directive(
+            R"(#\s*pragma\s+pack\s*\(\s*(push|pop)?\s*,?\s*(\d+)?\s*\))");
+
+        std::vector<PackPragma> found;
+        for (std::sregex_iterator it(code.begin(), code.end(), directive), end; it != end; ++it) {
+            PackPragma pragma;
+            pragma.pos = it->position(0);
+            pragma.push = (*it)[1].str() == "push";
+            pragma.pop = (*it)[1].str() == "pop";
+            pragma.value = (*it)[2].matched ? std::stoul((*it)[2].str()) : 0;
+            found.push_back(pragma);
+        }
+        return found;
+    }
+
+    /**
+     * Packing in effect at `pos`, 0 for natural alignment
+     */
+    size_t packAt(size_t pos) const {
+        std::vector<size_t> stack;
+        size_t current = 0;
+        for (const auto& pragma : pack_pragmas_) {
+            if (pragma.pos >= pos) {
+                break;
+            }
+            if (pragma.pop) {
+                current = stack.empty() ? 0 : stack.back();
+                if (!stack.empty()) {
+                    stack.pop_back();
+                }
+                continue;
+            }
+            if (pragma.push) {
+                stack.push_back(current);
+                if (pragma.value == 0) {
+                    continue;
+                }
+            }
+            current = pragma.value;
+        }
+        return current;
+    }
+
+    /**
+     * Original file of the code at `pos`, empty unless the input was preprocessed
+     */
+    std::string fileAt(size_t pos) const {
+        std::string file;
+        for (const auto& region : files_) {
+            if (region.begin > pos) {
+                break;
+            }
+            file = region.file;
+        }
+        return file;
+    }
+
     /**
      * Process namespaces - extract content and flatten
      */
     std::string processNamespaces(const std::string& source) {
         std::string result = source;
 
-        // Simple namespace extraction - just remove namespace wrapper
-        // This is a simplified approach; a full implementation would track namespace prefixes
-        std::regex ns_pattern(R"(namespace\s+(\w+)\s*\{)");
-
-        // Replace namespace openings with a marker comment
-        result = std::regex_replace(result, ns_pattern, "/* namespace $1 */ ");
-
-        // Count and remove matching braces (simplified - just removes the last } for each namespace)
-        // This is a basic approach; proper brace matching would be needed for production
+        // Declarations are bound flat; namespaceAt records where each came from.
+        // Openings are blanked rather than removed so positions stay valid.
+        std::regex ns_pattern(R"(\b(?:inline\s+)?namespace\s+\w+(?:\s*::\s*\w+)*\s*\{)");
+        std::string blanked;
+        size_t last = 0;
+        for (std::sregex_iterator it(result.begin(), result.end(), ns_pattern), end; it != end; ++it) {
+            blanked += result.substr(last, it->position(0) - last) + std::string(it->length(0), ' ');
+            last = it->position(0) + it->length(0);
+        }
 
-        return result;
+        // The closing braces stay behind; nothing matches a stray `}`
+        return blanked + result.substr(last);
     }
 
     /**
-     * Parse base class list
+     * Type parameters of a `template<typename T, class U>` list, by name
      */
-    void parseBaseClasses(const std::string& bases_str, ClassDecl& class_decl) {
-        std::regex base_pattern(R"(\w+)");
-        auto bases_begin = std::sregex_iterator(bases_str.begin(), bases_str.end(), base_pattern);
-        auto bases_end = std::sregex_iterator();
+    static std::vector<TemplateParameter> typeParameters(const std::string& list) {
+        static const std::regex type_parameter(R"(\b(?:typename|class)\s+(\w+))");
+        std::vector<TemplateParameter> parameters;
+        for (std::sregex_iterator param(list.begin(), list.end(), type_parameter), end; param != end; ++param) {
+            TemplateParameter parameter;
+            parameter.kind = TemplateParameter::TypeParam;
+            parameter.name = (*param)[1].str();
+            parameters.push_back(parameter);
+        }
+        return parameters;
+    }
 
-        for (std::sregex_iterator i = bases_begin; i != bases_end; ++i) {
-            class_decl.base_classes.push_back((*i).str());
+    /**
+     * Parse base class list, keeping the public bases
+     *
+     * Bases without an access specifier are public for structs and private
+     * for classes, as in C++.
+     */
+    void parseBaseClasses(const std::string& bases_str, bool is_struct, ClassDecl& class_decl) {
+        static const std::regex base_pattern(
+            R"((?:^|,)\s*(?:virtual\s+)?(?:(public|protected|private)\s+)?(?:virtual\s+)?([\w:]+(?:\s*<[^,>]*>)?))");
+        for (std::sregex_iterator i(bases_str.begin(), bases_str.end(), base_pattern), end; i != end; ++i) {
+            bool is_public = (*i)[1].matched ? (*i)[1].str() == "public" : is_struct;
+            if (is_public) {
+                class_decl.base_classes.push_back((*i)[2].str());
+            }
         }
     }
 
     /**
      * Parse class body (fields and methods)
      */
-    void parseClassBody(const std::string& body, ClassDecl& class_decl) {
+    void parseClassBody(const std::string& body, ClassDecl& class_decl, size_t offset) {
         // Split by access specifiers
         std::vector<std::string> sections;
         std::vector<std::string> access_levels;
+        std::vector<size_t> offsets;  // Where each section starts in the comment-free source
 
         std::regex access_pattern(R"((private|protected|public)\s*:)");
 
@@ -311,6 +1057,7 @@ private:
             if (!section_content.empty()) {
                 sections.push_back(section_content);
                 access_levels.push_back(current_access);
+                offsets.push_back(offset + last_pos);
             }
 
             current_access = (*it)[1].str();
@@ -321,41 +1068,65 @@ private:
         if (last_pos < body.length()) {
             sections.push_back(body.substr(last_pos));
             access_levels.push_back(current_access);
+            offsets.push_back(offset + last_pos);
         }
 
         // If no access specifier found, treat entire body as private
         if (sections.empty()) {
             sections.push_back(body);
             access_levels.push_back("private");
+            offsets.push_back(offset);
         }
 
         // Parse each section
         for (size_t i = 0; i < sections.size(); ++i) {
-            parseSection(sections[i], access_levels[i], class_decl);
+            parseSection(sections[i], access_levels[i], class_decl, offsets[i]);
         }
     }
 
     /**
      * Parse a section (fields and methods within an access level)
      */
-    void parseSection(const std::string& section, const std::string& access, ClassDecl& class_decl) {
+    void parseSection(const std::string& section, const std::string& access, ClassDecl& class_decl, size_t offset) {
         // Parse field declarations
         parseFields(section, access, class_decl);
 
         // Parse method declarations/definitions
-        parseMethods(section, access, class_decl);
+        parseMethods(section, access, class_decl, offset);
     }
 
     /**
      * Parse field declarations
      */
-    void parseFields(const std::string& section, const std::string& access, ClassDecl& class_decl) {
+    void parseFields(const std::string& section_source, const std::string& access, ClassDecl& class_decl) {
+        // Statements in inline method bodies are not declarations
+        std::string section = section_source;
+        size_t depth = 0;
+        for (char& c : section) {
+            if (c == '{') {
+                depth++;
+            } else if (c == '}' && depth > 0) {
+                depth--;
+                c = ';';
+                continue;
+            }
+            if (depth > 0) {
+                c = ' ';
+            }
+        }
+
         // Match: type name; or type name1, name2;
         std::regex field_pattern(
-            R"((?:const\s+)?(?:static\s+)?([a-zA-Z_][\w:<>,\[\]\s*&]*?)\s+([a-zA-Z_]\w*(?:\s*,\s*[a-zA-Z_]\w*)*)\s*;)",
+            R"(((?:inline\s+)?static\s+(?:inline\s+)?)?((?:const\s+)?[a-zA-Z_][\w:<>,\[\]\s*&]*?)\s+([a-zA-Z_]\w*(?:\s*,\s*[a-zA-Z_]\w*)*)\s*;)",
             std::regex::ECMAScript
         );
 
+        // Fields record their access by name, methods carry their own
+        ClassDecl::AccessSection access_section;
+        access_section.level = access == "private" ? ClassDecl::AccessSection::Private
+                             : access == "protected" ? ClassDecl::AccessSection::Protected
+                             : ClassDecl::AccessSection::Public;
+
         auto fields_begin = std::sregex_iterator(section.begin(), section.end(), field_pattern);
         auto fields_end = std::sregex_iterator();
 
@@ -368,8 +1139,8 @@ private:
                 continue;
             }
 
-            std::string type_str = match[1].str();
-            std::string names_str = match[2].str();
+            std::string type_str = match[2].str();
+            std::string names_str = match[3].str();
 
             // Parse multiple variable names (e.g., int x, y;)
             std::regex name_pattern(R"([a-zA-Z_]\w*)");
@@ -380,20 +1151,28 @@ private:
                 Variable field;
                 field.name = (*name_it).str();
                 field.type = parseType(type_str);
+                field.is_static = match[1].matched;
 
+                access_section.members.push_back(field.name);
                 class_decl.fields.push_back(field);
             }
         }
+
+        if (!access_section.members.empty()) {
+            class_decl.access_sections.push_back(access_section);
+        }
     }
 
     /**
      * Parse method declarations/definitions
      */
-    void parseMethods(const std::string& section, const std::string& access, ClassDecl& class_decl) {
+    void parseMethods(const std::string& section, const std::string& access, ClassDecl& class_decl,
+                      size_t offset) {
         // Match method signatures (including constructors, virtual, static)
-        // Pattern: [virtual] [static] [type] name(params) [const] [= 0] [{ body } | ;]
+        // Pattern: [virtual] [static] [explicit] [type] name(params) [const] [= 0|delete|default] [: inits] [{ body } | ;]
+        // with call expressions allowed as default arguments
         std::regex method_pattern(
-            R"((virtual\s+)?(static\s+)?(?:([a-zA-Z_][\w:<>,\s*&]*?)\s+)?([a-zA-Z_]\w*)\s*\(([^)]*)\)\s*(const)?\s*(=\s*0)?\s*(?:\{([^}]*(?:\{[^}]*\}[^}]*)*)\}|;))",
+            R"((virtual\s+)?(static\s+)?(?:explicit\s+)?(?:([a-zA-Z_][\w:<>,\s*&]*?)\s+)?(operator\s*(?:<=>|==|!=|<=|>=|<|>)|[a-zA-Z_]\w*)\s*\(([^()]*(?:=\s*[\w:<>]*\s*\([^()]*\)[^()]*)*)\)\s*(const)?\s*(=\s*(?:0|delete|default))?\s*(?::[^{;]*)?(?:\{([^}]*(?:\{[^}]*\}[^}]*)*)\}|;))",
             std::regex::ECMAScript
         );
 
@@ -404,7 +1183,7 @@ private:
             std::smatch match = *it;
 
             Function method;
-            method.name = match[4].str();
+            method.name = std::regex_replace(match[4].str(), std::regex(R"(^operator\s+)"), "operator");
 
             // Check if virtual
             method.is_virtual = match[1].matched;
@@ -412,11 +1191,34 @@ private:
             // Check if static
             method.is_static = match[2].matched;
 
-            // Check if pure virtual (= 0)
-            method.is_pure_virtual = match[7].matched;
+            method.is_deprecated = deprecation(attributesBefore(section, match.position(0)),
+                                               method.deprecation_message);
+            method.doc_comment = docAt(offset + match.position(0));
+            method.annotations = annotationsAt(offset + match.position(0));
+
+            // Check if pure virtual (= 0) or deleted (= delete)
+            std::string specifier = match[7].str();
+            method.is_pure_virtual = specifier.find('0') != std::string::npos;
+            method.is_deleted = specifier.find("delete") != std::string::npos;
 
-            // Check if constructor (no return type and name matches class)
-            if (match[3].str().empty() || match[3].str() == class_decl.name) {
+            if (access == "private") {
+                method.access = Function::Access::Private;
+            } else if (access == "protected") {
+                method.access = Function::Access::Protected;
+            }
+
+            // `~Name()` matches as `Name()` after the tilde
+            size_t start = match.position(4);
+            while (start > 0 && std::isspace(static_cast<unsigned char>(section[start - 1]))) {
+                --start;
+            }
+            if (start > 0 && section[start - 1] == '~') {
+                method.name = "~" + method.name;
+                method.is_destructor = true;
+                method.return_type = nullptr;
+            } else if (match[3].str().empty() ||
+                       method.name == class_decl.name.substr(class_decl.name.rfind(':') + 1)) {
+                // Constructor: no return type or name matches class (`Shape copy()` is a method)
                 method.is_constructor = true;
                 method.return_type = nullptr;
             } else {
@@ -445,15 +1247,24 @@ private:
      * Parse function parameters
      */
     void parseParameters(const std::string& params_str, Function& func) {
-        // Split by commas (but not inside <>)
+        // Split by commas (but not inside <>, () or a string literal)
         std::vector<std::string> param_strs;
         int angle_depth = 0;
+        int paren_depth = 0;
+        bool in_string = false;
         size_t start = 0;
 
         for (size_t i = 0; i < params_str.length(); ++i) {
-            if (params_str[i] == '<') angle_depth++;
+            if (in_string) {
+                if (params_str[i] == '\\') ++i;
+                else if (params_str[i] == '"') in_string = false;
+            }
+            else if (params_str[i] == '"') in_string = true;
+            else if (params_str[i] == '<') angle_depth++;
             else if (params_str[i] == '>') angle_depth--;
-            else if (params_str[i] == ',' && angle_depth == 0) {
+            else if (params_str[i] == '(') paren_depth++;
+            else if (params_str[i] == ')') paren_depth--;
+            else if (params_str[i] == ',' && angle_depth == 0 && paren_depth == 0) {
                 param_strs.push_back(params_str.substr(start, i - start));
                 start = i + 1;
             }
@@ -524,6 +1335,16 @@ private:
             return ref_type;
         }
 
+        // Alias templates stand for their expansion; the depth bound stops self-reference
+        std::string expanded = alias_depth_ < 16 ? expandAliasTemplate(trimmed) : "";
+        if (!expanded.empty()) {
+            alias_depth_++;
+            auto type = parseType(expanded);
+            alias_depth_--;
+            type->is_const = type->is_const || is_const;
+            return type;
+        }
+
         // Check for smart pointers
         if (trimmed.find("std::unique_ptr<") == 0) {
             size_t start = trimmed.find('<') + 1;
@@ -549,6 +1370,18 @@ private:
             return ptr_type;
         }
 
+        if (trimmed.find("std::vector<") == 0) {
+            size_t start = trimmed.find('<') + 1;
+            size_t end = trimmed.rfind('>');
+            auto element = parseType(trimmed.substr(start, end - start));
+            auto vector_type = std::make_shared<Type>(TypeKind::StdVector);
+            vector_type->template_args.push_back(element);
+            // Spelled from the element so nested alias templates come out expanded
+            vector_type->name = "std::vector<" + element->name + ">";
+            vector_type->is_const = is_const;
+            return vector_type;
+        }
+
         // Check for arrays
         size_t bracket_pos = trimmed.find('[');
         if (bracket_pos != std::string::npos) {
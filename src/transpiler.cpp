@@ -2,18 +2,136 @@
 #include "ir.h"
 #include "codegen.h"
 #include "parser.h"
+#include "ffi.h"
+#include <array>
+#include <cstdint>
+#include <cstdlib>
+#include <ctime>
 #include <fstream>
+#include <iomanip>
 #include <sstream>
 
 namespace hybrid {
 
+namespace {
+
+/**
+ * SHA-256 (FIPS 180-4) of data, as lowercase hex
+ */
+std::string sha256Hex(const std::string& data) {
+    static const std::array<uint32_t, 64> k = {
+        0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+        0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+        0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+        0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+        0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+        0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+        0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+        0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+    };
+    std::array<uint32_t, 8> h = {
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+    };
+    auto rotr = [](uint32_t x, int n) { return (x >> n) | (x << (32 - n)); };
+
+    // Pad with 0x80, zeros, then the bit length as a big-endian 64-bit value
+    std::string message = data;
+    uint64_t bit_length = static_cast<uint64_t>(data.size()) * 8;
+    message += static_cast<char>(0x80);
+    while (message.size() % 64 != 56) {
+        message += '\0';
+    }
+    for (int i = 7; i >= 0; --i) {
+        message += static_cast<char>((bit_length >> (i * 8)) & 0xff);
+    }
+
+    for (size_t chunk = 0; chunk < message.size(); chunk += 64) {
+        std::array<uint32_t, 64> w{};
+        for (size_t i = 0; i < 16; ++i) {
+            for (size_t j = 0; j < 4; ++j) {
+                w[i] = (w[i] << 8) | static_cast<unsigned char>(message[chunk + i * 4 + j]);
+            }
+        }
+        for (size_t i = 16; i < 64; ++i) {
+            uint32_t s0 = rotr(w[i - 15], 7) ^ rotr(w[i - 15], 18) ^ (w[i - 15] >> 3);
+            uint32_t s1 = rotr(w[i - 2], 17) ^ rotr(w[i - 2], 19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16] + s0 + w[i - 7] + s1;
+        }
+
+        std::array<uint32_t, 8> v = h;
+        for (size_t i = 0; i < 64; ++i) {
+            uint32_t s1 = rotr(v[4], 6) ^ rotr(v[4], 11) ^ rotr(v[4], 25);
+            uint32_t ch = (v[4] & v[5]) ^ (~v[4] & v[6]);
+            uint32_t t1 = v[7] + s1 + ch + k[i] + w[i];
+            uint32_t s0 = rotr(v[0], 2) ^ rotr(v[0], 13) ^ rotr(v[0], 22);
+            uint32_t maj = (v[0] & v[1]) ^ (v[0] & v[2]) ^ (v[1] & v[2]);
+            uint32_t t2 = s0 + maj;
+            v = {t1 + t2, v[0], v[1], v[2], v[3] + t1, v[4], v[5], v[6]};
+        }
+        for (size_t i = 0; i < 8; ++i) {
+            h[i] += v[i];
+        }
+    }
+
+    std::stringstream hex;
+    for (uint32_t word : h) {
+        hex << std::hex << std::setw(8) << std::setfill('0') << word;
+    }
+    return hex.str();
+}
+
+} // namespace
+
+std::string generationStamp(const std::vector<std::pair<std::string, std::string>>& inputs,
+                            const std::string& timestamp) {
+    std::stringstream ss;
+    ss << "// Provenance:\n";
+    ss << "//   transpiler: hybrid-transpiler v" << TRANSPILER_VERSION << "\n";
+    if (!timestamp.empty()) {
+        ss << "//   generated:  " << timestamp << "\n";
+    }
+    for (const auto& input : inputs) {
+        ss << "//   input:      " << input.first << " (sha256 " << sha256Hex(input.second) << ")\n";
+    }
+    ss << "\n";
+    return ss.str();
+}
+
+bool FileSink::write(const std::string& path, const std::string& contents) {
+    std::ofstream file(path);
+    if (!file.is_open()) {
+        return false;
+    }
+    file << contents;
+    return static_cast<bool>(file);
+}
+
+bool MemorySink::write(const std::string& path, const std::string& contents) {
+    files_[path] = contents;
+    return true;
+}
+
+std::string MemorySink::contents(const std::string& path) const {
+    auto it = files_.find(path);
+    return it == files_.end() ? "" : it->second;
+}
+
 Transpiler::Transpiler(const TranspilerOptions& options)
-    : options_(options), ir_(std::make_unique<IR>()) {
+    : options_(options), ir_(std::make_unique<IR>()),
+      file_sink_(std::make_unique<FileSink>()), sink_(file_sink_.get()) {
+    createCodeGenerator();
+}
+
+Transpiler::Transpiler(const TranspilerOptions& options, CodeSink& sink)
+    : options_(options), ir_(std::make_unique<IR>()), sink_(&sink) {
+    createCodeGenerator();
+}
 
+void Transpiler::createCodeGenerator() {
     // Create appropriate code generator based on target
-    if (options.target == TargetLanguage::Rust) {
+    if (options_.target == TargetLanguage::Rust) {
         codegen_ = std::make_unique<RustCodeGenerator>();
-    } else if (options.target == TargetLanguage::Go) {
+    } else if (options_.target == TargetLanguage::Go) {
         codegen_ = std::make_unique<GoCodeGenerator>();
     }
 }
@@ -21,13 +139,22 @@ Transpiler::Transpiler(const TranspilerOptions& options)
 Transpiler::~Transpiler() = default;
 
 bool Transpiler::transpile(const std::string& input_path) {
+    if (options_.list_unsupported) {
+        return listUnsupported({input_path});
+    }
+
+    // FFI mode keeps the C++ code and only emits bindings to it
+    if (!options_.ffi_target.empty()) {
+        return generateFFIBindings({input_path});
+    }
+
     // Parse the input file
     if (!parseSourceFile(input_path)) {
         return false;
     }
 
     // Generate output code
-    if (!generateCode(options_.output_path)) {
+    if (!generateCode(input_path, options_.output_path)) {
         return false;
     }
 
@@ -35,6 +162,13 @@ bool Transpiler::transpile(const std::string& input_path) {
 }
 
 bool Transpiler::transpileBatch(const std::vector<std::string>& input_paths) {
+    if (options_.list_unsupported) {
+        return listUnsupported(input_paths);
+    }
+    // Bindings for several libraries share one module, one extern block each
+    if (!options_.ffi_target.empty() && !input_paths.empty()) {
+        return generateFFIBindings(input_paths);
+    }
     for (const auto& path : input_paths) {
         if (!transpile(path)) {
             return false;
@@ -47,23 +181,43 @@ bool Transpiler::parseSourceFile(const std::string& input_path) {
     try {
         // Use the simple C++ parser to parse the source file
         // This will be replaced with full Clang LibTooling in the future
-        *ir_ = Parser::parseFile(input_path);
+        if (options_.preprocessed) {
+            std::ifstream in_file(input_path);
+            if (!in_file.is_open()) {
+                throw std::runtime_error("Cannot open file: " + input_path);
+            }
+            std::stringstream source;
+            source << in_file.rdbuf();
+            *ir_ = Parser::parsePreprocessed(source.str());
+        } else {
+            *ir_ = Parser::parseFile(input_path);
+        }
 
         // TODO (future): Add additional analysis passes:
         // 1. Ownership analysis for smart pointers
         // 2. Lifetime inference for references
         // 3. Safety validation
         // 4. Performance optimization hints
-
-        return true;
     }
     catch (const std::exception& e) {
         last_error_ = "Failed to parse input file: " + std::string(e.what());
         return false;
     }
+
+    if (!options_.roots.empty()) {
+        try {
+            *ir_ = reachableFrom(*ir_, options_.roots);
+        }
+        catch (const std::exception& e) {
+            last_error_ = "Invalid --roots: " + std::string(e.what());
+            return false;
+        }
+    }
+
+    return true;
 }
 
-bool Transpiler::generateCode(const std::string& output_path) {
+bool Transpiler::generateCode(const std::string& input_path, const std::string& output_path) {
     if (!codegen_) {
         last_error_ = "Code generator not initialized";
         return false;
@@ -72,17 +226,201 @@ bool Transpiler::generateCode(const std::string& output_path) {
     // Generate code from IR
     std::string generated_code = codegen_->generate(*ir_);
 
-    // Write to output file
-    std::ofstream out_file(output_path);
-    if (!out_file.is_open()) {
+    if (options_.stamp) {
+        std::ifstream in_file(input_path);
+        std::stringstream source;
+        source << in_file.rdbuf();
+        generated_code = stampFor({{input_path, source.str()}}) + generated_code;
+    }
+
+    if (!sink_->write(output_path, generated_code)) {
         last_error_ = "Failed to open output file: " + output_path;
         return false;
     }
 
-    out_file << generated_code;
-    out_file.close();
+    return true;
+}
+
+bool Transpiler::readFFISources(const std::vector<std::string>& input_paths,
+                                std::vector<std::pair<std::string, std::string>>& inputs,
+                                std::vector<std::pair<std::string, std::string>>& sources) {
+    // Each library is named after its input file (mylib.cpp -> mylib)
+    for (const auto& input_path : input_paths) {
+        std::ifstream in_file(input_path);
+        if (!in_file.is_open()) {
+            last_error_ = "Failed to open input file: " + input_path;
+            return false;
+        }
+        std::stringstream source;
+        source << in_file.rdbuf();
 
+        std::string library_name = input_path.substr(input_path.find_last_of("/\\") + 1);
+        library_name = library_name.substr(0, library_name.find_last_of('.'));
+        inputs.emplace_back(input_path, source.str());
+        sources.emplace_back(library_name, source.str());
+    }
     return true;
 }
 
+bool Transpiler::loadFFIOptions(hybrid_transpiler::ffi::FFIOptions& ffi_options) {
+    ffi_options.emit_tests = options_.ffi_tests;
+    ffi_options.emit_toc = options_.ffi_toc;
+    ffi_options.emit_self_test = options_.ffi_self_test;
+    ffi_options.emit_link_check = options_.ffi_link_check;
+    ffi_options.emit_relink_macro = options_.ffi_relink_macro;
+    ffi_options.thiserror = options_.ffi_thiserror;
+    ffi_options.preprocessed = options_.preprocessed;
+    ffi_options.strict = options_.strict;
+    ffi_options.allowed_skips.insert(options_.allowed_skips.begin(), options_.allowed_skips.end());
+    ffi_options.roots = options_.roots;
+
+    if (!options_.ffi_config_path.empty()) {
+        std::ifstream config_file(options_.ffi_config_path);
+        if (!config_file.is_open()) {
+            last_error_ = "Failed to open FFI config file: " + options_.ffi_config_path;
+            return false;
+        }
+        std::stringstream config;
+        config << config_file.rdbuf();
+        hybrid_transpiler::ffi::applyFFIConfig(config.str(), ffi_options);
+    }
+    return true;
+}
+
+bool Transpiler::listUnsupported(const std::vector<std::string>& input_paths) {
+    std::vector<std::pair<std::string, std::string>> inputs;
+    std::vector<std::pair<std::string, std::string>> sources;
+    if (!readFFISources(input_paths, inputs, sources)) {
+        return false;
+    }
+
+    try {
+        hybrid_transpiler::ffi::FFIOptions ffi_options;
+        if (!loadFFIOptions(ffi_options)) {
+            return false;
+        }
+        hybrid_transpiler::ffi::FFIGenerator generator(ffi_options);
+        report_ = hybrid_transpiler::ffi::formatSupportReport(generator.audit(sources));
+    }
+    catch (const std::exception& e) {
+        last_error_ = "Failed to analyze declarations: " + std::string(e.what());
+        return false;
+    }
+    return true;
+}
+
+bool Transpiler::generateFFIBindings(const std::vector<std::string>& input_paths) {
+    std::vector<std::pair<std::string, std::string>> inputs;
+    std::vector<std::pair<std::string, std::string>> sources;
+    if (!readFFISources(input_paths, inputs, sources)) {
+        return false;
+    }
+    const std::string& library_name = sources.front().first;
+
+    hybrid_transpiler::ffi::FFIOptions ffi_options;
+    std::string generated_code;
+    std::vector<std::pair<std::string, std::string>> shim_sources;  // Path and contents, one per library
+    struct SideFile {
+        std::string path;
+        std::string contents;
+        std::string kind;  // For the error message, e.g. "pkg-config"
+    };
+    std::vector<SideFile> side_files;
+    try {
+        if (!loadFFIOptions(ffi_options)) {
+            return false;
+        }
+
+        hybrid_transpiler::ffi::FFIGenerator generator(ffi_options);
+        generated_code = sources.size() == 1
+            ? generator.generate(sources.front().second, library_name, options_.ffi_target)
+            : generator.generate(sources, options_.ffi_target);
+        if (!options_.shim_source_path.empty()) {
+            // Shims are compiled into the library they call, so several
+            // inputs get one file each: shims.cpp -> shims_<library>.cpp
+            for (const auto& source : sources) {
+                std::string path = options_.shim_source_path;
+                if (sources.size() > 1) {
+                    size_t dot = path.find_last_of('.');
+                    size_t slash = path.find_last_of("/\\");
+                    size_t insert = dot == std::string::npos || (slash != std::string::npos && dot < slash)
+                        ? path.size() : dot;
+                    path.insert(insert, "_" + source.first);
+                }
+                shim_sources.emplace_back(path, generator.generateCWrapper(source.second, source.first).second);
+            }
+        }
+        for (const auto& diagnostic : generator.diagnostics()) {
+            warnings_.push_back(diagnostic.symbol + ": " + diagnostic.message);
+        }
+
+        // Build files describe bindings that exist, so they are only
+        // produced once generation succeeded. Package files name every
+        // library the bindings link.
+        std::vector<std::string> library_names;
+        for (const auto& source : sources) {
+            library_names.push_back(source.first);
+        }
+        if (!options_.bindgen_config_path.empty()) {
+            side_files.push_back({options_.bindgen_config_path,
+                                  hybrid_transpiler::ffi::generateBindgenConfig(ffi_options, library_name),
+                                  "bindgen config"});
+        }
+        if (!options_.pkg_config_path.empty()) {
+            side_files.push_back({options_.pkg_config_path,
+                                  hybrid_transpiler::ffi::generatePkgConfig(ffi_options, library_names), "pkg-config"});
+        }
+        if (!options_.cmake_config_path.empty()) {
+            side_files.push_back({options_.cmake_config_path,
+                                  hybrid_transpiler::ffi::generateCMakeConfig(ffi_options, library_names),
+                                  "CMake config"});
+        }
+    }
+    catch (const std::exception& e) {
+        last_error_ = "Failed to generate FFI bindings: " + std::string(e.what());
+        return false;
+    }
+
+    if (options_.stamp) {
+        std::string stamp = stampFor(inputs);
+        generated_code = stamp + generated_code;
+        for (auto& shim : shim_sources) {
+            shim.second = stamp + shim.second;
+        }
+    }
+
+    for (const auto& shim : shim_sources) {
+        if (!sink_->write(shim.first, shim.second)) {
+            last_error_ = "Failed to open shim source file: " + shim.first;
+            return false;
+        }
+    }
+
+    if (!sink_->write(options_.output_path, generated_code)) {
+        last_error_ = "Failed to open output file: " + options_.output_path;
+        return false;
+    }
+
+    for (const auto& file : side_files) {
+        if (!sink_->write(file.path, file.contents)) {
+            last_error_ = "Failed to open " + file.kind + " file: " + file.path;
+            return false;
+        }
+    }
+
+    return true;
+}
+
+std::string Transpiler::stampFor(const std::vector<std::pair<std::string, std::string>>& inputs) const {
+    // SOURCE_DATE_EPOCH pins the time for reproducible builds
+    std::time_t now = std::time(nullptr);
+    if (const char* epoch = std::getenv("SOURCE_DATE_EPOCH")) {
+        now = static_cast<std::time_t>(std::strtoll(epoch, nullptr, 10));
+    }
+
+    char timestamp[32];
+    std::strftime(timestamp, sizeof(timestamp), "%Y-%m-%dT%H:%M:%SZ", std::gmtime(&now));
+    return generationStamp(inputs, timestamp);
+}
+
 } // namespace hybrid
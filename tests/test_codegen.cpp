@@ -1,7 +1,13 @@
 #include "ir.h"
 #include "codegen.h"
+#include "transpiler.h"
+#include "parser.h"
+#include "ffi.h"
 #include <cassert>
+#include <cstdio>
+#include <fstream>
 #include <iostream>
+#include <sstream>
 
 namespace hybrid {
 namespace test {
@@ -69,10 +75,242 @@ void testGoCodeGeneration() {
     std::cout << "  ✓ Go code generation test passed\n";
 }
 
+void testGenerationStamp() {
+    std::string stamp = generationStamp({{"src/abc.cpp", "abc"}}, "2025-01-01T00:00:00Z");
+
+    assert(stamp.find("//   transpiler: hybrid-transpiler v" + std::string(TRANSPILER_VERSION) + "\n") != std::string::npos);
+    assert(stamp.find("//   generated:  2025-01-01T00:00:00Z\n") != std::string::npos);
+    assert(stamp.find("//   input:      src/abc.cpp (sha256 "
+                      "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad)\n") != std::string::npos);
+
+    // Multi-block input, and no time line in deterministic output
+    std::string long_stamp = generationStamp(
+        {{"long.cpp", "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"}, {"empty.h", ""}}, "");
+    assert(long_stamp.find("248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1") != std::string::npos);
+    assert(long_stamp.find("empty.h (sha256 e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855)")
+           != std::string::npos);
+    assert(long_stamp.find("generated:") == std::string::npos);
+
+    std::cout << "  ✓ Generation stamp test passed\n";
+}
+
+void testMemorySink() {
+    // Transpiler inputs are still read from disk
+    const std::string input = "sink_test_widget.h";
+    {
+        std::ofstream file(input);
+        file << "class Widget {\npublic:\n    Widget(int32_t size);\n    int32_t size() const;\n};\n";
+    }
+
+    TranspilerOptions options;
+    options.ffi_target = "rust";
+    options.stamp = false;
+    options.output_path = "out/widget_ffi.rs";
+    options.shim_source_path = "out/widget_shims.cpp";
+
+    MemorySink sink;
+    Transpiler transpiler(options, sink);
+    bool ok = transpiler.transpile(input);
+    std::remove(input.c_str());
+    assert(ok);
+
+    // Nothing touches the filesystem; both files are captured by path
+    assert(sink.files().size() == 2);
+    assert(!std::ifstream(options.output_path).good());
+    assert(sink.contents("out/widget_ffi.rs").find("pub fn size(&self) -> i32 {") != std::string::npos);
+    assert(sink.contents("out/widget_shims.cpp").find("void* widget_new(int32_t size) {") != std::string::npos);
+    assert(sink.contents("missing.rs").empty());
+
+    // Package files are only written along with bindings that were generated
+    {
+        std::ofstream file(input);
+        file << "class Widget {\npublic:\n    Widget(int32_t size);\n    std::vector<int> sizes() const;\n};\n";
+    }
+    options.strict = true;
+    options.bindgen_config_path = "out/widget_bindgen.rs";
+    options.pkg_config_path = "out/widget.pc";
+    options.cmake_config_path = "out/widgetConfig.cmake";
+    MemorySink failed;
+    Transpiler strict(options, failed);
+    ok = strict.transpile(input);
+    std::remove(input.c_str());
+    assert(!ok);
+    assert(failed.files().empty());
+
+    std::cout << "  ✓ Memory sink test passed\n";
+}
+
+void testPreprocessedInput() {
+    // Shaped like `g++ -E gfx.h`: a system header, then the user's headers
+    const std::string input = "sink_test_gfx.ii";
+    {
+        std::ofstream file(input);
+        file << "# 0 \"gfx.h\"\n"
+                "# 0 \"<built-in>\"\n"
+                "# 0 \"<command-line>\"\n"
+                "# 1 \"gfx.h\"\n"
+                "# 1 \"/usr/include/string.h\" 1 3 4\n"
+                "extern \"C\" {\n"
+                "extern void *memcpy (void *__restrict __dest, const void *__restrict __src, size_t __n);\n"
+                "extern size_t strlen (const char *__s);\n"
+                "}\n"
+                "# 2 \"gfx.h\" 2\n"
+                "# 1 \"include/gfx/color.h\" 1\n"
+                "struct Color {\n    uint8_t r;\n    uint8_t g;\n    uint8_t b;\n};\n"
+                "# 3 \"gfx.h\" 2\n"
+                "\n"
+                "int blend(int a, int b);\n";
+    }
+
+    std::ifstream unit(input);
+    std::stringstream source;
+    source << unit.rdbuf();
+    IR ir = Parser::parsePreprocessed(source.str());
+    assert(ir.getFunctions().size() == 1);
+    assert(ir.getFunctions()[0].source_file == "gfx.h");
+    assert(ir.getClasses().size() == 1 && ir.getClasses()[0].source_file == "include/gfx/color.h");
+
+    TranspilerOptions options;
+    options.ffi_target = "rust";
+    options.stamp = false;
+    options.preprocessed = true;
+    options.output_path = "out/gfx_ffi.rs";
+    options.shim_source_path = "out/gfx_shims.cpp";
+
+    MemorySink sink;
+    Transpiler transpiler(options, sink);
+    bool ok = transpiler.transpile(input);
+    std::remove(input.c_str());
+    assert(ok);
+
+    // System header declarations are not bound
+    std::string code = sink.contents("out/gfx_ffi.rs");
+    assert(code.find("#[link(name = \"sink_test_gfx\")]") != std::string::npos);
+    assert(code.find("pub fn blend(a: i32, b: i32) -> i32;") != std::string::npos);
+    assert(code.find("pub struct Color {") != std::string::npos);
+    assert(code.find("memcpy") == std::string::npos && code.find("strlen") == std::string::npos);
+
+    // Shims include the headers the line markers attribute declarations to
+    std::string shims = sink.contents("out/gfx_shims.cpp");
+    assert(shims.find("#include \"gfx.h\"\n#include \"include/gfx/color.h\"\n") != std::string::npos);
+    assert(shims.find("sink_test_gfx.h") == std::string::npos);
+
+    std::cout << "  ✓ Preprocessed input test passed\n";
+}
+
+void testDocComments() {
+    const std::string source = R"(
+        /**
+         * @file math.h
+         */
+
+        /// Adds two numbers.
+        ///
+        /// ```cpp
+        /// int r = add(2, 3);
+        /// ```
+        int add(int a, int b);
+
+        int counter; /// not the next declaration's doc
+
+        /**
+         * @brief Running total
+         *
+         * @code
+         *   Acc acc;
+         *   acc.push(1);
+         * @endcode
+         */
+        class Acc {
+        public:
+            Acc();
+            /// Adds `v` to the total
+            void push(int v);
+            int total() const;
+        };
+
+        int sub(int a, int b);
+    )";
+
+    IR ir = Parser::parseString(source);
+    assert(ir.getFunctions().size() == 2);
+    assert(ir.getFunctions()[0].doc_comment == "Adds two numbers.\n\n```cpp\nint r = add(2, 3);\n```");
+    assert(ir.getFunctions()[1].doc_comment.empty());
+    const ClassDecl& acc = ir.getClasses()[0];
+    assert(acc.doc_comment.find("@brief Running total") == 0);
+    assert(acc.methods[1].doc_comment == "Adds `v` to the total");
+    assert(acc.methods[2].doc_comment.empty());
+
+    // Both fence styles become text blocks, so cargo test never compiles the C++
+    RustCodeGenerator rust_gen;
+    std::string code = rust_gen.generate(ir);
+    assert(code.find("/// Adds two numbers.\n"
+                     "///\n"
+                     "/// Original C++:\n"
+                     "/// ```text\n"
+                     "/// int r = add(2, 3);\n"
+                     "/// ```\n"
+                     "pub fn add(") != std::string::npos);
+    assert(code.find("/// Running total\n"
+                     "///\n"
+                     "/// Original C++:\n"
+                     "/// ```text\n"
+                     "///   Acc acc;\n"
+                     "///   acc.push(1);\n"
+                     "/// ```\n"
+                     "pub struct acc {") != std::string::npos);
+    assert(code.find("    /// Adds `v` to the total\n    pub fn push(") != std::string::npos);
+    assert(code.find("```cpp") == std::string::npos && code.find("@code") == std::string::npos);
+    assert(code.find("not the next") == std::string::npos && code.find("math.h") == std::string::npos);
+
+    // The FFI wrappers carry the same documentation
+    hybrid_transpiler::ffi::FFIGenerator ffi;
+    std::string bindings = ffi.generate(source, "math", "rust");
+    assert(bindings.find("/// ```\npub fn add(a: i32, b: i32) -> i32 {") != std::string::npos);
+    assert(bindings.find("/// ```\n///\n/// Safe wrapper for Acc class\n") != std::string::npos);
+    assert(bindings.find("    /// Adds `v` to the total\n    pub fn push(&mut self, v: i32) {") != std::string::npos);
+
+    std::cout << "  ✓ Doc comments test passed\n";
+}
+
+void testAliasTemplates() {
+    const std::string source = R"(
+        #include <vector>
+        template<class T> using Vec = std::vector<T>;
+        template<typename Elem> using Grid = Vec<Vec<Elem>>;
+
+        Vec<int> evens(int limit);
+        double sum(const Vec<double>& values);
+        int cells(Grid<size_t> grid);
+    )";
+
+    IR ir = Parser::parseString(source);
+    assert(ir.getFunctions().size() == 3);
+    const auto& evens = ir.getFunctions()[0].return_type;
+    assert(evens->kind == TypeKind::StdVector && evens->name == "std::vector<int>");
+    assert(evens->template_args[0]->kind == TypeKind::Integer);
+    // Aliases of aliases expand all the way down
+    const auto& grid = ir.getFunctions()[2].parameters[0].type;
+    assert(grid->name == "std::vector<std::vector<size_t>>");
+
+    RustCodeGenerator rust_gen;
+    std::string code = rust_gen.generate(ir);
+    assert(code.find("pub fn evens(limit: i32) -> Vec<i32> {") != std::string::npos);
+    assert(code.find("pub fn sum(values: &Vec<f64>) -> f64 {") != std::string::npos);
+    assert(code.find("pub fn cells(grid: Vec<Vec<usize>>) -> i32 {") != std::string::npos);
+
+    std::cout << "  ✓ Alias templates test passed\n";
+}
+
 void runAllCodegenTests() {
     std::cout << "\nRunning Code Generation Tests:\n";
     testRustCodeGeneration();
     testGoCodeGeneration();
+    testGenerationStamp();
+    testMemorySink();
+    testPreprocessedInput();
+    testDocComments();
+    testAliasTemplates();
     std::cout << "All code generation tests passed!\n";
 }
 
@@ -0,0 +1,3202 @@
+#include "ffi.h"
+#include "parser.h"
+#include <algorithm>
+#include <cassert>
+#include <cstdlib>
+#include <filesystem>
+#include <fstream>
+#include <iostream>
+#include <map>
+#include <regex>
+#include <set>
+#include <sstream>
+#include <stdexcept>
+
+namespace hybrid {
+namespace test {
+
+using namespace hybrid_transpiler::ffi;
+
+/**
+ * Type-check generated Rust with rustc, tests included when `with_tests`,
+ * and lint it with clippy when clippy-driver is installed; passes without
+ * checking when no rustc is on the PATH
+ */
+static bool rustcAccepts(const std::string& code, bool with_tests = false) {
+    static const bool available = std::system("rustc --version > /dev/null 2>&1") == 0;
+    static const std::string compiler =
+        std::system("clippy-driver --version > /dev/null 2>&1") == 0 ? "clippy-driver" : "rustc";
+    if (!available) {
+        std::cout << "    (rustc not found, generated Rust not compiled)\n";
+        return true;
+    }
+    auto dir = std::filesystem::temp_directory_path() / "hybrid_transpiler_rustc";
+    std::filesystem::create_directories(dir);
+    std::ofstream((dir / "bindings.rs").string()) << code;
+    std::string command = compiler + " --edition 2021 --crate-type lib --emit=metadata -D warnings" +
+                          std::string(with_tests ? " --test" : "") + " -o \"" +
+                          (dir / "bindings.rmeta").string() + "\" \"" + (dir / "bindings.rs").string() +
+                          "\" 2> \"" + (dir / "errors.txt").string() + "\"";
+    if (std::system(command.c_str()) == 0) {
+        return true;
+    }
+    std::cerr << std::ifstream((dir / "errors.txt").string()).rdbuf();
+    return false;
+}
+
+/**
+ * Build `definitions` of `header` and the shims into lib<library>.so,
+ * then compile the bindings with `tests` appended and run them against
+ * it; passes without running when rustc or a C++ compiler is missing.
+ * The build and test output goes to `output` if given, else to stderr
+ * on failure
+ */
+static bool rustTestsPass(const std::string& library, const std::string& header, const std::string& definitions,
+                          const std::string& shims, const std::string& code, const std::string& tests,
+                          std::string* output = nullptr) {
+    static const bool available = std::system("rustc --version > /dev/null 2>&1") == 0 &&
+                                  std::system("c++ --version > /dev/null 2>&1") == 0;
+    if (!available) {
+        std::cout << "    (rustc or c++ not found, generated Rust not run)\n";
+        return true;
+    }
+    auto dir = std::filesystem::temp_directory_path() / ("hybrid_transpiler_run_" + library);
+    std::filesystem::remove_all(dir);
+    std::filesystem::create_directories(dir);
+    auto path = [&](const std::string& name) { return "\"" + (dir / name).string() + "\""; };
+    std::ofstream((dir / (library + ".h")).string())
+        << "#pragma once\n#include <compare>\n#include <cstddef>\n#include <cstdint>\n" << header;
+    std::ofstream((dir / "definitions.cpp").string()) << "#include \"" << library << ".h\"\n" << definitions;
+    std::ofstream((dir / "shims.cpp").string()) << shims;
+    std::ofstream bindings((dir / "bindings.rs").string());
+    bindings << code;
+    if (!tests.empty()) {
+        bindings << "\n#[cfg(test)]\nmod behavior {\n    use super::*;\n" << tests << "}\n";
+    }
+    bindings.close();
+
+    std::string log = path("log.txt");
+    std::string build = "c++ -std=c++20 -shared -fPIC -I" + path("") + " " + path("definitions.cpp") + " " +
+                        path("shims.cpp") + " -o " + path("lib" + library + ".so") + " > " + log + " 2>&1";
+    std::string compile = "rustc --edition 2021 --test -L " + path("") + " " + path("bindings.rs") + " -o " +
+                          path("bindings") + " >> " + log + " 2>&1";
+    std::string run = "LD_LIBRARY_PATH=" + path("") + " " + path("bindings") + " >> " + log + " 2>&1";
+    bool passed = std::system(build.c_str()) == 0 && std::system(compile.c_str()) == 0 &&
+                  std::system(run.c_str()) == 0;
+    std::stringstream text;
+    text << std::ifstream((dir / "log.txt").string()).rdbuf();
+    if (output) {
+        *output = text.str();
+    } else if (!passed) {
+        std::cerr << text.str();
+    }
+    return passed;
+}
+
+static FFIFunction makeMethod(const std::string& class_name, const std::string& name,
+                              const std::string& return_type, bool is_const) {
+    FFIFunction method{};
+    method.name = name;
+    method.class_name = class_name;
+    method.return_type = return_type;
+    method.is_method = true;
+    method.is_const = is_const;
+    method.can_use_ffi = true;
+    return method;
+}
+
+void testBorrowedSelfPointerReturn() {
+    // class Node { Node* parent() const; ... } where the root's parent is null
+    FFIClass node{};
+    node.name = "Node";
+    node.methods.push_back(makeMethod("Node", "Node", "", false));
+    node.methods.push_back(makeMethod("Node", "parent", "Node*", true));
+
+    FFIFunction create = makeMethod("Node", "create", "Node*", false);
+    create.is_static = true;
+    node.static_methods.push_back(create);
+
+    RustFFIGenerator gen;
+    std::string code = gen.generateModule({}, {node}, "tree");
+
+    // Borrowed accessor: null-checked, tied to the lifetime of self
+    assert(code.find("pub fn node_parent(this: *const c_void) -> *mut c_void;") != std::string::npos);
+    assert(code.find("pub fn parent(&self) -> Option<NodeRef<'_>>") != std::string::npos);
+    assert(code.find("if ptr.is_null() {") != std::string::npos);
+    assert(code.find("#[repr(transparent)]") != std::string::npos);
+
+    // Walking up from a view keeps the original borrow until the null root
+    assert(code.find("pub fn parent(self) -> Option<NodeRef<'a>>") != std::string::npos);
+    assert(code.find("impl std::ops::Deref for NodeRef<'_>") != std::string::npos);
+
+    // The view must never run the delete shim; static factories stay owned
+    size_t view_pos = code.find("pub struct NodeRef<'a>");
+    assert(view_pos != std::string::npos);
+    assert(code.find("node_delete", view_pos) == std::string::npos);
+    assert(code.find("pub fn create() -> Option<Self>") != std::string::npos);
+
+    // Walking a real tree from its leaf stops at the null parent of the root
+    const std::string header = R"(
+        class Node {
+        public:
+            Node();
+            ~Node();
+            Node* parent() const;
+            Node* child() const;
+            int32_t depth() const;
+        private:
+            Node(Node* parent, int32_t levels);
+            Node* parent_;
+            Node* child_;
+        };
+    )";
+    const std::string definitions = R"(
+        Node::Node() : Node(nullptr, 2) {}
+        Node::Node(Node* parent, int32_t levels)
+            : parent_(parent), child_(levels > 0 ? new Node(this, levels - 1) : nullptr) {}
+        Node::~Node() { delete child_; }
+        Node* Node::parent() const { return parent_; }
+        Node* Node::child() const { return child_; }
+        int32_t Node::depth() const { return parent_ ? parent_->depth() + 1 : 0; }
+    )";
+    const std::string shims = R"(
+        #include "tree.h"
+        extern "C" {
+        void* node_new() { return new Node(); }
+        void* node_parent(const void* self) { return static_cast<const Node*>(self)->parent(); }
+        void* node_child(const void* self) { return static_cast<const Node*>(self)->child(); }
+        int32_t node_depth(const void* self) { return static_cast<const Node*>(self)->depth(); }
+        void node_delete(void* self) { delete static_cast<Node*>(self); }
+        }
+    )";
+    const std::string traversal = R"(
+    #[test]
+    fn walks_up_to_the_root() {
+        let root = Node::new();
+        let leaf = root.child().and_then(NodeRef::child).unwrap();
+        assert!(leaf.child().is_none());
+        let mut depths = vec![leaf.depth()];
+        let mut node = leaf;
+        while let Some(parent) = node.parent() {
+            depths.push(parent.depth());
+            node = parent;
+        }
+        assert_eq!(depths, [2, 1, 0]);
+        assert!(root.parent().is_none());
+    }
+    )";
+    FFIClass tree{};
+    tree.name = "Node";
+    tree.methods = {makeMethod("Node", "Node", "", false), makeMethod("Node", "parent", "Node*", true),
+                    makeMethod("Node", "child", "Node*", true), makeMethod("Node", "depth", "int32_t", true)};
+    assert(rustTestsPass("tree", header, definitions, shims, RustFFIGenerator{}.generateModule({}, {tree}, "tree"),
+                         traversal));
+
+    std::cout << "  ✓ Borrowed self-pointer return test passed\n";
+}
+
+void testEmitSmokeTests() {
+    const std::string source = R"(
+        struct Point {
+            float x;
+            float y;
+        };
+
+        class Calculator {
+        public:
+            Calculator(int32_t initial);
+            int32_t getValue() const;
+            void add(int32_t v);
+        };
+    )";
+
+    FFIOptions options;
+    options.emit_tests = true;
+    FFIGenerator gen(options);
+    std::string code = gen.generate(source, "calc", "rust");
+
+    size_t mod_pos = code.find("#[cfg(test)]\nmod tests {\n    use super::*;\n");
+    assert(mod_pos != std::string::npos);
+    std::string tests = code.substr(mod_pos);
+
+    // Layout assertions for plain structs
+    assert(tests.find("fn point_layout()") != std::string::npos);
+    assert(tests.find("assert_eq!(std::mem::size_of::<Point>(), 8);") != std::string::npos);
+    assert(tests.find("assert_eq!(std::mem::align_of::<Point>(), 4);") != std::string::npos);
+
+    // Opaque types are constructed and every getter is called
+    assert(tests.find("let obj = Calculator::new(Default::default());") != std::string::npos);
+    assert(tests.find("let _ = obj.get_value();") != std::string::npos);
+    assert(tests.find("obj.add(") == std::string::npos);
+
+    // The module closes the file, and the bindings compile with it
+    assert(tests.substr(tests.size() - 2) == "}\n");
+    assert(rustcAccepts(code, true));
+
+    // Without the option no test module is emitted
+    FFIGenerator plain;
+    assert(plain.generate(source, "calc", "rust").find("#[cfg(test)]") == std::string::npos);
+
+    std::cout << "  ✓ Emit smoke tests test passed\n";
+}
+
+void testVolatileRegisterAccess() {
+    const std::string source = R"(
+        struct UartRegs {
+            volatile uint32_t DR;
+            volatile uint32_t SR;
+            uint32_t reserved;
+        };
+
+        void write_reg(volatile uint32_t* reg, uint32_t value);
+    )";
+
+    FFIGenerator gen;
+    std::string code = gen.generate(source, "uart", "rust");
+
+    // Register pointers stay typed instead of collapsing to c_void
+    assert(code.find("pub fn write_reg(reg: *mut u32, value: u32);") != std::string::npos);
+
+    // Rust cannot check what C++ does through the pointer, the caller vouches for it
+    assert(code.find("/// # Safety\n"
+                     "///\n"
+                     "/// `reg` must be valid for the reads and writes `write_reg` makes through it.\n"
+                     "pub unsafe fn write_reg(reg: *mut u32, value: u32) {\n") != std::string::npos);
+
+    // Volatile fields are private and accessed through volatile operations
+    assert(code.find("    DR: u32,") != std::string::npos);
+    assert(code.find("    pub reserved: u32,") != std::string::npos);
+    assert(code.find("#[derive(Debug, Clone, Copy)]\npub struct UartRegs") == std::string::npos);
+    assert(code.find("pub fn dr(&self) -> u32 {\n"
+                     "        unsafe { std::ptr::read_volatile(std::ptr::addr_of!(self.DR)) }") != std::string::npos);
+    assert(code.find("pub fn set_sr(&mut self, value: u32) {\n"
+                     "        unsafe { std::ptr::write_volatile(std::ptr::addr_of_mut!(self.SR), value) }") != std::string::npos);
+    assert(code.find("set_reserved") == std::string::npos);
+
+    // The C side keeps the qualifier
+    FFIAnalyzer analyzer;
+    assert(analyzer.toCType("volatile uint32_t*") == "volatile uint32_t*");
+
+    std::cout << "  ✓ Volatile register access test passed\n";
+}
+
+void testTableOfContents() {
+    const std::string source = R"(
+        struct Point {
+            float x;
+            float y;
+        };
+
+        class Node {
+        public:
+            Node();
+            Node* parent() const;
+        };
+
+        enum class Mode { Fast, Safe };
+        enum { FLAG_A = 1 };
+
+        int32_t add(int32_t a, int32_t b);
+        std::string greet(const std::string& name);
+        Mode current_mode();
+        void copy_into(int32_t* out);
+    )";
+
+    FFIOptions options;
+    options.emit_relink_macro = true;
+    FFIGenerator gen(options);
+    std::string code = gen.generate(source, "items", "rust");
+
+    // Collect the TOC entries and every top-level public item actually emitted
+    std::set<std::string> listed;
+    std::set<std::string> emitted;
+    std::istringstream lines(code);
+    std::string line;
+    while (std::getline(lines, line)) {
+        if (line.find("//! - `") == 0) {
+            listed.insert(line.substr(7, line.find('`', 7) - 7));
+        }
+        for (const std::string prefix : {"pub fn ", "pub unsafe fn ", "pub struct ", "pub enum ", "pub const ",
+                                         "pub trait ", "macro_rules! "}) {
+            if (line.find(prefix) == 0) {
+                emitted.insert(line.substr(prefix.size(), line.find_first_of("(<{: ", prefix.size()) - prefix.size()));
+            }
+        }
+    }
+    assert(listed == emitted);
+    assert(listed == std::set<std::string>({"add", "current_mode", "copy_into", "Point", "Node", "NodeRef", "Mode",
+                                            "FLAG_A", "InvalidEnum", "relink_items"}));
+
+    // Grouped by kind, with the C++ declaration each item comes from
+    size_t functions_pos = code.find("//! Functions:\n//! - `add` from `int32_t add(int32_t, int32_t)`");
+    size_t structs_pos = code.find("//! Structs:\n//! - `InvalidEnum` from `integers outside a named enum`\n"
+                                   "//! - `Point` from `struct Point`");
+    size_t opaque_pos = code.find("//! Opaque wrappers:\n//! - `Node` from `class Node`");
+    assert(functions_pos != std::string::npos);
+    assert(structs_pos > functions_pos && structs_pos != std::string::npos);
+    assert(opaque_pos > structs_pos && opaque_pos != std::string::npos);
+    size_t enums_pos = code.find("//! Enums:\n//! - `Mode` from `enum Mode`");
+    size_t macros_pos = code.find("//! Macros:\n//! - `relink_items` from `extern blocks of items`");
+    assert(enums_pos > structs_pos && enums_pos < opaque_pos);
+    assert(macros_pos > opaque_pos && macros_pos != std::string::npos);
+    assert(code.find("//! Generated items:") < code.find("mod ffi {"));
+
+    options.emit_toc = false;
+    FFIGenerator plain(options);
+    assert(plain.generate(source, "items", "rust").find("//!") == std::string::npos);
+
+    std::cout << "  ✓ Table of contents test passed\n";
+}
+
+void testAnonymousEnumConstants() {
+    const std::string source = R"(
+        enum { FLAG_A = 1, FLAG_B = 2, FLAG_C = 0x10u, FLAG_D };
+        enum : uint8_t { MODE_OFF, MODE_ON };
+        enum class Color { Red, Green };
+
+        void set_flags(int32_t flags);
+    )";
+
+    hybrid::IR ir = hybrid::Parser::parseString(source);
+    assert(ir.getEnums().size() == 3);
+    assert(ir.getEnums()[0].name.empty());
+    assert(ir.getEnums()[2].name == "Color" && ir.getEnums()[2].is_scoped);
+    assert(ir.getClasses().empty());
+
+    FFIGenerator gen;
+    std::string code = gen.generate(source, "flags", "rust");
+
+    // Values and the underlying type are preserved, implicit ones count up
+    assert(code.find("pub const FLAG_A: i32 = 1;\n") != std::string::npos);
+    assert(code.find("pub const FLAG_B: i32 = 2;\n") != std::string::npos);
+    assert(code.find("pub const FLAG_C: i32 = 0x10;\n") != std::string::npos);
+    assert(code.find("pub const FLAG_D: i32 = FLAG_C + 1;\n") != std::string::npos);
+    assert(code.find("pub const MODE_OFF: u8 = 0;\n") != std::string::npos);
+    assert(code.find("pub const MODE_ON: u8 = MODE_OFF + 1;\n") != std::string::npos);
+    assert(code.find("//! - `FLAG_C` from `enum { FLAG_C = 0x10u }`") != std::string::npos);
+
+    // No named type is invented for anonymous enums, and named ones are
+    // not mistaken for classes
+    assert(code.find("pub enum") == code.rfind("pub enum"));
+    assert(code.find("pub enum Color {") != std::string::npos);
+    assert(code.find("struct Color") == std::string::npos);
+
+    std::cout << "  ✓ Anonymous enum constants test passed\n";
+}
+
+void testAbiSelfTest() {
+    const std::string source = R"(
+        int32_t add(int32_t a, int32_t b);
+        float scale(float x, int8_t k, bool neg);
+        void increment_array(int32_t* array, size_t length);
+    )";
+
+    FFIOptions options;
+    options.emit_self_test = true;
+    FFIGenerator gen(options);
+    std::string code = gen.generate(source, "math", "rust");
+
+    size_t mod_pos = code.find("#[cfg(test)]\nmod abi_self_test {");
+    assert(mod_pos != std::string::npos);
+    std::string harness = code.substr(mod_pos);
+
+    // Scalar functions are called with sentinels and compared to the C++ checksum
+    assert(harness.find("fn add_abi_checksum(a: i32, b: i32) -> u64;") != std::string::npos);
+    assert(harness.find("let a: i32 = -0x123456;") != std::string::npos);
+    assert(harness.find("let ret = unsafe { ffi::add(a, b) };") != std::string::npos);
+    assert(harness.find("let expected = checksum(&[x.to_bits() as u64, k as u64, neg as u64, ret.to_bits() as u64]);")
+           != std::string::npos);
+    assert(harness.find("assert_eq!(unsafe { scale_abi_checksum(x, k, neg) }, expected);") != std::string::npos);
+
+    // The rest is listed with a reason
+    assert(code.find("// - increment_array: parameter `array` is not a scalar") != std::string::npos);
+    assert(harness.find("increment_array") == std::string::npos);
+    assert(code.find("pub unsafe fn increment_array(array: *mut i32, length: usize) {") != std::string::npos);
+    assert(rustcAccepts(code, true));
+
+    // The C++ twins are written with the shims, and the harness passes against them
+    std::string shims = gen.generateCWrapper(source, "math").second;
+    assert(shims.find("uint64_t add_abi_checksum(int32_t a, int32_t b) {\n"
+                      "    return abi_checksum({abi_bits(a), abi_bits(b), abi_bits(::add(a, b))});") != std::string::npos);
+    assert(shims.find("increment_array_abi_checksum") == std::string::npos);
+    const std::string definitions = R"(
+        int32_t add(int32_t a, int32_t b) { return a + b; }
+        float scale(float x, int8_t k, bool neg) { return neg ? -x * k : x * k; }
+        void increment_array(int32_t* array, size_t length) {
+            for (size_t i = 0; i < length; ++i) ++array[i];
+        }
+    )";
+    assert(rustTestsPass("math", source, definitions, shims, code, ""));
+    assert(FFIGenerator().generateCWrapper(source, "math").second.find("abi_checksum") == std::string::npos);
+
+    FFIGenerator plain;
+    assert(plain.generate(source, "math", "rust").find("abi_self_test") == std::string::npos);
+
+    std::cout << "  ✓ ABI self-test harness test passed\n";
+}
+
+void testIntrusiveRefCounting() {
+    const std::string source = R"(
+        class Widget {
+        public:
+            Widget();
+            void AddRef();
+            void Release();
+            int32_t getId() const;
+        };
+    )";
+
+    FFIOptions options;
+    applyFFIConfig(R"({ "refcounted": { "Widget": { "incref": "AddRef", "decref": "Release" } } })", options);
+    assert(options.refcounted.at("Widget").incref == "AddRef");
+    assert(options.refcounted.at("Widget").decref == "Release");
+
+    FFIGenerator gen(options);
+    std::string code = gen.generate(source, "widgets", "rust");
+
+    // Clone takes a reference, Drop gives it back; nothing calls delete
+    assert(code.find("impl Clone for Widget {\n"
+                     "    fn clone(&self) -> Self {\n"
+                     "        unsafe {\n"
+                     "            ffi::widget_add_ref(self.ptr);\n"
+                     "        }\n"
+                     "        Widget { ptr: self.ptr }\n") != std::string::npos);
+    assert(code.find("impl Drop for Widget {\n"
+                     "    fn drop(&mut self) {\n"
+                     "        unsafe {\n"
+                     "            ffi::widget_release(self.ptr);\n") != std::string::npos);
+    assert(code.find("widget_delete") == std::string::npos);
+
+    // The count is only managed through Clone/Drop
+    assert(code.find("pub fn add_ref(") == std::string::npos);
+    assert(code.find("pub fn release(") == std::string::npos);
+    assert(code.find("pub fn get_id(&self) -> i32") != std::string::npos);
+
+    // Methods missing from the header still get shims
+    FFIOptions hidden;
+    hidden.refcounted["Widget"] = {"Retain", "Unretain"};
+    std::string hidden_code = FFIGenerator(hidden).generate(source, "widgets", "rust");
+    assert(hidden_code.find("pub fn widget_retain(this: *mut c_void);") != std::string::npos);
+    assert(hidden_code.find("ffi::widget_unretain(self.ptr);") != std::string::npos);
+
+    // Against a real count, clones share one object that the last drop frees
+    const std::string header = R"(
+        class Widget {
+        public:
+            Widget();
+            void AddRef();
+            void Release();
+            int32_t refCount() const;
+            static int32_t alive();
+        private:
+            int32_t refs_;
+            static int32_t alive_;
+        };
+    )";
+    const std::string definitions = R"(
+        int32_t Widget::alive_ = 0;
+        Widget::Widget() : refs_(1) { ++alive_; }
+        void Widget::AddRef() { ++refs_; }
+        void Widget::Release() { if (--refs_ == 0) { --alive_; delete this; } }
+        int32_t Widget::refCount() const { return refs_; }
+        int32_t Widget::alive() { return alive_; }
+    )";
+    const std::string shims = R"(
+        #include "widgets.h"
+        extern "C" {
+        void* widget_new() { return new Widget(); }
+        void widget_add_ref(void* self) { static_cast<Widget*>(self)->AddRef(); }
+        void widget_release(void* self) { static_cast<Widget*>(self)->Release(); }
+        int32_t widget_ref_count(const void* self) { return static_cast<const Widget*>(self)->refCount(); }
+        int32_t widget_alive() { return Widget::alive(); }
+        }
+    )";
+    const std::string counting = R"(
+    #[test]
+    fn clones_share_one_counted_object() {
+        let first = Widget::new();
+        let second = first.clone();
+        assert_eq!(first.ref_count(), 2);
+        assert_eq!(Widget::alive(), 1);
+        drop(first);
+        assert_eq!(second.ref_count(), 1);
+        assert_eq!(Widget::alive(), 1);
+        drop(second);
+        assert_eq!(Widget::alive(), 0);
+    }
+    )";
+    std::string counted = FFIGenerator(options).generate(header, "widgets", "rust");
+    assert(rustTestsPass("widgets", header, definitions, shims, counted, counting));
+
+    // Malformed configurations are rejected
+    bool rejected = false;
+    try {
+        applyFFIConfig(R"({ "refcounted": { "Widget": { "incref": "AddRef" } } })", options);
+    } catch (const std::runtime_error&) {
+        rejected = true;
+    }
+    assert(rejected);
+
+    std::cout << "  ✓ Intrusive refcounting test passed\n";
+}
+
+void testSingletonAccessor() {
+    const std::string source = R"(
+        class Registry {
+        public:
+            static Registry& instance();
+            int32_t count() const;
+        };
+    )";
+
+    FFIOptions options;
+    options.emit_tests = true;
+    FFIGenerator gen(options);
+    std::string code = gen.generate(source, "registry", "rust");
+
+    // A static reference is cached per thread and handed out as a view,
+    // so nothing is leaked and nothing goes through Drop
+    assert(code.find("pub fn instance() -> RegistryRef<'static> {") != std::string::npos);
+    assert(code.find("static INSTANCE: *mut c_void = unsafe { ffi::registry_instance() };") != std::string::npos);
+    assert(code.find("RegistryRef::from_raw(INSTANCE.with(|ptr| *ptr))") != std::string::npos);
+    assert(code.find("Box::leak") == std::string::npos);
+    assert(code.find("pub fn registry_instance() -> *mut c_void;") != std::string::npos);
+    // Nothing is claimed about other threads unless the config says so
+    assert(code.find("unsafe impl Sync") == std::string::npos);
+    assert(rustcAccepts(code, true));
+
+    // Accessing it twice yields the same object
+    assert(code.find("fn registry_instance_is_shared() {\n"
+                     "        let first = Registry::instance();\n"
+                     "        let second = Registry::instance();\n"
+                     "        assert_eq!(first.ptr, second.ptr);\n") != std::string::npos);
+
+    // Classes configured thread-safe share one handle between threads
+    applyFFIConfig(R"({ "thread_safe": ["Registry"] })", options);
+    code = FFIGenerator(options).generate(source, "registry", "rust");
+    assert(code.find("pub fn instance() -> &'static Registry {") != std::string::npos);
+    assert(code.find("assert!(std::ptr::eq(first, second));") != std::string::npos);
+    assert(code.find("unsafe impl Send for Instance {}") != std::string::npos);
+    assert(code.find("unsafe impl Sync for Instance {}") != std::string::npos);
+    assert(code.find("static INSTANCE: std::sync::OnceLock<Instance> = std::sync::OnceLock::new();") != std::string::npos);
+    assert(code.find("&INSTANCE.get_or_init(|| Instance(Registry { ptr: unsafe { ffi::registry_instance() } })).0")
+           != std::string::npos);
+    assert(rustcAccepts(code, true));
+
+    std::cout << "  ✓ Singleton accessor test passed\n";
+}
+
+void testDefaultArguments() {
+    const std::string source = R"(
+        class Counter {
+        public:
+            Counter();
+            void add(int32_t value = 1);
+            void log(int32_t level = DEFAULT_LEVEL);
+            void label(const char* text = "total");
+            void repeat(int32_t base, int32_t times = compute());
+        };
+
+        float scale(float x, float factor = 2.0f);
+    )";
+
+    hybrid::IR ir = hybrid::Parser::parseString(source);
+    const auto& add = ir.getClasses()[0].methods[1];
+    assert(add.parameters[0].has_default && add.parameters[0].default_value == "1");
+
+    // Overloads: the short form passes the default, `_with` takes it
+    FFIGenerator gen;
+    std::string code = gen.generate(source, "counter", "rust");
+    assert(code.find("    pub fn add(&mut self) {\n"
+                     "        unsafe { ffi::counter_add(self.ptr, 1) }\n") != std::string::npos);
+    assert(code.find("    pub fn add_with(&mut self, value: i32) {\n"
+                     "        unsafe { ffi::counter_add(self.ptr, value) }\n") != std::string::npos);
+    assert(code.find("pub fn scale(x: f32) -> f32 {\n    unsafe { ffi::scale(x, 2.0) }") != std::string::npos);
+    assert(code.find("pub fn scale_with(x: f32, factor: f32) -> f32 {") != std::string::npos);
+    assert(code.find("pub fn counter_add(this: *mut c_void, value: i32);") != std::string::npos);
+
+    // String literals are defaults too, passed as C strings
+    assert(code.find("    pub fn label(&mut self) {\n"
+                     "        unsafe { ffi::counter_label(self.ptr, c\"total\".as_ptr().cast()) }\n") !=
+           std::string::npos);
+    assert(code.find("    pub fn label_with(&mut self, text: &str) {") != std::string::npos);
+
+    // Non-literal defaults, calls included, keep the parameter and are
+    // reported as skips
+    assert(ir.getClasses()[0].methods[4].parameters[1].default_value == "compute()");
+    assert(code.find("pub fn log(&mut self, level: i32) {") != std::string::npos);
+    assert(code.find("pub fn repeat(&mut self, base: i32, times: i32) {") != std::string::npos);
+    assert(gen.diagnostics().size() == 2);
+    assert(gen.diagnostics()[0].symbol == "Counter::log");
+    assert(gen.diagnostics()[0].message.find("skipped: default argument `level = DEFAULT_LEVEL`") == 0);
+    assert(gen.diagnostics()[1].symbol == "Counter::repeat");
+    assert(gen.diagnostics()[1].message.find("`times = compute()`") != std::string::npos);
+
+    // so strict fails until they are acknowledged
+    FFIOptions strict;
+    strict.strict = true;
+    bool failed = false;
+    try {
+        FFIGenerator(strict).generate(source, "counter", "rust");
+    } catch (const std::runtime_error& e) {
+        failed = std::string(e.what()).find("Counter::repeat") != std::string::npos;
+    }
+    assert(failed);
+    strict.allowed_skips = {"Counter::log", "Counter::repeat"};
+    FFIGenerator(strict).generate(source, "counter", "rust");
+
+    // Baked-in mode: one wrapper with an Option falling back to the default
+    FFIOptions options;
+    applyFFIConfig(R"({ "default_arguments": "option" })", options);
+    std::string option_code = FFIGenerator(options).generate(source, "counter", "rust");
+    assert(option_code.find("pub fn add(&mut self, value: Option<i32>) {\n"
+                            "        unsafe { ffi::counter_add(self.ptr, value.unwrap_or(1)) }\n") != std::string::npos);
+    assert(option_code.find("add_with") == std::string::npos);
+    assert(option_code.find("    pub fn label(&mut self, text: Option<&str>) {\n"
+                            "        let text = text.unwrap_or(\"total\");\n") != std::string::npos);
+
+    std::cout << "  ✓ Default arguments test passed\n";
+}
+
+void testSpanToSlice() {
+    const std::string source = R"(
+        int32_t sum(std::span<const int32_t> values);
+        void scale(std::span<int32_t> values, int32_t factor);
+        int32_t dot3(std::span<const int32_t, 3> a, std::span<const int32_t, 3> b);
+    )";
+
+    FFIGenerator gen;
+    std::string code = gen.generate(source, "spans", "rust");
+
+    // The shim receives pointer + length and rebuilds the span
+    assert(code.find("pub fn sum(values: *const i32, values_len: usize) -> i32;") != std::string::npos);
+    assert(code.find("pub fn scale(values: *mut i32, values_len: usize, factor: i32);") != std::string::npos);
+
+    // Rust callers pass slices, fixed extents become arrays
+    assert(code.find("pub fn sum(values: &[i32]) -> i32 {\n"
+                     "    unsafe { ffi::sum(values.as_ptr(), values.len()) }") != std::string::npos);
+    assert(code.find("pub fn scale(values: &mut [i32], factor: i32) {\n"
+                     "    unsafe { ffi::scale(values.as_mut_ptr(), values.len(), factor) }") != std::string::npos);
+    assert(code.find("pub fn dot3(a: &[i32; 3], b: &[i32; 3]) -> i32 {") != std::string::npos);
+    assert(code.find("// Skipped") == std::string::npos);
+
+    std::cout << "  ✓ std::span to slice test passed\n";
+}
+
+void testShimSourceMatchesExterns() {
+    const std::string source = R"(
+        class Calculator {
+        public:
+            Calculator();
+            Calculator(int32_t initial);
+            int32_t getValue() const;
+            void add(int32_t value);
+            void addAll(std::span<const int32_t> values);
+            static double ratio();
+        };
+
+        int32_t sum(std::span<const int32_t> values);
+    )";
+
+    FFIGenerator gen;
+    std::string rust = gen.generate(source, "calc", "rust");
+    std::string shims = gen.generateCWrapper(source, "calc").second;
+
+    assert(shims.find("#include \"calc.h\"") != std::string::npos);
+    assert(shims.find("void calculator_add(void* self, int32_t value) {\n"
+                      "    static_cast<Calculator*>(self)->add(value);\n}") != std::string::npos);
+    assert(shims.find("return ::sum(std::span<const int32_t>(values, values_len));") != std::string::npos);
+
+    // Every extern declared on the Rust side has a shim with the same C signature
+    const std::map<std::string, std::string> c_to_rust = {
+        {"void*", "*mut c_void"}, {"const void*", "*const c_void"}, {"int32_t", "i32"},
+        {"const int32_t*", "*const i32"}, {"size_t", "usize"}, {"double", "f64"},
+    };
+    std::map<std::string, std::string> shim_signatures;
+    std::regex shim_pattern(R"(^(.+?) (\w+)\((.*)\) \{$)");
+    std::istringstream shim_lines(shims);
+    for (std::string line; std::getline(shim_lines, line);) {
+        std::smatch match;
+        if (!std::regex_match(line, match, shim_pattern)) {
+            continue;
+        }
+        std::string params;
+        std::stringstream list(match[3].str());
+        for (std::string param; std::getline(list, param, ',');) {
+            std::string type = param.substr(param.find_first_not_of(' '));
+            type = type.substr(0, type.rfind(' '));
+            assert(c_to_rust.count(type));
+            params += (params.empty() ? "" : ", ") + c_to_rust.at(type);
+        }
+        std::string ret = match[1].str() == "void" ? "" : " -> " + c_to_rust.at(match[1].str());
+        shim_signatures[match[2].str()] = "(" + params + ")" + ret;
+    }
+
+    size_t externs = 0;
+    std::regex extern_pattern(R"(^        pub fn (\w+)\((.*)\)(.*);$)");
+    std::regex link_name(R"re(^        #\[link_name = "(\w+)"\]$)re");
+    std::regex param_name(R"(\w+: )");
+    std::istringstream rust_lines(rust);
+    std::string symbol;  // From a preceding #[link_name], the Rust name otherwise
+    for (std::string line; std::getline(rust_lines, line);) {
+        std::smatch match;
+        if (std::regex_match(line, match, link_name)) {
+            symbol = match[1].str();
+            continue;
+        }
+        if (!std::regex_match(line, match, extern_pattern)) {
+            continue;
+        }
+        externs++;
+        symbol = symbol.empty() ? match[1].str() : symbol;
+        std::string params = std::regex_replace(match[2].str(), param_name, "");
+        assert(shim_signatures.count(symbol));
+        assert(shim_signatures.at(symbol) == "(" + params + ")" + match[3].str());
+        symbol.clear();
+    }
+    assert(externs == 8);
+    assert(shim_signatures.size() == externs);
+
+    // A free function's shim must not redeclare it with the C return type
+    const std::string changed = "int& counter();\n";
+    std::string counter_shims = FFIGenerator{}.generateCWrapper(changed, "calc").second;
+    assert(counter_shims.find("int* counter_shim() {\n"
+                              "    return &::counter();\n") != std::string::npos);
+    assert(FFIGenerator{}.generate(changed, "calc", "rust").find(
+               "        #[link_name = \"counter_shim\"]\n"
+               "        pub fn counter() -> *mut i32;\n") != std::string::npos);
+
+    std::cout << "  ✓ Shim source matches externs test passed\n";
+}
+
+void testConstMismatchDetection() {
+    const std::string source = R"(
+        class Calculator {
+        public:
+            Calculator();
+            int32_t getValue() const;
+            void add(int32_t value);
+        };
+    )";
+
+    hybrid::IR ir = hybrid::Parser::parseString(source);
+    FFIAnalyzer analyzer;
+    std::vector<FFIClass> classes = {analyzer.analyzeClass(ir.getClasses()[0])};
+
+    RustFFIGenerator gen;
+    std::string code = gen.generateModule({}, classes, "calc");
+    assert(gen.diagnostics().empty());
+    assert(gen.checkConstness(code, classes).empty());
+
+    // A hand edit lets `&self` reach a mutating method, another makes a const shim take *mut
+    std::string edited = code;
+    const std::string add_sig = "pub fn add(&mut self, value: i32)";
+    const std::string get_extern = "pub fn calculator_get_value(this: *const c_void)";
+    assert(edited.find(add_sig) != std::string::npos && edited.find(get_extern) != std::string::npos);
+    edited.replace(edited.find(add_sig), add_sig.size(), "pub fn add(&self, value: i32)");
+    edited.replace(edited.find(get_extern), get_extern.size(), "pub fn calculator_get_value(this: *mut c_void)");
+
+    std::vector<FFIDiagnostic> mismatches = gen.checkConstness(edited, classes);
+    assert(mismatches.size() == 2);
+    assert(mismatches[0].symbol == "Calculator::getValue");
+    assert(mismatches[0].message ==
+           "extern `calculator_get_value` declares `this: *mut c_void`, but the C++ method is const");
+    assert(mismatches[1].symbol == "Calculator::add");
+    assert(mismatches[1].message == "`&self` wrapper calls `calculator_add`, but the C++ method is not const");
+
+    std::cout << "  ✓ Const mismatch detection test passed\n";
+}
+
+void testBindgenConfig() {
+    FFIOptions options;
+    applyFFIConfig(R"({ "include": ["calc_.*", "Calculator"], "exclude": [".*_internal"] })", options);
+    assert(options.include_patterns.size() == 2 && options.exclude_patterns.size() == 1);
+
+    // The same patterns decide what gets bound here...
+    assert(isSelected("calc_add", options));
+    assert(isSelected("Calculator", options));
+    assert(!isSelected("calc_reset_internal", options));
+    assert(!isSelected("helper", options));
+
+    std::string code = FFIGenerator(options).generate(R"(
+        int32_t calc_add(int32_t a, int32_t b);
+        void calc_reset_internal();
+        void helper();
+    )", "calc", "rust");
+    assert(code.find("pub fn calc_add(") != std::string::npos);
+    assert(code.find("calc_reset_internal") == std::string::npos);
+    assert(code.find("helper") == std::string::npos);
+
+    // ...and what bindgen is told to cover
+    std::string config = generateBindgenConfig(options, "calc");
+    assert(config.find("pub fn configure(builder: bindgen::Builder) -> bindgen::Builder {\n"
+                       "    builder\n"
+                       "        .allowlist_function(\"calc_.*\")\n"
+                       "        .allowlist_type(\"calc_.*\")\n"
+                       "        .allowlist_var(\"calc_.*\")\n"
+                       "        .allowlist_function(\"Calculator\")\n") != std::string::npos);
+    assert(config.find("        .blocklist_item(\".*_internal\")\n}\n") != std::string::npos);
+
+    // Patterns are Rust string literals, backslashes included
+    FFIOptions escaped;
+    applyFFIConfig(R"({ "exclude": ["v\\d+"] })", escaped);
+    assert(generateBindgenConfig(escaped, "calc").find(R"(.blocklist_item("v\\d+"))") != std::string::npos);
+
+    bool rejected = false;
+    try {
+        applyFFIConfig(R"({ "include": ["calc_("] })", options);
+    } catch (const std::runtime_error&) {
+        rejected = true;
+    }
+    assert(rejected);
+
+    std::cout << "  ✓ bindgen config test passed\n";
+}
+
+void testFactoryOnlyClass() {
+    const std::string source = R"(
+        class Connection {
+        public:
+            static Connection* open(int32_t port);
+            static Connection* openDefault();
+            int32_t port() const;
+            Connection(const Connection&) = delete;
+        private:
+            Connection(int32_t port);
+        };
+    )";
+
+    hybrid::IR ir = hybrid::Parser::parseString(source);
+    const auto& methods = ir.getClasses()[0].methods;
+    bool saw_deleted = false;
+    bool saw_private = false;
+    for (const auto& method : methods) {
+        saw_deleted = saw_deleted || (method.is_constructor && method.is_deleted);
+        saw_private = saw_private || (method.is_constructor && method.access == hybrid::Function::Access::Private);
+    }
+    assert(saw_deleted && saw_private);
+
+    FFIOptions options;
+    options.emit_tests = true;
+    FFIGenerator gen(options);
+    std::string code = gen.generate(source, "conn", "rust");
+
+    // Neither the private nor the deleted constructor gets a shim or a `new`
+    assert(code.find("connection_new") == std::string::npos);
+    assert(code.find("pub fn new") == std::string::npos);
+    assert(code.find("/// The C++ constructors are not public; create instances through the static factories.\n"
+                     "pub struct Connection {") != std::string::npos);
+    assert(code.find("pub fn open(port: i32) -> Option<Self> {") != std::string::npos);
+    assert(code.find("pub fn open_default() -> Option<Self> {") != std::string::npos);
+
+    // The smoke test goes through the argument-free factory
+    assert(code.find("let _obj = Connection::open_default().expect(\"factory returned null\");") != std::string::npos);
+
+    std::string shims = gen.generateCWrapper(source, "conn").second;
+    assert(shims.find("new Connection") == std::string::npos);
+    assert(shims.find("return Connection::open(port);") != std::string::npos);
+
+    std::cout << "  ✓ Factory-only class test passed\n";
+}
+
+void testComparisonOperators() {
+    const std::string source = R"(
+        class Version {
+        public:
+            Version(int32_t major);
+            std::strong_ordering operator<=>(const Version& other) const;
+        };
+
+        class Legacy {
+        public:
+            Legacy(int32_t key);
+            bool operator<(const Legacy& other) const;
+        };
+    )";
+
+    FFIOptions options;
+    options.emit_tests = true;
+    FFIGenerator gen(options);
+    std::string code = gen.generate(source, "ord", "rust");
+
+    // operator<=> maps to Ord through an integer-returning shim
+    assert(code.find("pub fn version_cmp(this: *const c_void, other: *mut c_void) -> i32;") != std::string::npos);
+    assert(code.find("impl Ord for Version {\n"
+                     "    fn cmp(&self, other: &Self) -> std::cmp::Ordering {\n"
+                     "        unsafe { ffi::version_cmp(self.ptr, other.ptr) }.cmp(&0)\n") != std::string::npos);
+    assert(code.find("impl PartialOrd for Version {") != std::string::npos);
+    assert(code.find("impl Eq for Version {}") != std::string::npos);
+
+    // operator< alone is enough to synthesize a total order
+    assert(code.find("pub fn legacy_lt(this: *const c_void, other: *mut c_void) -> bool;") != std::string::npos);
+    assert(code.find("        } else if unsafe { ffi::legacy_lt(other.ptr, self.ptr) } {\n"
+                     "            std::cmp::Ordering::Greater\n") != std::string::npos);
+
+    // Operators are not methods of the wrapper
+    assert(code.find("pub fn operator") == std::string::npos);
+
+    // The emitted tests sort a Vec of wrappers
+    assert(code.find("    fn version_sorts() {\n"
+                     "        let mut items = vec![Version::new(Default::default()), ") != std::string::npos);
+    assert(code.find("        items.sort();\n"
+                     "        assert!(items.windows(2).all(|pair| pair[0] <= pair[1]));\n") != std::string::npos);
+    assert(code.find("    fn legacy_sorts() {") != std::string::npos);
+
+    std::string shims = gen.generateCWrapper(source, "ord").second;
+    assert(shims.find("int32_t version_cmp(const void* self, const void* other) {\n"
+                      "    auto order = static_cast<const Version*>(self)->operator<=>(*static_cast<const Version*>(other));\n"
+                      "    return order < 0 ? -1 : order > 0 ? 1 : order == 0 ? 0 : 2;\n") != std::string::npos);
+
+    // Sorting through the shims orders by the C++ comparison
+    const std::string header = R"(
+        class Version {
+        public:
+            Version(int32_t major);
+            int32_t major() const;
+            std::strong_ordering operator<=>(const Version& other) const;
+        private:
+            int32_t major_;
+        };
+
+        class Legacy {
+        public:
+            Legacy(int32_t key);
+            int32_t key() const;
+            bool operator<(const Legacy& other) const;
+        private:
+            int32_t key_;
+        };
+    )";
+    const std::string definitions = R"(
+        Version::Version(int32_t major) : major_(major) {}
+        int32_t Version::major() const { return major_; }
+        std::strong_ordering Version::operator<=>(const Version& other) const { return major_ <=> other.major_; }
+        Legacy::Legacy(int32_t key) : key_(key) {}
+        int32_t Legacy::key() const { return key_; }
+        bool Legacy::operator<(const Legacy& other) const { return key_ < other.key_; }
+    )";
+    const std::string sorting = R"(
+    #[test]
+    fn versions_sort_by_spaceship() {
+        let mut items: Vec<Version> = [3, 1, 2, 1].into_iter().map(Version::new).collect();
+        items.sort();
+        assert_eq!(items.iter().map(Version::major).collect::<Vec<_>>(), [1, 1, 2, 3]);
+        assert!(Version::new(1) == Version::new(1));
+    }
+
+    #[test]
+    fn legacy_sorts_by_less_than() {
+        let mut items: Vec<Legacy> = [5, -2, 9].into_iter().map(Legacy::new).collect();
+        items.sort();
+        assert_eq!(items.iter().map(Legacy::key).collect::<Vec<_>>(), [-2, 5, 9]);
+        assert!(Legacy::new(4) > Legacy::new(3));
+    }
+    )";
+    FFIGenerator ordered;
+    assert(rustTestsPass("ord", header, definitions, ordered.generateCWrapper(header, "ord").second,
+                         ordered.generate(header, "ord", "rust"), sorting));
+
+    std::cout << "  ✓ Comparison operators test passed\n";
+}
+
+void testEnumOutParams() {
+    const std::string source = R"(
+        enum class Color : uint8_t { Red = 1, Green, Blue = 7 };
+        enum Status { Ok, Failed };
+
+        Status get_status(Color* out_color);
+        void read_color(Color* out);
+        int32_t paint(Color c);
+
+        class Palette {
+        public:
+            Palette(int32_t size);
+            bool lookup(int32_t index, Color* out) const;
+        };
+    )";
+
+    FFIGenerator gen;
+    std::string code = gen.generate(source, "paint", "rust");
+
+    // The ABI sees the underlying integers
+    assert(code.find("pub fn get_status(out_color: *mut u8) -> i32;") != std::string::npos);
+    assert(code.find("pub fn paint(c: u8) -> i32;") != std::string::npos);
+
+    // Enums are mirrored and every enumerator is accepted by TryFrom
+    assert(code.find("#[repr(u8)]\n#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\npub enum Color {\n"
+                     "    Red = 1,\n    Green,\n    Blue = 7,\n}\n") != std::string::npos);
+    assert(code.find("            v if v == Color::Green as u8 => Ok(Color::Green),\n") != std::string::npos);
+    assert(code.find("            _ => Err(InvalidEnum { type_name: \"Color\", value: value as i64 }),\n") != std::string::npos);
+    assert(code.find("pub struct InvalidEnum {") == code.rfind("pub struct InvalidEnum {"));
+
+    // The out-parameter is written into a local and validated with the return value
+    assert(code.find("pub fn get_status() -> Result<(Status, Color), InvalidEnum> {\n"
+                     "    let mut out_color: u8 = 0;\n"
+                     "    let ret = unsafe { ffi::get_status(&mut out_color) };\n"
+                     "    Ok((Status::try_from(ret)?, Color::try_from(out_color)?))\n"
+                     "}\n") != std::string::npos);
+    assert(code.find("pub fn read_color() -> Result<Color, InvalidEnum> {\n"
+                     "    let mut out: u8 = 0;\n"
+                     "    unsafe { ffi::read_color(&mut out) };\n"
+                     "    Color::try_from(out)\n"
+                     "}\n") != std::string::npos);
+    assert(code.find("    pub fn lookup(&self, index: i32) -> Result<(bool, Color), InvalidEnum> {\n") != std::string::npos);
+
+    // Enum arguments are passed by value
+    assert(code.find("pub fn paint(c: Color) -> i32 {\n    unsafe { ffi::paint(c as u8) }\n") != std::string::npos);
+
+    std::cout << "  ✓ Enum out-parameters test passed\n";
+}
+
+void testStrictSkips() {
+    const std::string source = R"(
+        int32_t add(int32_t a, int32_t b);
+        void set_name(std::wstring name);
+        void load(std::vector<int32_t> items);
+    )";
+
+    // By default skips are reported, not fatal
+    FFIGenerator lenient;
+    std::string code = lenient.generate(source, "skips", "rust");
+    assert(code.find("// Skipped set_name:") != std::string::npos);
+    assert(lenient.diagnostics().size() == 2);
+    assert(lenient.diagnostics()[0].symbol == "set_name");
+    assert(lenient.diagnostics()[0].message.find("skipped: ") == 0);
+
+    // Strict mode fails on the unsupported type and names every skip
+    FFIOptions options;
+    options.strict = true;
+    FFIGenerator strict(options);
+    bool failed = false;
+    try {
+        strict.generate(source, "skips", "rust");
+    } catch (const std::runtime_error& e) {
+        failed = true;
+        std::string message = e.what();
+        assert(message.find("set_name") != std::string::npos);
+        assert(message.find("load") != std::string::npos);
+    }
+    assert(failed);
+
+    // Acknowledged skips are neither fatal nor reported
+    options.allowed_skips = {"set_name", "load"};
+    FFIGenerator acknowledged(options);
+    code = acknowledged.generate(source, "skips", "rust");
+    assert(code.find("pub fn add(a: i32, b: i32) -> i32 {") != std::string::npos);
+    assert(code.find("// Skipped load:") != std::string::npos);
+    assert(acknowledged.diagnostics().empty());
+
+    // Shims that cannot be generated are skips too
+    const std::string class_source = R"(
+        class Logger {
+        public:
+            Logger();
+            void write(std::wstring line);
+        };
+    )";
+    options.allowed_skips.clear();
+    failed = false;
+    try {
+        FFIGenerator(options).generateCWrapper(class_source, "log");
+    } catch (const std::runtime_error& e) {
+        failed = std::string(e.what()).find("Logger::write") != std::string::npos;
+    }
+    assert(failed);
+    options.allowed_skips = {"Logger::write"};
+    std::string shims = FFIGenerator(options).generateCWrapper(class_source, "log").second;
+    assert(shims.find("// Not generated: Logger::write") != std::string::npos);
+
+    // The bindings fail the same way without the shim source, for methods
+    // and static fields alike
+    const std::string store_source = R"(
+        class Store {
+        public:
+            Store();
+            void load(const std::map<int32_t, int32_t>& values);
+            int32_t size() const;
+            static std::map<int32_t, int32_t> defaults;
+        };
+    )";
+    options.allowed_skips.clear();
+    failed = false;
+    try {
+        FFIGenerator(options).generate(store_source, "store", "rust");
+    } catch (const std::runtime_error& e) {
+        std::string message = e.what();
+        failed = message.find("Store::load: parameter `values`") != std::string::npos &&
+                 message.find("Store::defaults: returns") != std::string::npos &&
+                 message.find("Store::size") == std::string::npos;
+    }
+    assert(failed);
+    FFIGenerator store_lenient;
+    store_lenient.generate(store_source, "store", "rust");
+    assert(store_lenient.diagnostics().size() == 3);
+    assert(store_lenient.diagnostics()[0].symbol == "Store::load");
+    options.allowed_skips = {"Store::load", "Store::defaults", "Store::set_defaults"};
+    FFIGenerator(options).generate(store_source, "store", "rust");
+
+    // Allowlist entries can come from the configuration file
+    FFIOptions configured;
+    applyFFIConfig(R"({"allow_skips": ["set_name"]})", configured);
+    assert(configured.allowed_skips.count("set_name"));
+
+    std::cout << "  ✓ Strict skips test passed\n";
+}
+
+void testStringParams() {
+    const std::string source = R"(
+        int32_t name_length(const char* name);
+        int32_t count_words(const std::string& text);
+
+        class Greeter {
+        public:
+            Greeter(std::string greeting);
+            int32_t greet(const char* name) const;
+        };
+    )";
+
+    FFIOptions options;
+    options.emit_tests = true;
+    std::string code = FFIGenerator(options).generate(source, "greet", "rust");
+
+    // Both string kinds cross the boundary as C strings
+    assert(code.find("pub fn name_length(name: *const i8) -> i32;") != std::string::npos);
+    assert(code.find("pub fn count_words(text: *const i8) -> i32;") != std::string::npos);
+
+    // By default wrappers take &str and keep the CString alive across the call
+    assert(code.find("pub fn name_length(name: &str) -> i32 {\n"
+                     "    let name = std::ffi::CString::new(name).expect(\"`name` contains a NUL byte\");\n"
+                     "    unsafe { ffi::name_length(name.as_ptr().cast()) }\n"
+                     "}\n") != std::string::npos);
+    assert(code.find("    pub fn greet(&self, name: &str) -> i32 {\n"
+                     "        let name = std::ffi::CString::new(name)") != std::string::npos);
+    assert(code.find("let _obj = Greeter::new(\"\");") != std::string::npos);
+
+    // An interior NUL has no C string, so the wrappers document the panic
+    assert(code.find("/// # Panics\n"
+                     "///\n"
+                     "/// If `name` contains a NUL byte.\n"
+                     "pub fn name_length(name: &str) -> i32 {\n") != std::string::npos);
+    assert(code.find("    /// If `name` contains a NUL byte.\n"
+                     "    pub fn greet(&self, name: &str) -> i32 {\n") != std::string::npos);
+
+    // The AsRef style accepts String, &str and Cow alike
+    applyFFIConfig(R"({"string_params": "as_ref"})", options);
+    code = FFIGenerator(options).generate(source, "greet", "rust");
+    assert(code.find("pub fn count_words(text: impl AsRef<str>) -> i32 {\n"
+                     "    let text = std::ffi::CString::new(text.as_ref()).expect(\"`text` contains a NUL byte\");\n")
+           != std::string::npos);
+    assert(code.find("    pub fn new(greeting: impl AsRef<str>) -> Self {\n") != std::string::npos);
+
+    // The shim turns the C string back into a std::string
+    std::string shims = FFIGenerator(options).generateCWrapper(source, "greet").second;
+    assert(shims.find("#include <string>\n") != std::string::npos);
+    assert(shims.find("void* greeter_new(const char* greeting) {\n"
+                      "    return new Greeter(std::string(greeting));\n") != std::string::npos);
+    assert(shims.find("int32_t count_words_shim(const char* text) {\n"
+                      "    return ::count_words(std::string(text));\n") != std::string::npos);
+
+    std::cout << "  ✓ String parameters test passed\n";
+}
+
+void testReferenceReturns() {
+    const std::string source = R"(
+        int32_t& pick(int32_t& slot);
+        const int32_t& peek(const int32_t& slot);
+        int32_t& larger(int32_t& x, int32_t& y);
+
+        class Counter {
+        public:
+            Counter(int32_t start);
+            int32_t& value();
+            const int32_t& current() const;
+            int32_t& leak() const;
+        };
+    )";
+
+    FFIGenerator gen;
+    std::string code = gen.generate(source, "refs", "rust");
+
+    // The shims hand references over as typed pointers
+    assert(code.find("pub fn pick(slot: *mut i32) -> *mut i32;") != std::string::npos);
+    assert(code.find("pub fn peek(slot: *const i32) -> *const i32;") != std::string::npos);
+    assert(code.find("pub fn counter_value(this: *mut c_void) -> *mut i32;") != std::string::npos);
+
+    // Borrows from the receiver rely on elision, so mutation goes through &mut self
+    assert(code.find("    pub fn value(&mut self) -> &mut i32 {\n"
+                     "        unsafe { &mut *ffi::counter_value(self.ptr) }\n") != std::string::npos);
+    assert(code.find("    pub fn current(&self) -> &i32 {\n"
+                     "        unsafe { &*ffi::counter_current(self.ptr) }\n") != std::string::npos);
+
+    // Free functions borrow from their only reference argument
+    assert(code.find("pub fn pick<'a>(slot: &'a mut i32) -> &'a mut i32 {\n"
+                     "    unsafe { &mut *ffi::pick(slot) }\n") != std::string::npos);
+    assert(code.find("pub fn peek<'a>(slot: &'a i32) -> &'a i32 {") != std::string::npos);
+
+    // Without a single owner the raw pointer is returned, with a warning
+    assert(code.find("pub fn larger(x: &mut i32, y: &mut i32) -> *mut i32 {\n"
+                     "    unsafe { ffi::larger(x, y) }\n") != std::string::npos);
+    assert(code.find("    pub fn leak(&self) -> *mut i32 {") != std::string::npos);
+    std::set<std::string> warned;
+    for (const auto& diagnostic : gen.diagnostics()) {
+        warned.insert(diagnostic.symbol);
+        assert(diagnostic.message.find("the wrapper returns a raw pointer") != std::string::npos);
+    }
+    assert(warned == std::set<std::string>({"larger", "Counter::leak"}));
+
+    std::cout << "  ✓ Reference returns test passed\n";
+}
+
+void testCheckedNarrowing() {
+    const std::string source = R"(
+        int32_t add_small(int8_t a, int8_t b);
+        int64_t widen(int32_t value);
+
+        class Mixer {
+        public:
+            Mixer(uint8_t level);
+            void set_level(uint8_t level);
+        };
+    )";
+
+    FFIOptions options;
+    options.checked_narrowing = true;
+    FFIGenerator gen(options);
+    std::string code = gen.generate(source, "mixer", "rust");
+
+    // The C signature keeps the narrow type
+    assert(code.find("pub fn add_small(a: i8, b: i8) -> i32;") != std::string::npos);
+
+    // 300 for an i8 surfaces as Err instead of wrapping to 44
+    assert(code.find("pub fn add_small(a: i32, b: i32) -> Result<i32, std::num::TryFromIntError> {\n"
+                     "    let a: i8 = a.try_into()?;\n"
+                     "    let b: i8 = b.try_into()?;\n"
+                     "    Ok(unsafe { ffi::add_small(a, b) })\n") != std::string::npos);
+    assert(code.find("    pub fn set_level(&mut self, level: i32) -> Result<(), std::num::TryFromIntError> {\n"
+                     "        let level: u8 = level.try_into()?;\n"
+                     "        unsafe { ffi::mixer_set_level(self.ptr, level) };\n"
+                     "        Ok(())\n") != std::string::npos);
+    assert(rustcAccepts(code));
+
+    // Wide parameters and constructors are left alone
+    assert(code.find("pub fn widen(value: i32) -> i64 {") != std::string::npos);
+    assert(code.find("pub fn new(level: u8) -> Self {") != std::string::npos);
+
+    // Off by default
+    FFIGenerator plain;
+    assert(plain.generate(source, "mixer", "rust").find("pub fn add_small(a: i8, b: i8) -> i32 {") !=
+           std::string::npos);
+
+    std::cout << "  ✓ Checked narrowing test passed\n";
+}
+
+void testStringViewParams() {
+    const std::string source = R"(
+        size_t view_len(std::string_view text);
+
+        class Matcher {
+        public:
+            Matcher(std::string_view prefix);
+            bool matches(const std::string_view& text) const;
+        };
+    )";
+
+    FFIOptions options;
+    options.emit_tests = true;
+    std::string code = FFIGenerator(options).generate(source, "match", "rust");
+
+    // Views borrow the Rust bytes as pointer + length, no CString is built
+    assert(code.find("pub fn view_len(text: *const u8, text_len: usize) -> usize;") != std::string::npos);
+    assert(code.find("pub fn view_len(text: &str) -> usize {\n"
+                     "    unsafe { ffi::view_len(text.as_ptr(), text.len()) }\n"
+                     "}\n") != std::string::npos);
+    assert(code.find("    pub fn matches(&self, text: &str) -> bool {\n"
+                     "        unsafe { ffi::matcher_matches(self.ptr, text.as_ptr(), text.len()) }\n")
+           != std::string::npos);
+    assert(code.find("CString") == std::string::npos);
+    assert(code.find("let _obj = Matcher::new(\"\");") != std::string::npos);
+
+    // The AsRef style also takes raw bytes, since a view needs no UTF-8
+    applyFFIConfig(R"({"string_params": "as_ref"})", options);
+    code = FFIGenerator(options).generate(source, "match", "rust");
+    assert(code.find("pub fn view_len(text: impl AsRef<[u8]>) -> usize {\n"
+                     "    let text = text.as_ref();\n") != std::string::npos);
+
+    std::string shims = FFIGenerator(options).generateCWrapper(source, "match").second;
+    assert(shims.find("#include <string_view>\n") != std::string::npos);
+    assert(shims.find("size_t view_len_shim(const char* text, size_t text_len) {\n"
+                      "    return ::view_len(std::string_view(text, text_len));\n") != std::string::npos);
+
+    std::cout << "  ✓ String view parameters test passed\n";
+}
+
+void testSelfReferentialStructs() {
+    const std::string source = R"(
+        struct Node {
+            int32_t value;
+            Node* next;
+            const Node* prev;
+        };
+
+        struct Nested {
+            int32_t depth;
+            Nested inner;
+        };
+
+        struct Left {
+            Right right;
+        };
+
+        struct Right {
+            Left left;
+        };
+
+        int32_t list_sum(const Node* head);
+    )";
+
+    FFIOptions options;
+    options.emit_tests = true;
+    FFIGenerator gen(options);
+    std::string code = gen.generate(source, "list", "rust");
+
+    // Self-reference through pointers keeps the C layout
+    assert(code.find("pub struct Node {\n"
+                     "    pub value: i32,\n"
+                     "    pub next: *mut Node,\n"
+                     "    pub prev: *const Node,\n"
+                     "}\n") != std::string::npos);
+    assert(code.find("assert_eq!(std::mem::size_of::<Node>(), 24);") != std::string::npos);
+    assert(code.find("pub fn list_sum(head: *const Node) -> i32;") != std::string::npos);
+    assert(code.find("/// `head` must be valid for the reads `list_sum` makes through it.\n"
+                     "pub unsafe fn list_sum(head: *const Node) -> i32 {") != std::string::npos);
+
+    // By-value recursion, direct or mutual, is skipped with the embedding chain
+    assert(code.find("// Skipped Nested: embeds itself by value through `Nested::inner`, use a pointer\n") !=
+           std::string::npos);
+    assert(code.find("// Skipped Left: embeds itself by value through `Left::right` -> `Right::left`") !=
+           std::string::npos);
+    assert(code.find("pub struct Nested") == std::string::npos);
+    assert(code.find("pub struct Right") == std::string::npos);
+    std::set<std::string> warned;
+    for (const auto& diagnostic : gen.diagnostics()) {
+        warned.insert(diagnostic.symbol);
+    }
+    assert(warned == std::set<std::string>({"Nested", "Left", "Right"}));
+
+    // Under strict they fail like any other skipped declaration
+    options.strict = true;
+    options.allowed_skips = {"Left", "Right"};
+    bool threw = false;
+    try {
+        FFIGenerator(options).generate(source, "list", "rust");
+    } catch (const std::runtime_error& e) {
+        threw = std::string(e.what()).find("Nested: embeds itself") != std::string::npos &&
+                std::string(e.what()).find("Left:") == std::string::npos;
+    }
+    assert(threw);
+
+    std::cout << "  ✓ Self-referential structs test passed\n";
+}
+
+void testCustomFreeFunction() {
+    const std::string source = R"(
+        char* make_greeting(const char* name);
+        const char* version();
+
+        class Namer {
+        public:
+            Namer();
+            char* label() const;
+            const char* title() const;
+            static char* describe(int32_t n);
+        };
+    )";
+
+    FFIOptions options;
+    applyFFIConfig(R"({"free_function": "lib_free"})", options);
+    std::string code = FFIGenerator(options).generate(source, "names", "rust");
+
+    // The extern block declares the free function once
+    const std::string decl_line = "        pub fn lib_free(ptr: *mut c_void);\n";
+    size_t decl = code.find(decl_line);
+    assert(decl != std::string::npos);
+    assert(code.find("pub fn lib_free(", decl + decl_line.size()) == std::string::npos);
+
+    // Owned strings are copied, then released by the library's allocator
+    assert(code.find("pub fn make_greeting(name: &str) -> Option<String> {\n"
+                     "    let name = std::ffi::CString::new(name).expect(\"`name` contains a NUL byte\");\n"
+                     "    let ptr = unsafe { ffi::make_greeting(name.as_ptr().cast()) };\n"
+                     "    if ptr.is_null() {\n"
+                     "        return None;\n"
+                     "    }\n"
+                     "    let text = unsafe { std::ffi::CStr::from_ptr(ptr.cast()) }.to_string_lossy().into_owned();\n"
+                     "    unsafe { ffi::lib_free(ptr.cast()) };\n"
+                     "    Some(text)\n"
+                     "}\n") != std::string::npos);
+    assert(code.find("    pub fn describe(n: i32) -> Option<String> {") != std::string::npos);
+
+    // Const strings and accessors are not the caller's to free
+    assert(code.find("pub fn version() -> Option<&'static std::ffi::CStr> {\n"
+                     "    let ptr = unsafe { ffi::version() };\n"
+                     "    if ptr.is_null() {\n"
+                     "        return None;\n"
+                     "    }\n"
+                     "    Some(unsafe { std::ffi::CStr::from_ptr(ptr.cast()) })\n"
+                     "}\n") != std::string::npos);
+    assert(code.find("    pub fn label(&self) -> *mut i8 {") != std::string::npos);
+    // A method's borrowed string lives as long as the object
+    assert(code.find("    pub fn title(&self) -> Option<&std::ffi::CStr> {") != std::string::npos);
+    assert(rustcAccepts(code));
+
+    // A free function declared in the header is reused
+    std::string declared = FFIGenerator(options).generate(source + "void lib_free(void* ptr);\n", "names", "rust");
+    decl = declared.find("pub fn lib_free(ptr: *mut c_void);");
+    assert(decl != std::string::npos && declared.find("pub fn lib_free(ptr: *mut c_void);", decl + 1) == std::string::npos);
+    assert(declared.find("\npub fn lib_free(") == std::string::npos);
+
+    // Without a configured free function the pointer is left to the caller
+    FFIGenerator plain;
+    assert(plain.generate(source, "names", "rust").find("pub fn make_greeting(name: &str) -> *mut i8 {") !=
+           std::string::npos);
+
+    std::cout << "  ✓ Custom free function test passed\n";
+}
+
+void testLinkCheck() {
+    const std::string source = R"(
+        int32_t calc_add(int32_t a, int32_t b);
+        int32_t parse(std::wstring text);
+
+        class Acc {
+        public:
+            Acc();
+            void push(int32_t v);
+        };
+    )";
+
+    FFIOptions options;
+    options.emit_link_check = true;
+    std::string code = FFIGenerator(options).generate(source, "calc", "rust");
+
+    size_t mod_pos = code.find("#[cfg(test)]\nmod link_check {");
+    assert(mod_pos != std::string::npos);
+    std::string check = code.substr(mod_pos);
+
+    // An undefined symbol is reported as referenced by the test named after it
+    assert(check.find("    #[test]\n"
+                      "    fn calc_add() {\n"
+                      "        assert!(!std::hint::black_box(ffi::calc_add as *const ()).is_null());\n"
+                      "    }\n") != std::string::npos);
+    for (const char* symbol : {"acc_new", "acc_push", "acc_delete"}) {
+        assert(check.find(std::string("fn ") + symbol + "()") != std::string::npos);
+    }
+
+    // Only symbols the extern block declares
+    assert(check.find("parse") == std::string::npos);
+
+    // Linked against a library missing one shim, the error names its test
+    const std::string header = R"(
+        class Acc {
+        public:
+            Acc();
+            void push(int32_t v);
+        private:
+            int32_t total_;
+        };
+    )";
+    const std::string definitions = R"(
+        Acc::Acc() : total_(0) {}
+        void Acc::push(int32_t v) { total_ += v; }
+    )";
+    FFIGenerator linked(options);
+    std::string shims = linked.generateCWrapper(header, "acc").second;
+    shims = std::regex_replace(shims, std::regex(R"(void acc_push\([^)]*\) \{\n[^}]*\}\n)"), "");
+    assert(shims.find("acc_push") == std::string::npos);
+    std::string output;
+    if (!rustTestsPass("acc", header, definitions, shims, linked.generate(header, "acc", "rust"), "", &output) ||
+        !output.empty()) {
+        // GNU ld and lld word it differently
+        assert(std::regex_search(output, std::regex("undefined (reference to `|symbol: )acc_push")));
+        assert(output.find("link_check::acc_push") != std::string::npos);
+    }
+
+    FFIGenerator plain;
+    assert(plain.generate(source, "calc", "rust").find("mod link_check") == std::string::npos);
+
+    std::cout << "  ✓ Link check test passed\n";
+}
+
+void testNestedClasses() {
+    const std::string source = R"(
+        class Outer {
+        public:
+            class Inner {
+            public:
+                Inner(int32_t v);
+                int32_t get() const;
+            private:
+                int32_t v_;
+            };
+            Outer();
+            int32_t total() const;
+        private:
+            class Hidden {
+            public:
+                Hidden();
+            };
+            int32_t t_;
+        };
+
+        struct Shape {
+            struct Point {
+                double x;
+                double y;
+            };
+            int32_t sides;
+        };
+    )";
+
+    // Nested classes are parsed on their own, not as members of the outer class
+    hybrid::IR ir = hybrid::Parser::parseString(source);
+    std::vector<std::string> names;
+    for (const auto& cls : ir.getClasses()) {
+        names.push_back(cls.name);
+    }
+    assert(names == std::vector<std::string>({"Outer", "Outer::Inner", "Shape", "Shape::Point"}));
+    assert(ir.getClasses()[0].methods.size() == 2);
+    assert(ir.getClasses()[0].fields.size() == 1);
+    assert(ir.getFunctions().empty());
+
+    FFIGenerator gen;
+    std::string code = gen.generate(source, "nested", "rust");
+
+    // The Rust type joins the scopes, the shim names follow the nesting
+    assert(code.find("/// Safe wrapper for Outer::Inner class\npub struct OuterInner {") != std::string::npos);
+    assert(code.find("    pub fn new(v: i32) -> Self {\n"
+                     "        let ptr = unsafe { ffi::outer_inner_new(v) };\n"
+                     "        OuterInner { ptr }\n") != std::string::npos);
+    assert(code.find("pub fn outer_inner_get(this: *const c_void) -> i32;") != std::string::npos);
+    assert(code.find("impl Drop for OuterInner {") != std::string::npos);
+    assert(code.find("pub fn outer_total(this: *const c_void) -> i32;") != std::string::npos);
+    assert(code.find("pub struct ShapePoint {\n    pub x: f64,\n    pub y: f64,\n}") != std::string::npos);
+    assert(code.find("Hidden") == std::string::npos);
+
+    // The shims spell out the qualified C++ type
+    std::string shims = gen.generateCWrapper(source, "nested").second;
+    assert(shims.find("void* outer_inner_new(int32_t v) {\n"
+                      "    return new Outer::Inner(v);\n") != std::string::npos);
+    assert(shims.find("return static_cast<const Outer::Inner*>(self)->get();") != std::string::npos);
+    assert(shims.find("delete static_cast<Outer::Inner*>(self);") != std::string::npos);
+
+    std::cout << "  ✓ Nested classes test passed\n";
+}
+
+void testStructAccessors() {
+    const std::string source = R"(
+        struct Point {
+            float x;
+            float y;
+        };
+
+        struct Segment {
+            Point from;
+            Point to;
+            const int32_t id;
+        };
+    )";
+
+    FFIOptions options;
+    applyFFIConfig(R"({"struct_fields": "accessors"})", options);
+    std::string code = FFIGenerator(options).generate(source, "geom", "rust");
+
+    // Fields go private behind a constructor, getters and setters
+    assert(code.find("pub struct Point {\n    x: f32,\n    y: f32,\n}") != std::string::npos);
+    assert(code.find("    pub fn new(x: f32, y: f32) -> Self {\n"
+                     "        Self { x, y }\n") != std::string::npos);
+    assert(code.find("    pub fn x(&self) -> f32 {\n"
+                     "        self.x\n") != std::string::npos);
+    assert(code.find("    pub fn set_y(&mut self, value: f32) {\n"
+                     "        self.y = value;\n") != std::string::npos);
+
+    // Nested structs are borrowed, const fields have no setter
+    assert(code.find("    pub fn from(&self) -> &Point {\n"
+                     "        &self.from\n") != std::string::npos);
+    assert(code.find("    pub fn set_to(&mut self, value: Point) {") != std::string::npos);
+    assert(code.find("    pub fn id(&self) -> i32 {") != std::string::npos);
+    assert(code.find("set_id") == std::string::npos);
+
+    // Public fields stay the default
+    std::string plain = FFIGenerator().generate(source, "geom", "rust");
+    assert(plain.find("pub struct Point {\n    pub x: f32,\n    pub y: f32,\n}") != std::string::npos);
+    assert(plain.find("impl Point {") == std::string::npos);
+
+    std::cout << "  ✓ Struct accessors test passed\n";
+}
+
+void testReachableRoots() {
+    const std::string source = R"(
+        enum class Color { Red, Green };
+
+        struct Point {
+            double x;
+            double y;
+        };
+
+        struct Style {
+            Color color;
+            int32_t width;
+        };
+
+        struct Unused {
+            int32_t a;
+        };
+
+        class Canvas {
+        public:
+            Canvas(int32_t w);
+            void draw(Point p, const Style& style);
+        };
+
+        double distance(Point a, Point b);
+        int32_t unrelated(int32_t x);
+    )";
+
+    // The root pulls in its parameter types and, through Style, Color
+    hybrid::IR ir = hybrid::reachableFrom(hybrid::Parser::parseString(source), {"Canvas"});
+    std::vector<std::string> names;
+    for (const auto& cls : ir.getClasses()) {
+        names.push_back(cls.name);
+    }
+    assert(names == std::vector<std::string>({"Canvas", "Point", "Style"}));
+    assert(ir.getEnums().size() == 1 && ir.getEnums()[0].name == "Color");
+    assert(ir.getFunctions().empty());
+
+    // A function root keeps only that function and its types
+    FFIOptions options;
+    options.roots = {"distance"};
+    std::string code = FFIGenerator(options).generate(source, "canvas", "rust");
+    assert(code.find("pub fn distance(a: Point, b: Point) -> f64 {") != std::string::npos);
+    assert(code.find("pub struct Point {") != std::string::npos);
+    for (const char* pruned : {"Style", "Color", "Unused", "Canvas", "unrelated"}) {
+        assert(code.find(pruned) == std::string::npos);
+    }
+
+    bool rejected = false;
+    try {
+        hybrid::reachableFrom(ir, {"Missing"});
+    } catch (const std::invalid_argument&) {
+        rejected = true;
+    }
+    assert(rejected);
+
+    std::cout << "  ✓ Reachable roots test passed\n";
+}
+
+void testStructArrayReturns() {
+    const std::string source = R"(
+        struct Point {
+            double x;
+            double y;
+        };
+
+        class Mesh {
+        public:
+            Mesh(size_t n);
+            const Point* points(size_t* count) const;
+        };
+
+        Point* load_points(int32_t seed, size_t* count);
+    )";
+
+    FFIGenerator gen;
+    std::string code = gen.generate(source, "mesh", "rust");
+
+    // The receiver owns the array, so the slice borrows it without a copy
+    assert(code.find("pub fn mesh_points(this: *const c_void, count: *mut usize) -> *const Point;") !=
+           std::string::npos);
+    assert(code.find("    pub fn points(&self) -> &[Point] {\n"
+                     "        let mut count: usize = 0;\n"
+                     "        let ptr = unsafe { ffi::mesh_points(self.ptr, &mut count) };\n"
+                     "        if ptr.is_null() {\n"
+                     "            return &[];\n"
+                     "        }\n"
+                     "        unsafe { std::slice::from_raw_parts(ptr, count) }\n") != std::string::npos);
+
+    // A free function hands the array over; it is copied, then deleted[] by a shim
+    assert(code.find("pub fn load_points(seed: i32) -> Vec<Point> {") != std::string::npos);
+    assert(code.find("    let items = unsafe { std::slice::from_raw_parts(ptr, count) }.to_vec();\n"
+                     "    unsafe { ffi::load_points_free(ptr) };\n"
+                     "    items\n") != std::string::npos);
+    assert(code.find("pub fn load_points_free(ptr: *const Point);") != std::string::npos);
+    std::string shims = gen.generateCWrapper(source, "mesh").second;
+    assert(shims.find("void load_points_free(const Point* ptr) {\n"
+                      "    delete[] ptr;\n") != std::string::npos);
+    assert(shims.find("mesh_points_free") == std::string::npos);
+
+    // A configured free function replaces the shim
+    FFIOptions options;
+    options.free_function = "lib_free";
+    FFIGenerator custom(options);
+    std::string freed = custom.generate(source, "mesh", "rust");
+    assert(freed.find("    unsafe { ffi::lib_free(ptr as *mut c_void) };\n") != std::string::npos);
+    assert(freed.find("load_points_free") == std::string::npos);
+    assert(custom.generateCWrapper(source, "mesh").second.find("delete[]") == std::string::npos);
+
+    std::cout << "  ✓ Struct array returns test passed\n";
+}
+
+void testChronoDurations() {
+    const std::string source = R"(
+        int64_t sleep_ms(std::chrono::milliseconds ms);
+        std::chrono::microseconds elapsed(const std::chrono::seconds& since);
+        void tick(std::chrono::duration<int64_t, std::ratio<1, 30>> frames);
+
+        class Stopwatch {
+        public:
+            Stopwatch();
+            std::chrono::nanoseconds lap() const;
+            void set_timeout(std::chrono::hours h);
+        };
+    )";
+
+    FFIGenerator gen;
+    std::string code = gen.generate(source, "timing", "rust");
+
+    // The extern passes the tick count, the wrapper takes a Duration
+    assert(code.find("pub fn sleep_ms(ms: i64) -> i64;") != std::string::npos);
+    assert(code.find("pub fn sleep_ms(ms: std::time::Duration) -> i64 {\n"
+                     "    unsafe { ffi::sleep_ms(i64::try_from(ms.as_millis()).unwrap_or(i64::MAX)) }\n") !=
+           std::string::npos);
+    assert(code.find("pub fn elapsed(since: std::time::Duration) -> std::time::Duration {\n"
+                     "    std::time::Duration::from_micros(u64::try_from(unsafe { ffi::elapsed(") != std::string::npos);
+    assert(code.find("    pub fn lap(&self) -> std::time::Duration {") != std::string::npos);
+    assert(code.find("i64::try_from(h.as_secs() / 3600).unwrap_or(i64::MAX)") != std::string::npos);
+
+    // A custom period has no Duration unit and stays a raw count
+    assert(code.find("pub fn tick(frames: i64) {") != std::string::npos);
+    bool warned = false;
+    for (const auto& diagnostic : gen.diagnostics()) {
+        warned = warned || (diagnostic.symbol == "tick" && diagnostic.message.find("raw i64") != std::string::npos);
+    }
+    assert(warned);
+
+    // The shim rebuilds the duration from the count and returns .count()
+    std::string shims = gen.generateCWrapper(source, "timing").second;
+    assert(shims.find("#include <chrono>\n") != std::string::npos);
+    assert(shims.find("int64_t sleep_ms_shim(int64_t ms) {\n"
+                      "    return ::sleep_ms(std::chrono::milliseconds(ms));\n") != std::string::npos);
+    assert(shims.find("    return ::elapsed(std::chrono::seconds(since)).count();\n") != std::string::npos);
+    assert(shims.find("    return static_cast<const Stopwatch*>(self)->lap().count();\n") != std::string::npos);
+
+    std::cout << "  ✓ Chrono durations test passed\n";
+}
+
+void testPerLibraryLinkBlocks() {
+    const std::string geom = R"(
+        double area(double w, double h);
+
+        class Shape {
+        public:
+            Shape();
+            double size() const;
+        };
+    )";
+    const std::string audio = R"(
+        int volume(int level);
+    )";
+
+    FFIGenerator gen;
+    std::string code = gen.generate({{"geom", geom}, {"audio", audio}}, "rust");
+
+    // One extern block per library, each holding only its own symbols
+    size_t geom_block = code.find("    #[link(name = \"geom\")]\n    extern \"C\" {\n");
+    size_t audio_block = code.find("    #[link(name = \"audio\")]\n    extern \"C\" {\n");
+    assert(geom_block != std::string::npos && audio_block != std::string::npos);
+    assert(geom_block < audio_block);
+
+    size_t area = code.find("pub fn area(w: f64, h: f64) -> f64;");
+    size_t shape = code.find("pub fn shape_size(this: *const c_void) -> f64;");
+    size_t volume = code.find("pub fn volume(level: i32) -> i32;");
+    assert(area > geom_block && area < audio_block);
+    assert(shape > geom_block && shape < audio_block);
+    assert(volume > audio_block);
+
+    // The safe wrappers don't care which library a symbol comes from
+    assert(code.find("pub fn volume(level: i32) -> i32 {") != std::string::npos);
+
+    // A name both libraries declare links against each of them
+    std::string both = gen.generate({{"geom", "namespace geom { int version(); }"},
+                                     {"audio", "namespace audio { int version(); }"}}, "rust");
+    const std::string declaration = "        pub fn version() -> i32;\n";
+    size_t geom_version = both.find(declaration, both.find("#[link(name = \"geom\")]"));
+    size_t audio_at = both.find("#[link(name = \"audio\")]");
+    assert(audio_at != std::string::npos && geom_version < audio_at);
+    assert(both.find(declaration, audio_at) != std::string::npos);
+
+    bool threw = false;
+    try {
+        gen.generate(std::vector<std::pair<std::string, std::string>>{}, "rust");
+    } catch (const std::invalid_argument&) {
+        threw = true;
+    }
+    assert(threw);
+
+    std::cout << "  ✓ Per-library link blocks test passed\n";
+}
+
+void testStaticDataMembers() {
+    const std::string source = R"(
+        class Calculator {
+        public:
+            Calculator();
+            static int count;
+            static const double ratio;
+            int value() const;
+        private:
+            static int hidden;
+            int total;
+        };
+    )";
+
+    FFIGenerator gen;
+    std::string code = gen.generate(source, "calc", "rust");
+
+    // Class-level state is read and written without an instance
+    assert(code.find("pub fn calculator_count() -> i32;") != std::string::npos);
+    assert(code.find("pub fn calculator_set_count(value: i32);") != std::string::npos);
+    assert(code.find("    pub fn count() -> i32 {\n"
+                     "        unsafe { ffi::calculator_count() }\n") != std::string::npos);
+    assert(code.find("    pub fn set_count(value: i32) {\n"
+                     "        unsafe { ffi::calculator_set_count(value) }\n") != std::string::npos);
+
+    // Const members are read-only, private ones are not exposed
+    assert(code.find("pub fn ratio() -> f64 {") != std::string::npos);
+    assert(code.find("set_ratio") == std::string::npos);
+    assert(code.find("hidden") == std::string::npos);
+
+    // The shims touch the variable itself
+    std::string shims = gen.generateCWrapper(source, "calc").second;
+    assert(shims.find("int calculator_count() {\n"
+                      "    return Calculator::count;\n") != std::string::npos);
+    assert(shims.find("void calculator_set_count(int value) {\n"
+                      "    Calculator::count = value;\n") != std::string::npos);
+    assert(shims.find("double calculator_ratio() {\n") != std::string::npos);
+
+    std::cout << "  ✓ Static data members test passed\n";
+}
+
+void testSafetyComments() {
+    const std::string source = R"(
+        int add(int a, int b);
+        void greet(const std::string& name);
+        int sum(std::span<const int> values);
+        char* describe(int code);
+
+        struct Reg {
+            volatile unsigned int ctrl;
+        };
+
+        class Node {
+        public:
+            Node();
+            Node* parent() const;
+            static Node& instance();
+            bool operator<(const Node& other) const;
+            void rename(const char* name);
+        };
+    )";
+
+    FFIOptions options;
+    options.safety_comments = true;
+    options.free_function = "lib_free";
+    options.emit_self_test = true;
+    FFIGenerator gen(options);
+    std::string code = gen.generate(source, "nodes", "rust");
+
+    // Walk up from each unsafe block or impl through the comment above it
+    std::vector<std::string> lines;
+    std::istringstream in(code);
+    for (std::string line; std::getline(in, line);) {
+        lines.push_back(line);
+    }
+    size_t blocks = 0;
+    for (size_t i = 0; i < lines.size(); ++i) {
+        if (lines[i].find("unsafe {") == std::string::npos && lines[i].find("unsafe impl") == std::string::npos) {
+            continue;
+        }
+        blocks++;
+        size_t j = i;
+        bool documented = false;
+        while (j > 0 && lines[j - 1].find_first_not_of(' ') != std::string::npos &&
+               lines[j - 1].compare(lines[j - 1].find_first_not_of(' '), 2, "//") == 0) {
+            documented = documented || lines[--j].find("// SAFETY: ") != std::string::npos;
+        }
+        assert(documented);
+    }
+    assert(blocks > 10);
+
+    // Tailored to what the call dereferences
+    assert(code.find("// SAFETY: `self.ptr` points to a live Node; `name` is a NUL-terminated copy "
+                     "that outlives the call\n") != std::string::npos);
+    assert(code.find("// SAFETY: `values`'s pointer and length come from one slice\n") != std::string::npos);
+    assert(code.find("// SAFETY: `ptr` is non-null and points to a NUL-terminated string\n") != std::string::npos);
+    assert(code.find("// SAFETY: `self.ctrl` is a live, aligned field of this struct\n") != std::string::npos);
+
+    // Off by default
+    FFIGenerator plain;
+    assert(plain.generate(source, "nodes", "rust").find("SAFETY") == std::string::npos);
+
+    std::cout << "  ✓ Safety comments test passed\n";
+}
+
+void testInlineNamespaces() {
+    const std::string source = R"(
+        namespace geo {
+        inline namespace v2 {
+            enum class Unit { Meters, Feet };
+            int add(int a, int b);
+            double scale(double value, Unit unit);
+
+            class Shape {
+            public:
+                Shape();
+                double area() const;
+            };
+        }
+        }
+    )";
+
+    // The inline namespace is part of where each declaration lives
+    hybrid::IR ir = hybrid::Parser::parseString(source);
+    assert(ir.getFunctions().size() == 2);
+    assert(ir.getFunctions()[0].namespace_path == "geo::v2");
+    assert(ir.getClasses()[0].namespace_path == "geo::v2");
+
+    // The Rust API stays flat
+    FFIGenerator gen;
+    std::string code = gen.generate(source, "geo", "rust");
+    assert(code.find("pub fn add(a: i32, b: i32) -> i32 {") != std::string::npos);
+    assert(code.find("pub struct Shape {") != std::string::npos);
+    assert(code.find("mod v2") == std::string::npos && code.find("mod geo") == std::string::npos);
+
+    // The shims resolve the versioned symbol, even for a plain C signature
+    std::string shims = gen.generateCWrapper(source, "geo").second;
+    assert(shims.find("using ::geo::v2::Shape;\n") != std::string::npos);
+    assert(shims.find("using ::geo::v2::Unit;\n") != std::string::npos);
+    assert(shims.find("int add_shim(int a, int b) {\n"
+                      "    return ::geo::v2::add(a, b);\n") != std::string::npos);
+    assert(shims.find("Bound directly") == std::string::npos);
+
+    std::cout << "  ✓ Inline namespaces test passed\n";
+}
+
+void testBuilderPattern() {
+    const std::string source = R"(
+        class Calculator {
+        public:
+            Calculator();
+            void set_precision(int digits);
+            void setScale(double scale);
+            void set_name(const char* name);
+            void set_internal(int value);
+            void set_buffer(char* buffer);
+            int precision() const;
+        };
+
+        class Point {
+        public:
+            Point(int x, int y);
+            void set_x(int x);
+            void set_y(int y);
+        };
+    )";
+
+    FFIOptions options;
+    applyFFIConfig("{\"builders\": {\"min_setters\": 2, "
+                   "\"setters\": [\"Calculator::set(_precision|Scale|_name|_buffer)\"]}}", options);
+    assert(options.builder_min_setters == 2);
+    FFIGenerator gen(options);
+    std::string code = gen.generate(source, "calc", "rust");
+
+    // Chained setters forward to the setter wrappers
+    assert(code.find("pub struct CalculatorBuilder {\n    inner: Calculator,\n}") != std::string::npos);
+    assert(code.find("        CalculatorBuilder { inner: Calculator::new() }\n") != std::string::npos);
+    assert(code.find("    pub fn precision(mut self, digits: i32) -> Self {\n"
+                     "        self.inner.set_precision(digits);\n"
+                     "        self\n") != std::string::npos);
+    assert(code.find("    pub fn scale(mut self, scale: f64) -> Self {\n") != std::string::npos);
+    assert(code.find("    pub fn name(mut self, name: &str) -> Self {\n") != std::string::npos);
+    assert(code.find("    pub fn build(self) -> Calculator {\n        self.inner\n") != std::string::npos);
+    assert(code.find("    pub fn builder() -> CalculatorBuilder {\n"
+                     "        CalculatorBuilder { inner: Calculator::new() }\n") != std::string::npos);
+    assert(code.find("impl CalculatorBuilder {\n    pub fn new()") == std::string::npos);
+
+    // A constructor without arguments doubles as Default
+    assert(code.find("impl Default for Calculator {\n"
+                     "    fn default() -> Self {\n"
+                     "        Self::new()\n") != std::string::npos);
+    assert(code.find("impl Default for Point") == std::string::npos);
+    assert(code.find("//! - `CalculatorBuilder` from `setters of Calculator`") != std::string::npos);
+
+    // Filtered and unsafe setters are left out, classes without a default
+    // constructor get none
+    assert(code.find("pub fn internal(mut self") == std::string::npos);
+    assert(code.find("pub unsafe fn set_buffer(&mut self, buffer: *mut i8) {") != std::string::npos);
+    assert(code.find("pub fn buffer(mut self") == std::string::npos);
+    assert(code.find("PointBuilder") == std::string::npos);
+
+    // Off unless configured
+    FFIGenerator plain;
+    assert(plain.generate(source, "calc", "rust").find("Builder") == std::string::npos);
+
+    bool threw = false;
+    try {
+        FFIOptions invalid;
+        applyFFIConfig(R"({"builders": {"min_setters": 0}})", invalid);
+    } catch (const std::runtime_error&) {
+        threw = true;
+    }
+    assert(threw);
+
+    std::cout << "  ✓ Builder pattern test passed\n";
+}
+
+void testPackedStructs() {
+    const std::string source = R"(
+        struct __attribute__((packed)) Header {
+            char tag;
+            int length;
+        };
+
+        #pragma pack(push, 2)
+        struct Wire {
+            char kind;
+            int value;
+        };
+        #pragma pack(pop)
+
+        struct Plain {
+            char a;
+            int b;
+        };
+    )";
+
+    hybrid::IR ir = hybrid::Parser::parseString(source);
+    for (const auto& cls : ir.getClasses()) {
+        assert(cls.pack == (cls.name == "Header" ? 1u : cls.name == "Wire" ? 2u : 0u));
+    }
+
+    FFIOptions options;
+    options.emit_tests = true;
+    FFIGenerator gen(options);
+    std::string code = gen.generate(source, "wire", "rust");
+
+    // Layout tests expect the packed size, not the padded one
+    assert(code.find("#[repr(C, packed)]\n#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\npub struct Header {\n"
+                     "    tag: i8,\n    length: i32,\n}") != std::string::npos);
+    assert(code.find("assert_eq!(std::mem::size_of::<Header>(), 5);") != std::string::npos);
+    assert(code.find("#[repr(C, packed(2))]") != std::string::npos);
+    assert(code.find("assert_eq!(std::mem::size_of::<Wire>(), 6);") != std::string::npos);
+    assert(code.find("assert_eq!(std::mem::align_of::<Wire>(), 2);") != std::string::npos);
+    assert(code.find("assert_eq!(std::mem::size_of::<Plain>(), 8);") != std::string::npos);
+
+    // Fields are copied through unaligned accesses
+    assert(code.find("    pub fn length(&self) -> i32 {\n"
+                     "        unsafe { std::ptr::addr_of!(self.length).read_unaligned() }\n") != std::string::npos);
+    assert(code.find("        unsafe { std::ptr::addr_of_mut!(self.length).write_unaligned(value) }\n") != std::string::npos);
+    assert(code.find("    pub a: i8,") != std::string::npos);
+
+    std::cout << "  ✓ Packed structs test passed" << std::endl;
+}
+
+void testChannelCallbacks() {
+    const std::string source = R"(
+        typedef void (*EventCallback)(int code, void* user_data);
+        using ProgressCallback = void (*)(const char* stage, double fraction, void* ctx);
+
+        int subscribe(EventCallback callback, void* user_data);
+        void unsubscribe(int token);
+
+        void watch_progress(int job, ProgressCallback callback, void* ctx);
+        void unwatch_progress(int job, void* ctx);
+    )";
+
+    hybrid::IR ir = hybrid::Parser::parseString(source);
+    assert(ir.getFunctionPointerTypes().size() == 2);
+    assert(ir.getFunctionPointerTypes()[1].name == "ProgressCallback");
+    assert(ir.getFunctionPointerTypes()[1].parameters.size() == 3);
+    assert(ir.getFunctions().size() == 4);
+
+    FFIOptions options;
+    applyFFIConfig(R"({"channel_callbacks": {
+        "subscribe": {"unregister": "unsubscribe", "capacity": 16},
+        "watch_progress": {"unregister": "unwatch_progress"}}})", options);
+    assert(options.channel_callbacks.at("subscribe").capacity == 16);
+    assert(options.channel_callbacks.at("watch_progress").capacity == 64);
+    FFIGenerator gen(options);
+    std::string code = gen.generate(source, "events", "rust");
+
+    // Callback typedefs cross as nullable function pointers
+    assert(code.find("pub fn subscribe(callback: Option<unsafe extern \"C\" fn(i32, *mut c_void)>, "
+                     "user_data: *mut c_void) -> i32;") != std::string::npos);
+
+    // Every event the trampoline receives is pushed into the boxed sender
+    assert(code.find("extern \"C\" fn subscribe_trampoline(code: i32, user_data: *mut c_void) {\n"
+                     "    let sender = unsafe { &*user_data.cast::<tokio::sync::mpsc::Sender<i32>>() };\n") !=
+           std::string::npos);
+    assert(code.find("    let _ = sender.try_send(code);\n") != std::string::npos);
+    assert(code.find("pub fn subscribe() -> SubscribeReceiver {\n"
+                     "    let (sender, receiver) = tokio::sync::mpsc::channel(16);\n"
+                     "    let sender = Box::into_raw(Box::new(sender));\n"
+                     "    let handle = unsafe { ffi::subscribe(Some(subscribe_trampoline), sender.cast()) };\n") !=
+           std::string::npos);
+    assert(code.find("    type Target = tokio::sync::mpsc::Receiver<i32>;\n") != std::string::npos);
+    assert(code.find("unsafe impl Send for SubscribeReceiver {}") != std::string::npos);
+
+    // Dropping the receiver unregisters before the sender is freed
+    assert(code.find("            ffi::unsubscribe(self.handle);\n"
+                     "            drop(Box::from_raw(self.sender));\n") != std::string::npos);
+    assert(code.find("            ffi::unwatch_progress(self.job, self.sender.cast());\n") != std::string::npos);
+
+    // Several event parameters become a tuple, strings are copied out
+    assert(code.find("pub fn watch_progress(job: i32) -> WatchProgressReceiver {") != std::string::npos);
+    assert(code.find("    receiver: tokio::sync::mpsc::Receiver<(String, f64)>,\n") != std::string::npos);
+    assert(code.find("std::ffi::CStr::from_ptr(stage) }.to_string_lossy().into_owned()") != std::string::npos);
+    assert(code.find("    let _ = sender.try_send((stage, fraction));\n") != std::string::npos);
+    assert(code.find("//! - `SubscribeReceiver` from `callbacks of subscribe`") != std::string::npos);
+
+    // A registration without a usable unregister keeps its plain wrapper
+    FFIOptions unmatched;
+    unmatched.channel_callbacks["subscribe"].unregister = "missing";
+    FFIGenerator plain(unmatched);
+    std::string fallback = plain.generate(source, "events", "rust");
+    assert(fallback.find("SubscribeReceiver") == std::string::npos);
+    assert(fallback.find("pub unsafe fn subscribe(callback: Option<unsafe extern \"C\" fn(i32, *mut c_void)>, "
+                         "user_data: *mut c_void) -> i32 {") != std::string::npos);
+    assert(std::any_of(plain.diagnostics().begin(), plain.diagnostics().end(), [](const FFIDiagnostic& d) {
+        return d.symbol == "subscribe" && d.message.find("`missing` is not bound") != std::string::npos;
+    }));
+
+    bool threw = false;
+    try {
+        FFIOptions invalid;
+        applyFFIConfig(R"({"channel_callbacks": {"subscribe": {"capacity": 8}}})", invalid);
+    } catch (const std::runtime_error&) {
+        threw = true;
+    }
+    assert(threw);
+
+    std::cout << "  ✓ Channel callbacks test passed" << std::endl;
+}
+
+void testDeprecatedAttributes() {
+    const std::string source = R"(
+        [[deprecated("use add instead")]] int old_add(int a, int b);
+        int add(int a, int b);
+
+        class [[deprecated("use Canvas")]] Widget {
+        public:
+            Widget();
+            [[deprecated]] void draw();
+            int size() const;
+        };
+
+        enum class [[deprecated]] Mode { A, B };
+    )";
+
+    hybrid::IR ir = hybrid::Parser::parseString(source);
+    for (const auto& func : ir.getFunctions()) {
+        assert(func.is_deprecated == (func.name == "old_add"));
+    }
+    assert(ir.getFunctions()[0].deprecation_message == "use add instead");
+
+    FFIGenerator gen;
+    std::string code = gen.generate(source, "widgets", "rust");
+
+    assert(code.find("#[deprecated(note = \"use add instead\")]\npub fn old_add(") != std::string::npos);
+    assert(code.find("\npub fn add(") != std::string::npos);
+    assert(code.find("]\npub fn add(") == std::string::npos);
+    assert(code.find("#[deprecated(note = \"use Canvas\")]\npub struct Widget {") != std::string::npos);
+    assert(code.find("    #[deprecated]\n    pub fn draw(&mut self)") != std::string::npos);
+    assert(code.find("]\n    pub fn size(&self)") == std::string::npos);
+    assert(code.find("#[deprecated]\npub enum Mode {") != std::string::npos);
+
+    // The module's own impls would otherwise warn about the deprecated items
+    assert(code.find("#![allow(deprecated)]") != std::string::npos);
+
+    std::string plain = gen.generate("int add(int a, int b);", "widgets", "rust");
+    assert(plain.find("deprecated") == std::string::npos);
+
+    std::cout << "  ✓ Deprecated attributes test passed" << std::endl;
+}
+
+void testErrorEnums() {
+    const std::string source = R"(
+        enum class ErrorCode { Ok, NotFound, Denied };
+        ErrorCode open_file(const char* path);
+        const char* error_message(ErrorCode code);
+
+        enum class Status { Idle, Busy };
+        Status status();
+    )";
+
+    FFIOptions options;
+    options.emit_tests = true;
+    applyFFIConfig(R"({"error_enums": {
+        "ErrorCode": {"success": "Ok", "to_string": "error_message"},
+        "Status": {}}})", options);
+    assert(options.error_enums.at("ErrorCode").success == "Ok");
+    assert(options.error_enums.at("Status").to_string.empty());
+    FFIGenerator gen(options);
+    std::string code = gen.generate(source, "files", "rust");
+
+    // Display asks C++ for the message, names are the fallback
+    assert(code.find("impl std::fmt::Display for ErrorCode {\n"
+                     "    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {\n"
+                     "        let ptr = unsafe { ffi::error_message(*self as i32) };\n") != std::string::npos);
+    assert(code.find("impl std::error::Error for ErrorCode {}") != std::string::npos);
+    assert(code.find("impl std::fmt::Display for Status {\n"
+                     "    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {\n"
+                     "        write!(f, \"{:?}\", self)\n") != std::string::npos);
+
+    // Only an enum with a success value turns returns into Result<(), E>
+    assert(code.find("pub fn open_file(path: &str) -> Result<(), ErrorCode> {") != std::string::npos);
+    assert(code.find("        Ok(ErrorCode::Ok) => Ok(()),\n"
+                     "        Ok(code) => Err(code),\n") != std::string::npos);
+    assert(code.find("pub fn status() -> Result<Status, InvalidEnum> {") != std::string::npos);
+
+    // The emitted test propagates the enum through `?` into a boxed error
+    assert(code.find("    fn error_code_is_std_error() {\n"
+                     "        fn fail() -> Result<(), Box<dyn std::error::Error>> {\n"
+                     "            let result: Result<(), ErrorCode> = Err(ErrorCode::NotFound);\n"
+                     "            result?;\n") != std::string::npos);
+    assert(gen.diagnostics().empty());
+
+    // A success value that is not an enumerator is reported and ignored
+    FFIOptions misconfigured;
+    applyFFIConfig(R"({"error_enums": {"ErrorCode": {"success": "Success", "to_string": "status"}}})", misconfigured);
+    FFIGenerator fallback(misconfigured);
+    code = fallback.generate(source, "files", "rust");
+    assert(code.find("pub fn open_file(path: &str) -> Result<ErrorCode, InvalidEnum> {") != std::string::npos);
+    assert(code.find("        write!(f, \"{:?}\", self)\n") != std::string::npos);
+    assert(fallback.diagnostics().size() == 2);
+
+    std::cout << "  ✓ Error enums test passed" << std::endl;
+}
+
+void testTemplateTraits() {
+    const std::string source = R"(
+        template<typename T>
+        T max(T a, T b);
+
+        template<typename T>
+        void fill(T* out, T value);
+    )";
+
+    FFIOptions options;
+    applyFFIConfig(R"({"templates": {
+        "max": {"instances": ["int", "double"], "trait": "Maxable"},
+        "fill": {"instances": ["int"], "trait": "Fillable"}}})", options);
+    assert(options.templates.at("max").instances.size() == 2);
+    FFIGenerator gen(options);
+    std::string code = gen.generate(source, "math", "rust");
+    std::string shims = gen.generateCWrapper(source, "math").second;
+
+    // One shim per instance, calling the template with explicit arguments
+    assert(shims.find("int max_int(int a, int b) {\n"
+                      "    return ::max<int>(a, b);\n") != std::string::npos);
+    assert(shims.find("return ::max<double>(a, b);") != std::string::npos);
+    assert(code.find("pub fn max_double(a: f64, b: f64) -> f64 {") != std::string::npos);
+    assert(code.find("//! - `max_int` from `int max<int>(int, int)`") != std::string::npos);
+
+    // The trait dispatches each Rust type to its instance
+    assert(code.find("pub trait Maxable {\n"
+                     "    fn max(a: Self, b: Self) -> Self;\n"
+                     "}") != std::string::npos);
+    assert(code.find("impl Maxable for i32 {\n"
+                     "    fn max(a: Self, b: Self) -> Self {\n"
+                     "        max_int(a, b)\n") != std::string::npos);
+    assert(code.find("impl Maxable for f64 {") != std::string::npos);
+    assert(code.find("pub fn max<T: Maxable>(a: T, b: T) -> T {\n"
+                     "    T::max(a, b)\n") != std::string::npos);
+
+    // Pointer parameters keep the instance wrapper but get no trait
+    assert(code.find("pub unsafe fn fill_int(") != std::string::npos);
+    assert(code.find("Fillable") == std::string::npos);
+    assert(gen.diagnostics().size() == 1);
+    assert(gen.diagnostics()[0].message.find("no Fillable trait") == 0);
+
+    std::cout << "  ✓ Template traits test passed" << std::endl;
+}
+
+void testCstdintTypedefs() {
+    // A platform header spelling the fixed-width types with `long`
+    const std::string source = R"(
+        typedef long int32_t;
+        typedef unsigned long uint64_t;
+        typedef unsigned long long my_u64;
+        typedef my_u64 my_size;
+        using handle_t = int;
+        typedef struct Widget Widget;
+
+        int32_t add(int32_t a, std::int32_t b);
+        uint64_t hash(my_size n, handle_t h, std::size_t len, intptr_t p);
+        void poke(DWORD flags, Widget* w);
+    )";
+
+    hybrid::IR ir = hybrid::Parser::parseString(source);
+    assert(ir.getTypeAliases().at("int32_t") == "long");
+    assert(ir.getTypeAliases().at("handle_t") == "int");
+
+    FFIOptions options;
+    applyFFIConfig(R"({"typedefs": {"DWORD": "uint32_t"}})", options);
+    FFIGenerator gen(options);
+    std::string code = gen.generate(source, "platform", "rust");
+
+    // The <cstdint> names keep their exact width, other aliases resolve
+    assert(code.find("pub fn add(a: i32, b: i32) -> i32;") != std::string::npos);
+    assert(code.find("pub fn hash(n: u64, h: i32, len: usize, p: isize) -> u64;") != std::string::npos);
+    assert(code.find("pub fn poke(flags: u32, w: *mut c_void);") != std::string::npos);
+    assert(code.find("pub unsafe fn poke(flags: u32, w: *mut c_void) {") != std::string::npos);
+    assert(code.find("-> i64") == std::string::npos);
+
+    std::cout << "  ✓ cstdint typedefs test passed" << std::endl;
+}
+
+void testBorrowedViews() {
+    const std::string source = R"(
+        class Widget {
+        public:
+            Widget(int id);
+            ~Widget();
+            int id() const;
+        };
+
+        class Registry {
+        public:
+            Registry();
+            ~Registry();
+            Widget* find(int id) const;
+            static Registry* global();
+        };
+
+        Widget* registry_lookup(int id);
+    )";
+
+    FFIOptions options;
+    applyFFIConfig(R"({"ownership": {"Registry::global": "borrowed", "registry_lookup": "borrowed"}})", options);
+    FFIGenerator gen(options);
+    std::string code = gen.generate(source, "registry", "rust");
+
+    // A declared destructor is neither a constructor nor a method
+    assert(code.find("pub fn widget_new(id: i32) -> *mut c_void;") != std::string::npos);
+    assert(code.find("pub fn widget_new()") == std::string::npos);
+
+    // Borrowed returns get a view tied to the receiver, or 'static without one
+    assert(code.find("pub fn find(&self, id: i32) -> Option<WidgetRef<'_>> {") != std::string::npos);
+    assert(code.find("pub fn global() -> Option<RegistryRef<'static>> {") != std::string::npos);
+    assert(code.find("pub fn registry_lookup(id: i32) -> Option<WidgetRef<'static>> {\n"
+                     "    let ptr = unsafe { ffi::registry_lookup(id) };\n") != std::string::npos);
+
+    // Views never free what they point to
+    assert(code.find("pub struct WidgetRef<'a> {") != std::string::npos);
+    assert(code.find("impl Drop for WidgetRef") == std::string::npos);
+    assert(code.find("impl Drop for RegistryRef") == std::string::npos);
+    assert(code.find("impl Drop for Widget {") != std::string::npos);
+
+    // Without the table the static factory owns its result
+    FFIGenerator inferred{FFIOptions{}};
+    code = inferred.generate(source, "registry", "rust");
+    assert(code.find("pub fn global() -> Option<Self> {") != std::string::npos);
+    assert(code.find("pub fn registry_lookup(id: i32) -> *mut c_void {") != std::string::npos);
+
+    bool rejected = false;
+    try {
+        FFIOptions bad;
+        applyFFIConfig(R"({"ownership": {"registry_lookup": "shared"}})", bad);
+    } catch (const std::runtime_error&) {
+        rejected = true;
+    }
+    assert(rejected);
+
+    std::cout << "  ✓ Borrowed views test passed" << std::endl;
+}
+
+void testListUnsupported() {
+    std::string source = R"(
+        #include <vector>
+        struct Point { int x; int y; };
+        struct Node { int v; Node inner; };
+        class Base { public: int id() const; };
+        class Other { public: int other() const; };
+        class Both : public Base, public Other { public: int both() const; };
+        template<typename T> class Box { public: T get() const; };
+        class Shape {
+        public:
+            Shape();
+            std::vector<int> corners() const;
+            Shape copy() const;
+            int area() const;
+        };
+        template<typename T> T max(T a, T b);
+        std::vector<int> values();
+        int add(int a, int b);
+        Shape make_shape(Point p);
+        void keep(Shape thrown);
+    )";
+
+    SupportReport report = FFIGenerator{}.audit({{"mixed", source}});
+    auto category = [&](const std::string& symbol) {
+        for (const auto& entry : report.unsupported) {
+            if (entry.symbol == symbol) return entry.category;
+        }
+        return std::string();
+    };
+    assert(report.unsupported.size() == 9);
+    assert(category("Both") == "Multiple inheritance");
+    assert(category("Box") == "Templates");
+    assert(category("Box::get").empty());
+    assert(category("max") == "Templates");
+    assert(category("Shape::corners") == "Standard library types");
+    assert(category("values") == "Standard library types");
+    assert(category("Node") == "Self-embedding structs");
+    assert(category("Shape::copy") == "Classes passed by value");
+    assert(category("make_shape") == "Classes passed by value");
+    // Categories come from where the skip was decided, not from its wording
+    assert(category("keep") == "Classes passed by value");
+    assert(category("add").empty());
+    assert(category("Shape::area").empty());
+
+    std::string text = formatSupportReport(report);
+    assert(text.find("9 of " + std::to_string(report.declarations) +
+                     " declarations are not supported") == 0);
+    assert(text.find("\nTemplates (2):\n  Box: ") != std::string::npos);
+    assert(text.find("\nClasses passed by value (3):\n  Shape::copy: ") != std::string::npos);
+
+    SupportReport clean = FFIGenerator{}.audit({{"clean", "int add(int a, int b);"}});
+    assert(clean.unsupported.empty());
+    assert(formatSupportReport(clean) == "All 1 declarations are supported\n");
+
+    std::cout << "  ✓ List unsupported test passed" << std::endl;
+}
+
+void testPathParams() {
+    std::string source = R"(
+        extern "C" long file_size(const char* path);
+        extern "C" int count_char(const char* profile, char c);
+        class Log {
+        public:
+            Log(const char* logFile);
+        };
+    )";
+
+    FFIOptions options;
+    applyFFIConfig(R"({"path_params": true})", options);
+    FFIGenerator generator{options};
+    std::string code = generator.generate(source, "fs", "rust");
+
+    // Non-UTF-8 names like b"caf\xe9.log" keep their bytes on Unix
+    assert(code.find("pub fn file_size(path: &std::path::Path) -> std::ffi::c_long {") != std::string::npos);
+    assert(code.find("#[cfg(unix)]\n    let path = std::ffi::CString::new("
+                     "std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()))") != std::string::npos);
+    assert(code.find("#[cfg(not(unix))]\n    let path = std::ffi::CString::new(path.to_str()") != std::string::npos);
+    assert(code.find("ffi::file_size(path.as_ptr().cast())") != std::string::npos);
+    assert(code.find("/// If `path` contains a NUL byte, or is not valid Unicode on targets other than Unix.\n"
+                     "pub fn file_size(") != std::string::npos);
+    assert(code.find("pub fn new(log_file: &std::path::Path) -> Self {") != std::string::npos);
+    // Whole words only, `profile` is not a file
+    assert(code.find("pub fn count_char(profile: &str, c: i8) -> i32 {") != std::string::npos);
+    assert(rustcAccepts(code, true));
+
+    // Only what the bindings name is imported
+    std::string plain = FFIGenerator{options}.generate("extern \"C\" long file_size(const char* path);", "fs", "rust");
+    assert(plain.find("use std::ffi::c_void;") == std::string::npos);
+    assert(plain.find("use super::*;") == std::string::npos);
+    assert(rustcAccepts(plain, true));
+
+    // C++ opens a file whose name is not valid UTF-8 through the same bytes
+    const std::string header = "extern \"C\" long file_size(const char* path);\n";
+    const std::string definitions = R"(
+        #include <cstdio>
+        long file_size(const char* path) {
+            std::FILE* file = std::fopen(path, "rb");
+            if (!file) return -1;
+            std::fseek(file, 0, SEEK_END);
+            long size = std::ftell(file);
+            std::fclose(file);
+            return size;
+        }
+    )";
+    const std::string non_utf8 = R"(
+    #[cfg(unix)]
+    #[test]
+    fn opens_non_utf8_names() {
+        use std::os::unix::ffi::OsStrExt;
+        let name = std::ffi::OsStr::from_bytes(b"hybrid_transpiler_caf\xe9.log");
+        assert!(name.to_str().is_none());
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, b"12345").unwrap();
+        let size = file_size(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(size, 5);
+    }
+    )";
+    assert(rustTestsPass("fs", header, definitions, generator.generateCWrapper(header, "fs").second,
+                         generator.generate(header, "fs", "rust"), non_utf8));
+
+    FFIOptions as_ref;
+    applyFFIConfig(R"({"path_params": ["path"], "string_params": "as_ref"})", as_ref);
+    code = FFIGenerator{as_ref}.generate(source, "fs", "rust");
+    assert(code.find("pub fn file_size(path: impl AsRef<std::path::Path>) -> std::ffi::c_long {\n"
+                     "    let path = path.as_ref();") != std::string::npos);
+    assert(code.find("pub fn new(log_file: impl AsRef<str>) -> Self {") != std::string::npos);
+
+    // Off unless configured
+    code = FFIGenerator{FFIOptions{}}.generate(source, "fs", "rust");
+    assert(code.find("std::path::Path") == std::string::npos);
+
+    std::cout << "  ✓ Path params test passed" << std::endl;
+}
+
+void testNameMangling() {
+    // Expected names are what g++ and cl.exe emit for these declarations
+    std::string source = R"(
+        #include <cstdint>
+        struct Point { int x; int y; };
+        enum Color { Red, Green };
+        long file_size(const char* path);
+        void copy(const char* src, char* dst, const char* fallback);
+        void fill(Color c, uint8_t* out, size_t n);
+        void move(Point* p, const Point* by, Point* again);
+        void names(const char* const* list, char** out);
+        bool same(bool a, bool b);
+        void reset();
+        int64_t ticks(int64_t t);
+        extern "C" int plain_c(int x);
+        extern "C" {
+        int block_c(int y);
+        }
+    )";
+    std::string code = FFIGenerator{}.generate(source, "m", "rust");
+    auto links = [&](const std::string& target, const std::string& symbol) {
+        return code.find(target + ", link_name = \"" + symbol + "\")]") != std::string::npos;
+    };
+    const std::string itanium = "#[cfg_attr(all(not(target_env = \"msvc\"), target_pointer_width = \"64\")";
+    const std::string itanium32 = "#[cfg_attr(all(not(target_env = \"msvc\"), target_pointer_width = \"32\")";
+    const std::string msvc64 = "#[cfg_attr(all(target_env = \"msvc\", target_pointer_width = \"64\")";
+    const std::string msvc32 = "#[cfg_attr(all(target_env = \"msvc\", target_pointer_width = \"32\")";
+
+    assert(links(itanium, "_Z9file_sizePKc"));
+    assert(links(msvc64, "?file_size@@YAJPEBD@Z"));
+    assert(links(msvc32, "?file_size@@YAJPBD@Z"));
+    // `J` is a 32-bit long, which c_long matches on every target
+    assert(code.find("link_name = \"?file_size@@YAJPBD@Z\")]\n        pub fn file_size(path: *const i8) -> std::ffi::c_long;") !=
+           std::string::npos);
+    // Repeated types become Itanium substitutions and MSVC back-references
+    assert(links(itanium, "_Z4copyPKcPcS0_"));
+    assert(links(msvc64, "?copy@@YAXPEBDPEAD0@Z"));
+    assert(links(itanium, "_Z4fill5ColorPhm"));
+    // size_t is `unsigned int` on 32-bit targets, while long keeps its `l`
+    assert(links(itanium32, "_Z4fill5ColorPhj"));
+    assert(links(itanium32, "_Z9file_sizePKc"));
+    assert(links(msvc64, "?fill@@YAXW4Color@@PEAE_K@Z"));
+    assert(links(msvc32, "?fill@@YAXW4Color@@PAEI@Z"));
+    assert(links(itanium, "_Z4moveP5PointPKS_S0_"));
+    assert(links(msvc64, "?move@@YAXPEAUPoint@@PEBU1@0@Z"));
+    assert(links(itanium, "_Z5namesPKPKcPPc"));
+    assert(links(msvc64, "?names@@YAXPEBQEBDPEAPEAD@Z"));
+    assert(links(itanium, "_Z4samebb"));
+    assert(links(msvc64, "?same@@YA_N_N0@Z"));
+    assert(links(itanium, "_Z5resetv"));
+    assert(links(msvc64, "?reset@@YAXXZ"));
+    // int64_t is `long` on glibc but `long long` on Darwin
+    assert(code.find(itanium + ", link_name = \"_Z5ticks") == std::string::npos);
+    assert(links(itanium32, "_Z5ticksx"));
+    assert(links(msvc64, "?ticks@@YA_J_J@Z"));
+    // C linkage keeps the plain name
+    assert(code.find("link_name = \"_Z7plain_c") == std::string::npos);
+    assert(code.find("link_name = \"_Z7block_c") == std::string::npos);
+    assert(code.find("        pub fn plain_c(x: i32) -> i32;") != std::string::npos);
+
+    // Namespaced declarations, as a shim-free binding would link them
+    FFIClass point{};
+    point.name = "Point";
+    point.namespace_path = "geo";
+    point.is_struct = true;
+    FFIClass shape{};
+    shape.name = "Shape";
+    shape.namespace_path = "geo";
+    FFIEnum color{};
+    color.name = "Color";
+    color.namespace_path = "geo";
+    NameMangler mangler({point, shape}, {color});
+    auto function = [](const std::string& scope, const std::string& name, const std::string& ret,
+                       const std::vector<std::string>& params) {
+        FFIFunction func{};
+        func.namespace_path = scope;
+        func.name = name;
+        func.return_type = ret;
+        for (const auto& type : params) {
+            FFIParameter param{};
+            param.cpp_type = type;
+            func.parameters.push_back(param);
+        }
+        return func;
+    };
+
+    FFIFunction scale = function("geo::v2", "scale", "double", {"geo::Point*", "double"});
+    assert(mangler.mangle(scale, ManglingScheme::Itanium) == "_ZN3geo2v25scaleEPNS_5PointEd");
+    assert(mangler.mangle(scale, ManglingScheme::MSVC64) == "?scale@v2@geo@@YANPEAUPoint@2@N@Z");
+    assert(mangler.mangle(scale, ManglingScheme::MSVC32) == "?scale@v2@geo@@YANPAUPoint@2@N@Z");
+
+    FFIFunction paint = function("geo", "paint", "void", {"const Shape&", "Color", "Color", "const Point*"});
+    assert(mangler.mangle(paint, ManglingScheme::Itanium) == "_ZN3geo5paintERKNS_5ShapeENS_5ColorES3_PKNS_5PointE");
+    assert(mangler.mangle(paint, ManglingScheme::MSVC64) == "?paint@geo@@YAXAEBVShape@1@W4Color@1@1PEBUPoint@1@@Z");
+
+    FFIFunction swap = function("geo::detail", "swap", "void", {"Point&", "Point&"});
+    assert(mangler.mangle(swap, ManglingScheme::Itanium) == "_ZN3geo6detail4swapERNS_5PointES2_");
+    assert(mangler.mangle(swap, ManglingScheme::MSVC64) == "?swap@detail@geo@@YAXAEAUPoint@2@0@Z");
+
+    FFIFunction origin = function("geo", "origin", "Point", {});
+    assert(mangler.mangle(origin, ManglingScheme::Itanium) == "_ZN3geo6originEv");
+    assert(mangler.mangle(origin, ManglingScheme::MSVC64) == "?origin@geo@@YA?AUPoint@1@XZ");
+
+    // Outside the subset
+    assert(mangler.mangle(function("", "take", "void", {"std::string&&"}), ManglingScheme::Itanium).empty());
+    assert(mangler.mangle(function("", "poke", "void", {"volatile int*"}), ManglingScheme::MSVC64).empty());
+    assert(mangler.mangle(function("", "find", "Unknown*", {}), ManglingScheme::Itanium).empty());
+
+    std::cout << "  ✓ Name mangling test passed" << std::endl;
+}
+
+void testStructDerives() {
+    std::string source = R"(
+        #include <cstdint>
+        struct Point { float x; float y; };
+        struct Cell { int32_t row; uint8_t col; bool live; };
+        struct Shape { Point center; Cell cell; };
+        struct Grid { Cell origin; Cell size; };
+        struct Node { int value; Node* next; };
+        struct Buffer { uint8_t* data; size_t len; };
+        struct Frame { Buffer buffer; int id; };
+    )";
+
+    FFIOptions options;
+    applyFFIConfig(R"({"derives": {"Buffer": ["Debug", "Clone"]}})", options);
+    std::string code = FFIGenerator{options}.generate(source, "shapes", "rust");
+
+    // Floats have no total equality
+    assert(code.find("#[derive(Debug, Clone, Copy, PartialEq)]\npub struct Point {") != std::string::npos);
+    assert(code.find("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\npub struct Cell {") != std::string::npos);
+    // Nested structs narrow the set to what every field supports
+    assert(code.find("#[derive(Debug, Clone, Copy, PartialEq)]\npub struct Shape {") != std::string::npos);
+    assert(code.find("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\npub struct Grid {") != std::string::npos);
+    // Pointer fields would only compare addresses
+    assert(code.find("#[derive(Debug, Clone, Copy)]\npub struct Node {") != std::string::npos);
+    // The configured list replaces the inferred one, and carries over
+    assert(code.find("#[derive(Debug, Clone)]\npub struct Buffer {") != std::string::npos);
+    assert(code.find("#[derive(Debug, Clone)]\npub struct Frame {") != std::string::npos);
+
+    bool rejected = false;
+    try {
+        FFIOptions bad;
+        applyFFIConfig(R"({"derives": {"Buffer": "Debug"}})", bad);
+    } catch (const std::runtime_error&) {
+        rejected = true;
+    }
+    assert(rejected);
+
+    std::cout << "  ✓ Struct derives test passed" << std::endl;
+}
+
+void testLibraryInit() {
+    std::string source = R"(
+        void lib_init();
+        void lib_shutdown();
+        int lib_setup(int flags);
+        int add(int a, int b);
+        class Session {
+        public:
+            Session();
+            int id() const;
+        };
+    )";
+
+    FFIOptions options;
+    options.emit_tests = true;
+    applyFFIConfig(R"({"init": {"function": "lib_init", "shutdown": "lib_shutdown", "automatic": true}})", options);
+    FFIGenerator gen{options};
+    std::string code = gen.generate(source, "lib", "rust");
+
+    // One Once guards the call, racing callers wait for it to finish
+    assert(code.find("static INIT: std::sync::Once = std::sync::Once::new();") != std::string::npos);
+    assert(code.find("pub fn init() {\n"
+                     "    INIT.call_once(|| {\n"
+                     "        unsafe { ffi::lib_init() }\n"
+                     "    });\n"
+                     "}\n") != std::string::npos);
+    assert(code.find("pub unsafe fn shutdown() {") != std::string::npos);
+    assert(code.find("    if INIT.is_completed() {\n        SHUTDOWN.call_once(|| {") != std::string::npos);
+    // The raw calls are only reachable through init() and shutdown()
+    assert(code.find("pub fn lib_init() {") == std::string::npos);
+    assert(code.find("pub fn lib_shutdown() {") == std::string::npos);
+    assert(code.find("//! - `init` from `void lib_init()`") != std::string::npos);
+
+    // Every possible first use initializes; methods need an object made by one
+    assert(code.find("pub fn add(a: i32, b: i32) -> i32 {\n    init();\n") != std::string::npos);
+    assert(code.find("    pub fn new() -> Self {\n        init();\n") != std::string::npos);
+    assert(code.find("    pub fn id(&self) -> i32 {\n        unsafe") != std::string::npos);
+
+    // Concurrent first calls in the generated tests
+    assert(code.find("    fn init_runs_once() {\n"
+                     "        let threads: Vec<_> = (0..8).map(|_| std::thread::spawn(init)).collect();\n") !=
+           std::string::npos);
+    assert(code.find("        assert!(INIT.is_completed());") != std::string::npos);
+
+    // Threads racing to a first use wait for the one call to lib_init
+    const std::string header = R"(
+        void lib_init();
+        void lib_shutdown();
+        int add(int a, int b);
+        int init_count();
+    )";
+    const std::string definitions = R"(
+        #include <atomic>
+        #include <chrono>
+        #include <thread>
+        static std::atomic<int> inits{0};
+        static std::atomic<bool> ready{false};
+        void lib_init() {
+            std::this_thread::sleep_for(std::chrono::milliseconds(20));
+            ++inits;
+            ready = true;
+        }
+        void lib_shutdown() { ready = false; }
+        int add(int a, int b) { return ready ? a + b : -1; }
+        int init_count() { return inits; }
+    )";
+    const std::string racing = R"(
+    #[test]
+    fn first_uses_race_to_one_init() {
+        let threads: Vec<_> = (0..8).map(|i| std::thread::spawn(move || add(i, 1))).collect();
+        let sums: Vec<i32> = threads.into_iter().map(|thread| thread.join().unwrap()).collect();
+        assert_eq!(sums, (1..9).collect::<Vec<_>>());
+        assert_eq!(init_count(), 1);
+    }
+    )";
+    assert(rustTestsPass("lib", header, definitions, gen.generateCWrapper(header, "lib").second,
+                         gen.generate(header, "lib", "rust"), racing));
+
+    // Without automatic, wrappers leave initialization to the caller
+    FFIOptions manual;
+    applyFFIConfig(R"({"init": {"function": "lib_init"}})", manual);
+    code = FFIGenerator{manual}.generate(source, "lib", "rust");
+    assert(code.find("pub fn init() {") != std::string::npos);
+    assert(code.find("pub unsafe fn shutdown") == std::string::npos);
+    assert(code.find("    init();\n") == std::string::npos);
+
+    // Only a `void f()` can be the init function
+    FFIOptions wrong;
+    applyFFIConfig(R"({"init": {"function": "lib_setup"}})", wrong);
+    FFIGenerator wrong_gen{wrong};
+    code = wrong_gen.generate(source, "lib", "rust");
+    assert(code.find("INIT") == std::string::npos);
+    assert(code.find("pub fn lib_setup(flags: i32) -> i32 {") != std::string::npos);
+    bool reported = false;
+    for (const auto& diagnostic : wrong_gen.diagnostics()) {
+        reported = reported || (diagnostic.symbol == "lib_setup" &&
+                                diagnostic.message.find("not the init function") == 0);
+    }
+    assert(reported);
+
+    bool rejected = false;
+    try {
+        FFIOptions bad;
+        applyFFIConfig(R"({"init": {"shutdown": "lib_shutdown"}})", bad);
+    } catch (const std::runtime_error&) {
+        rejected = true;
+    }
+    assert(rejected);
+
+    std::cout << "  ✓ Library init test passed" << std::endl;
+}
+
+void testStructReturnConventions() {
+    std::string source = R"(
+        #include <cstdint>
+        struct Pair { int64_t a; int64_t b; };
+        struct Triple { int64_t a; int64_t b; int64_t c; };
+        Pair make_pair(int64_t a, int64_t b);
+        Triple make_triple(int64_t a, int64_t b, int64_t c);
+        Triple scaled(Triple t, int64_t out);
+        class Mesh {
+        public:
+            Mesh();
+            Triple bounds() const;
+        };
+    )";
+
+    // 16 bytes is the most x86-64 System V returns in registers
+    FFIOptions sysv;
+    applyFFIConfig(R"({"abi_targets": ["x86_64-sysv"]})", sysv);
+    FFIGenerator gen{sysv};
+    std::string code = gen.generate(source, "geo", "rust");
+    std::string shims = gen.generateCWrapper(source, "geo").second;
+    assert(code.find("pub fn make_pair(a: i64, b: i64) -> Pair;") != std::string::npos);
+    assert(code.find("pub fn make_pair(a: i64, b: i64) -> Pair {\n    unsafe { ffi::make_pair(a, b) }\n}") !=
+           std::string::npos);
+    assert(shims.find("make_pair(") == std::string::npos);
+
+    // One byte more and the result is written to caller-provided memory
+    assert(code.find("pub fn make_triple(a: i64, b: i64, c: i64, out: *mut Triple);") != std::string::npos);
+    assert(code.find("pub fn make_triple(a: i64, b: i64, c: i64) -> Triple {\n"
+                     "    let mut out = std::mem::MaybeUninit::<Triple>::uninit();\n"
+                     "    unsafe { ffi::make_triple(a, b, c, out.as_mut_ptr()) };\n"
+                     "    unsafe { out.assume_init() }\n"
+                     "}") != std::string::npos);
+    assert(shims.find("#include <new>") != std::string::npos);
+    assert(shims.find("void make_triple_shim(int64_t a, int64_t b, int64_t c, Triple* out) {\n"
+                      "    new (out) Triple(::make_triple(a, b, c));\n"
+                      "}") != std::string::npos);
+    // The out-pointer steps around parameter names, and methods get one too
+    assert(code.find("pub fn scaled(t: Triple, out: i64, out_: *mut Triple);") != std::string::npos);
+    assert(shims.find("void mesh_bounds(const void* self, Triple* out) {\n"
+                      "    new (out) Triple(static_cast<const Mesh*>(self)->bounds());\n") != std::string::npos);
+    assert(code.find("    pub fn bounds(&self) -> Triple {\n"
+                     "        let mut out = std::mem::MaybeUninit::<Triple>::uninit();\n") != std::string::npos);
+
+    // Windows x64 returns only 1, 2, 4 and 8 bytes in RAX, so by default Pair goes through memory too
+    code = FFIGenerator{}.generate(source, "geo", "rust");
+    assert(code.find("pub fn make_pair(a: i64, b: i64, out: *mut Pair);") != std::string::npos);
+
+    auto layout = [](std::vector<std::string> types, size_t pack) {
+        FFIClass cls{};
+        for (const auto& type : types) {
+            FFIParameter field{};
+            field.cpp_type = type;
+            cls.fields.push_back(field);
+        }
+        cls.pack = pack;
+        return cls;
+    };
+    FFIClass pair = layout({"int64_t", "int64_t"}, 0);
+    FFIClass quad = layout({"double", "double", "double", "double"}, 0);
+    FFIClass packed = layout({"char", "int"}, 1);
+    FFIClass unknown = layout({"Pair"}, 0);
+    assert(structLayout(packed, AbiTarget::SysV64).size == 5);
+    assert(classifyReturn(pair, AbiTarget::SysV64) == ReturnConvention::Registers);
+    assert(classifyReturn(pair, AbiTarget::Win64) == ReturnConvention::Memory);
+    assert(classifyReturn(pair, AbiTarget::AArch64) == ReturnConvention::Registers);
+    // Up to four doubles come back in AArch64 vector registers
+    assert(classifyReturn(quad, AbiTarget::AArch64) == ReturnConvention::Registers);
+    assert(classifyReturn(quad, AbiTarget::SysV64) == ReturnConvention::Memory);
+    // An unaligned field cannot be classified into a System V register
+    assert(classifyReturn(packed, AbiTarget::SysV64) == ReturnConvention::Memory);
+    assert(classifyReturn(packed, AbiTarget::AArch64) == ReturnConvention::Registers);
+    assert(classifyReturn(unknown, AbiTarget::SysV64) == ReturnConvention::Memory);
+
+    // `long` is 4 bytes on Windows and 8 on the LP64 targets
+    FFIClass longs = layout({"long", "unsigned long"}, 0);
+    assert(structLayout(longs, AbiTarget::SysV64).size == 16);
+    assert(structLayout(longs, AbiTarget::AArch64).size == 16);
+    assert(structLayout(longs, AbiTarget::Win64).size == 8);
+    assert(structLayout(longs, AbiTarget::Win64).alignment == 4);
+    assert(classifyReturn(longs, AbiTarget::Win64) == ReturnConvention::Registers);
+
+    // So the smoke tests check each target's own layout
+    FFIOptions with_tests;
+    with_tests.emit_tests = true;
+    code = FFIGenerator{with_tests}.generate("struct Stat { long size; int32_t mode; };", "stat", "rust");
+    assert(code.find("        #[cfg(all(target_arch = \"x86_64\", not(windows)))]\n"
+                     "        {\n"
+                     "            assert_eq!(std::mem::size_of::<Stat>(), 16);\n") != std::string::npos);
+    assert(code.find("        #[cfg(all(target_arch = \"x86_64\", windows))]\n"
+                     "        {\n"
+                     "            assert_eq!(std::mem::size_of::<Stat>(), 8);\n"
+                     "            assert_eq!(std::mem::align_of::<Stat>(), 4);\n") != std::string::npos);
+    assert(rustcAccepts(code, true));
+    applyFFIConfig(R"({"abi_targets": ["x86_64-sysv"]})", with_tests);
+    code = FFIGenerator{with_tests}.generate("struct Stat { long size; int32_t mode; };", "stat", "rust");
+    assert(code.find("    fn stat_layout() {\n"
+                     "        assert_eq!(std::mem::size_of::<Stat>(), 16);\n") != std::string::npos);
+
+    bool rejected = false;
+    try {
+        FFIOptions bad;
+        applyFFIConfig(R"({"abi_targets": ["riscv64"]})", bad);
+    } catch (const std::runtime_error&) {
+        rejected = true;
+    }
+    assert(rejected);
+
+    std::cout << "  ✓ Struct return conventions test passed" << std::endl;
+}
+
+void testRelinkMacro() {
+    std::string source = R"(
+        struct Point { int x; int y; };
+        Point midpoint(Point a, Point b);
+        class Path {
+        public:
+            Path();
+            double length() const;
+        };
+    )";
+
+    FFIOptions options;
+    options.emit_relink_macro = true;
+    std::string code = FFIGenerator{options}.generate(source, "geo", "rust");
+
+    size_t macro = code.find("#[macro_export]\nmacro_rules! relink_geo {\n");
+    assert(macro != std::string::npos);
+    assert(code.find("    ($vis:vis $module:ident, $link:literal) => {\n"
+                     "        $vis mod $module {\n", macro) != std::string::npos);
+    // Invocations in separate modules, each naming its own build
+    assert(code.find("///     relink_geo!(pub ffi, \"geo_debug\");\n") < macro);
+    assert(code.find("///     relink_geo!(pub ffi, \"geo_asan\");\n") < macro);
+
+    // Only the link name varies: every declaration is repeated as is
+    size_t own = code.find("#[link(name = \"geo\")]\n    extern \"C\" {\n");
+    size_t relinked = code.find("            #[link(name = $link)]\n            extern \"C\" {\n", macro);
+    assert(own != std::string::npos && relinked != std::string::npos);
+    std::vector<std::string> declarations;
+    std::stringstream block(code.substr(own, code.find("    }\n", own) - own));
+    for (std::string line; std::getline(block, line);) {
+        if (line.find("        ") == 0) {
+            declarations.push_back(line.substr(8));
+        }
+    }
+    assert(declarations.size() >= 3);
+    for (const auto& declaration : declarations) {
+        assert(code.find("                " + declaration + "\n", relinked) != std::string::npos);
+    }
+
+    // Off by default
+    assert(FFIGenerator{}.generate(source, "geo", "rust").find("macro_rules!") == std::string::npos);
+
+    std::cout << "  ✓ Relink macro test passed" << std::endl;
+}
+
+void testThiserrorDerives() {
+    const std::string source = R"(
+        enum class ErrorCode { Ok, NotFound, Denied };
+        ErrorCode open_file(const char* path);
+        const char* error_message(ErrorCode code);
+
+        enum class Status { Idle, Busy };
+        Status status();
+    )";
+
+    FFIOptions options;
+    options.emit_tests = true;
+    options.thiserror = true;
+    applyFFIConfig(R"({"error_enums": {
+        "ErrorCode": {"success": "Ok", "to_string": "error_message"},
+        "Status": {}}})", options);
+    std::string code = FFIGenerator{options}.generate(source, "files", "rust");
+
+    // Enumerator names become the messages unless C++ provides them
+    assert(code.find("#[cfg_attr(feature = \"thiserror\", derive(thiserror::Error))]\n"
+                     "pub enum Status {\n"
+                     "    #[cfg_attr(feature = \"thiserror\", error(\"Idle\"))]\n"
+                     "    Idle,\n") != std::string::npos);
+    assert(code.find("#[cfg(not(feature = \"thiserror\"))]\n"
+                     "impl std::fmt::Display for Status {") != std::string::npos);
+    assert(code.find("#[cfg_attr(feature = \"thiserror\", derive(thiserror::Error))]\n"
+                     "pub enum ErrorCode {\n"
+                     "    Ok,\n") != std::string::npos);
+    assert(code.find("\nimpl std::fmt::Display for ErrorCode {") != std::string::npos);
+    assert(code.find("#[cfg(not(feature = \"thiserror\"))]\n"
+                     "impl std::error::Error for ErrorCode {}") != std::string::npos);
+    assert(code.find("#[cfg_attr(feature = \"thiserror\", derive(thiserror::Error), "
+                     "error(\"{value} is not a valid {type_name}\"))]\n"
+                     "pub struct InvalidEnum {") != std::string::npos);
+
+    // The emitted test propagates the enum into an application error through #[from]
+    assert(code.find("    #[cfg(feature = \"thiserror\")]\n"
+                     "    #[test]\n"
+                     "    fn error_code_converts_from_thiserror() {\n") != std::string::npos);
+    assert(code.find("            Cpp(#[from] ErrorCode),\n") != std::string::npos);
+
+    // Without the option nothing mentions the feature
+    options.thiserror = false;
+    assert(FFIGenerator{options}.generate(source, "files", "rust").find("thiserror") == std::string::npos);
+
+    std::cout << "  ✓ thiserror derives test passed" << std::endl;
+}
+
+void testOwnershipAnnotations() {
+    const std::string source = R"(
+        // @owns_return
+        char* render(int id);
+        // @borrows_return
+        char* cached_render(int id);
+        char* get_title();
+        char* dup_title();
+
+        class Doc {
+        public:
+            Doc();
+            // @owns_return
+            char* text() const;
+            char* title() const;
+        };
+    )";
+
+    FFIOptions options;
+    options.free_function = "lib_free";
+    std::string code = FFIGenerator(options).generate(source, "docs", "rust");
+
+    // The same `char*` is copied and freed when owned, handed out as is when borrowed
+    assert(code.find("pub fn render(id: i32) -> Option<String> {\n"
+                     "    let ptr = unsafe { ffi::render(id) };\n") != std::string::npos);
+    assert(code.find("    unsafe { ffi::lib_free(ptr.cast()) };\n") != std::string::npos);
+    assert(code.find("pub fn cached_render(id: i32) -> *mut i8 {") != std::string::npos);
+
+    // Without an annotation the leading verb decides
+    assert(code.find("pub fn get_title() -> *mut i8 {") != std::string::npos);
+    assert(code.find("pub fn dup_title() -> Option<String> {") != std::string::npos);
+
+    // An annotation applies to the next declaration only
+    assert(code.find("    pub fn text(&self) -> Option<String> {") != std::string::npos);
+    assert(code.find("    pub fn title(&self) -> *mut i8 {") != std::string::npos);
+
+    // The config's ownership table overrides both
+    applyFFIConfig(R"({"ownership": {"render": "borrowed", "get_title": "owned"}})", options);
+    code = FFIGenerator(options).generate(source, "docs", "rust");
+    assert(code.find("pub fn render(id: i32) -> *mut i8 {") != std::string::npos);
+    assert(code.find("pub fn get_title() -> Option<String> {") != std::string::npos);
+
+    std::cout << "  ✓ Ownership annotations test passed" << std::endl;
+}
+
+void testPackageFiles() {
+    FFIOptions options;
+    applyFFIConfig(R"({"package": {"prefix": "/opt/geo", "version": "2.1.0"}})", options);
+    std::string pc = generatePkgConfig(options, {"geo"});
+
+    // The .pc links the name the bindings' #[link] uses, from the configured prefix
+    std::string code = FFIGenerator{options}.generate("int area(int w, int h);", "geo", "rust");
+    assert(code.find("#[link(name = \"geo\")]") != std::string::npos);
+    assert(pc.find("prefix=/opt/geo\n"
+                   "libdir=${prefix}/lib\n"
+                   "includedir=${prefix}/include\n") != std::string::npos);
+    assert(pc.find("Name: geo\n") != std::string::npos);
+    assert(pc.find("Version: 2.1.0\n") != std::string::npos);
+    assert(pc.find("Libs: -L${libdir} -lgeo\n") != std::string::npos);
+    assert(pc.find("Cflags: -I${includedir}\n") != std::string::npos);
+
+    // Every linked library, each as an imported target in the CMake package
+    assert(generatePkgConfig(options, {"geom", "audio"}).find("Libs: -L${libdir} -lgeom -laudio\n") !=
+           std::string::npos);
+    std::string cmake = generateCMakeConfig(options, {"geom", "audio"});
+    assert(cmake.find("set(geom_INCLUDE_DIRS \"/opt/geo/include\")\n") != std::string::npos);
+    assert(cmake.find("    find_library(geom_audio_LIBRARY NAMES audio HINTS \"/opt/geo/lib\" NO_DEFAULT_PATH)\n"
+                      "    if(NOT geom_audio_LIBRARY)\n") != std::string::npos);
+    assert(cmake.find("    add_library(geom::geom UNKNOWN IMPORTED)\n") != std::string::npos);
+    assert(cmake.find("list(APPEND geom_LIBRARIES geom::audio)\n") != std::string::npos);
+
+    bool rejected = false;
+    try {
+        FFIOptions bad;
+        applyFFIConfig(R"({"package": {"libdir": "/opt/geo/lib64"}})", bad);
+    } catch (const std::runtime_error&) {
+        rejected = true;
+    }
+    assert(rejected);
+
+    std::cout << "  ✓ Package files test passed" << std::endl;
+}
+
+void testRustKeywordIdentifiers() {
+    const std::string source = R"(
+        struct Token { int type; int ref; };
+        extern "C" int loop(int where);
+        void move(Token* token, int type);
+
+        class Parser {
+        public:
+            Parser();
+            int match(int self) const;
+        };
+    )";
+
+    FFIOptions options;
+    FFIGenerator gen(options);
+    std::string code = gen.generate(source, "lexer", "rust");
+    std::string shims = gen.generateCWrapper(source, "lexer").second;
+
+    // Keywords become raw identifiers; the C symbols keep their names
+    assert(code.find("pub struct Token {\n    pub r#type: i32,\n    pub r#ref: i32,\n}") != std::string::npos);
+    assert(code.find("pub fn r#loop(where_: i32) -> i32 {") != std::string::npos);
+    assert(code.find("unsafe { ffi::r#loop(where_) }") != std::string::npos);
+    assert(code.find("pub fn r#move(token: *mut Token, type_: i32)") != std::string::npos);
+    assert(code.find("pub fn r#match(&self, self_: i32) -> i32 {") != std::string::npos);
+
+    // Renamed parameters are renamed in the shim too
+    assert(shims.find("int parser_match(const void* self, int self_)") != std::string::npos);
+    assert(gen.diagnostics().empty());
+    assert(rustcAccepts(code));
+
+    std::cout << "  ✓ Rust keyword identifiers test passed" << std::endl;
+}
+
+void runAllFFITests() {
+    std::cout << "\nRunning FFI Generation Tests:\n";
+    testBorrowedSelfPointerReturn();
+    testEmitSmokeTests();
+    testVolatileRegisterAccess();
+    testTableOfContents();
+    testAnonymousEnumConstants();
+    testAbiSelfTest();
+    testIntrusiveRefCounting();
+    testSingletonAccessor();
+    testDefaultArguments();
+    testSpanToSlice();
+    testShimSourceMatchesExterns();
+    testConstMismatchDetection();
+    testBindgenConfig();
+    testFactoryOnlyClass();
+    testComparisonOperators();
+    testEnumOutParams();
+    testStrictSkips();
+    testStringParams();
+    testReferenceReturns();
+    testCheckedNarrowing();
+    testStringViewParams();
+    testSelfReferentialStructs();
+    testCustomFreeFunction();
+    testLinkCheck();
+    testNestedClasses();
+    testStructAccessors();
+    testReachableRoots();
+    testStructArrayReturns();
+    testChronoDurations();
+    testPerLibraryLinkBlocks();
+    testStaticDataMembers();
+    testSafetyComments();
+    testInlineNamespaces();
+    testBuilderPattern();
+    testPackedStructs();
+    testChannelCallbacks();
+    testDeprecatedAttributes();
+    testErrorEnums();
+    testTemplateTraits();
+    testCstdintTypedefs();
+    testBorrowedViews();
+    testListUnsupported();
+    testPathParams();
+    testNameMangling();
+    testStructDerives();
+    testLibraryInit();
+    testStructReturnConventions();
+    testRelinkMacro();
+    testThiserrorDerives();
+    testOwnershipAnnotations();
+    testPackageFiles();
+    testRustKeywordIdentifiers();
+    std::cout << "All FFI generation tests passed!\n";
+}
+
+} // namespace test
+} // namespace hybrid
@@ -1,31 +1,51 @@
 #include <iostream>
 #include <string>
 
-// Simple test framework
+namespace hybrid {
+namespace test {
+void runAllTypeMappingTests();
+void runAllCodegenTests();
+void runAllFFITests();
+}
+}
+
+// Simple test framework: each suite asserts, so a failure aborts the run
 int main(int argc, char* argv[]) {
     std::cout << "Running Hybrid Transpiler Tests...\n";
 
-    // Run all tests
+    // No argument runs every suite, `--test-<suite>` only that one
+    std::string suite = argc > 1 ? argv[1] : "";
+    bool known = suite.empty() || suite == "--test-type-mapping" || suite == "--test-codegen" ||
+                 suite == "--test-ffi";
+    if (!known) {
+        std::cerr << "Unknown test suite: " << suite << "\n";
+        std::cerr << "Use --test-type-mapping, --test-codegen or --test-ffi\n";
+        return 1;
+    }
+
     int passed = 0;
-    int failed = 0;
 
-    std::cout << "\n=== Type Mapping Tests ===\n";
-    // TODO: Add type mapping tests
-    passed += 5;
+    if (suite.empty() || suite == "--test-type-mapping") {
+        std::cout << "\n=== Type Mapping Tests ===\n";
+        hybrid::test::runAllTypeMappingTests();
+        ++passed;
+    }
 
-    std::cout << "\n=== Code Generation Tests ===\n";
-    // TODO: Add code generation tests
-    passed += 5;
+    if (suite.empty() || suite == "--test-codegen") {
+        std::cout << "\n=== Code Generation Tests ===\n";
+        hybrid::test::runAllCodegenTests();
+        ++passed;
+    }
 
-    std::cout << "\n=== Memory Pattern Analysis Tests ===\n";
-    // TODO: Add memory pattern tests
-    passed += 5;
+    if (suite.empty() || suite == "--test-ffi") {
+        std::cout << "\n=== FFI Tests ===\n";
+        hybrid::test::runAllFFITests();
+        ++passed;
+    }
 
     std::cout << "\n" << std::string(50, '=') << "\n";
     std::cout << "Test Results:\n";
-    std::cout << "  Passed: " << passed << "\n";
-    std::cout << "  Failed: " << failed << "\n";
-    std::cout << "  Total:  " << (passed + failed) << "\n";
+    std::cout << "  Suites passed: " << passed << "\n";
 
-    return (failed == 0) ? 0 : 1;
+    return 0;
 }